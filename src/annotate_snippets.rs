@@ -0,0 +1,189 @@
+//! Conversions between this crate's diagnostics and the snippets of [`annotate_snippets`].
+//!
+//! This allows projects to evaluate both renderers side by side, and to migrate gradually.
+//!
+//! Annotations in a [`Slice`] use character offsets relative to the source of that slice,
+//! so converting a diagnostic needs access to the source code through the [`Files`] trait.
+//!
+//! [`Slice`]: Slice
+//! [`Files`]: Files
+
+use std::borrow::Cow;
+use ::annotate_snippets::snippet::{self, AnnotationType, Slice, Snippet, SourceAnnotation};
+use crate::diagnostic::{Annotation, AnnotationStyle, Diagnostic, Note, Severity};
+use crate::file::{Error, Files};
+
+impl From<Severity> for AnnotationType {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Bug | Severity::Error => AnnotationType::Error,
+            Severity::Warning => AnnotationType::Warning,
+            Severity::Note => AnnotationType::Note,
+            Severity::Help => AnnotationType::Help,
+        }
+    }
+}
+
+impl From<AnnotationType> for Severity {
+    fn from(annotation_type: AnnotationType) -> Self {
+        match annotation_type {
+            AnnotationType::Error => Severity::Error,
+            AnnotationType::Warning => Severity::Warning,
+            AnnotationType::Info | AnnotationType::Note => Severity::Note,
+            AnnotationType::Help => Severity::Help,
+        }
+    }
+}
+
+/// Converts a diagnostic into a snippet.
+///
+/// The annotations of every file become a [`Slice`] containing the lines from the first
+/// to the last annotated line in that file. Slices are ordered by the first appearance of
+/// their file in the annotations of the diagnostic.
+///
+/// Primary annotations use the annotation type corresponding to the severity of the diagnostic,
+/// while secondary annotations use [`AnnotationType::Warning`], as that is drawn
+/// with `-` characters. Notes become the footer of the snippet.
+///
+/// As the snippet borrows the source code, this fails with [`Error::OwnedSource`]
+/// if `files` returns an owned source.
+///
+/// [`Slice`]: Slice
+/// [`AnnotationType::Warning`]: AnnotationType::Warning
+/// [`Error::OwnedSource`]: Error::OwnedSource
+pub fn to_snippet<'a, F: Files>(diagnostic: &'a Diagnostic<F::FileId>, files: &'a F) -> Result<Snippet<'a>, Error> {
+    let mut file_ids: Vec<&F::FileId> = Vec::new();
+
+    for annotation in diagnostic.annotations.iter() {
+        if !file_ids.contains(&&annotation.file_id) {
+            file_ids.push(&annotation.file_id);
+        }
+    }
+
+    let mut slices = Vec::with_capacity(file_ids.len());
+
+    for file_id in file_ids.into_iter() {
+        let annotations = diagnostic.annotations.iter().filter(|a| &a.file_id == file_id).collect::<Vec<_>>();
+        let first_line_index = annotations.iter().map(|a| files.line_index(file_id.clone(), a.range.start))
+            .try_fold(usize::MAX, |acc, line_index| line_index.map(|line_index| acc.min(line_index)))?;
+        let last_line_index = annotations.iter().map(|a| files.line_index(file_id.clone(), a.range.end))
+            .try_fold(0, |acc, line_index| line_index.map(|line_index| acc.max(line_index)))?;
+
+        let Cow::Borrowed(source) = files.source(file_id.clone())? else {
+            return Err(Error::OwnedSource);
+        };
+        let start = files.line_range(file_id.clone(), first_line_index)?.start;
+        let end = files.line_range(file_id.clone(), last_line_index)?.end;
+        let slice_source = &source[start..end];
+
+        let char_offset = |byte_index: usize| -> Result<usize, Error> {
+            if !source.is_char_boundary(byte_index) {
+                return Err(Error::InvalidCharBoundary { given: byte_index });
+            }
+
+            Ok(source[start..byte_index.clamp(start, end)].chars().count())
+        };
+
+        slices.push(Slice {
+            source: slice_source,
+            line_start: files.line_number(file_id.clone(), first_line_index)?,
+            origin: Some(files.name(file_id.clone())?),
+            annotations: annotations.iter().map(|a| Ok(SourceAnnotation {
+                range: (char_offset(a.range.start)?, char_offset(a.range.end)?),
+                label: &a.label,
+                annotation_type: match a.style {
+                    AnnotationStyle::Primary => diagnostic.severity.into(),
+                    AnnotationStyle::Secondary => AnnotationType::Warning,
+                },
+            })).collect::<Result<_, Error>>()?,
+            fold: true,
+        });
+    }
+
+    Ok(Snippet {
+        title: Some(snippet::Annotation {
+            id: diagnostic.name.as_deref(),
+            label: Some(&diagnostic.message),
+            annotation_type: diagnostic.severity.into(),
+        }),
+        footer: diagnostic.notes.iter().map(|note| snippet::Annotation {
+            id: None,
+            label: Some(&note.message),
+            annotation_type: note.severity.into(),
+        }).collect(),
+        slices,
+        opt: Default::default(),
+    })
+}
+
+/// Converts a snippet into a diagnostic.
+///
+/// `file_of` is called for every slice, and has to return the file of that slice, as well
+/// as the byte index in that file at which the source of the slice starts.
+///
+/// Annotations that have the same annotation type as the title of the snippet become primary
+/// annotations, and all other annotations become secondary annotations. Snippets without
+/// a title are treated as errors.
+pub fn from_snippet<FileId>(snippet: &Snippet<'_>, mut file_of: impl FnMut(&Slice<'_>) -> Result<(FileId, usize), Error>)
+    -> Result<Diagnostic<FileId>, Error> where FileId: Clone {
+    let annotation_type = snippet.title.as_ref().map(|title| title.annotation_type).unwrap_or(AnnotationType::Error);
+    let mut diagnostic = Diagnostic::new(annotation_type.into());
+
+    if let Some(title) = snippet.title.as_ref() {
+        diagnostic.name = title.id.map(String::from);
+        diagnostic.message = title.label.map(String::from).unwrap_or_default();
+    }
+
+    for slice in snippet.slices.iter() {
+        let (file_id, offset) = file_of(slice)?;
+        let byte_index = |char_offset: usize| offset + slice.source.char_indices().nth(char_offset)
+            .map(|(i, _)| i).unwrap_or(slice.source.len());
+
+        for annotation in slice.annotations.iter() {
+            let style = if annotation.annotation_type == annotation_type {
+                AnnotationStyle::Primary
+            } else {
+                AnnotationStyle::Secondary
+            };
+
+            diagnostic.annotations.push(Annotation::new(style, file_id.clone(),
+                byte_index(annotation.range.0)..byte_index(annotation.range.1))
+                .with_label(annotation.label));
+        }
+    }
+
+    for footer in snippet.footer.iter() {
+        diagnostic.notes.push(Note::new(footer.annotation_type.into(), footer.label.unwrap_or_default()));
+    }
+
+    Ok(diagnostic)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use crate::file::SimpleFile;
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let file = SimpleFile::new("test_file.test", "let main = 23;\nlet 🗻 = main;\nprint(example_source);\n");
+        let diagnostic = Diagnostic::new(Severity::Error)
+            .with_name("E0308")
+            .with_message("Mismatched types")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 26..30)
+                .with_label("expected `String`"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
+                .with_label("declared here"))
+            .with_note(Note::help("Change something"));
+
+        let snippet = to_snippet(&diagnostic, &file).unwrap();
+        assert_eq!(snippet.slices.len(), 1);
+        assert_eq!(snippet.slices[0].source, "let main = 23;\nlet 🗻 = main;\n");
+        assert_eq!(snippet.slices[0].line_start, 1);
+        // "🗻" is 4 bytes long, but a single character
+        assert_eq!(snippet.slices[0].annotations[0].range, (23, 27));
+
+        assert_eq!(from_snippet(&snippet, |_| Ok(((), 0))).unwrap(), diagnostic);
+    }
+}
@@ -0,0 +1,159 @@
+//! Output through the [`anstyle`] ecosystem.
+//!
+//! The renderer sets colors through [`WriteColor`]. [`AnstyleWriter`] implements it for any
+//! [`io::Write`] by translating every [`ColorSpec`] into an [`anstyle::Style`] and writing its
+//! ANSI escape codes. This means that [`ColorConfig`] implementations work unchanged, and that
+//! the output can go through an `anstream` stream, which strips or converts these escape codes
+//! depending on the terminal, like any other output of an application using `anstream`.
+//!
+//! This doesn't need the default `termcolor` feature, so applications using `anstream` can
+//! disable it to avoid depending on `termcolor`.
+//!
+//! # Example
+//! ```rust
+//! use diagnostic_render::anstyle::AnstyleWriter;
+//! use diagnostic_render::diagnostic::{Diagnostic, Severity};
+//! use diagnostic_render::file::SimpleFile;
+//! use diagnostic_render::render::{DiagnosticRenderer, RenderConfig};
+//! use diagnostic_render::render::color::DefaultColorConfig;
+//!
+//! // With `anstream`, this would be `AnstyleWriter::new(anstream::stderr())`
+//! let mut writer = AnstyleWriter::new(Vec::new());
+//! let file = SimpleFile::new("main.rs", "fn main() {}\n");
+//! let mut renderer = DiagnosticRenderer::new(&mut writer, DefaultColorConfig, file, RenderConfig::default());
+//! renderer.render(vec![Diagnostic::new(Severity::Warning).with_message("unused function")]).unwrap();
+//!
+//! assert!(writer.into_inner().starts_with(b"\x1b[0m\x1b[33mwarning"));
+//! ```
+//!
+//! [`anstyle`]: ::anstyle
+//! [`WriteColor`]: WriteColor
+//! [`AnstyleWriter`]: AnstyleWriter
+//! [`io::Write`]: io::Write
+//! [`ColorSpec`]: ColorSpec
+//! [`anstyle::Style`]: ::anstyle::Style
+//! [`ColorConfig`]: crate::render::color::ColorConfig
+
+use std::io;
+use ::anstyle::{AnsiColor, Ansi256Color, Effects, Reset, RgbColor, Style};
+use crate::render::style::{Color, ColorSpec, WriteColor};
+use crate::render::theme::TextStyle;
+
+/// Converts a [`ColorSpec`] into an [`anstyle::Style`].
+///
+/// The intense variants of the basic colors become the bright ANSI colors.
+///
+/// [`ColorSpec`]: ColorSpec
+/// [`anstyle::Style`]: Style
+pub fn to_style(spec: &ColorSpec) -> Style {
+    let mut effects = Effects::new();
+
+    for (enabled, effect) in [(spec.bold(), Effects::BOLD), (spec.dimmed(), Effects::DIMMED), (spec.italic(), Effects::ITALIC),
+                              (spec.underline(), Effects::UNDERLINE), (spec.strikethrough(), Effects::STRIKETHROUGH)] {
+        if enabled {
+            effects |= effect;
+        }
+    }
+
+    Style::new()
+        .fg_color(spec.fg().map(|&color| to_color(color, spec.intense())))
+        .bg_color(spec.bg().map(|&color| to_color(color, spec.intense())))
+        .effects(effects)
+}
+
+fn to_color(color: Color, intense: bool) -> ::anstyle::Color {
+    let ansi = |normal: AnsiColor, bright: AnsiColor| if intense { bright } else { normal }.into();
+
+    match color {
+        Color::Black => ansi(AnsiColor::Black, AnsiColor::BrightBlack),
+        Color::Blue => ansi(AnsiColor::Blue, AnsiColor::BrightBlue),
+        Color::Green => ansi(AnsiColor::Green, AnsiColor::BrightGreen),
+        Color::Red => ansi(AnsiColor::Red, AnsiColor::BrightRed),
+        Color::Cyan => ansi(AnsiColor::Cyan, AnsiColor::BrightCyan),
+        Color::Magenta => ansi(AnsiColor::Magenta, AnsiColor::BrightMagenta),
+        Color::Yellow => ansi(AnsiColor::Yellow, AnsiColor::BrightYellow),
+        Color::White => ansi(AnsiColor::White, AnsiColor::BrightWhite),
+        Color::Ansi256(index) => Ansi256Color(index).into(),
+        Color::Rgb(r, g, b) => RgbColor(r, g, b).into(),
+    }
+}
+
+impl From<&TextStyle> for Style {
+    fn from(style: &TextStyle) -> Self {
+        to_style(&style.to_color_spec())
+    }
+}
+
+/// Implements [`WriteColor`] for any writer by writing the ANSI escape codes of
+/// [`anstyle`] styles.
+///
+/// [`WriteColor`]: WriteColor
+/// [`anstyle`]: ::anstyle
+#[derive(Debug)]
+pub struct AnstyleWriter<W> {
+    inner: W,
+}
+
+impl<W: io::Write> AnstyleWriter<W> {
+    /// Creates a new writer, writing to `inner`.
+    pub fn new(inner: W) -> Self {
+        AnstyleWriter { inner }
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: io::Write> io::Write for AnstyleWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: io::Write> WriteColor for AnstyleWriter<W> {
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        if spec.reset() {
+            write!(self.inner, "{}", Reset.render())?;
+        }
+
+        write!(self.inner, "{}", to_style(spec).render())
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        write!(self.inner, "{}", Reset.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use super::*;
+
+    #[test]
+    fn test_to_style() {
+        let mut spec = ColorSpec::new();
+        spec.set_fg(Some(Color::Blue)).set_bg(Some(Color::Rgb(1, 2, 3))).set_intense(true).set_bold(true);
+
+        assert_eq!(to_style(&spec), Style::new()
+            .fg_color(Some(AnsiColor::BrightBlue.into()))
+            .bg_color(Some(RgbColor(1, 2, 3).into()))
+            .bold());
+        assert_eq!(Style::from(&TextStyle::fg(Color::Ansi256(130)).bold()),
+            Style::new().fg_color(Some(Ansi256Color(130).into())).bold());
+    }
+}
@@ -0,0 +1,344 @@
+//! Reads files and diagnostics as JSON from stdin, and prints the rendered diagnostics to stdout.
+//!
+//! This allows tools not written in Rust to use this renderer. The input is an object like this,
+//! where only `files` and `diagnostics` are required:
+//!
+//! ```text
+//! {
+//!   "files": [{ "name": "src/main.rs", "source": "let main = 23;\n" }],
+//!   "config": { "display_style": "rich" },
+//!   "diagnostics": [{
+//!     "severity": "error",
+//!     "name": "E0308",
+//!     "message": "Mismatched types",
+//!     "annotations": [{ "style": "primary", "file": "src/main.rs", "range": { "start": 11, "end": 13 }, "label": "expected `String`" }],
+//!     "notes": [{ "severity": "help", "message": "Change something" }],
+//!     "suggestions": [{ "message": "Use a string", "replacements": [{ "file": "src/main.rs", "range": { "start": 11, "end": 13 }, "text": "\"23\"" }] }],
+//!     "sub_diagnostics": []
+//!   }]
+//! }
+//! ```
+//!
+//! Annotations and replacements refer to files by their name. `config` is deserialized
+//! as a [`RenderConfig`]. This is the same format as the objects written by the `JsonEmitter`,
+//! except that locations are only given as byte ranges.
+//!
+//! Colors are used if stdout is a terminal, unless `--color always` or `--color never` is given.
+//!
+//! The exit code is the one suggested by [`SeverityCounts::suggested_exit_code`] for the rendered
+//! diagnostics, or `2` if the arguments or the input are invalid. Diagnostics are [checked] and
+//! their ranges [validated] against the files before anything is rendered.
+//!
+//! [checked]: Diagnostic::check
+//! [validated]: Diagnostic::validated
+
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::process::ExitCode;
+use serde::Deserialize;
+use termcolor::{ColorChoice, StandardStream};
+use diagnostic_render::diagnostic::{Annotation, AnnotationStyle, Applicability, Diagnostic, Note, Replacement, Severity, SeverityCounts, Suggestion};
+use diagnostic_render::file::SimpleFiles;
+use diagnostic_render::render::{DiagnosticRenderer, RenderConfig};
+use diagnostic_render::render::color::{colors_enabled, AutoColorConfig, DefaultColorConfig};
+
+const USAGE: &str = "Usage: diagnostic-render [--color auto|always|never] < input.json";
+
+type Files = SimpleFiles<String, String>;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Input {
+    files: Vec<InputFile>,
+    #[serde(default)]
+    config: RenderConfig,
+    diagnostics: Vec<InputDiagnostic>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputFile {
+    name: String,
+    source: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputDiagnostic {
+    severity: Severity,
+    severity_name: Option<String>,
+    name: Option<String>,
+    url: Option<String>,
+    message: String,
+    #[serde(default)]
+    annotations: Vec<InputAnnotation>,
+    #[serde(default)]
+    notes: Vec<InputNote>,
+    #[serde(default)]
+    suggestions: Vec<InputSuggestion>,
+    #[serde(default)]
+    sub_diagnostics: Vec<InputDiagnostic>,
+    #[serde(default)]
+    suppressed_count: u32,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputAnnotation {
+    style: InputAnnotationStyle,
+    file: String,
+    range: InputRange,
+    #[serde(default)]
+    label: String,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum InputAnnotationStyle {
+    Primary,
+    Secondary,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputRange {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputNote {
+    severity: Severity,
+    prefix: Option<String>,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputSuggestion {
+    message: String,
+    #[serde(default)]
+    applicability: InputApplicability,
+    replacements: Vec<InputReplacement>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum InputApplicability {
+    MachineApplicable,
+    MaybeIncorrect,
+    HasPlaceholders,
+    #[default]
+    Unspecified,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputReplacement {
+    file: String,
+    range: InputRange,
+    text: String,
+}
+
+/// Looks up file IDs by the name of the file.
+struct FileIds<'a> {
+    files: &'a [InputFile],
+}
+
+impl FileIds<'_> {
+    fn get(&self, name: &str) -> Result<usize, String> {
+        self.files.iter().position(|file| file.name == name)
+            .ok_or_else(|| format!("unknown file `{}`", name))
+    }
+
+    fn diagnostic(&self, input: InputDiagnostic) -> Result<Diagnostic<usize>, String> {
+        let mut diagnostic = Diagnostic::new(input.severity)
+            .with_message(input.message)
+            .with_suppressed_count(input.suppressed_count);
+        diagnostic.severity_name = input.severity_name;
+        diagnostic.name = input.name;
+        diagnostic.url = input.url;
+        diagnostic.metadata = input.metadata;
+
+        for annotation in input.annotations.into_iter() {
+            let style = match annotation.style {
+                InputAnnotationStyle::Primary => AnnotationStyle::Primary,
+                InputAnnotationStyle::Secondary => AnnotationStyle::Secondary,
+            };
+
+            diagnostic = diagnostic.with_annotation(Annotation::new(style, self.get(&annotation.file)?,
+                annotation.range.start..annotation.range.end).with_label(annotation.label));
+        }
+
+        for note in input.notes.into_iter() {
+            let mut result = Note::new(note.severity, note.message);
+            result.prefix = note.prefix;
+            diagnostic = diagnostic.with_note(result);
+        }
+
+        for suggestion in input.suggestions.into_iter() {
+            let mut result = Suggestion::new(suggestion.message).with_applicability(match suggestion.applicability {
+                InputApplicability::MachineApplicable => Applicability::MachineApplicable,
+                InputApplicability::MaybeIncorrect => Applicability::MaybeIncorrect,
+                InputApplicability::HasPlaceholders => Applicability::HasPlaceholders,
+                InputApplicability::Unspecified => Applicability::Unspecified,
+            });
+
+            for replacement in suggestion.replacements.into_iter() {
+                result = result.with_replacement(Replacement::new(self.get(&replacement.file)?,
+                    replacement.range.start..replacement.range.end, replacement.text));
+            }
+
+            diagnostic = diagnostic.with_suggestion(result);
+        }
+
+        for sub_diagnostic in input.sub_diagnostics.into_iter() {
+            diagnostic = diagnostic.with_sub_diagnostic(self.diagnostic(sub_diagnostic)?);
+        }
+
+        Ok(diagnostic)
+    }
+}
+
+/// Converts the input into a file database, a render configuration and the diagnostics to render,
+/// checking that the diagnostics are valid for the files.
+fn convert(mut input: Input) -> Result<(Files, RenderConfig, Vec<Diagnostic<usize>>), String> {
+    let diagnostics = std::mem::take(&mut input.diagnostics);
+    let ids = FileIds { files: &input.files };
+    let diagnostics = diagnostics.into_iter()
+        .map(|diagnostic| ids.diagnostic(diagnostic))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut files = SimpleFiles::new();
+
+    for file in input.files.into_iter() {
+        files.add(file.name, file.source);
+    }
+
+    // The input isn't trusted, so mistakes are reported before they reach the renderer
+    let diagnostics = diagnostics.into_iter().enumerate()
+        .map(|(i, diagnostic)| {
+            diagnostic.check().map_err(|err| format!("{} (diagnostic {})", err, i))?;
+            diagnostic.validated(&files).map_err(|err| format!("invalid diagnostic: {} (diagnostic {})", err, i))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((files, input.config, diagnostics))
+}
+
+fn run() -> Result<SeverityCounts, String> {
+    let mut args = std::env::args().skip(1);
+    let mut colors = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--color" => colors = Some(match args.next().as_deref() {
+                Some("auto") => colors_enabled(std::io::stdout().is_terminal()),
+                Some("always") => true,
+                Some("never") => false,
+                _ => return Err(format!("`--color` must be `auto`, `always` or `never`\n{}", USAGE)),
+            }),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(SeverityCounts::new());
+            },
+            arg => return Err(format!("unexpected argument `{}`\n{}", arg, USAGE)),
+        }
+    }
+
+    let colors = colors.unwrap_or_else(|| colors_enabled(std::io::stdout().is_terminal()));
+    let input: Input = serde_json::from_reader(std::io::stdin().lock())
+        .map_err(|err| format!("invalid input: {}", err))?;
+    let (files, config, diagnostics) = convert(input)?;
+
+    let mut stdout = StandardStream::stdout(if colors { ColorChoice::Always } else { ColorChoice::Never });
+    let mut renderer = DiagnosticRenderer::new(&mut stdout, AutoColorConfig::with_enabled(DefaultColorConfig, colors), files, config);
+    renderer.render(diagnostics).map_err(|err| err.to_string())?;
+    Ok(renderer.counts())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(counts) => ExitCode::from(counts.suggested_exit_code()),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(2)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diagnostic_render::render::string::render_to_string;
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let input: Input = serde_json::from_str(r#"{
+            "files": [{ "name": "main.test", "source": "let main = 23;\nsomething += 3.0;\n" }],
+            "config": { "surrounding_lines": 0 },
+            "diagnostics": [{
+                "severity": "error",
+                "name": "E0308",
+                "message": "Mismatched types",
+                "annotations": [{ "style": "primary", "file": "main.test", "range": { "start": 11, "end": 13 }, "label": "expected `String`" }],
+                "notes": [{ "severity": "help", "message": "Change something" }]
+            }]
+        }"#).unwrap();
+        let (files, config, diagnostics) = convert(input).unwrap();
+
+        insta::assert_snapshot!(render_to_string(files, config, diagnostics).unwrap(), @r###"
+        error[E0308]: Mismatched types
+         --> main.test:1:12
+        1 | let main = 23;
+          |            ^^ expected `String`
+          = help: Change something
+        "###);
+    }
+
+    #[test]
+    fn unknown_file() {
+        let input: Input = serde_json::from_str(r#"{
+            "files": [],
+            "diagnostics": [{
+                "severity": "warning",
+                "message": "Unused variable",
+                "annotations": [{ "style": "primary", "file": "main.test", "range": { "start": 4, "end": 8 } }]
+            }]
+        }"#).unwrap();
+
+        assert_eq!(convert(input).err().as_deref(), Some("unknown file `main.test`"));
+    }
+
+    #[test]
+    fn invalid_diagnostic() {
+        let input: Input = serde_json::from_str(r#"{
+            "files": [{ "name": "main.test", "source": "let main = 23;\n" }],
+            "diagnostics": [{
+                "severity": "error",
+                "message": "Mismatched types",
+                "suggestions": [{ "message": "Use a string", "replacements": [{ "file": "main.test", "range": { "start": 8, "end": 4 }, "text": "x" }] }]
+            }, {
+                "severity": "error",
+                "message": "Mismatched types",
+                "annotations": [{ "style": "primary", "file": "main.test", "range": { "start": 11, "end": 20 } }]
+            }]
+        }"#).unwrap();
+
+        assert_eq!(convert(input).err().as_deref(), Some("invalid diagnostic: range 8..4 ends before it starts (diagnostic 0)"));
+
+        let input: Input = serde_json::from_str(r#"{
+            "files": [{ "name": "main.test", "source": "let main = 23;\n" }],
+            "diagnostics": [{
+                "severity": "error",
+                "message": "Mismatched types",
+                "annotations": [{ "style": "primary", "file": "main.test", "range": { "start": 11, "end": 20 } }]
+            }]
+        }"#).unwrap();
+
+        assert_eq!(convert(input).err().as_deref(), Some("invalid diagnostic: invalid index 20, maximum index is 15 (diagnostic 0)"));
+    }
+}
@@ -0,0 +1,103 @@
+//! Conversions from the diagnostics of [`codespan_reporting`].
+//!
+//! This allows existing compilers to switch renderers without rewriting
+//! the code that constructs their diagnostics.
+//!
+//! # Example
+//! ```rust
+//! use codespan_reporting::diagnostic::{Diagnostic as CodespanDiagnostic, Label};
+//! use diagnostic_render::diagnostic::{Annotation, Diagnostic};
+//!
+//! let diagnostic: Diagnostic<()> = CodespanDiagnostic::error()
+//!     .with_message("Mismatched types")
+//!     .with_labels(vec![Label::primary((), 11..13).with_message("expected `String`")])
+//!     .into();
+//!
+//! assert_eq!(diagnostic, Diagnostic::error()
+//!     .with_message("Mismatched types")
+//!     .with_annotation(Annotation::primary((), 11..13).with_label("expected `String`")));
+//! ```
+
+use codespan_reporting::diagnostic as codespan;
+use crate::diagnostic::{Annotation, AnnotationStyle, Diagnostic, Note, Severity};
+
+impl From<codespan::Severity> for Severity {
+    fn from(severity: codespan::Severity) -> Self {
+        match severity {
+            codespan::Severity::Bug => Severity::Bug,
+            codespan::Severity::Error => Severity::Error,
+            codespan::Severity::Warning => Severity::Warning,
+            codespan::Severity::Note => Severity::Note,
+            codespan::Severity::Help => Severity::Help,
+        }
+    }
+}
+
+impl From<codespan::LabelStyle> for AnnotationStyle {
+    fn from(style: codespan::LabelStyle) -> Self {
+        match style {
+            codespan::LabelStyle::Primary => AnnotationStyle::Primary,
+            codespan::LabelStyle::Secondary => AnnotationStyle::Secondary,
+        }
+    }
+}
+
+impl<FileId> From<codespan::Label<FileId>> for Annotation<FileId> {
+    fn from(label: codespan::Label<FileId>) -> Self {
+        Annotation::new(label.style.into(), label.file_id, label.range)
+            .with_label(label.message)
+    }
+}
+
+impl<FileId> From<codespan::Diagnostic<FileId>> for Diagnostic<FileId> {
+    /// Converts a `codespan-reporting` diagnostic.
+    ///
+    /// Notes starting with `help: ` or `note: ` are converted into notes of the corresponding
+    /// severity, with that prefix removed. All other notes get a severity of [`Severity::Note`].
+    ///
+    /// [`Severity::Note`]: Severity::Note
+    fn from(diagnostic: codespan::Diagnostic<FileId>) -> Self {
+        let mut result = Diagnostic::new(diagnostic.severity.into())
+            .with_message(diagnostic.message)
+            .with_annotations(diagnostic.labels.into_iter().map(Annotation::from).collect())
+            .with_notes(diagnostic.notes.into_iter().map(|note| {
+                if let Some(message) = note.strip_prefix("help: ") {
+                    Note::help(message)
+                } else if let Some(message) = note.strip_prefix("note: ") {
+                    Note::note(message)
+                } else {
+                    Note::note(note)
+                }
+            }).collect());
+
+        result.name = diagnostic.code;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let diagnostic: Diagnostic<usize> = codespan::Diagnostic::warning()
+            .with_code("W0001")
+            .with_message("Unused variable")
+            .with_labels(vec![
+                codespan::Label::primary(0, 4..8).with_message("never used"),
+                codespan::Label::secondary(1, 0..3),
+            ])
+            .with_notes(vec![String::from("help: remove it"), String::from("some context")])
+            .into();
+
+        assert_eq!(diagnostic, Diagnostic::warning()
+            .with_name("W0001")
+            .with_message("Unused variable")
+            .with_annotation(Annotation::primary(0, 4..8).with_label("never used"))
+            .with_annotation(Annotation::secondary(1, 0..3))
+            .with_note(Note::help("remove it"))
+            .with_note(Note::note("some context")));
+    }
+}
@@ -4,8 +4,13 @@
 //! for their specific use cases, and convert them to this crate's
 //! representation when needed.
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::ops::Range;
+use crate::file::{Error, Files};
+use crate::render::string::DisplayDiagnostic;
+use crate::render::theme::TextStyle;
 
 /// A severity level for diagnostic messages.
 ///
@@ -20,6 +25,7 @@ use std::ops::Range;
 /// assert!(Severity::Note > Severity::Help);
 /// ```
 #[derive(Clone, Copy, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum Severity {
     /// A help message
     Help,
@@ -45,6 +51,103 @@ impl Display for Severity {
     }
 }
 
+/// The number of diagnostics of every severity, like the diagnostics rendered by a
+/// [`DiagnosticRenderer`]. This can be used to decide on the exit code of a program.
+///
+/// [`DiagnosticRenderer`]: crate::render::DiagnosticRenderer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SeverityCounts {
+    counts: [usize; 5],
+}
+
+impl SeverityCounts {
+    /// Create new counts, with no diagnostics of any severity.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Increments the count of a severity.
+    pub fn add(&mut self, severity: Severity) {
+        self.counts[severity as usize] += 1;
+    }
+
+    /// Returns the number of diagnostics with a severity.
+    pub fn get(&self, severity: Severity) -> usize {
+        self.counts[severity as usize]
+    }
+
+    /// Returns the number of diagnostics of all severities.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Returns whether there are any diagnostics with [`Severity::Error`] or [`Severity::Bug`].
+    ///
+    /// [`Severity::Error`]: Severity::Error
+    /// [`Severity::Bug`]: Severity::Bug
+    pub fn has_errors(&self) -> bool {
+        self.get(Severity::Error) + self.get(Severity::Bug) > 0
+    }
+
+    /// Returns the exit code a program should use after emitting these diagnostics,
+    /// following the conventions of rustc: `101` if there are any bugs, `1` if there are
+    /// any errors, and `0` otherwise.
+    ///
+    /// ```rust
+    /// use std::process::ExitCode;
+    /// use diagnostic_render::diagnostic::{Severity, SeverityCounts};
+    ///
+    /// let mut counts = SeverityCounts::new();
+    /// counts.add(Severity::Warning);
+    /// assert_eq!(counts.suggested_exit_code(), 0);
+    ///
+    /// counts.add(Severity::Error);
+    /// assert_eq!(counts.suggested_exit_code(), 1);
+    ///
+    /// counts.add(Severity::Bug);
+    /// assert_eq!(counts.suggested_exit_code(), 101);
+    ///
+    /// let exit_code = ExitCode::from(counts.suggested_exit_code());
+    /// ```
+    pub fn suggested_exit_code(&self) -> u8 {
+        if self.get(Severity::Bug) > 0 {
+            101
+        } else if self.has_errors() {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Returns the severity and message of a summary of these counts, worded like in rustc,
+    /// or `None` if there are no errors or warnings.
+    ///
+    /// ```rust
+    /// use diagnostic_render::diagnostic::{Severity, SeverityCounts};
+    ///
+    /// let mut counts = SeverityCounts::new();
+    /// counts.add(Severity::Warning);
+    /// assert_eq!(counts.summary(), Some((Severity::Warning, String::from("1 warning emitted"))));
+    ///
+    /// counts.add(Severity::Error);
+    /// counts.add(Severity::Error);
+    /// assert_eq!(counts.summary(), Some((Severity::Error, String::from("aborting due to 2 previous errors; 1 warning emitted"))));
+    /// ```
+    pub fn summary(&self) -> Option<(Severity, String)> {
+        let plural = |count: usize| if count == 1 { "" } else { "s" };
+        let errors = self.get(Severity::Error) + self.get(Severity::Bug);
+        let warnings = self.get(Severity::Warning);
+
+        match (errors, warnings) {
+            (0, 0) => None,
+            (0, warnings) => Some((Severity::Warning, format!("{} warning{} emitted", warnings, plural(warnings)))),
+            (errors, 0) => Some((Severity::Error, format!("aborting due to {} previous error{}", errors, plural(errors)))),
+            (errors, warnings) => Some((Severity::Error, format!("aborting due to {} previous error{}; {} warning{} emitted",
+                errors, plural(errors), warnings, plural(warnings)))),
+        }
+    }
+}
+
 /// A style for annotations.
 ///
 /// These are ordered in the following way:
@@ -71,8 +174,27 @@ pub struct Annotation<FileId> {
     /// The range in bytes we are going to include in the final snippet.
     pub range: Range<usize>,
     /// An optional label to provide some additional information for the
-    /// underlined code. These should not include line breaks.
+    /// underlined code.
+    ///
+    /// Labels can include line breaks. Every further line is shown below the first one,
+    /// starting at the same column.
     pub label: String,
+    /// An optional style to use for this annotation instead of the one for its style and
+    /// the severity of the diagnostic, like green for an expected type and red for the type
+    /// that was found instead.
+    ///
+    /// The style is applied through [`ColorConfig::styled_annotation`], so it is not used
+    /// when colors are disabled.
+    ///
+    /// [`ColorConfig::styled_annotation`]: crate::render::color::ColorConfig::styled_annotation
+    pub color: Option<TextStyle>,
+    /// The priority of the label of this annotation, which is `0` by default.
+    ///
+    /// If a single-line annotation has a higher priority than all other annotations on its
+    /// line, its label is shown next to its underline, and the labels of the others are moved
+    /// below it. This is only possible if no other annotation on the line reaches further to
+    /// the right than it.
+    pub priority: i32,
 }
 
 impl<FileId> Annotation<FileId> {
@@ -83,6 +205,8 @@ impl<FileId> Annotation<FileId> {
             file_id,
             range: range.into(),
             label: String::new(),
+            color: None,
+            priority: 0,
         }
     }
 
@@ -105,6 +229,18 @@ impl<FileId> Annotation<FileId> {
         self.label = label.to_string();
         self
     }
+
+    /// Set the style used for the annotation instead of the one for its style and severity.
+    pub fn with_color(mut self, color: TextStyle) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Set the priority of the label of the annotation.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
 }
 
 /// A note associated with the primary cause of a diagnostic.
@@ -126,19 +262,71 @@ pub struct Note {
     /// This can include line breaks for improved formatting.
     /// It should not be empty.
     pub message: String,
+    /// The expected and found values of a note comparing them, which are rendered aligned
+    /// on two lines instead of the message. The message is still used by emitters.
+    pub expected_found: Option<ExpectedFound>,
+    /// A custom prefix written instead of the name of the severity, like `hint`, `docs`
+    /// or `see also`. The severity is still used for filtering and colors.
+    pub prefix: Option<String>,
 }
 
 impl Note {
     /// Create a new note.
     pub fn new<M: ToString>(severity: Severity, message: M) -> Self {
         Note {
-            severity, message: message.to_string(),
+            severity, message: message.to_string(), expected_found: None, prefix: None,
+        }
+    }
+
+    /// Create a new note with a severity of [`Severity::Note`] comparing an expected and
+    /// a found value, like the types in a type mismatch. They are rendered aligned
+    /// on two lines:
+    ///
+    /// ```text
+    ///   = note: expected `String`
+    ///              found `integer`
+    /// ```
+    ///
+    /// [`Severity::Note`]: Severity::Note
+    pub fn expected_found<E: ToString, F: ToString>(expected: E, found: F) -> Self {
+        let expected_found = ExpectedFound::new(expected, found);
+
+        Note {
+            severity: Severity::Note,
+            message: format!("expected {}\n   found {}", expected_found.expected, expected_found.found),
+            expected_found: Some(expected_found),
+            prefix: None,
         }
     }
 
+    /// Set a custom prefix, which is written instead of the name of the severity.
+    pub fn with_prefix<P: ToString>(mut self, prefix: P) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Returns the custom prefix of this note, or the name of its severity if it has none.
+    pub fn prefix_or_severity(&self) -> Cow<'_, str> {
+        match self.prefix.as_ref() {
+            Some(prefix) => Cow::Borrowed(prefix),
+            None => Cow::Owned(self.severity.to_string()),
+        }
+    }
+
+    /// Set whether the parts of the expected and found values that differ from each other
+    /// are emphasized. This does nothing if this note does not compare values.
+    pub fn with_highlighted_differences(mut self, highlight_differences: bool) -> Self {
+        if let Some(expected_found) = self.expected_found.as_mut() {
+            expected_found.highlight_differences = highlight_differences;
+        }
+
+        self
+    }
+
     /// Create a new note with a severity of [`Severity::Note`].
     ///
     /// [`Severity::Note`]: Severity::Note
+    #[allow(clippy::self_named_constructors)]
     pub fn note<M: ToString>(message: M) -> Self {
         Self::new(Severity::Note, message)
     }
@@ -151,6 +339,130 @@ impl Note {
     }
 }
 
+/// An expected and a found value compared by a [`Note`].
+///
+/// [`Note`]: Note
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedFound {
+    /// The expected value.
+    pub expected: String,
+    /// The value that was found instead.
+    pub found: String,
+    /// Whether the parts of both values that differ from each other are emphasized.
+    /// Only the middle part between the longest common prefix and suffix is emphasized.
+    pub highlight_differences: bool,
+}
+
+impl ExpectedFound {
+    /// Create a new comparison of an expected and a found value.
+    pub fn new<E: ToString, F: ToString>(expected: E, found: F) -> Self {
+        ExpectedFound {
+            expected: expected.to_string(),
+            found: found.to_string(),
+            highlight_differences: false,
+        }
+    }
+
+    /// Returns the byte lengths of the longest common prefix and suffix of both values,
+    /// which don't overlap in either value.
+    pub fn common_affixes(&self) -> (usize, usize) {
+        let prefix = self.expected.char_indices().zip(self.found.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(self.expected.len().min(self.found.len()), |((i, _), _)| i);
+        let max_suffix = self.expected.len().min(self.found.len()) - prefix;
+        let suffix = self.expected[prefix..].chars().rev().zip(self.found[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .scan(0, |len, c| { *len += c; Some(*len) })
+            .take_while(|&len| len <= max_suffix)
+            .last().unwrap_or(0);
+
+        (prefix, suffix)
+    }
+}
+
+/// How confident the author of a [`Suggestion`] is that applying it is correct.
+///
+/// This is not shown when rendering diagnostics, but can be used by tools
+/// that apply suggestions automatically.
+///
+/// [`Suggestion`]: Suggestion
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Applicability {
+    /// The suggestion is definitely what the user intended, and can be applied automatically.
+    MachineApplicable,
+    /// The suggestion may be what the user intended, but it is uncertain.
+    MaybeIncorrect,
+    /// The suggestion contains placeholders like `(...)` that have to be filled in by the user.
+    HasPlaceholders,
+    /// The applicability of the suggestion is unknown.
+    #[default]
+    Unspecified,
+}
+
+/// A part of a [`Suggestion`], replacing a range of code with new text.
+///
+/// An empty range inserts the text without removing any code.
+///
+/// [`Suggestion`]: Suggestion
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replacement<FileId> {
+    /// The file containing the code to replace.
+    pub file_id: FileId,
+    /// The range in bytes of the code to replace.
+    pub range: Range<usize>,
+    /// The text to insert in place of the range.
+    pub text: String,
+}
+
+impl<FileId> Replacement<FileId> {
+    /// Create a new replacement.
+    pub fn new<R: Into<Range<usize>>, T: ToString>(file_id: FileId, range: R, text: T) -> Self {
+        Replacement {
+            file_id,
+            range: range.into(),
+            text: text.to_string(),
+        }
+    }
+}
+
+/// A suggestion on how to fix the cause of a diagnostic, by changing code.
+///
+/// They are displayed at the end of diagnostics after the notes, showing
+/// the code with the suggested changes applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suggestion<FileId> {
+    /// The message describing the suggestion, like `try using a conversion method`.
+    pub message: String,
+    /// The changes to make to the code.
+    pub replacements: Vec<Replacement<FileId>>,
+    /// How confident the author of the suggestion is that applying it is correct.
+    pub applicability: Applicability,
+}
+
+impl<FileId> Suggestion<FileId> {
+    /// Create a new suggestion without any replacements.
+    pub fn new<M: ToString>(message: M) -> Self {
+        Suggestion {
+            message: message.to_string(),
+            replacements: Vec::new(),
+            applicability: Applicability::default(),
+        }
+    }
+
+    /// Add a replacement to the suggestion.
+    pub fn with_replacement(mut self, replacement: Replacement<FileId>) -> Self {
+        self.replacements.push(replacement);
+        self
+    }
+
+    /// Set the applicability of the suggestion.
+    pub fn with_applicability(mut self, applicability: Applicability) -> Self {
+        self.applicability = applicability;
+        self
+    }
+}
+
 /// Represents a diagnostic message that can provide information like errors and
 /// warnings to the user.
 ///
@@ -163,14 +475,31 @@ impl Note {
 pub struct Diagnostic<FileId> {
     /// The overall severity of the diagnostic.
     pub severity: Severity,
+    /// The name shown instead of the name of [`Diagnostic::severity`], like `deprecation`
+    /// or `fatal`, or `None` to show the name from [`Strings::severity`].
+    ///
+    /// The diagnostic is still filtered and counted by its severity.
+    ///
+    /// [`Diagnostic::severity`]: Diagnostic::severity
+    /// [`Strings::severity`]: crate::render::strings::Strings::severity
+    pub severity_name: Option<String>,
     /// An optional name or code that identifies this diagnostic.
     pub name: Option<String>,
+    /// An optional URL to documentation about this diagnostic, like a description of
+    /// the lint or error code that caused it.
+    pub url: Option<String>,
     /// The main message associated with this diagnostic.
     ///
     /// These should not include line breaks, and in order support the 'short'
     /// diagnostic display style, the message should be specific enough to make
     /// sense on its own, without additional context provided by annotations and notes.
     pub message: String,
+    /// Named arguments for the message, labels, notes and suggestion messages of this diagnostic,
+    /// used when they are resolved through [`Strings::message`] when rendering, like message
+    /// identifiers of a Fluent bundle. They don't apply to sub-diagnostics.
+    ///
+    /// [`Strings::message`]: crate::render::strings::Strings::message
+    pub args: Vec<(String, String)>,
     /// Source annotations that describe the cause of the diagnostic.
     ///
     /// The order of the annotations inside the vector does not have any meaning.
@@ -178,14 +507,35 @@ pub struct Diagnostic<FileId> {
     pub annotations: Vec<Annotation<FileId>>,
     /// Notes that are associated with the primary cause of the diagnostic.
     pub notes: Vec<Note>,
+    /// Suggestions on how to fix the cause of the diagnostic. They are shown below notes.
+    pub suggestions: Vec<Suggestion<FileId>>,
 
-    // /// Additional diagnostics that can be used to show context from other files,
-    // /// provide help by showing changed code, or similar. They are shown below notes.
-    // pub sub_diagnostics: Vec<Diagnostic<FileId>>,
+    /// Additional diagnostics that can be used to show context from other files,
+    /// provide help by showing changed code, or similar. They are shown indented below
+    /// notes and suggestions.
+    ///
+    /// Their severity should usually be [`Severity::Note`] or [`Severity::Help`].
+    ///
+    /// [`Severity::Note`]: Severity::Note
+    /// [`Severity::Help`]: Severity::Help
+    pub sub_diagnostics: Vec<Diagnostic<FileId>>,
 
     /// The number of diagnostics following this one that are hidden due to
     /// something like panic mode in error reporting.
     pub suppressed_count: u32,
+    /// The number of duplicates of this diagnostic that were merged into it by [`deduplicate`].
+    /// If this is not 0, a note like `(message repeated 3 times)` is added when rendering it,
+    /// formatted with [`Strings::repeated`].
+    ///
+    /// [`deduplicate`]: deduplicate
+    /// [`Strings::repeated`]: crate::render::strings::Strings::repeated
+    pub duplicate_count: u32,
+
+    /// Additional information for tools consuming this diagnostic, like rule categories,
+    /// fix identifiers or tracking URLs. This is ignored when rendering diagnostics as text,
+    /// but included by emitters for machine-readable formats, like the properties of
+    /// a SARIF result.
+    pub metadata: BTreeMap<String, String>,
 }
 
 impl<FileId> Diagnostic<FileId> {
@@ -193,11 +543,18 @@ impl<FileId> Diagnostic<FileId> {
     pub fn new(severity: Severity) -> Self {
         Diagnostic {
             severity,
+            severity_name: None,
             name: None,
+            url: None,
             message: String::new(),
+            args: Vec::new(),
             annotations: Vec::new(),
             notes: Vec::new(),
+            suggestions: Vec::new(),
+            sub_diagnostics: Vec::new(),
             suppressed_count: 0,
+            duplicate_count: 0,
+            metadata: BTreeMap::new(),
         }
     }
 
@@ -236,18 +593,75 @@ impl<FileId> Diagnostic<FileId> {
         Self::new(Severity::Help)
     }
 
+    /// Create a new diagnostic with a severity of [`Severity::Error`] from an error, so that
+    /// runtime errors can be rendered like other diagnostics.
+    ///
+    /// The message is the [`Display`] output of the error, and every error in its chain of
+    /// [`source`]s is added as a note with the prefix `caused by`.
+    ///
+    /// ```
+    /// use diagnostic_render::diagnostic::Diagnostic;
+    /// use diagnostic_render::file::SimpleFile;
+    ///
+    /// let error = std::fs::read_to_string("missing.toml")
+    ///     .map_err(|err| std::io::Error::new(err.kind(), format!("failed to read config: {}", err)))
+    ///     .unwrap_err();
+    /// let diagnostic: Diagnostic<()> = Diagnostic::from_error(&error);
+    /// assert!(diagnostic.message.starts_with("failed to read config"));
+    /// ```
+    ///
+    /// [`Severity::Error`]: Severity::Error
+    /// [`Display`]: Display
+    /// [`source`]: std::error::Error::source
+    pub fn from_error<E: std::error::Error + ?Sized>(error: &E) -> Self {
+        let mut diagnostic = Self::error().with_message(error);
+        let mut source = error.source();
+
+        while let Some(cause) = source {
+            diagnostic.notes.push(Note::note(cause).with_prefix("caused by"));
+            source = cause.source();
+        }
+
+        diagnostic
+    }
+
+    /// Set the name shown instead of the name of the severity of the diagnostic.
+    pub fn with_severity_name<N: ToString>(mut self, severity_name: N) -> Self {
+        self.severity_name = Some(severity_name.to_string());
+        self
+    }
+
     /// Set the name or code of the diagnostic.
     pub fn with_name<M: ToString>(mut self, name: M) -> Self {
         self.name = Some(name.to_string());
         self
     }
 
+    /// Set the URL to documentation about the diagnostic.
+    pub fn with_url<U: ToString>(mut self, url: U) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
     /// Set the message of the diagnostic.
     pub fn with_message<M: ToString>(mut self, message: M) -> Self {
         self.message = message.to_string();
         self
     }
 
+    /// Add a named argument for the message, labels and notes of the diagnostic.
+    pub fn with_arg<N: ToString, V: ToString>(mut self, name: N, value: V) -> Self {
+        self.args.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add an entry to the metadata of the diagnostic, replacing the value of the key
+    /// if it already has one.
+    pub fn with_metadata<K: ToString, V: ToString>(mut self, key: K, value: V) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+
     /// Add an annotation to the diagnostic.
     pub fn with_annotation(mut self, annotation: Annotation<FileId>) -> Self {
         self.annotations.push(annotation);
@@ -272,9 +686,544 @@ impl<FileId> Diagnostic<FileId> {
         self
     }
 
+    /// Add a suggestion to the diagnostic.
+    pub fn with_suggestion(mut self, suggestion: Suggestion<FileId>) -> Self {
+        self.suggestions.push(suggestion);
+        self
+    }
+
+    /// Add a sub-diagnostic to the diagnostic.
+    pub fn with_sub_diagnostic(mut self, sub_diagnostic: Diagnostic<FileId>) -> Self {
+        self.sub_diagnostics.push(sub_diagnostic);
+        self
+    }
+
     /// Sets the number of suppressed diagnostics.
     pub fn with_suppressed_count(mut self, suppressed_count: u32) -> Self {
         self.suppressed_count = suppressed_count;
         self
     }
+
+    /// Sets the number of duplicates that were merged into this diagnostic.
+    pub fn with_duplicate_count(mut self, duplicate_count: u32) -> Self {
+        self.duplicate_count = duplicate_count;
+        self
+    }
+
+    /// Checks this diagnostic and its sub-diagnostics for mistakes that don't depend on
+    /// the source code, returning all problems that were found.
+    ///
+    /// See [`DiagnosticBuilder::build`] for the checks that are done. To check the ranges
+    /// against the source code, use [`Diagnostic::validated`].
+    ///
+    /// [`DiagnosticBuilder::build`]: DiagnosticBuilder::build
+    /// [`Diagnostic::validated`]: Diagnostic::validated
+    pub fn check(&self) -> Result<(), ValidationError> {
+        let mut problems = Vec::new();
+        self.find_problems(&mut problems);
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { problems })
+        }
+    }
+
+    fn find_problems(&self, problems: &mut Vec<ValidationProblem>) {
+        if self.message.is_empty() && self.suppressed_count == 0 {
+            problems.push(ValidationProblem::EmptyMessage);
+        }
+
+        let ranges = self.annotations.iter().map(|annotation| &annotation.range)
+            .chain(self.suggestions.iter().flat_map(|s| s.replacements.iter()).map(|replacement| &replacement.range));
+
+        for range in ranges {
+            if range.start > range.end {
+                problems.push(ValidationProblem::ReversedRange { start: range.start, end: range.end });
+            }
+        }
+
+        for annotation in self.annotations.iter() {
+            if let Some(c) = annotation.label.chars().find(|&c| c.is_control() && c != '\n' && c != '\t') {
+                problems.push(ValidationProblem::ControlCharacter { label: annotation.label.clone(), character: c });
+            }
+        }
+
+        for sub_diagnostic in self.sub_diagnostics.iter() {
+            sub_diagnostic.find_problems(problems);
+        }
+    }
+
+    /// Returns the annotation that determines the position of this diagnostic.
+    ///
+    /// This is the annotation with the earliest starting position out of the ones
+    /// with the highest style that appears in the annotations of this diagnostic.
+    pub fn primary_annotation(&self) -> Option<&Annotation<FileId>> {
+        self.annotations.iter().min_by_key(|a| (a.style, a.range.start))
+    }
+}
+
+impl<FileId: Clone> Diagnostic<FileId> {
+    /// Returns an adapter that implements [`Display`] by rendering this diagnostic without colors,
+    /// so that it can be used in `format!`, `panic!`, or error messages.
+    ///
+    /// ```
+    /// use diagnostic_render::diagnostic::{Annotation, Diagnostic};
+    /// use diagnostic_render::file::SimpleFile;
+    ///
+    /// let file = SimpleFile::new("main.rs", "let x = 1;\n");
+    /// let diagnostic = Diagnostic::warning()
+    ///     .with_message("unused variable")
+    ///     .with_annotation(Annotation::primary((), 4..5));
+    ///
+    /// assert_eq!(format!("{}", diagnostic.display(&file)),
+    ///     "warning: unused variable\n --> main.rs:1:5\n1 | let x = 1;\n  |     ^\n");
+    /// ```
+    ///
+    /// [`Display`]: Display
+    pub fn display<'a, F: Files<FileId=FileId>>(&'a self, files: &'a F) -> DisplayDiagnostic<'a, F> {
+        DisplayDiagnostic::new(self, files)
+    }
+
+    /// Checks that the ranges of all annotations and replacements of this diagnostic and its
+    /// sub-diagnostics are valid for the source code of their files.
+    ///
+    /// This fails with [`Error::InvalidRange`] for ranges that end before they start,
+    /// [`Error::IndexTooLarge`] for ranges extending past the end of the file, and
+    /// [`Error::InvalidCharBoundary`] for ranges starting or ending inside a UTF-8 code point.
+    ///
+    /// [`Error::InvalidRange`]: Error::InvalidRange
+    /// [`Error::IndexTooLarge`]: Error::IndexTooLarge
+    /// [`Error::InvalidCharBoundary`]: Error::InvalidCharBoundary
+    pub fn validated<F: Files<FileId=FileId>>(self, files: &F) -> Result<Self, Error> {
+        self.map_ranges(files, &mut |source, range| {
+            if range.start > range.end {
+                return Err(Error::InvalidRange { start: range.start, end: range.end });
+            }
+
+            for index in [range.start, range.end] {
+                if index > source.len() {
+                    return Err(Error::IndexTooLarge { given: index, max: source.len() });
+                } else if !source.is_char_boundary(index) {
+                    return Err(Error::InvalidCharBoundary { given: index });
+                }
+            }
+
+            Ok(range)
+        })
+    }
+
+    /// Clamps the ranges of all annotations and replacements of this diagnostic and its
+    /// sub-diagnostics, so that they are valid for the source code of their files.
+    ///
+    /// Reversed ranges are swapped, indices past the end of the file are moved to the end,
+    /// and indices inside a UTF-8 code point are moved to the start of that code point.
+    /// This only fails if the source code of a file can't be accessed.
+    pub fn clamped<F: Files<FileId=FileId>>(self, files: &F) -> Result<Self, Error> {
+        self.map_ranges(files, &mut |source, range| {
+            let clamp = |mut index: usize| {
+                index = index.min(source.len());
+
+                while !source.is_char_boundary(index) {
+                    index -= 1;
+                }
+
+                index
+            };
+
+            Ok(clamp(range.start.min(range.end))..clamp(range.start.max(range.end)))
+        })
+    }
+
+    /// Replaces the ranges of all annotations and replacements of this diagnostic and its
+    /// sub-diagnostics, given the source code of their file.
+    fn map_ranges<F: Files<FileId=FileId>>(mut self, files: &F,
+                                           f: &mut impl FnMut(&str, Range<usize>) -> Result<Range<usize>, Error>) -> Result<Self, Error> {
+        for annotation in self.annotations.iter_mut() {
+            annotation.range = f(&files.source(annotation.file_id.clone())?, annotation.range.clone())?;
+        }
+
+        for replacement in self.suggestions.iter_mut().flat_map(|s| s.replacements.iter_mut()) {
+            replacement.range = f(&files.source(replacement.file_id.clone())?, replacement.range.clone())?;
+        }
+
+        self.sub_diagnostics = self.sub_diagnostics.into_iter()
+            .map(|sub_diagnostic| sub_diagnostic.map_ranges(files, f))
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+}
+
+/// A mistake in a diagnostic, found by [`Diagnostic::check`].
+///
+/// [`Diagnostic::check`]: Diagnostic::check
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationProblem {
+    /// The message of a diagnostic is empty, and it doesn't only count suppressed diagnostics.
+    EmptyMessage,
+    /// The range of an annotation or replacement ends before it starts.
+    ReversedRange {
+        /// The start of the range.
+        start: usize,
+        /// The end of the range.
+        end: usize,
+    },
+    /// The label of an annotation contains a control character other than a line break or tab,
+    /// which would break the layout of the rendered output.
+    ControlCharacter {
+        /// The label containing the character.
+        label: String,
+        /// The first control character in the label.
+        character: char,
+    },
+}
+
+impl Display for ValidationProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationProblem::EmptyMessage => write!(f, "empty message"),
+            ValidationProblem::ReversedRange { start, end } => write!(f, "range {}..{} ends before it starts", start, end),
+            ValidationProblem::ControlCharacter { label, character } => write!(f, "label {:?} contains control character {:?}", label, character),
+        }
+    }
+}
+
+/// The error returned when a diagnostic has [problems].
+///
+/// [problems]: ValidationProblem
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    /// All problems found in the diagnostic and its sub-diagnostics. This is never empty.
+    pub problems: Vec<ValidationProblem>,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid diagnostic: ")?;
+
+        for (i, problem) in self.problems.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+
+            write!(f, "{}", problem)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Builds a [`Diagnostic`], and checks it for mistakes before it reaches the renderer.
+///
+/// ```
+/// use diagnostic_render::diagnostic::{Annotation, DiagnosticBuilder, Severity, ValidationProblem};
+///
+/// let result = DiagnosticBuilder::new(Severity::Error)
+///     .with_annotation(Annotation::primary((), 13..11))
+///     .build();
+///
+/// assert_eq!(result.unwrap_err().problems, vec![
+///     ValidationProblem::EmptyMessage,
+///     ValidationProblem::ReversedRange { start: 13, end: 11 },
+/// ]);
+/// ```
+///
+/// [`Diagnostic`]: Diagnostic
+#[derive(Clone, Debug)]
+pub struct DiagnosticBuilder<FileId> {
+    diagnostic: Diagnostic<FileId>,
+}
+
+impl<FileId> DiagnosticBuilder<FileId> {
+    /// Create a new builder for a diagnostic with a severity.
+    pub fn new(severity: Severity) -> Self {
+        DiagnosticBuilder {
+            diagnostic: Diagnostic::new(severity),
+        }
+    }
+
+    /// Set the name shown instead of the name of the severity of the diagnostic.
+    pub fn with_severity_name<N: ToString>(self, severity_name: N) -> Self {
+        self.map(|d| d.with_severity_name(severity_name))
+    }
+
+    /// Set the name or code of the diagnostic.
+    pub fn with_name<M: ToString>(self, name: M) -> Self {
+        self.map(|d| d.with_name(name))
+    }
+
+    /// Set the URL to documentation about the diagnostic.
+    pub fn with_url<U: ToString>(self, url: U) -> Self {
+        self.map(|d| d.with_url(url))
+    }
+
+    /// Set the message of the diagnostic.
+    pub fn with_message<M: ToString>(self, message: M) -> Self {
+        self.map(|d| d.with_message(message))
+    }
+
+    /// Add a named argument for the message, labels and notes of the diagnostic.
+    pub fn with_arg<N: ToString, V: ToString>(self, name: N, value: V) -> Self {
+        self.map(|d| d.with_arg(name, value))
+    }
+
+    /// Add an entry to the metadata of the diagnostic.
+    pub fn with_metadata<K: ToString, V: ToString>(self, key: K, value: V) -> Self {
+        self.map(|d| d.with_metadata(key, value))
+    }
+
+    /// Add an annotation to the diagnostic.
+    pub fn with_annotation(self, annotation: Annotation<FileId>) -> Self {
+        self.map(|d| d.with_annotation(annotation))
+    }
+
+    /// Add some annotations to the diagnostic.
+    pub fn with_annotations(self, annotations: Vec<Annotation<FileId>>) -> Self {
+        self.map(|d| d.with_annotations(annotations))
+    }
+
+    /// Add a note to the diagnostic.
+    pub fn with_note(self, note: Note) -> Self {
+        self.map(|d| d.with_note(note))
+    }
+
+    /// Add some notes to the diagnostic.
+    pub fn with_notes(self, notes: Vec<Note>) -> Self {
+        self.map(|d| d.with_notes(notes))
+    }
+
+    /// Add a suggestion to the diagnostic.
+    pub fn with_suggestion(self, suggestion: Suggestion<FileId>) -> Self {
+        self.map(|d| d.with_suggestion(suggestion))
+    }
+
+    /// Add a sub-diagnostic to the diagnostic. It is checked together with the diagnostic.
+    pub fn with_sub_diagnostic(self, sub_diagnostic: Diagnostic<FileId>) -> Self {
+        self.map(|d| d.with_sub_diagnostic(sub_diagnostic))
+    }
+
+    /// Sets the number of suppressed diagnostics.
+    pub fn with_suppressed_count(self, suppressed_count: u32) -> Self {
+        self.map(|d| d.with_suppressed_count(suppressed_count))
+    }
+
+    /// Sets the number of duplicates that were merged into this diagnostic.
+    pub fn with_duplicate_count(self, duplicate_count: u32) -> Self {
+        self.map(|d| d.with_duplicate_count(duplicate_count))
+    }
+
+    fn map(mut self, f: impl FnOnce(Diagnostic<FileId>) -> Diagnostic<FileId>) -> Self {
+        self.diagnostic = f(self.diagnostic);
+        self
+    }
+
+    /// Checks the diagnostic and returns it, or returns all problems found in it.
+    ///
+    /// A diagnostic has to have a message, unless it only counts suppressed diagnostics.
+    /// The ranges of its annotations and replacements must not end before they start, and the
+    /// labels of its annotations must not contain control characters other than line breaks
+    /// and tabs. The same applies to all sub-diagnostics.
+    pub fn build(self) -> Result<Diagnostic<FileId>, ValidationError> {
+        self.diagnostic.check()?;
+        Ok(self.diagnostic)
+    }
+}
+
+/// Merges diagnostics with the same severity, name, message, and annotation ranges,
+/// keeping the first one of them.
+///
+/// The number of duplicates merged into a diagnostic is added to its [`Diagnostic::duplicate_count`],
+/// which the renderer shows as a note like `(message repeated 3 times)`, counting all occurrences.
+/// The order of the remaining diagnostics is not changed.
+///
+/// [`Diagnostic::duplicate_count`]: Diagnostic::duplicate_count
+pub fn deduplicate<FileId: Clone + Ord>(diagnostics: Vec<Diagnostic<FileId>>) -> Vec<Diagnostic<FileId>> {
+    let mut seen: BTreeMap<_, usize> = BTreeMap::new();
+    let mut result: Vec<Diagnostic<FileId>> = Vec::with_capacity(diagnostics.len());
+
+    for diagnostic in diagnostics.into_iter() {
+        let key = (diagnostic.severity, diagnostic.name.clone(), diagnostic.message.clone(),
+            diagnostic.annotations.iter().map(|a| (a.file_id.clone(), a.range.start, a.range.end)).collect::<Vec<_>>());
+
+        match seen.get(&key) {
+            Some(&index) => result[index].duplicate_count += diagnostic.duplicate_count + 1,
+            None => {
+                seen.insert(key, result.len());
+                result.push(diagnostic);
+            },
+        }
+    }
+
+    result
+}
+
+/// Sorts diagnostics by the file of their primary annotation, the line and byte index that
+/// annotation starts at, and then by severity, from highest to lowest.
+///
+/// Diagnostics without annotations come last. The sort is stable, so diagnostics that are
+/// equal in all of these come in their original order.
+pub fn sort<F: Files>(diagnostics: &mut [Diagnostic<F::FileId>], files: &F) -> Result<(), Error> {
+    let keys = diagnostics.iter().map(|d| {
+        let location = d.primary_annotation()
+            .map(|a| Ok::<_, Error>((a.file_id.clone(), files.line_index(a.file_id.clone(), a.range.start)?, a.range.start)))
+            .transpose()?;
+        Ok((location.is_none(), location, std::cmp::Reverse(d.severity)))
+    }).collect::<Result<Vec<_>, Error>>()?;
+
+    // The index of the diagnostic that has to be moved to every position
+    let mut order = (0..diagnostics.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+
+    for i in 0..order.len() {
+        // Diagnostics before this position have already been swapped away from their
+        // original index, so follow the swaps to where the diagnostic is now
+        let mut j = order[i];
+
+        while j < i {
+            j = order[j];
+        }
+
+        diagnostics.swap(i, j);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use crate::file::{SimpleFile, SimpleFiles};
+    use super::*;
+
+    #[test]
+    fn validated() {
+        let file = SimpleFile::new("main.test", "let 🗻 = 23;\n");
+        let diagnostic = |range: Range<usize>| Diagnostic::error()
+            .with_annotation(Annotation::primary((), 0..3))
+            .with_sub_diagnostic(Diagnostic::note().with_annotation(Annotation::secondary((), range)));
+
+        assert_eq!(diagnostic(4..8).validated(&file).unwrap(), diagnostic(4..8));
+        assert_eq!(diagnostic(15..15).validated(&file).unwrap(), diagnostic(15..15));
+        assert!(matches!(diagnostic(Range { start: 8, end: 4 }).validated(&file), Err(Error::InvalidRange { start: 8, end: 4 })));
+        assert!(matches!(diagnostic(4..20).validated(&file), Err(Error::IndexTooLarge { given: 20, max: 15 })));
+        assert!(matches!(diagnostic(5..8).validated(&file), Err(Error::InvalidCharBoundary { given: 5 })));
+    }
+
+    #[test]
+    fn from_error() {
+        #[derive(Debug)]
+        struct ConfigError(std::io::Error);
+
+        impl Display for ConfigError {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(f, "failed to read config")
+            }
+        }
+
+        impl std::error::Error for ConfigError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let error = ConfigError(std::io::Error::other(Error::FileMissing));
+        let diagnostic: Diagnostic<()> = Diagnostic::from_error(&error);
+
+        assert_eq!(diagnostic, Diagnostic::error()
+            .with_message("failed to read config")
+            .with_note(Note::note(Error::FileMissing).with_prefix("caused by")));
+        assert_eq!(diagnostic.display(&SimpleFile::new("main.test", "")).to_string(),
+            "error: failed to read config\n = caused by: file missing\n");
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(DiagnosticBuilder::new(Severity::Error)
+            .with_message("Mismatched types")
+            .with_annotation(Annotation::primary((), 11..13).with_label("expected\n\t`String`"))
+            .build(), Ok(Diagnostic::error()
+            .with_message("Mismatched types")
+            .with_annotation(Annotation::primary((), 11..13).with_label("expected\n\t`String`"))));
+        assert_eq!(DiagnosticBuilder::<()>::new(Severity::Error).with_suppressed_count(3).build(),
+            Ok(Diagnostic::error().with_suppressed_count(3)));
+
+        let error = DiagnosticBuilder::new(Severity::Error)
+            .with_message("Mismatched types")
+            .with_annotation(Annotation::primary((), 11..13).with_label("expected \x1b[31m`String`"))
+            .with_suggestion(Suggestion::new("Use a string").with_replacement(Replacement::new((), Range { start: 13, end: 11 }, "\"23\"")))
+            .with_sub_diagnostic(Diagnostic::note())
+            .build().unwrap_err();
+
+        assert_eq!(error.problems, vec![
+            ValidationProblem::ReversedRange { start: 13, end: 11 },
+            ValidationProblem::ControlCharacter { label: String::from("expected \x1b[31m`String`"), character: '\x1b' },
+            ValidationProblem::EmptyMessage,
+        ]);
+        assert_eq!(error.to_string(), "invalid diagnostic: range 13..11 ends before it starts; \
+            label \"expected \\u{1b}[31m`String`\" contains control character '\\u{1b}'; empty message");
+    }
+
+    #[test]
+    fn common_affixes() {
+        assert_eq!(ExpectedFound::new("Vec<String>", "Vec<i32>").common_affixes(), (4, 1));
+        assert_eq!(ExpectedFound::new("&str", "&&str").common_affixes(), (1, 3));
+        assert_eq!(ExpectedFound::new("(u8, u8)", "(u8, u8, u8)").common_affixes(), (7, 1));
+        assert_eq!(ExpectedFound::new("Option<ä>", "Option<ö>").common_affixes(), (7, 1));
+        assert_eq!(ExpectedFound::new("String", "String").common_affixes(), (6, 0));
+    }
+
+    #[test]
+    fn deduplicate() {
+        let diagnostic = |range: Range<usize>| Diagnostic::error()
+            .with_message("Mismatched types")
+            .with_annotation(Annotation::primary((), range));
+
+        assert_eq!(super::deduplicate(vec![diagnostic(0..3), diagnostic(4..8), diagnostic(0..3), diagnostic(0..3)]), vec![
+            diagnostic(0..3).with_duplicate_count(2),
+            diagnostic(4..8),
+        ]);
+        assert_eq!(super::deduplicate(vec![diagnostic(0..3), diagnostic(0..3).with_name("E0308")]),
+            vec![diagnostic(0..3), diagnostic(0..3).with_name("E0308")]);
+    }
+
+    #[test]
+    fn sort() {
+        let mut files = SimpleFiles::new();
+        let main = files.add("main.test", "let main = 23;\nlet other = main;\n");
+        let other = files.add("other.test", "let value = 42;\n");
+
+        let mut diagnostics = vec![
+            Diagnostic::note().with_message("No annotations"),
+            Diagnostic::warning().with_annotation(Annotation::primary(other, 4..9)),
+            Diagnostic::warning().with_annotation(Annotation::primary(main, 19..24)),
+            Diagnostic::error().with_annotation(Annotation::primary(main, 19..24)),
+            Diagnostic::error().with_annotation(Annotation::secondary(main, 0..3))
+                .with_annotation(Annotation::primary(main, 4..8)),
+        ];
+        super::sort(&mut diagnostics, &files).unwrap();
+
+        assert_eq!(diagnostics, vec![
+            Diagnostic::error().with_annotation(Annotation::secondary(main, 0..3))
+                .with_annotation(Annotation::primary(main, 4..8)),
+            Diagnostic::error().with_annotation(Annotation::primary(main, 19..24)),
+            Diagnostic::warning().with_annotation(Annotation::primary(main, 19..24)),
+            Diagnostic::warning().with_annotation(Annotation::primary(other, 4..9)),
+            Diagnostic::note().with_message("No annotations"),
+        ]);
+    }
+
+    #[test]
+    fn clamped() {
+        let file = SimpleFile::new("main.test", "let 🗻 = 23;\n");
+        let suggestion = |range: Range<usize>| Diagnostic::error()
+            .with_suggestion(Suggestion::new("replace it").with_replacement(Replacement::new((), range, "x")));
+
+        assert_eq!(suggestion(4..8).clamped(&file).unwrap(), suggestion(4..8));
+        assert_eq!(suggestion(Range { start: 8, end: 4 }).clamped(&file).unwrap(), suggestion(4..8));
+        assert_eq!(suggestion(10..20).clamped(&file).unwrap(), suggestion(10..15));
+        assert_eq!(suggestion(5..7).clamped(&file).unwrap(), suggestion(4..4));
+    }
 }
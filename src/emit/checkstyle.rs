@@ -0,0 +1,157 @@
+//! An emitter for the [Checkstyle] XML format.
+//!
+//! Checkstyle reports are read by Jenkins and many code review bots.
+//!
+//! [Checkstyle]: https://checkstyle.org/
+
+use std::fmt::Debug;
+use std::io::Write;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::file::Files;
+use super::{annotation_locations, escape_xml, impl_emitter, Result};
+
+/// Writes diagnostics as a Checkstyle XML report.
+///
+/// ```text
+/// <?xml version="1.0" encoding="UTF-8"?>
+/// <checkstyle version="4.3">
+///   <file name="src/main.rs">
+///     <error line="2" column="14" severity="error" message="Mismatched types" source="E0308"/>
+///   </file>
+/// </checkstyle>
+/// ```
+///
+/// Diagnostics are grouped by the file of their [primary annotation], in the order these files
+/// first appear in. Diagnostics without a primary annotation are left out, as every error in
+/// a Checkstyle report belongs to a file. Notes are appended to the message on separate lines.
+///
+/// [primary annotation]: Diagnostic::primary_annotation
+#[derive(Debug)]
+pub struct CheckstyleEmitter<'w, W, F> {
+    f: &'w mut W, files: F,
+}
+
+impl<'w, W, F> CheckstyleEmitter<'w, W, F> {
+    /// Creates a new Checkstyle emitter.
+    pub fn new(f: &'w mut W, files: F) -> Self {
+        CheckstyleEmitter {
+            f, files,
+        }
+    }
+}
+
+impl<'w, W: Write, FileId, F: Files<FileId=FileId>> CheckstyleEmitter<'w, W, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Writes the Checkstyle report containing the given diagnostics.
+    pub fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        let mut files: Vec<(&FileId, Vec<&Diagnostic<FileId>>)> = Vec::new();
+
+        for diagnostic in diagnostics.iter() {
+            let Some(annotation) = diagnostic.primary_annotation() else {
+                continue;
+            };
+
+            match files.iter_mut().find(|(file, _)| **file == annotation.file_id) {
+                Some((_, diagnostics)) => diagnostics.push(diagnostic),
+                None => files.push((&annotation.file_id, vec![diagnostic])),
+            }
+        }
+
+        writeln!(self.f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(self.f, r#"<checkstyle version="4.3">"#)?;
+
+        for (file, diagnostics) in files.into_iter() {
+            writeln!(self.f, r#"  <file name="{}">"#, escape_xml(self.files.name(file.clone())?, true))?;
+
+            for diagnostic in diagnostics.into_iter() {
+                self.emit_diagnostic(diagnostic)?;
+            }
+
+            writeln!(self.f, "  </file>")?;
+        }
+
+        writeln!(self.f, "</checkstyle>")?;
+        Ok(())
+    }
+
+    fn emit_diagnostic(&mut self, diagnostic: &Diagnostic<FileId>) -> Result {
+        let Some(annotation) = diagnostic.primary_annotation() else {
+            return Ok(());
+        };
+
+        let (start, _) = annotation_locations(&self.files, annotation)?;
+        let mut message = diagnostic.message.clone();
+
+        for note in diagnostic.notes.iter() {
+            message.push('\n');
+            message.push_str(&format!("{}: {}", note.prefix_or_severity(), note.message));
+        }
+
+        write!(self.f, r#"    <error line="{}" column="{}" severity="{}" message="{}""#,
+            start.line_number, start.column_number, severity(diagnostic.severity), escape_xml(&message, true))?;
+
+        if let Some(name) = diagnostic.name.as_ref() {
+            write!(self.f, r#" source="{}""#, escape_xml(name, true))?;
+        }
+
+        writeln!(self.f, "/>")?;
+        Ok(())
+    }
+}
+
+impl_emitter!(CheckstyleEmitter);
+
+/// Returns the Checkstyle severity corresponding to a severity.
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note | Severity::Help => "info",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{Annotation, AnnotationStyle, Note};
+    use crate::file::SimpleFiles;
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let mut buf = Vec::new();
+        let mut files = SimpleFiles::new();
+        let main = files.add("src/main.rs", "let main = 23;\nsomething += 3.0;\n");
+        let other = files.add("src/<other>.rs", "let other = 23;\n");
+
+        let diagnostics = vec![
+            Diagnostic::new(Severity::Error)
+                .with_name("E0308")
+                .with_message("Mismatched types")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 11..13))
+                .with_note(Note::help("Change \"23\" to something else")),
+            Diagnostic::new(Severity::Warning)
+                .with_message("Unused variable")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, other, 4..9)),
+            Diagnostic::new(Severity::Note)
+                .with_message("Without annotations"),
+            Diagnostic::new(Severity::Help)
+                .with_message("Something & something else")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 15..24)),
+        ];
+        let mut emitter = CheckstyleEmitter::new(&mut buf, &files);
+        emitter.emit(&diagnostics).unwrap();
+
+        insta::assert_snapshot!(String::from_utf8(buf).unwrap(), @r###"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <checkstyle version="4.3">
+          <file name="src/main.rs">
+            <error line="1" column="12" severity="error" message="Mismatched types&#10;help: Change &quot;23&quot; to something else" source="E0308"/>
+            <error line="2" column="1" severity="info" message="Something &amp; something else"/>
+          </file>
+          <file name="src/&lt;other&gt;.rs">
+            <error line="1" column="5" severity="warning" message="Unused variable"/>
+          </file>
+        </checkstyle>
+        "###);
+    }
+}
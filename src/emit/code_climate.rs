@@ -0,0 +1,176 @@
+//! An emitter for the [Code Climate] issue format, which is also used by the
+//! [GitLab Code Quality] report.
+//!
+//! [Code Climate]: https://github.com/codeclimate/platform/blob/master/spec/analyzers/SPEC.md
+//! [GitLab Code Quality]: https://docs.gitlab.com/ee/ci/testing/code_quality.html
+
+use std::fmt::Debug;
+use std::io::Write;
+use serde_json::{json, Value};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::file::{Error, Files};
+use super::{annotation_locations, impl_emitter, Result};
+
+/// Writes diagnostics as a JSON array of Code Climate issues.
+///
+/// The location of an issue is the [primary annotation] of the diagnostic, so diagnostics without
+/// one are left out. The check name is the [name] of the diagnostic, or its severity if it
+/// doesn't have one. Notes are appended to the description on separate lines.
+///
+/// The fingerprint identifying an issue across runs is a hash of the check name, the path,
+/// the message and the annotated source code, so it doesn't change when the code is moved
+/// to other lines.
+///
+/// [primary annotation]: Diagnostic::primary_annotation
+/// [name]: Diagnostic::name
+#[derive(Debug)]
+pub struct CodeClimateEmitter<'w, W, F> {
+    f: &'w mut W, files: F,
+}
+
+impl<'w, W, F> CodeClimateEmitter<'w, W, F> {
+    /// Creates a new Code Climate emitter.
+    pub fn new(f: &'w mut W, files: F) -> Self {
+        CodeClimateEmitter {
+            f, files,
+        }
+    }
+}
+
+impl<'w, W: Write, FileId, F: Files<FileId=FileId>> CodeClimateEmitter<'w, W, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Writes the array of issues for the given diagnostics.
+    pub fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        let issues = self.to_value(diagnostics)?;
+
+        serde_json::to_writer_pretty(&mut *self.f, &issues).map_err(std::io::Error::from)?;
+        writeln!(self.f)?;
+        Ok(())
+    }
+
+    /// Converts the given diagnostics into an array of issues, without writing it.
+    pub fn to_value(&self, diagnostics: &[Diagnostic<FileId>]) -> std::result::Result<Value, Error> {
+        let mut issues = Vec::with_capacity(diagnostics.len());
+
+        for diagnostic in diagnostics.iter() {
+            if let Some(issue) = self.issue(diagnostic)? {
+                issues.push(issue);
+            }
+        }
+
+        Ok(Value::Array(issues))
+    }
+
+    fn issue(&self, diagnostic: &Diagnostic<FileId>) -> std::result::Result<Option<Value>, Error> {
+        let Some(annotation) = diagnostic.primary_annotation() else {
+            return Ok(None);
+        };
+
+        let (start, end) = annotation_locations(&self.files, annotation)?;
+        let path = self.files.name(annotation.file_id.clone())?;
+        let check_name = diagnostic.name.clone().unwrap_or_else(|| diagnostic.severity.to_string());
+
+        let mut description = diagnostic.message.clone();
+
+        for note in diagnostic.notes.iter() {
+            description.push('\n');
+            description.push_str(&format!("{}: {}", note.prefix_or_severity(), note.message));
+        }
+
+        let source = self.files.source(annotation.file_id.clone())?;
+        let annotated = source.get(annotation.range.clone()).unwrap_or_default();
+        let fingerprint = fingerprint(&[&check_name, path, &diagnostic.message, annotated]);
+
+        Ok(Some(json!({
+            "type": "issue",
+            "check_name": check_name,
+            "description": description,
+            "severity": severity(diagnostic.severity),
+            "fingerprint": fingerprint,
+            "location": {
+                "path": path,
+                "lines": {
+                    "begin": start.line_number,
+                    "end": end.line_number,
+                },
+            },
+        })))
+    }
+}
+
+impl_emitter!(CodeClimateEmitter);
+
+/// Returns the Code Climate severity corresponding to a severity.
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug => "critical",
+        Severity::Error => "major",
+        Severity::Warning => "minor",
+        Severity::Note | Severity::Help => "info",
+    }
+}
+
+/// Returns a 64-bit FNV-1a hash of the given parts as a hexadecimal string. This is used
+/// instead of the hasher of the standard library, which may change between Rust versions.
+fn fingerprint(parts: &[&str]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let mut hash = OFFSET_BASIS;
+
+    for part in parts.iter() {
+        // Parts are separated by a zero byte, so that moving text between them changes the hash
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{Annotation, AnnotationStyle, Note};
+    use crate::file::SimpleFile;
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let mut buf = Vec::new();
+        let file = SimpleFile::new("src/main.rs", "let main = 23;\nsomething += 3.0;\n");
+        let diagnostics = vec![
+            Diagnostic::new(Severity::Error)
+                .with_name("E0308")
+                .with_message("Mismatched types")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..24))
+                .with_note(Note::help("Change something")),
+            Diagnostic::new(Severity::Warning)
+                .with_message("Unused variable")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)),
+            Diagnostic::new(Severity::Note)
+                .with_message("Without annotations"),
+        ];
+        let mut emitter = CodeClimateEmitter::new(&mut buf, file);
+        emitter.emit(&diagnostics).unwrap();
+
+        insta::assert_snapshot!(String::from_utf8(buf).unwrap());
+    }
+
+    #[test]
+    fn fingerprint() {
+        let file = SimpleFile::new("src/main.rs", "let main = 23;\n\nlet main = 23;\n");
+        let mut buf = Vec::new();
+        let emitter = CodeClimateEmitter::new(&mut buf, &file);
+        let issue = |range| Diagnostic::new(Severity::Warning)
+            .with_message("Unused variable")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), range));
+
+        let issues = emitter.to_value(&[issue(4..8), issue(20..24), issue(0..3)]).unwrap();
+
+        assert_eq!(issues[0]["fingerprint"], issues[1]["fingerprint"]);
+        assert_ne!(issues[0]["fingerprint"], issues[2]["fingerprint"]);
+        assert_eq!(super::fingerprint(&["a", "bc"]).len(), 16);
+        assert_ne!(super::fingerprint(&["a", "bc"]), super::fingerprint(&["ab", "c"]));
+    }
+}
@@ -0,0 +1,132 @@
+//! An emitter for [GitHub Actions workflow commands].
+//!
+//! When printed in a GitHub Actions workflow run, these create annotations
+//! that are shown directly on the lines of code in pull requests.
+//!
+//! [GitHub Actions workflow commands]: https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions
+
+use std::fmt::Debug;
+use std::io::Write;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::file::Files;
+use super::{annotation_locations, impl_emitter, Result};
+
+/// Writes diagnostics as GitHub Actions workflow commands, one line per diagnostic.
+///
+/// ```text
+/// ::error file=src/main.rs,line=2,col=14,endLine=2,endColumn=17,title=E0308::Mismatched types
+/// ```
+///
+/// The location of a command is the location of the [primary annotation] of the diagnostic.
+/// Notes are appended to the message on separate lines.
+///
+/// [primary annotation]: Diagnostic::primary_annotation
+#[derive(Debug)]
+pub struct GithubEmitter<'w, W, F> {
+    f: &'w mut W, files: F,
+}
+
+impl<'w, W, F> GithubEmitter<'w, W, F> {
+    /// Creates a new GitHub Actions emitter.
+    pub fn new(f: &'w mut W, files: F) -> Self {
+        GithubEmitter {
+            f, files,
+        }
+    }
+}
+
+impl<'w, W: Write, FileId, F: Files<FileId=FileId>> GithubEmitter<'w, W, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Writes a workflow command for each of the given diagnostics.
+    pub fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        for diagnostic in diagnostics.iter() {
+            self.emit_diagnostic(diagnostic)?;
+        }
+
+        Ok(())
+    }
+
+    fn emit_diagnostic(&mut self, diagnostic: &Diagnostic<FileId>) -> Result {
+        let mut properties = Vec::new();
+
+        if let Some(annotation) = diagnostic.primary_annotation() {
+            let (start, end) = annotation_locations(&self.files, annotation)?;
+
+            properties.push(format!("file={}", escape_property(self.files.name(annotation.file_id.clone())?)));
+            properties.push(format!("line={}", start.line_number));
+            properties.push(format!("col={}", start.column_number));
+            properties.push(format!("endLine={}", end.line_number));
+            properties.push(format!("endColumn={}", end.column_number));
+        }
+
+        if let Some(name) = diagnostic.name.as_ref() {
+            properties.push(format!("title={}", escape_property(name)));
+        }
+
+        let mut message = diagnostic.message.clone();
+
+        for note in diagnostic.notes.iter() {
+            message.push('\n');
+            message.push_str(&format!("{}: {}", note.prefix_or_severity(), note.message));
+        }
+
+        write!(self.f, "::{}", command(diagnostic.severity))?;
+
+        if !properties.is_empty() {
+            write!(self.f, " {}", properties.join(","))?;
+        }
+
+        writeln!(self.f, "::{}", escape_data(&message))?;
+        Ok(())
+    }
+}
+
+impl_emitter!(GithubEmitter);
+
+/// Returns the workflow command corresponding to a severity.
+fn command(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note | Severity::Help => "notice",
+    }
+}
+
+/// Escapes the message of a workflow command.
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escapes a property value of a workflow command.
+fn escape_property(property: &str) -> String {
+    escape_data(property).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{Annotation, AnnotationStyle, Note};
+    use crate::file::SimpleFile;
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let mut buf = Vec::new();
+        let file = SimpleFile::new("src/test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+        let mut emitter = GithubEmitter::new(&mut buf, file);
+        emitter.emit(&[
+            Diagnostic::new(Severity::Error)
+                .with_name("E0308")
+                .with_message("Mismatched types: 100% wrong")
+                .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8))
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 28..31))
+                .with_note(Note::help("Change something")),
+            Diagnostic::new(Severity::Note)
+                .with_message("No location"),
+        ]).unwrap();
+
+        insta::assert_snapshot!(String::from_utf8(buf).unwrap(), @r###"
+        ::error file=src/test_file.test,line=2,col=14,endLine=2,endColumn=17,title=E0308::Mismatched types: 100%25 wrong%0Ahelp: Change something
+        ::notice::No location
+        "###);
+    }
+}
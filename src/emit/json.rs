@@ -0,0 +1,186 @@
+//! An emitter for newline-delimited JSON, with one object per diagnostic.
+//!
+//! Every diagnostic is written and flushed as soon as it is emitted, so other programs
+//! can read diagnostics incrementally from a pipe.
+
+use std::fmt::Debug;
+use std::io::Write;
+use serde_json::{json, Map, Value};
+use crate::diagnostic::{Annotation, AnnotationStyle, Applicability, Diagnostic, Note, Suggestion};
+use crate::file::{Error, Files};
+use super::{annotation_locations, impl_emitter, Result};
+
+/// Writes diagnostics as newline-delimited JSON, one object per line.
+///
+/// ```text
+/// {"annotations":[...],"message":"Mismatched types","name":"E0308","notes":[],"severity":"error",...}
+/// ```
+///
+/// Every object contains the severity, name, URL, message, annotations, notes, suggestions,
+/// sub-diagnostics and metadata of a diagnostic. Annotations include both their byte range
+/// and the line and column numbers of its start and end.
+#[derive(Debug)]
+pub struct JsonEmitter<'w, W, F> {
+    f: &'w mut W, files: F,
+}
+
+impl<'w, W, F> JsonEmitter<'w, W, F> {
+    /// Creates a new newline-delimited JSON emitter.
+    pub fn new(f: &'w mut W, files: F) -> Self {
+        JsonEmitter {
+            f, files,
+        }
+    }
+}
+
+impl<'w, W: Write, FileId, F: Files<FileId=FileId>> JsonEmitter<'w, W, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Writes a line for each of the given diagnostics, flushing the writer after every line.
+    pub fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        for diagnostic in diagnostics.iter() {
+            self.emit_one(diagnostic)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a line for a single diagnostic, and flushes the writer.
+    pub fn emit_one(&mut self, diagnostic: &Diagnostic<FileId>) -> Result {
+        let value = self.to_value(diagnostic)?;
+
+        serde_json::to_writer(&mut *self.f, &value).map_err(std::io::Error::from)?;
+        writeln!(self.f)?;
+        self.f.flush()?;
+        Ok(())
+    }
+
+    /// Converts a diagnostic into the JSON object written by this emitter, without writing it.
+    pub fn to_value(&self, diagnostic: &Diagnostic<FileId>) -> std::result::Result<Value, Error> {
+        let mut object = Map::new();
+        object.insert(String::from("severity"), json!(diagnostic.severity.to_string()));
+
+        if let Some(name) = diagnostic.name.as_ref() {
+            object.insert(String::from("name"), json!(name));
+        }
+
+        if let Some(url) = diagnostic.url.as_ref() {
+            object.insert(String::from("url"), json!(url));
+        }
+
+        object.insert(String::from("message"), json!(diagnostic.message));
+        object.insert(String::from("annotations"), diagnostic.annotations.iter()
+            .map(|annotation| self.annotation(annotation))
+            .collect::<std::result::Result<Value, Error>>()?);
+        object.insert(String::from("notes"), diagnostic.notes.iter().map(note).collect());
+        object.insert(String::from("suggestions"), diagnostic.suggestions.iter()
+            .map(|suggestion| self.suggestion(suggestion))
+            .collect::<std::result::Result<Value, Error>>()?);
+        object.insert(String::from("sub_diagnostics"), diagnostic.sub_diagnostics.iter()
+            .map(|sub_diagnostic| self.to_value(sub_diagnostic))
+            .collect::<std::result::Result<Value, Error>>()?);
+
+        if diagnostic.suppressed_count > 0 {
+            object.insert(String::from("suppressed_count"), json!(diagnostic.suppressed_count));
+        }
+
+        if diagnostic.duplicate_count > 0 {
+            object.insert(String::from("duplicate_count"), json!(diagnostic.duplicate_count));
+        }
+
+        if !diagnostic.metadata.is_empty() {
+            object.insert(String::from("metadata"), json!(diagnostic.metadata));
+        }
+
+        Ok(Value::Object(object))
+    }
+
+    fn annotation(&self, annotation: &Annotation<FileId>) -> std::result::Result<Value, Error> {
+        let (start, end) = annotation_locations(&self.files, annotation)?;
+
+        Ok(json!({
+            "style": match annotation.style {
+                AnnotationStyle::Primary => "primary",
+                AnnotationStyle::Secondary => "secondary",
+            },
+            "file": self.files.name(annotation.file_id.clone())?,
+            "range": { "start": annotation.range.start, "end": annotation.range.end },
+            "start": { "line": start.line_number, "column": start.column_number },
+            "end": { "line": end.line_number, "column": end.column_number },
+            "label": annotation.label,
+        }))
+    }
+
+    fn suggestion(&self, suggestion: &Suggestion<FileId>) -> std::result::Result<Value, Error> {
+        let replacements = suggestion.replacements.iter().map(|replacement| Ok(json!({
+            "file": self.files.name(replacement.file_id.clone())?,
+            "range": { "start": replacement.range.start, "end": replacement.range.end },
+            "text": replacement.text,
+        }))).collect::<std::result::Result<Value, Error>>()?;
+
+        Ok(json!({
+            "message": suggestion.message,
+            "applicability": applicability(suggestion.applicability),
+            "replacements": replacements,
+        }))
+    }
+}
+
+impl_emitter!(JsonEmitter);
+
+fn note(note: &Note) -> Value {
+    let mut object = Map::new();
+    object.insert(String::from("severity"), json!(note.severity.to_string()));
+
+    if let Some(prefix) = note.prefix.as_ref() {
+        object.insert(String::from("prefix"), json!(prefix));
+    }
+
+    object.insert(String::from("message"), json!(note.message));
+
+    if let Some(expected_found) = note.expected_found.as_ref() {
+        object.insert(String::from("expected"), json!(expected_found.expected));
+        object.insert(String::from("found"), json!(expected_found.found));
+    }
+
+    Value::Object(object)
+}
+
+/// Returns the name of an applicability in the JSON output.
+fn applicability(applicability: Applicability) -> &'static str {
+    match applicability {
+        Applicability::MachineApplicable => "machine_applicable",
+        Applicability::MaybeIncorrect => "maybe_incorrect",
+        Applicability::HasPlaceholders => "has_placeholders",
+        Applicability::Unspecified => "unspecified",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{Replacement, Severity};
+    use crate::file::SimpleFile;
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let mut buf = Vec::new();
+        let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\n");
+        let diagnostic = Diagnostic::new(Severity::Error)
+            .with_name("E0308")
+            .with_message("Mismatched types")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)
+                .with_label("expected `String`"))
+            .with_note(Note::help("Change something").with_prefix("hint"))
+            .with_suggestion(Suggestion::new("Use a string")
+                .with_replacement(Replacement::new((), 11..13, "\"23\""))
+                .with_applicability(Applicability::MaybeIncorrect))
+            .with_metadata("category", "types");
+        let mut emitter = JsonEmitter::new(&mut buf, file);
+        emitter.emit(&[diagnostic, Diagnostic::new(Severity::Warning).with_message("Something")]).unwrap();
+
+        insta::assert_snapshot!(String::from_utf8(buf).unwrap(), @r###"
+        {"annotations":[{"end":{"column":14,"line":1},"file":"test_file.test","label":"expected `String`","range":{"end":13,"start":11},"start":{"column":12,"line":1},"style":"primary"}],"message":"Mismatched types","metadata":{"category":"types"},"name":"E0308","notes":[{"message":"Change something","prefix":"hint","severity":"help"}],"severity":"error","sub_diagnostics":[],"suggestions":[{"applicability":"maybe_incorrect","message":"Use a string","replacements":[{"file":"test_file.test","range":{"end":13,"start":11},"text":"\"23\""}]}]}
+        {"annotations":[],"message":"Something","notes":[],"severity":"warning","sub_diagnostics":[],"suggestions":[]}
+        "###);
+    }
+}
@@ -0,0 +1,193 @@
+//! An emitter for [JUnit] XML reports.
+//!
+//! Many CI systems can only visualize test reports in this format, so diagnostics are reported
+//! as failed test cases.
+//!
+//! [JUnit]: https://github.com/testmoapp/junitxml
+
+use std::fmt::Debug;
+use std::io::Write;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::file::Files;
+use crate::render::RenderConfig;
+use crate::render::string::render_to_string;
+use super::{escape_xml, impl_emitter, Result};
+
+/// Writes diagnostics as a JUnit XML report, with a test case for every file.
+///
+/// ```text
+/// <?xml version="1.0" encoding="UTF-8"?>
+/// <testsuites>
+///   <testsuite name="lint" tests="1" failures="1">
+///     <testcase name="src/main.rs" classname="lint">
+///       <failure message="Mismatched types" type="error">error[E0308]: Mismatched types
+///  --> src/main.rs:2:14
+/// ...</failure>
+///     </testcase>
+///   </testsuite>
+/// </testsuites>
+/// ```
+///
+/// Diagnostics are grouped by the file of their [primary annotation], in the order these files
+/// first appear in. Diagnostics without a primary annotation are grouped into a test case named
+/// after the test suite. A test case fails if it contains a warning or a more severe diagnostic;
+/// the message and type of the failure are the message and severity of the most severe one.
+/// The diagnostics of a test case are rendered without colors using the [`RenderConfig`] of the
+/// emitter and included as the body of the failure, or as its standard output if it doesn't fail.
+///
+/// [primary annotation]: Diagnostic::primary_annotation
+/// [`RenderConfig`]: RenderConfig
+#[derive(Debug)]
+pub struct JunitEmitter<'w, W, F> {
+    f: &'w mut W, files: F, suite_name: String, config: RenderConfig,
+}
+
+impl<'w, W, F> JunitEmitter<'w, W, F> {
+    /// Creates a new JUnit emitter. `suite_name` is the name of the test suite, which is usually
+    /// the name of the tool that produced the diagnostics.
+    pub fn new<N: ToString>(f: &'w mut W, files: F, suite_name: N) -> Self {
+        JunitEmitter {
+            f, files, suite_name: suite_name.to_string(), config: RenderConfig::default(),
+        }
+    }
+
+    /// Sets the configuration used to render the diagnostics of a test case.
+    pub fn with_config(mut self, config: RenderConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl<'w, W: Write, FileId, F: Files<FileId=FileId>> JunitEmitter<'w, W, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Writes the JUnit report containing the given diagnostics.
+    pub fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        let mut test_cases: Vec<(Option<&FileId>, Vec<&Diagnostic<FileId>>)> = Vec::new();
+
+        for diagnostic in diagnostics.iter() {
+            let file = diagnostic.primary_annotation().map(|annotation| &annotation.file_id);
+
+            match test_cases.iter_mut().find(|(other, _)| *other == file) {
+                Some((_, diagnostics)) => diagnostics.push(diagnostic),
+                None => test_cases.push((file, vec![diagnostic])),
+            }
+        }
+
+        let failures = test_cases.iter()
+            .filter(|(_, diagnostics)| most_severe(diagnostics).severity >= Severity::Warning)
+            .count();
+        let suite_name = escape_xml(&self.suite_name, true);
+
+        writeln!(self.f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(self.f, "<testsuites>")?;
+        writeln!(self.f, r#"  <testsuite name="{}" tests="{}" failures="{}">"#, suite_name, test_cases.len(), failures)?;
+
+        for (file, diagnostics) in test_cases.into_iter() {
+            let name = match file {
+                Some(file) => escape_xml(self.files.name(file.clone())?, true),
+                None => suite_name.clone(),
+            };
+
+            writeln!(self.f, r#"    <testcase name="{}" classname="{}">"#, name, suite_name)?;
+            self.emit_test_case(&diagnostics)?;
+            writeln!(self.f, "    </testcase>")?;
+        }
+
+        writeln!(self.f, "  </testsuite>")?;
+        writeln!(self.f, "</testsuites>")?;
+        Ok(())
+    }
+
+    fn emit_test_case(&mut self, diagnostics: &[&Diagnostic<FileId>]) -> Result {
+        let output = render_to_string(&self.files, self.config.clone(),
+            diagnostics.iter().map(|&diagnostic| diagnostic.clone()).collect())?;
+        let output = escape_xml(&output, false);
+        let diagnostic = most_severe(diagnostics);
+
+        if diagnostic.severity >= Severity::Warning {
+            writeln!(self.f, r#"      <failure message="{}" type="{}">{}</failure>"#,
+                escape_xml(&diagnostic.message, true), diagnostic.severity, output)?;
+        } else {
+            writeln!(self.f, "      <system-out>{}</system-out>", output)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl_emitter!(JunitEmitter);
+
+/// Returns the first diagnostic with the highest severity. `diagnostics` must not be empty.
+fn most_severe<'d, FileId>(diagnostics: &[&'d Diagnostic<FileId>]) -> &'d Diagnostic<FileId> {
+    diagnostics.iter().copied()
+        .reduce(|most_severe, diagnostic| if diagnostic.severity > most_severe.severity { diagnostic } else { most_severe })
+        .expect("test case without diagnostics")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{Annotation, AnnotationStyle, Note};
+    use crate::file::SimpleFiles;
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let mut buf = Vec::new();
+        let mut files = SimpleFiles::new();
+        let main = files.add("src/main.rs", "let main = 23;\nsomething += 3.0;\n");
+        let other = files.add("src/other.rs", "let other = 23;\n");
+
+        let diagnostics = vec![
+            Diagnostic::new(Severity::Note)
+                .with_message("Something & something else")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 15..24)),
+            Diagnostic::new(Severity::Error)
+                .with_name("E0308")
+                .with_message("Mismatched <types>")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 11..13)
+                    .with_label("expected `String`"))
+                .with_note(Note::help("Change something")),
+            Diagnostic::new(Severity::Help)
+                .with_message("Unused variable")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, other, 4..9)),
+            Diagnostic::new(Severity::Warning)
+                .with_message("Without annotations"),
+        ];
+        let mut emitter = JunitEmitter::new(&mut buf, &files, "test");
+        emitter.emit(&diagnostics).unwrap();
+
+        insta::assert_snapshot!(String::from_utf8(buf).unwrap(), @r###"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <testsuites>
+          <testsuite name="test" tests="3" failures="2">
+            <testcase name="src/main.rs" classname="test">
+              <failure message="Mismatched &lt;types&gt;" type="error">note: Something &amp; something else
+         --&gt; src/main.rs:2:1
+        1 | let main = 23;
+        2 | something += 3.0;
+          | ^^^^^^^^^
+
+        error[E0308]: Mismatched &lt;types&gt;
+         --&gt; src/main.rs:1:12
+        1 | let main = 23;
+          |            ^^ expected `String`
+        2 | something += 3.0;
+          = help: Change something
+        </failure>
+            </testcase>
+            <testcase name="src/other.rs" classname="test">
+              <system-out>help: Unused variable
+         --&gt; src/other.rs:1:5
+        1 | let other = 23;
+          |     ^^^^^
+        </system-out>
+            </testcase>
+            <testcase name="test" classname="test">
+              <failure message="Without annotations" type="warning">warning: Without annotations
+        </failure>
+            </testcase>
+          </testsuite>
+        </testsuites>
+        "###);
+    }
+}
@@ -0,0 +1,249 @@
+//! Emitters for machine-readable output formats.
+//!
+//! Unlike the [`DiagnosticRenderer`], which is intended to be read by humans,
+//! these write diagnostics in formats that are consumed by other tools, like
+//! CI systems and static analysis dashboards.
+//!
+//! All emitters, as well as the [`DiagnosticRenderer`], implement the [`Emitter`] trait,
+//! so the output format can be selected at runtime.
+//!
+//! [`DiagnosticRenderer`]: crate::render::DiagnosticRenderer
+//! [`Emitter`]: Emitter
+
+use std::fmt::Debug;
+use crate::render::style::WriteColor;
+use crate::diagnostic::{Annotation, Diagnostic};
+use crate::file::{Error, Files, Location};
+use crate::render::{DiagnosticRenderer, RenderError};
+use crate::render::color::ColorConfig;
+
+pub mod checkstyle;
+#[cfg(feature = "json")]
+pub mod code_climate;
+pub mod github;
+pub mod junit;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "json")]
+pub mod rdjson;
+#[cfg(feature = "json")]
+pub mod sarif;
+
+/// Result type for methods of emitters.
+type Result = std::result::Result<(), EmitError>;
+
+/// An error returned by an [`Emitter`].
+///
+/// [`Emitter`]: Emitter
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EmitError {
+    /// Accessing a file in the file database, or writing the output failed.
+    File(Error),
+    /// Rendering diagnostics using a [`DiagnosticRenderer`] failed.
+    ///
+    /// [`DiagnosticRenderer`]: DiagnosticRenderer
+    Render(RenderError),
+}
+
+impl From<Error> for EmitError {
+    fn from(err: Error) -> EmitError {
+        EmitError::File(err)
+    }
+}
+
+impl From<RenderError> for EmitError {
+    fn from(err: RenderError) -> EmitError {
+        EmitError::Render(err)
+    }
+}
+
+impl From<std::io::Error> for EmitError {
+    fn from(err: std::io::Error) -> EmitError {
+        EmitError::File(Error::Io(err))
+    }
+}
+
+impl std::fmt::Display for EmitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmitError::File(err) => write!(f, "{}", err),
+            EmitError::Render(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for EmitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EmitError::File(err) => Some(err),
+            EmitError::Render(err) => Some(err),
+        }
+    }
+}
+
+/// Writes diagnostics in some output format.
+///
+/// This is implemented by all emitters in this module and by the [`DiagnosticRenderer`],
+/// so applications can choose the output format at runtime, for example from a command line flag.
+///
+/// Like the [`DiagnosticRenderer`], every emitter is constructed with the file database it looks up
+/// annotations in, so [`emit`] only takes the diagnostics. They are passed as a slice, because
+/// formats writing a single document, like a SARIF log or a JUnit report, need all diagnostics at once.
+///
+/// ```rust
+/// use diagnostic_render::diagnostic::{Annotation, Diagnostic};
+/// use diagnostic_render::emit::Emitter;
+/// use diagnostic_render::emit::github::GithubEmitter;
+/// use diagnostic_render::file::SimpleFile;
+/// use diagnostic_render::render::{DiagnosticRenderer, RenderConfig};
+/// use diagnostic_render::render::color::DefaultColorConfig;
+/// use diagnostic_render::render::string::FmtWriter;
+///
+/// let format = "github";
+/// let file = SimpleFile::new("main.test", "let main = 23;\n");
+/// let mut buf = FmtWriter::new(String::new());
+///
+/// let mut emitter: Box<dyn Emitter<()> + '_> = match format {
+///     "github" => Box::new(GithubEmitter::new(&mut buf, &file)),
+///     _ => Box::new(DiagnosticRenderer::new(&mut buf, DefaultColorConfig, &file, RenderConfig::default())),
+/// };
+///
+/// emitter.emit(&[Diagnostic::warning().with_message("Unused value")
+///     .with_annotation(Annotation::primary((), 4..8))]).unwrap();
+/// drop(emitter);
+///
+/// assert_eq!(buf.into_inner(),
+///     "::warning file=main.test,line=1,col=5,endLine=1,endColumn=9::Unused value\n");
+/// ```
+///
+/// [`DiagnosticRenderer`]: DiagnosticRenderer
+/// [`emit`]: Emitter::emit
+pub trait Emitter<FileId> {
+    /// Writes the given diagnostics.
+    ///
+    /// Emitters writing a single document, like a SARIF log, write a complete document
+    /// every time this is called.
+    fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result;
+}
+
+impl<FileId, E: Emitter<FileId> + ?Sized> Emitter<FileId> for &mut E {
+    fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        (**self).emit(diagnostics)
+    }
+}
+
+impl<FileId, E: Emitter<FileId> + ?Sized> Emitter<FileId> for Box<E> {
+    fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        (**self).emit(diagnostics)
+    }
+}
+
+impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Emitter<FileId> for DiagnosticRenderer<'w, W, C, FileId, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        Ok(self.render_slice(diagnostics)?)
+    }
+}
+
+/// Implements [`Emitter`] for an emitter of this module by calling its `emit` method.
+///
+/// [`Emitter`]: Emitter
+macro_rules! impl_emitter {
+    ($emitter:ident) => {
+        impl<'w, W: std::io::Write, FileId, F: $crate::file::Files<FileId=FileId>> $crate::emit::Emitter<FileId> for $emitter<'w, W, F>
+            where FileId: Clone + std::fmt::Debug + Eq + Ord {
+            fn emit(&mut self, diagnostics: &[$crate::diagnostic::Diagnostic<FileId>]) -> std::result::Result<(), $crate::emit::EmitError> {
+                $emitter::emit(self, diagnostics)
+            }
+        }
+    };
+}
+
+use impl_emitter;
+
+/// Returns the user-facing locations of the start and end of an annotation.
+fn annotation_locations<FileId: Clone>(files: &impl Files<FileId=FileId>, annotation: &Annotation<FileId>) -> std::result::Result<(Location, Location), Error> {
+    Ok((files.location(annotation.file_id.clone(), annotation.range.start)?,
+        files.location(annotation.file_id.clone(), annotation.range.end)?))
+}
+
+/// Escapes text for use in XML. Line breaks and tabs are only escaped in attribute values,
+/// where they would otherwise be normalized to spaces.
+fn escape_xml(text: &str, attribute: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            '\n' if attribute => result.push_str("&#10;"),
+            '\r' if attribute => result.push_str("&#13;"),
+            '\t' if attribute => result.push_str("&#9;"),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{Annotation, AnnotationStyle, Severity};
+    use crate::file::SimpleFile;
+    use crate::render::RenderConfig;
+    use crate::render::color::DisabledColorConfig;
+    use crate::render::string::FmtWriter;
+    use super::*;
+    use super::checkstyle::CheckstyleEmitter;
+    use super::github::GithubEmitter;
+
+    #[test]
+    fn emitter_trait() {
+        let file = SimpleFile::new("main.test", "let main = 23;\n");
+        let diagnostics = vec![Diagnostic::new(Severity::Warning)
+            .with_message("Unused value")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))];
+
+        for format in ["rich", "github", "checkstyle"] {
+            let mut output = String::new();
+            let mut writer = FmtWriter::new(&mut output);
+            let mut buf = Vec::new();
+
+            let mut emitter: Box<dyn Emitter<()> + '_> = match format {
+                "github" => Box::new(GithubEmitter::new(&mut buf, &file)),
+                "checkstyle" => Box::new(CheckstyleEmitter::new(&mut buf, &file)),
+                _ => Box::new(DiagnosticRenderer::new(&mut writer, DisabledColorConfig, &file, RenderConfig::default())),
+            };
+
+            emitter.emit(&diagnostics).unwrap();
+            drop(emitter);
+            drop(writer);
+            output.push_str(&String::from_utf8(buf).unwrap());
+
+            assert_eq!(output.lines().next(), Some(match format {
+                "github" => "::warning file=main.test,line=1,col=5,endLine=1,endColumn=9::Unused value",
+                "checkstyle" => r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                _ => "warning: Unused value",
+            }));
+        }
+    }
+
+    #[test]
+    fn emitter_trait_render_error() {
+        let file = SimpleFile::new("main.test", "let main = 23;\n");
+        let diagnostics = vec![Diagnostic::new(Severity::Warning)
+            .with_message("Unused value")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..80))];
+
+        let mut writer = FmtWriter::new(String::new());
+        let mut emitter: Box<dyn Emitter<()> + '_> = Box::new(DiagnosticRenderer::new(&mut writer, DisabledColorConfig, &file, RenderConfig::default()));
+
+        assert!(matches!(emitter.emit(&diagnostics), Err(EmitError::Render(RenderError::File {
+            error: Error::IndexTooLarge { given: 80, .. }, ..
+        }))));
+    }
+}
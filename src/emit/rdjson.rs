@@ -0,0 +1,210 @@
+//! An emitter for the [Reviewdog Diagnostic Format] (rdjson).
+//!
+//! reviewdog uses these to post diagnostics as review comments on pull requests.
+//!
+//! [Reviewdog Diagnostic Format]: https://github.com/reviewdog/reviewdog/tree/master/proto/rdf
+
+use std::fmt::Debug;
+use std::io::Write;
+use serde_json::{json, Map, Value};
+use crate::diagnostic::{Annotation, Diagnostic, Severity};
+use crate::file::{Error, Files};
+use super::{impl_emitter, Result};
+
+/// Writes diagnostics as a reviewdog diagnostic result in the rdjson format.
+///
+/// The location of a diagnostic is its [primary annotation], and secondary annotations
+/// become related locations. Replacements of suggestions become reviewdog suggestions if they
+/// are in the same file as the primary annotation, as reviewdog suggestions can't refer to other
+/// files. Notes are appended to the message on separate lines.
+///
+/// Column numbers count bytes, as required by the format.
+///
+/// [primary annotation]: Diagnostic::primary_annotation
+#[derive(Debug)]
+pub struct RdjsonEmitter<'w, W, F> {
+    f: &'w mut W, files: F, source_name: String, source_url: Option<String>,
+}
+
+impl<'w, W, F> RdjsonEmitter<'w, W, F> {
+    /// Creates a new rdjson emitter. `source_name` is the name of the tool that produced
+    /// the diagnostics.
+    pub fn new<N: ToString>(f: &'w mut W, files: F, source_name: N) -> Self {
+        RdjsonEmitter {
+            f, files, source_name: source_name.to_string(), source_url: None,
+        }
+    }
+
+    /// Sets the URL pointing to more information about the tool that produced the diagnostics.
+    pub fn with_source_url<U: ToString>(mut self, source_url: U) -> Self {
+        self.source_url = Some(source_url.to_string());
+        self
+    }
+}
+
+impl<'w, W: Write, FileId, F: Files<FileId=FileId>> RdjsonEmitter<'w, W, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Writes the diagnostic result containing the given diagnostics.
+    pub fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        let result = self.to_value(diagnostics)?;
+
+        serde_json::to_writer_pretty(&mut *self.f, &result).map_err(std::io::Error::from)?;
+        writeln!(self.f)?;
+        Ok(())
+    }
+
+    /// Converts the given diagnostics into a diagnostic result, without writing it.
+    pub fn to_value(&self, diagnostics: &[Diagnostic<FileId>]) -> std::result::Result<Value, Error> {
+        let mut source = Map::new();
+        source.insert(String::from("name"), json!(self.source_name));
+
+        if let Some(url) = self.source_url.as_ref() {
+            source.insert(String::from("url"), json!(url));
+        }
+
+        Ok(json!({
+            "source": source,
+            "diagnostics": diagnostics.iter()
+                .map(|diagnostic| self.diagnostic(diagnostic))
+                .collect::<std::result::Result<Vec<Value>, Error>>()?,
+        }))
+    }
+
+    fn diagnostic(&self, diagnostic: &Diagnostic<FileId>) -> std::result::Result<Value, Error> {
+        let mut message = diagnostic.message.clone();
+
+        for note in diagnostic.notes.iter() {
+            message.push('\n');
+            message.push_str(&format!("{}: {}", note.prefix_or_severity(), note.message));
+        }
+
+        let mut result = Map::new();
+        result.insert(String::from("message"), json!(message));
+
+        let primary = diagnostic.primary_annotation();
+
+        if let Some(annotation) = primary {
+            result.insert(String::from("location"), self.location(&annotation.file_id, annotation.range.clone())?);
+        }
+
+        result.insert(String::from("severity"), json!(severity(diagnostic.severity)));
+
+        if let Some(name) = diagnostic.name.as_ref() {
+            let mut code = Map::new();
+            code.insert(String::from("value"), json!(name));
+
+            if let Some(url) = diagnostic.url.as_ref() {
+                code.insert(String::from("url"), json!(url));
+            }
+
+            result.insert(String::from("code"), Value::Object(code));
+        }
+
+        let suggestions = diagnostic.suggestions.iter()
+            .flat_map(|suggestion| suggestion.replacements.iter())
+            .filter(|replacement| primary.is_some_and(|a| a.file_id == replacement.file_id))
+            .map(|replacement| Ok(json!({
+                "range": self.range(&replacement.file_id, replacement.range.clone())?,
+                "text": replacement.text,
+            })))
+            .collect::<std::result::Result<Vec<Value>, Error>>()?;
+
+        if !suggestions.is_empty() {
+            result.insert(String::from("suggestions"), Value::Array(suggestions));
+        }
+
+        let related_locations = diagnostic.annotations.iter()
+            .filter(|annotation| !primary.is_some_and(|primary| std::ptr::eq(primary, *annotation)))
+            .map(|annotation| self.related_location(annotation))
+            .collect::<std::result::Result<Vec<Value>, Error>>()?;
+
+        if !related_locations.is_empty() {
+            result.insert(String::from("related_locations"), Value::Array(related_locations));
+        }
+
+        Ok(Value::Object(result))
+    }
+
+    fn related_location(&self, annotation: &Annotation<FileId>) -> std::result::Result<Value, Error> {
+        let mut location = Map::new();
+
+        if !annotation.label.is_empty() {
+            location.insert(String::from("message"), json!(annotation.label));
+        }
+
+        location.insert(String::from("location"), self.location(&annotation.file_id, annotation.range.clone())?);
+        Ok(Value::Object(location))
+    }
+
+    fn location(&self, file: &FileId, range: std::ops::Range<usize>) -> std::result::Result<Value, Error> {
+        Ok(json!({
+            "path": self.files.name(file.clone())?,
+            "range": self.range(file, range)?,
+        }))
+    }
+
+    fn range(&self, file: &FileId, range: std::ops::Range<usize>) -> std::result::Result<Value, Error> {
+        Ok(json!({
+            "start": self.position(file, range.start)?,
+            "end": self.position(file, range.end)?,
+        }))
+    }
+
+    /// Returns the line number and the byte-based column number of a byte index.
+    fn position(&self, file: &FileId, byte_index: usize) -> std::result::Result<Value, Error> {
+        let line_index = self.files.line_index(file.clone(), byte_index)?;
+        let line_start = self.files.line_range(file.clone(), line_index)?.start;
+
+        Ok(json!({
+            "line": self.files.line_number(file.clone(), line_index)?,
+            "column": byte_index.saturating_sub(line_start) + 1,
+        }))
+    }
+}
+
+impl_emitter!(RdjsonEmitter);
+
+/// Returns the rdjson severity corresponding to a severity.
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug | Severity::Error => "ERROR",
+        Severity::Warning => "WARNING",
+        Severity::Note | Severity::Help => "INFO",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{AnnotationStyle, Note, Replacement, Suggestion};
+    use crate::file::SimpleFiles;
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let mut buf = Vec::new();
+        let mut files = SimpleFiles::new();
+        let main = files.add("src/main.rs", "let ä = 23;\nsomething += 3.0;\n");
+        let other = files.add("src/other.rs", "let other = 23;\n");
+
+        let diagnostics = vec![
+            Diagnostic::new(Severity::Error)
+                .with_name("E0308")
+                .with_url("https://example.com/E0308")
+                .with_message("Mismatched types")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 9..11)
+                    .with_label("expected `String`"))
+                .with_annotation(Annotation::new(AnnotationStyle::Secondary, other, 4..9)
+                    .with_label("declared here"))
+                .with_note(Note::help("Change something"))
+                .with_suggestion(Suggestion::new("Use a string")
+                    .with_replacement(Replacement::new(main, 9..11, "\"23\""))
+                    .with_replacement(Replacement::new(other, 4..9, "_other"))),
+            Diagnostic::new(Severity::Note)
+                .with_message("Without annotations"),
+        ];
+        let mut emitter = RdjsonEmitter::new(&mut buf, &files, "test").with_source_url("https://example.com");
+        emitter.emit(&diagnostics).unwrap();
+
+        insta::assert_snapshot!(String::from_utf8(buf).unwrap());
+    }
+}
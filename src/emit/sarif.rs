@@ -0,0 +1,250 @@
+//! An emitter for the [SARIF 2.1.0] format.
+//!
+//! SARIF logs can be uploaded to GitHub code scanning and other static analysis dashboards.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use std::fmt::Debug;
+use std::io::Write;
+use serde_json::{json, Map, Value};
+use crate::diagnostic::{Annotation, AnnotationStyle, Diagnostic, Severity};
+use crate::file::{Error, Files};
+use super::{annotation_locations, impl_emitter, Result};
+
+/// Information about the tool that produced the diagnostics,
+/// included in the SARIF log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SarifTool {
+    /// The name of the tool.
+    pub name: String,
+    /// The version of the tool.
+    pub version: Option<String>,
+    /// A URL pointing to more information about the tool.
+    pub information_uri: Option<String>,
+}
+
+impl SarifTool {
+    /// Creates tool information with only a name.
+    pub fn new<N: ToString>(name: N) -> Self {
+        SarifTool {
+            name: name.to_string(),
+            version: None,
+            information_uri: None,
+        }
+    }
+
+    /// Sets the version of the tool.
+    pub fn with_version<V: ToString>(mut self, version: V) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    /// Sets the URL pointing to more information about the tool.
+    pub fn with_information_uri<U: ToString>(mut self, information_uri: U) -> Self {
+        self.information_uri = Some(information_uri.to_string());
+        self
+    }
+}
+
+/// Writes diagnostics as a SARIF log.
+///
+/// All diagnostics passed to [`emit`] are written as the results
+/// of a single run.
+///
+/// Primary annotations become the locations of a result, while secondary annotations
+/// become related locations. Notes are appended to the message of a result, and the
+/// metadata of a diagnostic becomes the properties of its result.
+///
+/// [`emit`]: SarifEmitter::emit
+#[derive(Debug)]
+pub struct SarifEmitter<'w, W, F> {
+    f: &'w mut W, files: F, tool: SarifTool,
+}
+
+impl<'w, W, F> SarifEmitter<'w, W, F> {
+    /// Creates a new SARIF emitter.
+    pub fn new(f: &'w mut W, files: F, tool: SarifTool) -> Self {
+        SarifEmitter {
+            f, files, tool,
+        }
+    }
+}
+
+impl<'w, W: Write, FileId, F: Files<FileId=FileId>> SarifEmitter<'w, W, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Writes the SARIF log containing the given diagnostics.
+    pub fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result {
+        let log = self.to_value(diagnostics)?;
+
+        serde_json::to_writer_pretty(&mut *self.f, &log).map_err(std::io::Error::from)?;
+        writeln!(self.f)?;
+        Ok(())
+    }
+
+    /// Converts the given diagnostics into a SARIF log, without writing it.
+    pub fn to_value(&self, diagnostics: &[Diagnostic<FileId>]) -> std::result::Result<Value, Error> {
+        let mut rules: Vec<(&str, Option<&str>)> = Vec::new();
+        let mut results = Vec::with_capacity(diagnostics.len());
+
+        for diagnostic in diagnostics.iter() {
+            if let Some(name) = diagnostic.name.as_deref() {
+                if !rules.iter().any(|(rule, _)| *rule == name) {
+                    rules.push((name, diagnostic.url.as_deref()));
+                }
+            }
+
+            results.push(self.result(diagnostic)?);
+        }
+
+        let mut driver = Map::new();
+        driver.insert(String::from("name"), json!(self.tool.name));
+
+        if let Some(version) = self.tool.version.as_ref() {
+            driver.insert(String::from("version"), json!(version));
+        }
+
+        if let Some(information_uri) = self.tool.information_uri.as_ref() {
+            driver.insert(String::from("informationUri"), json!(information_uri));
+        }
+
+        driver.insert(String::from("rules"), rules.into_iter().map(|(name, url)| match url {
+            Some(url) => json!({ "id": name, "helpUri": url }),
+            None => json!({ "id": name }),
+        }).collect());
+
+        Ok(json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": { "driver": driver },
+                "columnKind": "unicodeCodePoints",
+                "results": results,
+            }],
+        }))
+    }
+
+    fn result(&self, diagnostic: &Diagnostic<FileId>) -> std::result::Result<Value, Error> {
+        let mut message = diagnostic.message.clone();
+
+        for note in diagnostic.notes.iter() {
+            if !message.is_empty() {
+                message.push('\n');
+            }
+
+            message.push_str(&format!("{}: {}", note.prefix_or_severity(), note.message));
+        }
+
+        let mut locations = Vec::new();
+        let mut related_locations = Vec::new();
+
+        for annotation in diagnostic.annotations.iter() {
+            match annotation.style {
+                AnnotationStyle::Primary => locations.push(self.location(annotation, None)?),
+                AnnotationStyle::Secondary => related_locations.push(self.location(annotation, Some(related_locations.len()))?),
+            }
+        }
+
+        let mut result = Map::new();
+
+        if let Some(name) = diagnostic.name.as_ref() {
+            result.insert(String::from("ruleId"), json!(name));
+        }
+
+        result.insert(String::from("level"), json!(level(diagnostic.severity)));
+        result.insert(String::from("message"), json!({ "text": message }));
+        result.insert(String::from("locations"), Value::Array(locations));
+
+        if !related_locations.is_empty() {
+            result.insert(String::from("relatedLocations"), Value::Array(related_locations));
+        }
+
+        if !diagnostic.metadata.is_empty() {
+            result.insert(String::from("properties"), json!(diagnostic.metadata));
+        }
+
+        Ok(Value::Object(result))
+    }
+
+    fn location(&self, annotation: &Annotation<FileId>, id: Option<usize>) -> std::result::Result<Value, Error> {
+        let (start, end) = annotation_locations(&self.files, annotation)?;
+
+        let mut location = Map::new();
+
+        if let Some(id) = id {
+            location.insert(String::from("id"), json!(id));
+        }
+
+        location.insert(String::from("physicalLocation"), json!({
+            "artifactLocation": { "uri": self.files.name(annotation.file_id.clone())? },
+            "region": {
+                "startLine": start.line_number,
+                "startColumn": start.column_number,
+                "endLine": end.line_number,
+                "endColumn": end.column_number,
+                "byteOffset": annotation.range.start,
+                "byteLength": annotation.range.end.saturating_sub(annotation.range.start),
+            },
+        }));
+
+        if !annotation.label.is_empty() {
+            location.insert(String::from("message"), json!({ "text": annotation.label }));
+        }
+
+        Ok(Value::Object(location))
+    }
+}
+
+impl_emitter!(SarifEmitter);
+
+/// Returns the SARIF result level corresponding to a severity.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note | Severity::Help => "note",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::Note;
+    use crate::file::SimpleFile;
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let mut buf = Vec::new();
+        let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+        let diagnostic = Diagnostic::new(Severity::Error)
+            .with_name("E0308")
+            .with_message("Mismatched types")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 28..31)
+                .with_label("expected integer"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
+                .with_label("declared here"))
+            .with_note(Note::help("Change something"));
+        let mut emitter = SarifEmitter::new(&mut buf, file, SarifTool::new("test").with_version("1.0.0"));
+        emitter.emit(&[diagnostic]).unwrap();
+
+        insta::assert_snapshot!(String::from_utf8(buf).unwrap());
+    }
+
+    #[test]
+    fn test_metadata_1() {
+        let file = SimpleFile::new("test_file.test", "let main = 23;\n");
+        let diagnostic = Diagnostic::new(Severity::Warning)
+            .with_message("Unused variable")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))
+            .with_metadata("category", "style")
+            .with_metadata("tracking", "https://example.com/issues/23");
+        let mut buf = Vec::new();
+        let emitter = SarifEmitter::new(&mut buf, file, SarifTool::new("test"));
+        let log = emitter.to_value(&[diagnostic.clone(), diagnostic.with_metadata("category", "correctness")]).unwrap();
+
+        assert_eq!(log["runs"][0]["results"][0]["properties"], json!({
+            "category": "style",
+            "tracking": "https://example.com/issues/23",
+        }));
+        assert_eq!(log["runs"][0]["results"][1]["properties"]["category"], json!("correctness"));
+    }
+}
@@ -9,6 +9,10 @@
 //!
 //! - [`SimpleFile`]: For single-file use-cases
 //! - [`SimpleFiles`]: For multi-file use-cases
+//! - [`BytesFile`] and [`BytesFiles`]: For source code that is not guaranteed to be valid UTF-8
+//! - [`FsFiles`]: For files that are read from the file system when they are first needed
+//!
+//! [`CachedFiles`] can be wrapped around any other implementation to speed up line lookups.
 //!
 //! These data structures provide a pretty minimal API, however,
 //! so end-users are encouraged to create their own implementations for their
@@ -19,8 +23,18 @@
 //! [`Diagnostics`]: crate::diagnostic::Diagnostic
 //! [`SimpleFile`]: SimpleFile
 //! [`SimpleFiles`]: SimpleFiles
+//! [`BytesFile`]: BytesFile
+//! [`BytesFiles`]: BytesFiles
+//! [`FsFiles`]: FsFiles
+//! [`CachedFiles`]: CachedFiles
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
 use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
 
 /// An enum representing an error that happened while looking up a file or a piece of content in that file.
 #[derive(Debug)]
@@ -36,6 +50,11 @@ pub enum Error {
     ColumnTooLarge { given: usize, max: usize },
     /// The given index is contained in the file, but is not a boundary of a UTF-8 code point.
     InvalidCharBoundary { given: usize },
+    /// The given range ends before it starts.
+    InvalidRange { start: usize, end: usize },
+    /// The source of the file was returned as an owned string, but the operation
+    /// needs to borrow it from the file database.
+    OwnedSource,
     /// There was a error while doing IO.
     Io(std::io::Error),
 }
@@ -60,6 +79,8 @@ impl std::fmt::Display for Error {
                 write!(f, "invalid column {}, maximum column {}", given, max)
             }
             Error::InvalidCharBoundary { .. } => write!(f, "index is not a code point boundary"),
+            Error::InvalidRange { start, end } => write!(f, "invalid range {}..{}, it ends before it starts", start, end),
+            Error::OwnedSource => write!(f, "source is owned, but has to be borrowed"),
             Error::Io(err) => write!(f, "{}", err),
         }
     }
@@ -81,13 +102,53 @@ impl std::error::Error for Error {
 /// This can be ignored if this is not needed, however.
 pub trait Files {
     /// A unique identifier for files in the file provider.
-    type FileId: Copy + Eq + Ord;
+    type FileId: Clone + Eq + Ord;
 
     /// The user-facing name of a file.
     fn name(&self, id: Self::FileId) -> Result<&str, Error>;
 
     /// The source code of a file.
-    fn source(&self, id: Self::FileId) -> Result<&str, Error>;
+    ///
+    /// # Note for trait implementors
+    ///
+    /// The source can be returned as an owned string, for example if it is decompressed
+    /// or generated on the fly instead of being kept in memory. This method is called many
+    /// times while rendering a diagnostic, so it might be useful to cache the source of
+    /// files that are expensive to produce.
+    fn source(&self, id: Self::FileId) -> Result<Cow<'_, str>, Error>;
+
+    /// The original bytes of the source code of a file, used for rendering hexdumps.
+    ///
+    /// # Note for trait implementors
+    ///
+    /// A default implementation is provided, which returns the bytes of [`source`].
+    /// This only has to be overridden if the source was decoded from bytes that
+    /// are not valid UTF-8, like in [`BytesFile`].
+    ///
+    /// [`source`]: Files::source
+    /// [`BytesFile`]: BytesFile
+    fn bytes(&self, id: Self::FileId) -> Result<Cow<'_, [u8]>, Error> {
+        Ok(match self.source(id)? {
+            Cow::Borrowed(source) => Cow::Borrowed(source.as_bytes()),
+            Cow::Owned(source) => Cow::Owned(source.into_bytes()),
+        })
+    }
+
+    /// The starting byte index of the line at the given line index.
+    ///
+    /// For the line index one past the last line, this returns the length of the source,
+    /// and for line indices after that, it fails with [`Error::LineTooLarge`].
+    ///
+    /// # Note for trait implementors
+    ///
+    /// This can be implemented by indexing a list of line starts that was computed
+    /// by calling the [`line_starts`] function that is exported from the [`file`] module.
+    /// It might be useful to pre-compute and cache these line starts.
+    ///
+    /// [`Error::LineTooLarge`]: Error::LineTooLarge
+    /// [`line_starts`]: line_starts
+    /// [`file`]: crate::file
+    fn line_start(&self, id: Self::FileId, line_index: usize) -> Result<usize, Error>;
 
     /// The index of the line at the given byte index.
     /// If the byte index is past the end of the file, returns the maximum line index in the file.
@@ -95,14 +156,34 @@ pub trait Files {
     ///
     /// # Note for trait implementors
     ///
-    /// This can be implemented efficiently by performing a binary search over
-    /// a list of line starts that was computed by calling the [`line_starts`]
-    /// function that is exported from the [`file`] module. It might be useful
-    /// to pre-compute and cache these line starts.
+    /// A default implementation is provided, which performs a binary search
+    /// using [`line_start`]. It can be implemented more efficiently by performing
+    /// a binary search over a list of line starts directly.
     ///
-    /// [`line_starts`]: line_starts
-    /// [`file`]: crate::file
-    fn line_index(&self, id: Self::FileId, byte_index: usize) -> Result<usize, Error>;
+    /// [`line_start`]: Files::line_start
+    fn line_index(&self, id: Self::FileId, byte_index: usize) -> Result<usize, Error> {
+        // Line `line_index` exists if the line after it has a start, even if that is the end of the source
+        let starts_before = |line_index: usize| match self.line_start(id.clone(), line_index + 1) {
+            Ok(_) => Ok(self.line_start(id.clone(), line_index)? <= byte_index),
+            Err(Error::LineTooLarge { .. }) => Ok(false),
+            Err(err) => Err(err),
+        };
+
+        let mut low = 0;
+        let mut high = self.source(id.clone())?.len() + 1;
+
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+
+            if starts_before(middle)? {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+
+        Ok(low)
+    }
 
     /// The user-facing line number at the given line index.
     /// It is not necessarily checked that the specified line index
@@ -136,9 +217,9 @@ pub trait Files {
         line_index: usize,
         byte_index: usize,
     ) -> Result<usize, Error> {
-        let source = self.source(id)?;
+        let source = self.source(id.clone())?;
         let line_range = self.line_range(id, line_index)?;
-        let column_index = column_index(source.as_ref(), line_range, byte_index);
+        let column_index = column_index(&source, line_range, byte_index);
 
         Ok(column_index + 1)
     }
@@ -146,18 +227,78 @@ pub trait Files {
     /// Convenience method for returning line and column number at the given
     /// byte index in the file.
     fn location(&self, id: Self::FileId, byte_index: usize) -> Result<Location, Error> {
-        let line_index = self.line_index(id, byte_index)?;
+        let line_index = self.line_index(id.clone(), byte_index)?;
 
         Ok(Location {
-            line_number: self.line_number(id, line_index)?,
+            line_number: self.line_number(id.clone(), line_index)?,
             column_number: self.column_number(id, line_index, byte_index)?,
         })
     }
 
     /// The byte range of a line in the source of the file.
-    fn line_range(&self, id: Self::FileId, line_index: usize) -> Result<Range<usize>, Error>;
+    ///
+    /// # Note for trait implementors
+    ///
+    /// A default implementation is provided, based on [`line_start`].
+    ///
+    /// [`line_start`]: Files::line_start
+    fn line_range(&self, id: Self::FileId, line_index: usize) -> Result<Range<usize>, Error> {
+        Ok(self.line_start(id.clone(), line_index)?..self.line_start(id, line_index + 1)?)
+    }
+}
+
+/// Implements [`Files`] for a pointer type by delegating every method to the pointed-to value,
+/// so that file databases can be shared with the renderer instead of being moved into it.
+///
+/// [`Files`]: Files
+macro_rules! impl_files_for_pointer {
+    ($($pointer:ty),* $(,)?) => {
+        $(
+            impl<F: Files + ?Sized> Files for $pointer {
+                type FileId = F::FileId;
+
+                fn name(&self, id: F::FileId) -> Result<&str, Error> {
+                    (**self).name(id)
+                }
+
+                fn source(&self, id: F::FileId) -> Result<Cow<'_, str>, Error> {
+                    (**self).source(id)
+                }
+
+                fn bytes(&self, id: F::FileId) -> Result<Cow<'_, [u8]>, Error> {
+                    (**self).bytes(id)
+                }
+
+                fn line_start(&self, id: F::FileId, line_index: usize) -> Result<usize, Error> {
+                    (**self).line_start(id, line_index)
+                }
+
+                fn line_index(&self, id: F::FileId, byte_index: usize) -> Result<usize, Error> {
+                    (**self).line_index(id, byte_index)
+                }
+
+                fn line_number(&self, id: F::FileId, line_index: usize) -> Result<usize, Error> {
+                    (**self).line_number(id, line_index)
+                }
+
+                fn column_number(&self, id: F::FileId, line_index: usize, byte_index: usize) -> Result<usize, Error> {
+                    (**self).column_number(id, line_index, byte_index)
+                }
+
+                fn location(&self, id: F::FileId, byte_index: usize) -> Result<Location, Error> {
+                    (**self).location(id, byte_index)
+                }
+
+                fn line_range(&self, id: F::FileId, line_index: usize) -> Result<Range<usize>, Error> {
+                    (**self).line_range(id, line_index)
+                }
+            }
+        )*
+    };
 }
 
+impl_files_for_pointer!(&F, &mut F, Box<F>, Rc<F>, Arc<F>);
+
 /// A user-facing location in a source file.
 ///
 /// Returned by [`Files::location`].
@@ -244,6 +385,51 @@ pub fn line_starts(source: &str) -> impl '_ + Iterator<Item = usize> {
     std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1))
 }
 
+/// Decode source code that is not guaranteed to be valid UTF-8.
+///
+/// Unlike [`String::from_utf8_lossy`], every byte that is not part of a valid UTF-8
+/// code point is replaced with a single substitute character (`\x1a`), so byte indices
+/// into the original source are also valid byte indices into the decoded source.
+/// The substitute character is displayed according to [`RenderConfig::control_chars`].
+///
+/// [`RenderConfig::control_chars`]: crate::render::RenderConfig::control_chars
+///
+/// # Example
+///
+/// ```rust
+/// use diagnostic_render::file;
+///
+/// let source = b"let \xff = \"\xe2\x88\x88\xe2\x88\";";
+///
+/// assert_eq!(file::decode_lossy(source), "let \x1a = \"∈\x1a\x1a\";");
+/// assert_eq!(file::decode_lossy(source).len(), source.len());
+/// ```
+pub fn decode_lossy(source: &[u8]) -> Cow<'_, str> {
+    let mut rest = match std::str::from_utf8(source) {
+        Ok(source) => return Cow::Borrowed(source),
+        Err(_) => source,
+    };
+
+    let mut result = String::with_capacity(source.len());
+
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                result.push_str(valid);
+                return Cow::Owned(result);
+            },
+            Err(err) => {
+                let (valid, invalid) = rest.split_at(err.valid_up_to());
+                result.push_str(std::str::from_utf8(valid).expect("failed despite previous check"));
+
+                let invalid_len = err.error_len().unwrap_or(invalid.len());
+                result.extend(std::iter::repeat_n('\x1a', invalid_len));
+                rest = &invalid[invalid_len..];
+            },
+        }
+    }
+}
+
 /// A file database that contains a single source file.
 ///
 /// Because there is only single file in this database we use `()` as a [`FileId`].
@@ -282,9 +468,20 @@ impl<Name, Source> SimpleFile<Name, Source> where Name: AsRef<str>, Source: AsRe
         &self.source
     }
 
-    /// Return the starting byte index of the line with the specified line index.
-    /// Convenience method that already generates errors if necessary.
-    fn line_start(&self, line_index: usize) -> Result<usize, Error> {
+}
+
+impl<Name, Source> Files for SimpleFile<Name, Source> where Name: AsRef<str>, Source: AsRef<str> {
+    type FileId = ();
+
+    fn name(&self, (): ()) -> Result<&str, Error> {
+        Ok(self.name.as_ref())
+    }
+
+    fn source(&self, (): ()) -> Result<Cow<'_, str>, Error> {
+        Ok(Cow::Borrowed(self.source.as_ref()))
+    }
+
+    fn line_start(&self, (): (), line_index: usize) -> Result<usize, Error> {
         use std::cmp::Ordering;
 
         match line_index.cmp(&self.line_starts.len()) {
@@ -300,18 +497,6 @@ impl<Name, Source> SimpleFile<Name, Source> where Name: AsRef<str>, Source: AsRe
             }),
         }
     }
-}
-
-impl<Name, Source> Files for SimpleFile<Name, Source> where Name: AsRef<str>, Source: AsRef<str> {
-    type FileId = ();
-
-    fn name(&self, (): ()) -> Result<&str, Error> {
-        Ok(self.name.as_ref())
-    }
-
-    fn source(&self, (): ()) -> Result<&str, Error> {
-        Ok(self.source.as_ref())
-    }
 
     fn line_index(&self, (): (), byte_index: usize) -> Result<usize, Error> {
         Ok(self
@@ -319,13 +504,6 @@ impl<Name, Source> Files for SimpleFile<Name, Source> where Name: AsRef<str>, So
             .binary_search(&byte_index)
             .unwrap_or_else(|next_line| next_line - 1))
     }
-
-    fn line_range(&self, (): (), line_index: usize) -> Result<Range<usize>, Error> {
-        let line_start = self.line_start(line_index)?;
-        let next_line_start = self.line_start(line_index + 1)?;
-
-        Ok(line_start..next_line_start)
-    }
 }
 
 /// A file database that can store multiple source files.
@@ -367,16 +545,475 @@ impl<Name, Source> Files for SimpleFiles<Name, Source> where Name: AsRef<str>, S
         Ok(self.get(file_id)?.name().as_ref())
     }
 
-    fn source(&self, file_id: usize) -> Result<&str, Error> {
-        Ok(self.get(file_id)?.source().as_ref())
+    fn source(&self, file_id: usize) -> Result<Cow<'_, str>, Error> {
+        Ok(Cow::Borrowed(self.get(file_id)?.source().as_ref()))
+    }
+
+    fn line_start(&self, file_id: usize, line_index: usize) -> Result<usize, Error> {
+        self.get(file_id)?.line_start((), line_index)
+    }
+
+    fn line_index(&self, file_id: usize, byte_index: usize) -> Result<usize, Error> {
+        self.get(file_id)?.line_index((), byte_index)
+    }
+}
+
+/// A file database that contains a single source file that is not guaranteed to be valid UTF-8.
+///
+/// The source is decoded using [`decode_lossy`] when the file is created, so byte indices
+/// into the original source can be used for annotations.
+///
+/// [`decode_lossy`]: decode_lossy
+#[derive(Debug, Clone)]
+pub struct BytesFile<Name, Source> {
+    /// The original source code of the file.
+    bytes: Source,
+    /// The file containing the decoded source code.
+    file: SimpleFile<Name, String>,
+}
+
+impl<Name, Source> BytesFile<Name, Source> where Name: AsRef<str>, Source: AsRef<[u8]> {
+    /// Create a new source file.
+    pub fn new(name: Name, bytes: Source) -> BytesFile<Name, Source> {
+        BytesFile {
+            file: SimpleFile::new(name, decode_lossy(bytes.as_ref()).into_owned()),
+            bytes,
+        }
+    }
+
+    /// Return the name of the file.
+    pub fn name(&self) -> &Name {
+        self.file.name()
+    }
+
+    /// Return the original source of the file.
+    pub fn bytes(&self) -> &Source {
+        &self.bytes
+    }
+
+    /// Return the decoded source of the file.
+    pub fn source(&self) -> &str {
+        self.file.source()
+    }
+}
+
+impl<Name, Source> Files for BytesFile<Name, Source> where Name: AsRef<str>, Source: AsRef<[u8]> {
+    type FileId = ();
+
+    fn name(&self, (): ()) -> Result<&str, Error> {
+        Files::name(&self.file, ())
+    }
+
+    fn source(&self, (): ()) -> Result<Cow<'_, str>, Error> {
+        Files::source(&self.file, ())
+    }
+
+    fn bytes(&self, (): ()) -> Result<Cow<'_, [u8]>, Error> {
+        Ok(Cow::Borrowed(self.bytes.as_ref()))
+    }
+
+    fn line_start(&self, (): (), line_index: usize) -> Result<usize, Error> {
+        self.file.line_start((), line_index)
+    }
+
+    fn line_index(&self, (): (), byte_index: usize) -> Result<usize, Error> {
+        self.file.line_index((), byte_index)
+    }
+}
+
+/// A file database that can store multiple source files that are not guaranteed to be valid UTF-8.
+///
+/// It is a glorified `Vec<BytesFile>` that implements the `Files` trait.
+#[derive(Debug, Default, Clone)]
+pub struct BytesFiles<Name, Source> {
+    files: Vec<BytesFile<Name, Source>>,
+}
+
+impl<Name, Source> BytesFiles<Name, Source> where Name: AsRef<str>, Source: AsRef<[u8]> {
+    /// Create a new files database.
+    pub fn new() -> BytesFiles<Name, Source> {
+        BytesFiles {
+            files: Vec::new()
+        }
+    }
+
+    /// Add a file to the database, returning the handle that can be used to
+    /// refer to it again.
+    pub fn add(&mut self, name: Name, bytes: Source) -> usize {
+        let file_id = self.files.len();
+        self.files.push(BytesFile::new(name, bytes));
+        file_id
+    }
+
+    /// Get the file corresponding to the given id.
+    pub fn get(&self, file_id: usize) -> Result<&BytesFile<Name, Source>, Error> {
+        self.files.get(file_id).ok_or(Error::FileMissing)
+    }
+}
+
+impl<Name, Source> Files for BytesFiles<Name, Source> where Name: AsRef<str>, Source: AsRef<[u8]> {
+    type FileId = usize;
+
+    fn name(&self, file_id: usize) -> Result<&str, Error> {
+        Ok(self.get(file_id)?.name().as_ref())
+    }
+
+    fn source(&self, file_id: usize) -> Result<Cow<'_, str>, Error> {
+        Ok(Cow::Borrowed(self.get(file_id)?.source()))
+    }
+
+    fn bytes(&self, file_id: usize) -> Result<Cow<'_, [u8]>, Error> {
+        Ok(Cow::Borrowed(self.get(file_id)?.bytes().as_ref()))
+    }
+
+    fn line_start(&self, file_id: usize, line_index: usize) -> Result<usize, Error> {
+        self.get(file_id)?.line_start((), line_index)
     }
 
     fn line_index(&self, file_id: usize, byte_index: usize) -> Result<usize, Error> {
         self.get(file_id)?.line_index((), byte_index)
     }
+}
+
+/// A file database that reads source files from the file system, using their paths as [`FileId`].
+///
+/// Files are read, and their line starts calculated, when they are accessed for the first time,
+/// so they don't have to be added to the database first. The contents are then cached until
+/// the file is [invalidated]. Files are decoded like in [`BytesFile`], so they don't have to be
+/// valid UTF-8.
+///
+/// Paths are stored as [`Arc<Path>`], so file IDs are cheap to clone. This is [`Sync`], so the
+/// same database can be used when rendering on multiple threads.
+///
+/// ```
+/// use std::path::Path;
+/// use std::sync::Arc;
+/// use diagnostic_render::file::{Error, Files, FsFiles};
+///
+/// let files = FsFiles::new();
+/// let path: Arc<Path> = Arc::from(Path::new("missing.rs"));
+///
+/// assert!(matches!(files.source(path), Err(Error::Io(_))));
+/// ```
+///
+/// [`FileId`]: Files::FileId
+/// [invalidated]: FsFiles::invalidate
+/// [`BytesFile`]: BytesFile
+/// [`Arc<Path>`]: Arc
+/// [`Sync`]: Sync
+#[derive(Debug, Default)]
+pub struct FsFiles {
+    /// The index of the slot of every path that was accessed.
+    indices: Mutex<BTreeMap<Arc<Path>, usize>>,
+    /// The contents of the files, in chunks that double in size, so that slots never move
+    /// when new paths are added through a shared reference.
+    chunks: FsChunks,
+}
+
+type FsFile = OnceLock<BytesFile<String, Vec<u8>>>;
+
+#[derive(Debug)]
+struct FsChunks([OnceLock<Box<[FsFile]>>; usize::BITS as usize]);
+
+impl Default for FsChunks {
+    fn default() -> Self {
+        FsChunks(std::array::from_fn(|_| OnceLock::new()))
+    }
+}
+
+impl FsFiles {
+    /// Create a new files database.
+    pub fn new() -> FsFiles {
+        FsFiles::default()
+    }
+
+    /// Remove the cached contents of a file, so that it is read again the next time it is accessed.
+    pub fn invalidate(&mut self, path: &Path) {
+        if let Some(&index) = self.indices.get_mut().unwrap_or_else(PoisonError::into_inner).get(path) {
+            self.slot_mut(index).take();
+        }
+    }
+
+    /// Remove the cached contents of all files.
+    pub fn invalidate_all(&mut self) {
+        self.chunks.0.iter_mut().filter_map(OnceLock::get_mut).flatten().for_each(|file| { file.take(); });
+    }
+
+    /// Get the file at the given path, reading it if it is not cached yet.
+    pub fn get(&self, path: &Path) -> Result<&BytesFile<String, Vec<u8>>, Error> {
+        let index = {
+            let mut indices = self.indices.lock().unwrap_or_else(PoisonError::into_inner);
+
+            match indices.get(path) {
+                Some(&index) => index,
+                None => {
+                    let index = indices.len();
+                    indices.insert(Arc::from(path), index);
+                    index
+                },
+            }
+        };
+
+        let file = self.slot(index);
+
+        if file.get().is_none() {
+            let bytes = std::fs::read(path)?;
+            let _ = file.set(BytesFile::new(path.to_string_lossy().into_owned(), bytes));
+        }
+
+        Ok(file.get().expect("failed despite previous check"))
+    }
+
+    /// Returns the chunk containing the slot at `index`, and the index of the slot in that chunk.
+    fn chunk_index(index: usize) -> (usize, usize) {
+        let chunk = (index + 1).ilog2() as usize;
+        (chunk, index + 1 - (1 << chunk))
+    }
+
+    fn slot(&self, index: usize) -> &FsFile {
+        let (chunk, index) = Self::chunk_index(index);
+        &self.chunks.0[chunk].get_or_init(|| (0..1 << chunk).map(|_| OnceLock::new()).collect())[index]
+    }
+
+    fn slot_mut(&mut self, index: usize) -> &mut FsFile {
+        let (chunk, index) = Self::chunk_index(index);
+        &mut self.chunks.0[chunk].get_mut().expect("slot of an accessed path is missing")[index]
+    }
+}
+
+impl Files for FsFiles {
+    type FileId = Arc<Path>;
+
+    fn name(&self, path: Arc<Path>) -> Result<&str, Error> {
+        Ok(self.get(&path)?.name())
+    }
+
+    fn source(&self, path: Arc<Path>) -> Result<Cow<'_, str>, Error> {
+        Ok(Cow::Borrowed(self.get(&path)?.source()))
+    }
+
+    fn bytes(&self, path: Arc<Path>) -> Result<Cow<'_, [u8]>, Error> {
+        Ok(Cow::Borrowed(self.get(&path)?.bytes()))
+    }
+
+    fn line_start(&self, path: Arc<Path>, line_index: usize) -> Result<usize, Error> {
+        self.get(&path)?.line_start((), line_index)
+    }
+
+    fn line_index(&self, path: Arc<Path>, byte_index: usize) -> Result<usize, Error> {
+        self.get(&path)?.line_index((), byte_index)
+    }
+}
+
+/// A wrapper around another file database that calculates the line starts of every file once,
+/// when it is first accessed, and answers [`line_start`], [`line_index`], and [`line_range`] using them.
+///
+/// This avoids repeatedly searching the source of large files if the wrapped implementation
+/// doesn't cache line starts itself. The source of a file is only requested from the wrapped
+/// database the first time its lines are looked up, so this also avoids producing the source
+/// again for databases that don't keep it in memory. Lines are split at `\n`, like with
+/// [`line_starts`]. All other methods, including [`line_number`] and [`column_number`], are passed through.
+///
+/// The cache is behind a [`Mutex`], so this is [`Sync`] if the wrapped database and its file IDs are.
+///
+/// [`line_start`]: Files::line_start
+/// [`line_index`]: Files::line_index
+/// [`line_range`]: Files::line_range
+/// [`line_starts`]: line_starts
+/// [`line_number`]: Files::line_number
+/// [`column_number`]: Files::column_number
+/// [`Mutex`]: Mutex
+/// [`Sync`]: Sync
+#[derive(Debug)]
+pub struct CachedFiles<F: Files> {
+    files: F,
+    lines: Mutex<BTreeMap<F::FileId, CachedLines>>,
+}
+
+/// The line starts and the length of the source of a file in [`CachedFiles`].
+///
+/// [`CachedFiles`]: CachedFiles
+#[derive(Debug)]
+struct CachedLines {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl<F: Files> CachedFiles<F> {
+    /// Wrap a file database.
+    pub fn new(files: F) -> CachedFiles<F> {
+        CachedFiles {
+            files,
+            lines: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Return the wrapped file database.
+    pub fn inner(&self) -> &F {
+        &self.files
+    }
+
+    /// Return the wrapped file database, removing the cached line starts.
+    pub fn into_inner(self) -> F {
+        self.files
+    }
+
+    /// Remove the cached line starts of all files. This has to be called
+    /// when the source of a file in the wrapped database changes.
+    pub fn clear_cache(&mut self) {
+        self.lines.get_mut().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+
+    /// Call a function with the line starts of a file and the length of its source,
+    /// calculating them if they are not cached yet.
+    fn with_line_starts<T>(&self, id: F::FileId, f: impl FnOnce(&[usize], usize) -> Result<T, Error>) -> Result<T, Error> {
+        let mut cache = self.lines.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let lines = match cache.entry(id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let source = self.files.source(entry.key().clone())?;
+
+                entry.insert(CachedLines {
+                    line_starts: line_starts(&source).collect(),
+                    len: source.len(),
+                })
+            },
+        };
+
+        f(&lines.line_starts, lines.len)
+    }
+}
+
+impl<F: Files> Files for CachedFiles<F> {
+    type FileId = F::FileId;
+
+    fn name(&self, id: F::FileId) -> Result<&str, Error> {
+        self.files.name(id)
+    }
+
+    fn source(&self, id: F::FileId) -> Result<Cow<'_, str>, Error> {
+        self.files.source(id)
+    }
+
+    fn bytes(&self, id: F::FileId) -> Result<Cow<'_, [u8]>, Error> {
+        self.files.bytes(id)
+    }
+
+    fn line_start(&self, id: F::FileId, line_index: usize) -> Result<usize, Error> {
+        self.with_line_starts(id, |line_starts, len| match line_index.cmp(&line_starts.len()) {
+            std::cmp::Ordering::Less => Ok(line_starts[line_index]),
+            std::cmp::Ordering::Equal => Ok(len),
+            std::cmp::Ordering::Greater => Err(Error::LineTooLarge {
+                given: line_index,
+                max: line_starts.len() - 1,
+            }),
+        })
+    }
+
+    fn line_index(&self, id: F::FileId, byte_index: usize) -> Result<usize, Error> {
+        self.with_line_starts(id, |line_starts, _| Ok(line_starts
+            .binary_search(&byte_index)
+            .unwrap_or_else(|next_line| next_line - 1)))
+    }
+
+    fn line_number(&self, id: F::FileId, line_index: usize) -> Result<usize, Error> {
+        self.files.line_number(id, line_index)
+    }
+
+    fn column_number(&self, id: F::FileId, line_index: usize, byte_index: usize) -> Result<usize, Error> {
+        self.files.column_number(id, line_index, byte_index)
+    }
+}
+
+/// A wrapper around another file database that adds an offset to the line numbers of files,
+/// for rendering code that was extracted from a larger document, like a doctest or an embedded
+/// SQL query, with the line numbers of that document.
+///
+/// All other methods are passed through. Files without an offset keep their line numbers.
+///
+/// ```
+/// use diagnostic_render::file::{Files, LineOffsets, SimpleFiles};
+///
+/// let mut files = SimpleFiles::new();
+/// let query = files.add("queries.md", "SELECT *\nFROM users;\n");
+/// let files = LineOffsets::new(files).with_offset(query, 41);
+///
+/// assert_eq!(files.location(query, 9).unwrap().line_number, 43);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineOffsets<F: Files> {
+    files: F,
+    offsets: BTreeMap<F::FileId, usize>,
+}
+
+impl<F: Files> LineOffsets<F> {
+    /// Wrap a file database.
+    pub fn new(files: F) -> LineOffsets<F> {
+        LineOffsets {
+            files,
+            offsets: BTreeMap::new(),
+        }
+    }
+
+    /// Set the number of lines that come before the source of a file in the document
+    /// it was extracted from.
+    pub fn with_offset(mut self, id: F::FileId, offset: usize) -> Self {
+        self.set_offset(id, offset);
+        self
+    }
+
+    /// Set the number of lines that come before the source of a file in the document
+    /// it was extracted from.
+    pub fn set_offset(&mut self, id: F::FileId, offset: usize) {
+        self.offsets.insert(id, offset);
+    }
+
+    /// Return the wrapped file database.
+    pub fn inner(&self) -> &F {
+        &self.files
+    }
+
+    /// Return the wrapped file database, removing the offsets.
+    pub fn into_inner(self) -> F {
+        self.files
+    }
+}
+
+impl<F: Files> Files for LineOffsets<F> {
+    type FileId = F::FileId;
+
+    fn name(&self, id: F::FileId) -> Result<&str, Error> {
+        self.files.name(id)
+    }
+
+    fn source(&self, id: F::FileId) -> Result<Cow<'_, str>, Error> {
+        self.files.source(id)
+    }
+
+    fn bytes(&self, id: F::FileId) -> Result<Cow<'_, [u8]>, Error> {
+        self.files.bytes(id)
+    }
+
+    fn line_start(&self, id: F::FileId, line_index: usize) -> Result<usize, Error> {
+        self.files.line_start(id, line_index)
+    }
+
+    fn line_index(&self, id: F::FileId, byte_index: usize) -> Result<usize, Error> {
+        self.files.line_index(id, byte_index)
+    }
+
+    fn line_number(&self, id: F::FileId, line_index: usize) -> Result<usize, Error> {
+        let offset = self.offsets.get(&id).copied().unwrap_or(0);
+        Ok(self.files.line_number(id, line_index)? + offset)
+    }
 
-    fn line_range(&self, file_id: usize, line_index: usize) -> Result<Range<usize>, Error> {
-        self.get(file_id)?.line_range((), line_index)
+    fn column_number(&self, id: F::FileId, line_index: usize, byte_index: usize) -> Result<usize, Error> {
+        self.files.column_number(id, line_index, byte_index)
+    }
+
+    fn line_range(&self, id: F::FileId, line_index: usize) -> Result<Range<usize>, Error> {
+        self.files.line_range(id, line_index)
     }
 }
 
@@ -414,4 +1051,194 @@ mod tests {
 
         assert_eq!(line_sources, ["foo\n", "bar\r\n", "\n", "baz"]);
     }
+
+    #[test]
+    fn simple_files() {
+        let mut files = SimpleFiles::new();
+        let foo = files.add("foo.test", "foo");
+        let bar = files.add("bar.test", TEST_SOURCE);
+
+        assert_ne!(foo, bar);
+        assert_eq!(files.name(foo).unwrap(), "foo.test");
+        assert_eq!(files.source(bar).unwrap(), TEST_SOURCE);
+        assert_eq!(files.line_range(bar, 1).unwrap(), 4..9);
+        assert_eq!(files.location(bar, 6).unwrap(), Location { line_number: 2, column_number: 3 });
+        assert!(matches!(files.name(bar + 1), Err(Error::FileMissing)));
+    }
+
+    #[test]
+    fn fs_files() {
+        let path: Arc<Path> = Arc::from(std::env::temp_dir().join(format!("diagnostic_render_fs_files_{}.test", std::process::id())));
+        std::fs::write(&path, TEST_SOURCE).unwrap();
+
+        let mut files = FsFiles::new();
+
+        assert_eq!(files.source(path.clone()).unwrap(), TEST_SOURCE);
+        assert_eq!(files.line_range(path.clone(), 1).unwrap(), 4..9);
+
+        std::fs::write(&path, "foo\nbar").unwrap();
+        assert_eq!(files.source(path.clone()).unwrap(), TEST_SOURCE);
+
+        files.invalidate(&path);
+        assert_eq!(files.source(path.clone()).unwrap(), "foo\nbar");
+
+        std::fs::remove_file(&path).unwrap();
+        files.invalidate_all();
+        assert!(matches!(files.source(path), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn fs_files_many() {
+        let dir = std::env::temp_dir().join(format!("diagnostic_render_fs_files_many_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let paths = (0..20).map(|i| {
+            let path: Arc<Path> = Arc::from(dir.join(format!("{}.test", i)));
+            std::fs::write(&path, i.to_string()).unwrap();
+            path
+        }).collect::<Vec<_>>();
+
+        let files = FsFiles::new();
+        let sources = paths.iter().map(|path| files.source(path.clone()).unwrap()).collect::<Vec<_>>();
+
+        // Sources of earlier files stay borrowed while later ones are read
+        for (i, source) in sources.iter().enumerate() {
+            assert_eq!(source, &i.to_string());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cached_files() {
+        let mut files = SimpleFiles::new();
+        let foo = files.add("foo.test", "foo");
+        let bar = files.add("bar.test", TEST_SOURCE);
+        let cached = CachedFiles::new(files.clone());
+
+        for (file, len) in [(foo, 3), (bar, TEST_SOURCE.len())] {
+            for byte_index in 0..=len {
+                assert_eq!(cached.line_index(file, byte_index).unwrap(), files.line_index(file, byte_index).unwrap());
+                assert_eq!(cached.location(file, byte_index).unwrap(), files.location(file, byte_index).unwrap());
+            }
+        }
+
+        for line_index in 0..4 {
+            assert_eq!(cached.line_range(bar, line_index).unwrap(), files.line_range(bar, line_index).unwrap());
+        }
+
+        assert!(matches!(cached.line_range(bar, 4), Err(Error::LineTooLarge { given: 5, max: 3 })));
+        assert!(matches!(cached.line_index(bar + 1, 0), Err(Error::FileMissing)));
+    }
+
+    #[test]
+    fn cached_files_source_requests() {
+        struct CountingFile {
+            file: SimpleFile<&'static str, &'static str>,
+            requests: std::sync::atomic::AtomicUsize,
+        }
+
+        impl Files for CountingFile {
+            type FileId = ();
+
+            fn name(&self, (): ()) -> Result<&str, Error> {
+                Files::name(&self.file, ())
+            }
+
+            fn source(&self, (): ()) -> Result<Cow<'_, str>, Error> {
+                self.requests.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                Files::source(&self.file, ())
+            }
+
+            fn line_start(&self, (): (), line_index: usize) -> Result<usize, Error> {
+                self.file.line_start((), line_index)
+            }
+        }
+
+        fn assert_sync<T: Sync>(_: &T) {}
+
+        let cached = CachedFiles::new(CountingFile {
+            file: SimpleFile::new("test.test", TEST_SOURCE),
+            requests: Default::default(),
+        });
+        assert_sync(&cached);
+
+        for byte_index in 0..=TEST_SOURCE.len() {
+            cached.line_index((), byte_index).unwrap();
+        }
+
+        assert_eq!(cached.line_range((), 3).unwrap(), 10..TEST_SOURCE.len());
+        assert_eq!(cached.inner().requests.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn line_offsets() {
+        let mut files = SimpleFiles::new();
+        let foo = files.add("foo.test", "foo");
+        let bar = files.add("bar.test", TEST_SOURCE);
+        let files = LineOffsets::new(files).with_offset(bar, 10);
+
+        assert_eq!(files.location(foo, 1).unwrap(), Location { line_number: 1, column_number: 2 });
+        assert_eq!(files.location(bar, 6).unwrap(), Location { line_number: 12, column_number: 3 });
+        assert_eq!(files.line_range(bar, 1).unwrap(), 4..9);
+    }
+
+    #[test]
+    fn default_methods() {
+        struct MinimalFile(SimpleFile<&'static str, &'static str>);
+
+        impl Files for MinimalFile {
+            type FileId = ();
+
+            fn name(&self, (): ()) -> Result<&str, Error> {
+                Files::name(&self.0, ())
+            }
+
+            fn source(&self, (): ()) -> Result<Cow<'_, str>, Error> {
+                Files::source(&self.0, ())
+            }
+
+            fn line_start(&self, (): (), line_index: usize) -> Result<usize, Error> {
+                self.0.line_start((), line_index)
+            }
+        }
+
+        for source in [TEST_SOURCE, "foo\n", "", "\n\n"] {
+            let file = SimpleFile::new("test", source);
+            let minimal = MinimalFile(file.clone());
+
+            for byte_index in 0..=source.len() + 1 {
+                assert_eq!(minimal.line_index((), byte_index).unwrap(), file.line_index((), byte_index).unwrap());
+                assert_eq!(minimal.location((), byte_index).unwrap(), file.location((), byte_index).unwrap());
+            }
+
+            let last_line_index = file.line_index((), source.len()).unwrap();
+            assert_eq!(minimal.line_range((), last_line_index).unwrap(), file.line_range((), last_line_index).unwrap());
+        }
+    }
+
+    #[test]
+    fn pointers() {
+        fn line_range(files: impl Files<FileId=()>) -> Range<usize> {
+            files.line_range((), 1).unwrap()
+        }
+
+        let mut file = SimpleFile::new("test", TEST_SOURCE);
+
+        assert_eq!(line_range(&file), 4..9);
+        assert_eq!(line_range(&mut file), 4..9);
+        assert_eq!(line_range(Box::new(file.clone())), 4..9);
+        assert_eq!(line_range(Rc::new(file.clone())), 4..9);
+        assert_eq!(line_range(Arc::new(file)), 4..9);
+    }
+
+    #[test]
+    fn decode_lossy_invalid() {
+        let source: &[u8] = b"foo\n\xc3\nb\xffar\r\n\xf0\x9f\x97";
+        let file = BytesFile::new("test", source);
+
+        assert_eq!(file.source(), "foo\n\x1a\nb\x1aar\r\n\x1a\x1a\x1a");
+        assert_eq!(file.line_range((), 2).unwrap(), 6..12);
+        assert_eq!(file.location((), 8).unwrap(), Location { line_number: 3, column_number: 3 });
+    }
 }
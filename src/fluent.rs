@@ -0,0 +1,228 @@
+//! Resolving the messages of diagnostics through a [Fluent] bundle when rendering them.
+//!
+//! With [`FluentStrings`], the message, labels, notes and suggestion messages of a diagnostic
+//! can be identifiers of Fluent messages, which are formatted with the [`Diagnostic::args`]
+//! of that diagnostic when it is rendered. Attributes of a message are referenced as
+//! `message-id.attribute`. Texts that don't refer to a message in the bundle are written as
+//! they are, so translated and untranslated diagnostics can be mixed.
+//!
+//! # Example
+//! ```rust
+//! use fluent_bundle::FluentResource;
+//! use fluent_bundle::concurrent::FluentBundle;
+//! use diagnostic_render::diagnostic::{Annotation, AnnotationStyle, Diagnostic, Severity};
+//! use diagnostic_render::file::SimpleFile;
+//! use diagnostic_render::fluent::FluentStrings;
+//! use diagnostic_render::render::{DiagnosticRenderer, RenderConfig};
+//! use diagnostic_render::render::color::DisabledColorConfig;
+//! use diagnostic_render::render::string::FmtWriter;
+//!
+//! let resource = FluentResource::try_new(String::from("
+//! unused-variable = unused variable: `{ $name }`
+//!     .label = never used
+//! ")).unwrap();
+//! let mut bundle = FluentBundle::new_concurrent(Vec::new());
+//! bundle.add_resource(resource).unwrap();
+//!
+//! let mut output = FmtWriter::new(String::new());
+//! let file = SimpleFile::new("main.rs", "let x = 1;\n");
+//! let mut renderer = DiagnosticRenderer::new(&mut output, DisabledColorConfig, file, RenderConfig::default())
+//!     .with_strings(FluentStrings::new(bundle));
+//! renderer.render(vec![Diagnostic::new(Severity::Warning)
+//!     .with_message("unused-variable")
+//!     .with_arg("name", "x")
+//!     .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..5).with_label("unused-variable.label"))]).unwrap();
+//!
+//! let output = output.into_inner();
+//! assert!(output.starts_with("warning: unused variable: `x`"));
+//! assert!(output.contains("^ never used"));
+//! ```
+//!
+//! [Fluent]: https://projectfluent.org/
+//! [`FluentStrings`]: FluentStrings
+//! [`Diagnostic::args`]: crate::diagnostic::Diagnostic::args
+
+use std::borrow::{Borrow, Cow};
+use std::fmt::{Debug, Formatter};
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use crate::diagnostic::{Severity, SeverityCounts};
+use crate::render::strings::{EnglishStrings, Strings};
+
+/// A [`Strings`] implementation that resolves texts through a Fluent bundle.
+///
+/// The fixed texts written by the renderer can be localized through the bundle as well,
+/// using the following message identifiers. The ones missing from the bundle are written
+/// in English, like with [`EnglishStrings`].
+///
+/// | Identifier | Arguments | Text |
+/// |---|---|---|
+/// | `severity-bug`, `severity-error`, `severity-warning`, `severity-note`, `severity-help` | | The names of the severities |
+/// | `suppressed` | `$count` | The line for suppressed diagnostics |
+/// | `lines-skipped` | `$count` | The text for skipped unannotated lines |
+/// | `hidden-annotations` | `$count` | The line for annotations hidden by [`RenderConfig::max_annotations_per_line`] |
+/// | `more-information` | `$url` | The note linking to the documentation of a diagnostic |
+/// | `summary` | `$errors`, `$warnings` | The summary written by [`DiagnosticRenderer::render_summary`] |
+///
+/// [`Strings`]: Strings
+/// [`EnglishStrings`]: EnglishStrings
+/// [`RenderConfig::max_annotations_per_line`]: crate::render::RenderConfig::max_annotations_per_line
+/// [`DiagnosticRenderer::render_summary`]: crate::render::DiagnosticRenderer::render_summary
+pub struct FluentStrings<R = FluentResource> {
+    bundle: FluentBundle<R>,
+}
+
+impl<R: Borrow<FluentResource>> FluentStrings<R> {
+    /// Creates new strings resolved through the given bundle.
+    ///
+    /// This disables the Unicode isolation marks the bundle inserts around arguments by
+    /// default, as they are not invisible in every terminal and count towards the width of
+    /// labels.
+    pub fn new(mut bundle: FluentBundle<R>) -> Self {
+        bundle.set_use_isolating(false);
+
+        FluentStrings {
+            bundle,
+        }
+    }
+
+    /// Returns the bundle the strings are resolved through.
+    pub fn bundle(&self) -> &FluentBundle<R> {
+        &self.bundle
+    }
+
+    /// Formats the message or attribute referenced by `id`, or returns `None` if the bundle
+    /// doesn't contain it.
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+        let (id, attribute) = match id.split_once('.') {
+            Some((id, attribute)) => (id, Some(attribute)),
+            None => (id, None),
+        };
+
+        let message = self.bundle.get_message(id)?;
+        let pattern = match attribute {
+            Some(attribute) => message.get_attribute(attribute)?.value(),
+            None => message.value()?,
+        };
+
+        // Errors like missing arguments are shown in the formatted text itself
+        let mut errors = Vec::new();
+        Some(self.bundle.format_pattern(pattern, args, &mut errors).into_owned())
+    }
+
+    fn format_count(&self, id: &str, count: usize) -> Option<String> {
+        let mut args = FluentArgs::new();
+        args.set("count", count);
+        self.format(id, Some(&args))
+    }
+}
+
+impl<R> Debug for FluentStrings<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FluentStrings")
+            .field("locales", &self.bundle.locales)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: Borrow<FluentResource> + Send + Sync> Strings for FluentStrings<R> {
+    fn severity(&self, severity: Severity) -> Cow<'_, str> {
+        self.format(&format!("severity-{}", severity), None)
+            .map_or_else(|| EnglishStrings.severity(severity), Cow::Owned)
+    }
+
+    fn message<'a>(&self, message: &'a str, args: &[(String, String)]) -> Cow<'a, str> {
+        let mut fluent_args = FluentArgs::new();
+
+        for (name, value) in args.iter() {
+            // Numbers are passed as numbers, so that they can select plural forms
+            fluent_args.set(name.as_str(), FluentValue::try_number(value.as_str()));
+        }
+
+        self.format(message, Some(&fluent_args)).map_or(Cow::Borrowed(message), Cow::Owned)
+    }
+
+    fn suppressed(&self, count: usize) -> String {
+        self.format_count("suppressed", count).unwrap_or_else(|| EnglishStrings.suppressed(count))
+    }
+
+    fn lines_skipped(&self, count: usize) -> String {
+        self.format_count("lines-skipped", count).unwrap_or_else(|| EnglishStrings.lines_skipped(count))
+    }
+
+    fn hidden_annotations(&self, count: usize) -> String {
+        self.format_count("hidden-annotations", count).unwrap_or_else(|| EnglishStrings.hidden_annotations(count))
+    }
+
+    fn more_information(&self, url: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.set("url", url);
+        self.format("more-information", Some(&args)).unwrap_or_else(|| EnglishStrings.more_information(url))
+    }
+
+    fn summary(&self, counts: &SeverityCounts) -> Option<(Severity, String)> {
+        let errors = counts.get(Severity::Error) + counts.get(Severity::Bug);
+        let warnings = counts.get(Severity::Warning);
+
+        if errors == 0 && warnings == 0 {
+            return None;
+        }
+
+        let mut args = FluentArgs::new();
+        args.set("errors", errors);
+        args.set("warnings", warnings);
+
+        match self.format("summary", Some(&args)) {
+            Some(summary) => Some((if errors > 0 { Severity::Error } else { Severity::Warning }, summary)),
+            None => EnglishStrings.summary(counts),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use super::*;
+
+    fn strings(source: &str) -> FluentStrings {
+        let mut bundle = FluentBundle::new_concurrent(vec!["de".parse().unwrap()]);
+        bundle.add_resource(FluentResource::try_new(source.to_owned()).unwrap()).unwrap();
+        FluentStrings::new(bundle)
+    }
+
+    #[test]
+    fn test_message() {
+        let strings = strings("
+unused = { $count ->
+    [one] eine ungenutzte Variable
+   *[other] { $count } ungenutzte Variablen
+}
+    .label = `{ $name }` wird nie benutzt
+");
+
+        assert_eq!(strings.message("unused", &[(String::from("count"), String::from("1"))]), "eine ungenutzte Variable");
+        assert_eq!(strings.message("unused", &[(String::from("count"), String::from("3"))]), "3 ungenutzte Variablen");
+        assert_eq!(strings.message("unused.label", &[(String::from("name"), String::from("x"))]), "`x` wird nie benutzt");
+        assert!(matches!(strings.message("not a message id.", &[]), Cow::Borrowed("not a message id.")));
+        assert!(matches!(strings.message("unused.missing", &[]), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_fixed_strings() {
+        let strings = strings("
+severity-error = Fehler
+suppressed = ... und { $count } weitere
+summary = { $errors } Fehler, { $warnings } Warnungen
+");
+
+        let mut counts = SeverityCounts::new();
+        counts.add(Severity::Warning);
+
+        assert_eq!(strings.severity(Severity::Error), "Fehler");
+        assert_eq!(strings.severity(Severity::Warning), "warning");
+        assert_eq!(strings.suppressed(2), "... und 2 weitere");
+        assert_eq!(strings.lines_skipped(2), "(2 lines skipped)");
+        assert_eq!(strings.summary(&counts), Some((Severity::Warning, String::from("0 Fehler, 1 Warnungen"))));
+        assert_eq!(strings.summary(&SeverityCounts::new()), None);
+    }
+}
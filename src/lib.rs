@@ -12,6 +12,23 @@
 //! [ASCII renderer]: render::DiagnosticRenderer
 //! [diagnostics]: diagnostic::Diagnostic
 
+pub mod emit;
 pub mod file;
 pub mod diagnostic;
 pub mod render;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+#[cfg(feature = "codespan")]
+pub mod codespan;
+#[cfg(feature = "annotate-snippets")]
+pub mod annotate_snippets;
+#[cfg(feature = "miette")]
+pub mod miette;
+#[cfg(feature = "anstyle")]
+pub mod anstyle;
+#[cfg(feature = "fluent")]
+pub mod fluent;
+#[cfg(feature = "log")]
+pub mod log;
+#[cfg(feature = "tracing")]
+pub mod tracing;
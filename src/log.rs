@@ -0,0 +1,144 @@
+//! Emitting diagnostics as [`log`] records.
+//!
+//! This allows applications using `log` to capture diagnostics in their normal logging
+//! pipeline. Every diagnostic is rendered without colors and logged as a single record,
+//! with a level depending on its severity.
+//!
+//! # Example
+//! ```rust
+//! use diagnostic_render::diagnostic::{Annotation, Diagnostic};
+//! use diagnostic_render::file::SimpleFile;
+//! use diagnostic_render::log::LogEmitter;
+//! use diagnostic_render::render::RenderConfig;
+//!
+//! let file = SimpleFile::new("main.test", "let main = 23;\n");
+//! let mut emitter = LogEmitter::new(&file, RenderConfig::default());
+//!
+//! emitter.emit(&[Diagnostic::warning().with_message("Unused value")
+//!     .with_annotation(Annotation::primary((), 4..8))]).unwrap();
+//! ```
+
+use std::fmt::Debug;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::emit::{EmitError, Emitter};
+use crate::file::Files;
+use crate::render::RenderConfig;
+use crate::render::string::render_to_string;
+
+impl From<Severity> for ::log::Level {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Bug | Severity::Error => ::log::Level::Error,
+            Severity::Warning => ::log::Level::Warn,
+            Severity::Note | Severity::Help => ::log::Level::Info,
+        }
+    }
+}
+
+/// Logs every diagnostic as a record, with the diagnostic rendered using a [`RenderConfig`]
+/// as its message. The level of the record is converted from the severity of the diagnostic.
+///
+/// Diagnostics are only rendered if their level is enabled. The target of the records
+/// is `diagnostic_render::log`, unless a different one is set using [`LogEmitter::with_target`].
+///
+/// [`RenderConfig`]: RenderConfig
+/// [`LogEmitter::with_target`]: LogEmitter::with_target
+#[derive(Debug)]
+pub struct LogEmitter<F> {
+    files: F, config: RenderConfig, target: String,
+}
+
+impl<F> LogEmitter<F> {
+    /// Creates a new log emitter, rendering diagnostics using `config`.
+    pub fn new(files: F, config: RenderConfig) -> Self {
+        LogEmitter {
+            files, config, target: String::from(module_path!()),
+        }
+    }
+
+    /// Sets the target of the records.
+    pub fn with_target<T: ToString>(mut self, target: T) -> Self {
+        self.target = target.to_string();
+        self
+    }
+}
+
+impl<FileId, F: Files<FileId=FileId>> LogEmitter<F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Logs a record for each of the given diagnostics.
+    pub fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result<(), EmitError> {
+        for diagnostic in diagnostics.iter() {
+            let level = ::log::Level::from(diagnostic.severity);
+
+            if !::log::log_enabled!(target: &self.target, level) {
+                continue;
+            }
+
+            let output = render_to_string(&self.files, self.config.clone(), vec![diagnostic.clone()])?;
+
+            // Diagnostics can be filtered out by the configuration, leaving no output
+            if !output.is_empty() {
+                ::log::log!(target: &self.target, level, "{}", output.trim_end());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<FileId, F: Files<FileId=FileId>> Emitter<FileId> for LogEmitter<F>
+    where FileId: Clone + Debug + Eq + Ord {
+    fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result<(), EmitError> {
+        LogEmitter::emit(self, diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use crate::diagnostic::{Annotation, AnnotationStyle};
+    use crate::file::SimpleFile;
+    use super::*;
+
+    static RECORDS: Mutex<Vec<(::log::Level, String, String)>> = Mutex::new(Vec::new());
+
+    struct TestLogger;
+
+    impl ::log::Log for TestLogger {
+        fn enabled(&self, metadata: &::log::Metadata) -> bool {
+            metadata.level() <= ::log::Level::Warn
+        }
+
+        fn log(&self, record: &::log::Record) {
+            if self.enabled(record.metadata()) {
+                RECORDS.lock().unwrap().push((record.level(), record.target().to_owned(), record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn test_1() {
+        ::log::set_logger(&TestLogger).unwrap();
+        ::log::set_max_level(::log::LevelFilter::Trace);
+
+        let file = SimpleFile::new("main.test", "let main = 23;\n");
+        let mut emitter = LogEmitter::new(&file, RenderConfig::default()).with_target("test");
+        emitter.emit(&[
+            Diagnostic::new(Severity::Error)
+                .with_message("Mismatched types")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)),
+            Diagnostic::new(Severity::Note)
+                .with_message("Not enabled"),
+            Diagnostic::new(Severity::Warning)
+                .with_message("Unused value"),
+        ]).unwrap();
+
+        assert_eq!(*RECORDS.lock().unwrap(), vec![
+            (::log::Level::Error, String::from("test"),
+                String::from("error: Mismatched types\n --> main.test:1:12\n1 | let main = 23;\n  |            ^^")),
+            (::log::Level::Warn, String::from("test"), String::from("warning: Unused value")),
+        ]);
+    }
+}
@@ -0,0 +1,219 @@
+//! Conversions between this crate's diagnostics and the types of the
+//! [Language Server Protocol], as defined by the [`lsp_types`] crate.
+//!
+//! Positions in the Language Server Protocol use UTF-16 code units for their
+//! columns, so all conversions need access to the source code through the [`Files`] trait.
+//!
+//! [Language Server Protocol]: https://microsoft.github.io/language-server-protocol/
+//! [`Files`]: Files
+
+use std::ops::Range;
+use lsp_types::{CodeDescription, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString, Position, Url};
+use crate::diagnostic::{Annotation, AnnotationStyle, Diagnostic, Note, Severity};
+use crate::file::{Error, Files};
+
+impl From<Severity> for DiagnosticSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Bug | Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Note => DiagnosticSeverity::INFORMATION,
+            Severity::Help => DiagnosticSeverity::HINT,
+        }
+    }
+}
+
+impl TryFrom<DiagnosticSeverity> for Severity {
+    /// The unknown severity.
+    type Error = DiagnosticSeverity;
+
+    fn try_from(severity: DiagnosticSeverity) -> Result<Self, DiagnosticSeverity> {
+        match severity {
+            DiagnosticSeverity::ERROR => Ok(Severity::Error),
+            DiagnosticSeverity::WARNING => Ok(Severity::Warning),
+            DiagnosticSeverity::INFORMATION => Ok(Severity::Note),
+            DiagnosticSeverity::HINT => Ok(Severity::Help),
+            severity => Err(severity),
+        }
+    }
+}
+
+/// Returns the LSP position (with a UTF-16 column) of a byte index in a file.
+pub fn position<F: Files>(files: &F, file_id: F::FileId, byte_index: usize) -> Result<Position, Error> {
+    let source = files.source(file_id.clone())?;
+
+    if byte_index > source.len() {
+        return Err(Error::IndexTooLarge { given: byte_index, max: source.len() });
+    } else if !source.is_char_boundary(byte_index) {
+        return Err(Error::InvalidCharBoundary { given: byte_index });
+    }
+
+    let line_index = files.line_index(file_id.clone(), byte_index)?;
+    let line_start = files.line_range(file_id, line_index)?.start;
+    let character = source[line_start..byte_index].encode_utf16().count();
+
+    Ok(Position::new(line_index as u32, character as u32))
+}
+
+/// Returns the byte index of an LSP position (with a UTF-16 column) in a file.
+///
+/// As specified by the Language Server Protocol, a column past the end of the line
+/// refers to the end of the line.
+pub fn byte_index<F: Files>(files: &F, file_id: F::FileId, position: Position) -> Result<usize, Error> {
+    let source = files.source(file_id.clone())?;
+    let line_range = files.line_range(file_id, position.line as usize)?;
+    let line = source[line_range.clone()].trim_end_matches(['\n', '\r']);
+
+    let mut character = 0;
+
+    for (i, c) in line.char_indices() {
+        if character >= position.character as usize {
+            return Ok(line_range.start + i);
+        }
+
+        character += c.len_utf16();
+    }
+
+    Ok(line_range.start + line.len())
+}
+
+/// Returns the LSP range (with UTF-16 columns) of a byte range in a file.
+pub fn range<F: Files>(files: &F, file_id: F::FileId, range: Range<usize>) -> Result<lsp_types::Range, Error> {
+    Ok(lsp_types::Range::new(position(files, file_id.clone(), range.start)?, position(files, file_id, range.end)?))
+}
+
+/// Converts a diagnostic into an LSP diagnostic.
+///
+/// The range of the LSP diagnostic is the range of the [primary annotation]; its file is returned
+/// alongside the LSP diagnostic, as this is the document the diagnostic should be published for.
+/// All other annotations become related information, using `uri` to get the URI of their file.
+/// Notes are appended to the message on separate lines. The URL of the diagnostic
+/// becomes the code description, if it is a valid URL.
+///
+/// Returns `None` if the diagnostic has no annotations, as LSP diagnostics always need a range.
+///
+/// [primary annotation]: Diagnostic::primary_annotation
+pub fn to_lsp<F: Files>(diagnostic: &Diagnostic<F::FileId>, files: &F, mut uri: impl FnMut(F::FileId) -> Result<Url, Error>)
+    -> Result<Option<(F::FileId, lsp_types::Diagnostic)>, Error> {
+    let primary = match diagnostic.primary_annotation() {
+        Some(primary) => primary,
+        None => return Ok(None),
+    };
+
+    let mut message = diagnostic.message.clone();
+
+    for note in diagnostic.notes.iter() {
+        message.push('\n');
+        message.push_str(&format!("{}: {}", note.prefix_or_severity(), note.message));
+    }
+
+    let mut related_information = Vec::new();
+
+    for annotation in diagnostic.annotations.iter() {
+        if std::ptr::eq(annotation, primary) {
+            continue;
+        }
+
+        related_information.push(DiagnosticRelatedInformation {
+            location: Location::new(uri(annotation.file_id.clone())?, range(files, annotation.file_id.clone(), annotation.range.clone())?),
+            message: annotation.label.clone(),
+        });
+    }
+
+    Ok(Some((primary.file_id.clone(), lsp_types::Diagnostic {
+        range: range(files, primary.file_id.clone(), primary.range.clone())?,
+        severity: Some(diagnostic.severity.into()),
+        code: diagnostic.name.clone().map(NumberOrString::String),
+        code_description: diagnostic.url.as_ref().and_then(|url| Url::parse(url).ok()).map(|href| CodeDescription { href }),
+        message,
+        related_information: if related_information.is_empty() { None } else { Some(related_information) },
+        ..Default::default()
+    })))
+}
+
+/// Converts an LSP diagnostic that was published for the file `file_id` into a diagnostic.
+///
+/// The range of the LSP diagnostic becomes a primary annotation without a label.
+/// Related information becomes secondary annotations, using `file_id_of` to find the
+/// file of their URI.
+///
+/// Diagnostics without a severity are treated as errors. Unknown severities
+/// are converted to notes.
+pub fn from_lsp<F: Files>(diagnostic: &lsp_types::Diagnostic, file_id: F::FileId, files: &F, mut file_id_of: impl FnMut(&Url) -> Result<F::FileId, Error>)
+    -> Result<Diagnostic<F::FileId>, Error> {
+    let severity = diagnostic.severity.map(|severity| Severity::try_from(severity).unwrap_or(Severity::Note)).unwrap_or(Severity::Error);
+    let mut result = Diagnostic::new(severity)
+        .with_message(&diagnostic.message)
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, file_id.clone(),
+            byte_index(files, file_id.clone(), diagnostic.range.start)?..byte_index(files, file_id, diagnostic.range.end)?));
+
+    result.name = diagnostic.code.as_ref().map(|code| match code {
+        NumberOrString::Number(number) => number.to_string(),
+        NumberOrString::String(string) => string.clone(),
+    });
+
+    result.url = diagnostic.code_description.as_ref().map(|description| description.href.to_string());
+
+    for information in diagnostic.related_information.iter().flatten() {
+        let related_file_id = file_id_of(&information.location.uri)?;
+        let range = information.location.range;
+
+        result = result.with_annotation(Annotation::new(AnnotationStyle::Secondary, related_file_id.clone(),
+            byte_index(files, related_file_id.clone(), range.start)?..byte_index(files, related_file_id, range.end)?)
+            .with_label(&information.message));
+    }
+
+    if let Some(source) = diagnostic.source.as_ref() {
+        result = result.with_note(Note::note(format!("reported by {}", source)));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use crate::file::SimpleFiles;
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        let mut files = SimpleFiles::new();
+        let file = files.add("test_file.test", "let a = 1;\nlet 🗻 = \"∈\";\n");
+
+        assert_eq!(position(&files, file, 0).unwrap(), Position::new(0, 0));
+        assert_eq!(position(&files, file, 15).unwrap(), Position::new(1, 4));
+        // "🗻" is 4 bytes in UTF-8, but 2 code units in UTF-16
+        assert_eq!(position(&files, file, 19).unwrap(), Position::new(1, 6));
+        assert_eq!(byte_index(&files, file, Position::new(1, 6)).unwrap(), 19);
+        assert_eq!(byte_index(&files, file, Position::new(0, 100)).unwrap(), 10);
+        assert!(position(&files, file, 16).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut files = SimpleFiles::new();
+        let main = files.add("main.test", "let main = 23;\nsomething += 3.0;\n");
+        let other = files.add("other.test", "let other = 1;\n");
+        let uri = |file_id: usize| Ok(Url::parse(&format!("file:///{}", files.get(file_id)?.name())).expect("invalid URL"));
+
+        let diagnostic = Diagnostic::new(Severity::Warning)
+            .with_name("W001")
+            .with_url("https://example.com/W001")
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 28..31))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, other, 4..9)
+                .with_label("declared here"));
+
+        let (file_id, lsp_diagnostic) = to_lsp(&diagnostic, &files, uri).unwrap().unwrap();
+        assert_eq!(file_id, main);
+        assert_eq!(lsp_diagnostic.range, lsp_types::Range::new(Position::new(1, 13), Position::new(1, 16)));
+        assert_eq!(lsp_diagnostic.severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(lsp_diagnostic.code, Some(NumberOrString::String(String::from("W001"))));
+
+        let result = from_lsp(&lsp_diagnostic, file_id, &files, |url| {
+            if url.path() == "/other.test" { Ok(other) } else { Err(Error::FileMissing) }
+        }).unwrap();
+        assert_eq!(result, diagnostic);
+    }
+}
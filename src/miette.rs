@@ -0,0 +1,108 @@
+//! Conversions from the diagnostics of [`miette`].
+//!
+//! This allows application errors built with `miette` to be rendered with this crate's layout.
+//!
+//! `miette` diagnostics carry their own source code instead of referring to a file,
+//! so the file ID of all annotations has to be given when converting. Spans are
+//! byte ranges in both crates, so they can be used as they are.
+//!
+//! # Example
+//! ```rust
+//! use diagnostic_render::diagnostic::Diagnostic;
+//! use diagnostic_render::miette::from_miette;
+//!
+//! let error = miette::MietteDiagnostic::new("Mismatched types")
+//!     .with_code("E0308")
+//!     .with_label(miette::LabeledSpan::at(11..13, "expected `String`"));
+//!
+//! let diagnostic: Diagnostic<()> = from_miette(&error, ());
+//! assert_eq!(diagnostic.name.as_deref(), Some("E0308"));
+//! assert_eq!(diagnostic.annotations[0].range, 11..13);
+//! ```
+
+use crate::diagnostic::{Annotation, AnnotationStyle, Diagnostic, Note, Severity};
+
+impl From<miette::Severity> for Severity {
+    fn from(severity: miette::Severity) -> Self {
+        match severity {
+            miette::Severity::Advice => Severity::Help,
+            miette::Severity::Warning => Severity::Warning,
+            miette::Severity::Error => Severity::Error,
+        }
+    }
+}
+
+/// Converts a `miette` diagnostic into a diagnostic, with all annotations referring to `file_id`.
+///
+/// The message is the [`Display`] output of the diagnostic, its code becomes the name,
+/// and its URL is kept as the URL of the diagnostic.
+/// Diagnostics without a severity are treated as errors.
+///
+/// Labels marked as primary become primary annotations. If no label is marked as primary,
+/// the first label is used as the primary annotation instead. The help text becomes a note.
+///
+/// Related diagnostics are not converted, as they may refer to a different file;
+/// they can be converted separately by iterating over [`related`].
+///
+/// [`Display`]: std::fmt::Display
+/// [`related`]: miette::Diagnostic::related
+pub fn from_miette<FileId: Clone>(diagnostic: &(impl miette::Diagnostic + ?Sized), file_id: FileId) -> Diagnostic<FileId> {
+    let mut result = Diagnostic::new(diagnostic.severity().map(Severity::from).unwrap_or(Severity::Error))
+        .with_message(diagnostic.to_string());
+    result.name = diagnostic.code().map(|code| code.to_string());
+
+    let labels = diagnostic.labels().map(|labels| labels.collect::<Vec<_>>()).unwrap_or_default();
+    let has_primary = labels.iter().any(|label| label.primary());
+
+    for (i, label) in labels.into_iter().enumerate() {
+        let style = if label.primary() || (!has_primary && i == 0) {
+            AnnotationStyle::Primary
+        } else {
+            AnnotationStyle::Secondary
+        };
+
+        let mut annotation = Annotation::new(style, file_id.clone(), label.offset()..label.offset() + label.len());
+
+        if let Some(label) = label.label() {
+            annotation = annotation.with_label(label);
+        }
+
+        result = result.with_annotation(annotation);
+    }
+
+    if let Some(help) = diagnostic.help() {
+        result = result.with_note(Note::help(help.to_string()));
+    }
+
+    result.url = diagnostic.url().map(|url| url.to_string());
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::{LabeledSpan, MietteDiagnostic};
+    use pretty_assertions::assert_eq;
+    use super::*;
+
+    #[test]
+    fn test_from_miette() {
+        let error = MietteDiagnostic::new("Mismatched types")
+            .with_code("E0308")
+            .with_severity(miette::Severity::Warning)
+            .with_labels(vec![
+                LabeledSpan::at(4..8, "declared here"),
+                LabeledSpan::new_primary_with_span(Some(String::from("expected `String`")), 11..13),
+            ])
+            .with_help("Change something")
+            .with_url("https://example.com/E0308");
+
+        assert_eq!(from_miette(&error, ()), Diagnostic::warning()
+            .with_name("E0308")
+            .with_message("Mismatched types")
+            .with_annotation(Annotation::secondary((), 4..8).with_label("declared here"))
+            .with_annotation(Annotation::primary((), 11..13).with_label("expected `String`"))
+            .with_url("https://example.com/E0308")
+            .with_note(Note::help("Change something")));
+    }
+}
@@ -0,0 +1,163 @@
+//! Contains the [`RenderBackend`] trait, for drawing diagnostics with something other
+//! than a [`WriteColor`], like TUI widgets or test recorders, while reusing the layout
+//! of the text renderer.
+//!
+//! [`render_with_backend`] walks diagnostics and calls the methods of a backend for every part
+//! of them. The [`DiagnosticRenderer`] is not implemented on top of this trait: it has its own
+//! walker, and the two only share the layout computed by [`layout`]. A backend therefore does not
+//! receive everything the text renderer draws. It gets the lines of source code that annotations
+//! start or end on, the rows of annotations below them, and the notes. Surrounding lines,
+//! lines between the start and end of multi-line annotations, and suggestions are not passed to
+//! backends, and [`RenderConfig::display_style`] is ignored.
+//!
+//! [`DiagnosticRenderer`]: crate::render::DiagnosticRenderer
+//! [`layout`]: crate::render::layout::layout
+//! [`RenderConfig::display_style`]: crate::render::RenderConfig::display_style
+//! [`RenderBackend`]: RenderBackend
+//! [`WriteColor`]: crate::render::style::WriteColor
+//! [`render_with_backend`]: render_with_backend
+
+use std::fmt::Debug;
+use crate::diagnostic::{AnnotationStyle, Diagnostic, Note};
+use crate::file::{Files, Location};
+use crate::render::data::{AnnotationData, ConnectingSinglelineAnnotationData, LabelAnnotationLineData};
+use crate::render::layout::{layout, RenderedLine};
+use crate::render::{location, FilesExt, RenderConfig, Result};
+
+/// Receives the parts of diagnostics from [`render_with_backend`].
+///
+/// All methods do nothing by default, so backends only have to implement the ones for
+/// the parts they draw.
+///
+/// [`render_with_backend`]: render_with_backend
+pub trait RenderBackend<FileId> {
+    /// Called at the start of a diagnostic, before any of its parts.
+    ///
+    /// Sub-diagnostics are started after the notes of their parent, and before it ends.
+    fn start_diagnostic(&mut self, _diagnostic: &Diagnostic<FileId>) -> Result {
+        Ok(())
+    }
+
+    /// Called before the first line of source code of every file a diagnostic has annotations in.
+    ///
+    /// `location` is the location of the primary annotation in this file, or of its first
+    /// annotation if it contains no primary annotations.
+    fn file(&mut self, _file_id: &FileId, _name: &str, _location: Location) -> Result {
+        Ok(())
+    }
+
+    /// Called for every line of source code that an annotation starts or ends on.
+    ///
+    /// `text` is the line without its line ending. The columns in the rows of `line` are
+    /// display columns, so tabs count as [`RenderConfig::tab_width`] columns, and wide
+    /// characters as two.
+    ///
+    /// [`RenderConfig::tab_width`]: RenderConfig::tab_width
+    fn source_line(&mut self, _line: &RenderedLine<'_, FileId>, _text: &str) -> Result {
+        Ok(())
+    }
+
+    /// Called for every row of annotations below a line of source code, after [`Self::source_line`].
+    ///
+    /// By default, this calls [`Self::underline`] and [`Self::label`] for the underlines and
+    /// labels in this row.
+    ///
+    /// [`Self::source_line`]: Self::source_line
+    /// [`Self::underline`]: Self::underline
+    /// [`Self::label`]: Self::label
+    fn annotation_row(&mut self, row: &[AnnotationData<'_>]) -> Result {
+        for data in row.iter() {
+            match data {
+                AnnotationData::ConnectingSingleline(data) if !data.as_multiline => self.underline(data)?,
+                AnnotationData::Label(data) => self.label(data)?,
+                _ => {},
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Called for the underline of a single-line annotation.
+    fn underline(&mut self, _data: &ConnectingSinglelineAnnotationData) -> Result {
+        Ok(())
+    }
+
+    /// Called for the label of an annotation.
+    fn label(&mut self, _data: &LabelAnnotationLineData<'_>) -> Result {
+        Ok(())
+    }
+
+    /// Called for every note of a diagnostic, after all lines of source code.
+    fn note(&mut self, _note: &Note) -> Result {
+        Ok(())
+    }
+
+    /// Called at the end of a diagnostic, after its sub-diagnostics.
+    fn end_diagnostic(&mut self, _diagnostic: &Diagnostic<FileId>) -> Result {
+        Ok(())
+    }
+}
+
+/// Renders diagnostics by calling the methods of `backend` for their parts.
+///
+/// Like the text renderer, this skips diagnostics below [`RenderConfig::min_severity`], and
+/// notes and sub-diagnostics below it if [`RenderConfig::filter_children`] is set. The lines of
+/// source code are the ones returned by [`layout`], so surrounding lines are not included, and
+/// neither are suggestions. Unlike in the text renderer, lines between the start and end of
+/// a multi-line annotation are never folded, since they aren't passed to the backend at all.
+///
+/// [`RenderConfig::min_severity`]: RenderConfig::min_severity
+/// [`RenderConfig::filter_children`]: RenderConfig::filter_children
+/// [`layout`]: layout
+pub fn render_with_backend<B, F>(backend: &mut B, files: &F, config: &RenderConfig, diagnostics: &[Diagnostic<F::FileId>]) -> Result
+    where B: RenderBackend<F::FileId> + ?Sized, F: Files, F::FileId: Clone + Debug + Eq + Ord {
+    for diagnostic in diagnostics.iter().filter(|d| d.severity >= config.min_severity) {
+        render_diagnostic(backend, files, config, diagnostic)?;
+    }
+
+    Ok(())
+}
+
+fn render_diagnostic<B, F>(backend: &mut B, files: &F, config: &RenderConfig, diagnostic: &Diagnostic<F::FileId>) -> Result
+    where B: RenderBackend<F::FileId> + ?Sized, F: Files, F::FileId: Clone + Debug + Eq + Ord {
+    backend.start_diagnostic(diagnostic)?;
+
+    let primary_file = diagnostic.primary_annotation().map(|a| &a.file_id);
+    let mut current_file = None;
+
+    for line in layout(diagnostic, files, config)?.iter() {
+        if current_file != Some(&line.file_id) {
+            // Like in the text renderer, the header of the primary file shows the location of its
+            // primary annotation, and the headers of other files the location of their first annotation
+            let mut annotations = diagnostic.annotations.iter().filter(|a| a.file_id == line.file_id);
+            let byte_index = if primary_file == Some(&line.file_id) {
+                annotations.find(|a| a.style == AnnotationStyle::Primary)
+            } else {
+                annotations.min_by_key(|a| (a.style, a.range.start))
+            }.map_or(0, |a| a.range.start);
+
+            backend.file(&line.file_id, files.file_name(&line.file_id)?,
+                location(files, config, &line.file_id, byte_index)?)?;
+            current_file = Some(&line.file_id);
+        }
+
+        let source = files.file_source(&line.file_id)?;
+        let range = files.file_line_range(&line.file_id, line.line_index)?;
+        let text = source.get(range).unwrap_or_default().trim_end_matches(['\n', '\r']);
+        backend.source_line(line, text)?;
+
+        for row in line.rows.iter() {
+            backend.annotation_row(row)?;
+        }
+    }
+
+    for note in diagnostic.notes.iter().filter(|note| !config.filter_children || note.severity >= config.min_severity) {
+        backend.note(note)?;
+    }
+
+    for sub_diagnostic in diagnostic.sub_diagnostics.iter().filter(|d| !config.filter_children || d.severity >= config.min_severity) {
+        render_diagnostic(backend, files, config, sub_diagnostic)?;
+    }
+
+    backend.end_diagnostic(diagnostic)
+}
@@ -4,14 +4,38 @@
 //! [`AnnotationData`]: AnnotationData
 
 use std::fmt::Debug;
+use unicode_segmentation::UnicodeSegmentation;
 use crate::diagnostic::{Annotation, Diagnostic};
 use crate::file::{Error, Files};
 use crate::render::data::{AnnotationData, ConnectingMultilineAnnotationData, ConnectingSinglelineAnnotationData, ContinuingMultilineAnnotationData, EndAnnotationLineData, HangingAnnotationLineData, LabelAnnotationLineData, StartAnnotationLineData, StartEndAnnotationData};
-use crate::render::LineColumn;
+use crate::render::{LineColumn, RenderConfig, Window};
+use crate::render::text;
+
+pub fn calculate<'a, FileId: Clone + Debug>(diagnostic: &Diagnostic<FileId>, files: &impl Files<FileId=FileId>, file: &FileId,
+                                           line_index: usize,
+                                           annotations: &[(usize, &'a Annotation<FileId>)], continuing_annotations: &[(usize, &Annotation<FileId>)],
+                                           config: &RenderConfig) -> Result<Vec<Vec<AnnotationData<'a>>>, Error> {
+    calculate_windowed(diagnostic, files, file, line_index, annotations, continuing_annotations, config, None)
+}
+
+/// Like [`calculate`], but with the columns converted into the columns they are displayed at
+/// in `window`. Laying out the annotations after this conversion keeps labels of annotations
+/// that were moved together from overlapping.
+///
+/// [`calculate`]: calculate
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn calculate_windowed<'a, FileId: Clone + Debug>(diagnostic: &Diagnostic<FileId>, files: &impl Files<FileId=FileId>, file: &FileId,
+                                                           line_index: usize,
+                                                           annotations: &[(usize, &'a Annotation<FileId>)], continuing_annotations: &[(usize, &Annotation<FileId>)],
+                                                           config: &RenderConfig, window: Option<&Window>) -> Result<Vec<Vec<AnnotationData<'a>>>, Error> {
+    let source = files.source(file.clone())?;
+    let line_range = files.line_range(file.clone(), line_index)?;
+    let line_start = line_range.start;
+    // The width in columns of the source line up to a byte index on that line
+    let width_to = |byte_index: usize| source.get(line_start..byte_index.max(line_start))
+        .map(|text| text::width(text, config))
+        .ok_or(Error::InvalidCharBoundary { given: byte_index });
 
-pub fn calculate<FileId: Copy + Debug>(diagnostic: &Diagnostic<FileId>, files: &impl Files<FileId=FileId>, file: FileId,
-                                       line_index: usize,
-                                       annotations: &[&Annotation<FileId>], continuing_annotations: &[&Annotation<FileId>]) -> Result<Vec<Vec<AnnotationData>>, Error> {
     // Create a list of the start and end points of annotations on the source line.
     // Every element is a tuple of a reference to the annotation, and its start / end data
     //
@@ -23,39 +47,65 @@ pub fn calculate<FileId: Copy + Debug>(diagnostic: &Diagnostic<FileId>, files: &
     // Every annotation falls in one of these categories, because `annotations` only includes
     // such annotations in the first place.
     let mut starts_ends = annotations.iter()
-        .fold(Result::<_, Error>::Ok(Vec::new()), |acc, &a| {
-            if let Ok(mut acc) = acc {
-                let start = files.line_index(file, a.range.start)?;
-                let end = files.line_index(file, a.range.end)?;
-
-                // Either start or end has to match line_index
-                let start_part = if start == line_index {
-                    Some(StartAnnotationLineData {
-                        style: a.style,
-                        severity: diagnostic.severity,
-                        location: LineColumn::new(line_index, a.range.start - files.line_range(file, start)?.start),
-                    })
-                } else { None };
+        .try_fold(Vec::new(), |mut acc, &(annotation_index, a)| {
+            let start = files.line_index(file.clone(), a.range.start)?;
+            let end = files.line_index(file.clone(), a.range.end)?;
+            let mut start_column_index = width_to(a.range.start)?;
+
+            // The end column is inclusive, so it is the column before the end byte index.
+            // Zero-width annotations point at a single column instead, and end where they start.
+            let mut end_column_index = if a.range.is_empty() {
+                start_column_index
+            } else {
+                width_to(a.range.end)?.saturating_sub(1)
+            };
 
-                let end_part = if end == line_index {
-                    Some(EndAnnotationLineData {
-                        style: a.style,
-                        severity: diagnostic.severity,
-                        location: LineColumn::new(line_index, (a.range.end - files.line_range(file, end)?.start).saturating_sub(1)),
-                    })
-                } else { None };
-
-                match (start_part, end_part) {
-                    (Some(start_part), Some(end_part)) => acc.push((a, StartEndAnnotationData::Both(start_part, end_part))),
-                    (Some(start_part), _) => acc.push((a, StartEndAnnotationData::Start(start_part))),
-                    (_, Some(end_part)) => acc.push((a, StartEndAnnotationData::End(end_part))),
-                    _ => panic!("Annotation neither starts nor ends in this line, despite previous check"),
-                };
+            // Annotations only covering characters that don't take up any columns, like combining
+            // marks, are widened to the grapheme cluster they are part of, so they still get a caret
+            if start == line_index && end == line_index && !a.range.is_empty() && width_to(a.range.end)? == start_column_index {
+                let line = source.get(line_range.clone()).ok_or(Error::InvalidCharBoundary { given: a.range.start })?;
+                let (cluster_start, cluster) = line.grapheme_indices(true)
+                    .take_while(|&(i, _)| line_start + i <= a.range.start)
+                    .last().unwrap_or((0, ""));
 
-                Ok(acc)
-            } else {
-                acc
+                start_column_index = width_to(line_start + cluster_start)?;
+                end_column_index = start_column_index + text::width(cluster, config).max(1) - 1;
+            }
+
+            if let Some(window) = window {
+                start_column_index = window.column(start_column_index);
+                end_column_index = window.column(end_column_index);
             }
+
+            // Either start or end has to match line_index
+            let start_part = if start == line_index {
+                Some(StartAnnotationLineData {
+                    style: a.style,
+                    severity: diagnostic.severity,
+                    annotation_index,
+                    location: LineColumn::new(line_index, start_column_index),
+                })
+            } else { None };
+
+            let end_part = if end == line_index {
+                Some(EndAnnotationLineData {
+                    style: a.style,
+                    severity: diagnostic.severity,
+                    annotation_index,
+                    location: LineColumn::new(line_index, end_column_index),
+                })
+            } else { None };
+
+            match (start_part, end_part) {
+                (Some(start_part), Some(end_part)) => acc.push((a, StartEndAnnotationData::Both(start_part, end_part))),
+                (Some(start_part), _) => acc.push((a, StartEndAnnotationData::Start(start_part))),
+                (_, Some(end_part)) => acc.push((a, StartEndAnnotationData::End(end_part))),
+                // Annotations that neither start nor end on this line are continuing annotations,
+                // which are not part of `annotations`. Ignore them if they are passed anyway.
+                (None, None) => {},
+            };
+
+            Ok::<_, Error>(acc)
         })?;
     // Sort the start / end data by column index (ascending).
     // For the "both" variant, the start column index is used.
@@ -70,15 +120,49 @@ pub fn calculate<FileId: Copy + Debug>(diagnostic: &Diagnostic<FileId>, files: &
 
     // eprintln!("[debug] {:#?}", &starts_ends);
 
+    // Only the last annotation can have its label next to its underline, so a prioritized
+    // annotation is moved to the end
+    let prioritized = prioritized_annotation(&starts_ends);
+
+    if let Some(i) = prioritized {
+        let annotation = starts_ends.remove(i);
+        starts_ends.push(annotation);
+    }
+
     // Calculate vertical offsets
-    let vertical_offsets = calculate_vertical_offsets(&starts_ends)?;
+    let vertical_offsets = calculate_vertical_offsets(&starts_ends, prioritized.is_some())?;
     // eprintln!("[debug] vertical offsets: {:?}", &vertical_offsets);
+    let vertical_offsets = make_room_for_labels(&starts_ends, vertical_offsets);
 
     let final_data = calculate_final_data(diagnostic, files, file, line_index, &starts_ends, vertical_offsets, continuing_annotations)?;
     Ok(final_data)
 }
 
-fn calculate_vertical_offsets<FileId: Copy + Debug>(starts_ends: &[(&Annotation<FileId>, StartEndAnnotationData)]) -> Result<Vec<u32>, Error> {
+/// Returns the index of the single-line annotation with a label whose priority is higher
+/// than the one of all other annotations on the line, if no other annotation reaches
+/// further to the right than it.
+fn prioritized_annotation<FileId>(starts_ends: &[(&Annotation<FileId>, StartEndAnnotationData)]) -> Option<usize> {
+    let (i, (a, start_end)) = starts_ends.iter().enumerate().max_by_key(|(_, (a, _))| a.priority)?;
+
+    let StartEndAnnotationData::Both(_, end) = start_end else {
+        return None;
+    };
+
+    let rightmost = |start_end: &StartEndAnnotationData| match start_end {
+        StartEndAnnotationData::Start(start) => start.location.column_index,
+        StartEndAnnotationData::End(end) | StartEndAnnotationData::Both(_, end) => end.location.column_index,
+    };
+
+    let is_valid = !a.label.is_empty() && starts_ends.iter().enumerate()
+        .filter(|&(j, _)| j != i)
+        .all(|(_, (other, other_start_end))| other.priority < a.priority && rightmost(other_start_end) <= end.location.column_index);
+
+    is_valid.then_some(i)
+}
+
+/// `prioritized` is whether the last annotation was moved there because of its priority,
+/// in which case it gets vertical offset 0 even if other annotations overlap with it.
+fn calculate_vertical_offsets<FileId: Clone + Debug>(starts_ends: &[(&Annotation<FileId>, StartEndAnnotationData)], prioritized: bool) -> Result<Vec<u32>, Error> {
     let mut vertical_offsets = vec![0u32; starts_ends.len()];
     let mut next_vertical_offset: u32 = 0;
     let mut processed = vec![false; starts_ends.len()];
@@ -151,8 +235,10 @@ fn calculate_vertical_offsets<FileId: Copy + Debug>(starts_ends: &[(&Annotation<
                 if next_vertical_offset == 0 {
                     // Iterate through starts_ends again (same order, in reverse)
                     // The last one has to be skipped, as that is definitely this one
-                    // and will make the condition always match
-                    for (_j, (_, start_end_2)) in starts_ends.iter().enumerate().rev().skip(1) {
+                    // and will make the condition always match.
+                    // A prioritized annotation doesn't reach past the end of the last one,
+                    // so the label can stay next to its underline.
+                    for (_j, (_, start_end_2)) in starts_ends.iter().enumerate().rev().skip(1).filter(|_| !prioritized) {
                         let end = match start_end_2 {
                             // If one of these ends after the rightmost single-line annotation,
                             // increase vertical_offset by 1 for all annotations
@@ -202,11 +288,10 @@ fn calculate_vertical_offsets<FileId: Copy + Debug>(starts_ends: &[(&Annotation<
         // because this changes the indices, the index of the corresponding annotations
         // in the starts_ends vector was saved with the byte index in a tuple above
         start_byte_indices.retain(|a| a.is_some());
-        // Unwrap all the Option values.
-        // This shouldn't panic, as we have removed all None elements before.
-        let mut starts = start_byte_indices.into_iter().map(|a| a.expect("`None` despite previous check")).collect::<Vec<_>>();
+        // Unwrap all the Option values, which are all `Some` after removing the `None` elements above.
+        let mut starts = start_byte_indices.into_iter().flatten().collect::<Vec<_>>();
         // Sort by start byte index (ascending)
-        starts.sort_unstable_by(|(_, a, _), (_, b, _)| a.cmp(b));
+        starts.sort_unstable_by_key(|(_, a, _)| *a);
 
         // Iterates through all multi-line annotations ending on this line in
         // descending start byte index order, to be able to assign lower vertical offsets
@@ -317,9 +402,34 @@ fn calculate_vertical_offsets<FileId: Copy + Debug>(starts_ends: &[(&Annotation<
     //    | |                                                     a parameter list
     for (i, (_, start_end)) in starts_ends.iter().enumerate() {
         match start_end {
-            StartEndAnnotationData::Start(_) => {
-                assert!(next_start_vertical_offset < end_offset_for_start, "assertion failed: next_start_vertical_offset < end_offset_for_start\n\
-                next_start_vertical_offset: {}, end_offset_for_start: {}, static_offset_from_start: {}", next_start_vertical_offset, end_offset_for_start, static_offset_from_start);
+            StartEndAnnotationData::Start(start) => {
+                if next_start_vertical_offset >= end_offset_for_start && can_connect_on_underline(starts_ends, i) {
+                    // A starting annotation before all other ones can run over to the left on the
+                    // line used for underlines, like a single one, which leaves the space after
+                    // the underlines to the other annotations and their labels:
+                    // 1 |   let main = other(23,
+                    //   |  ____________^---- --
+                    //   | |            |     |
+                    //   | |            |     argument
+                    //   | |            function
+                    vertical_offsets[i] = 0;
+                    processed[i] = true;
+                    continue;
+                } else if next_start_vertical_offset >= end_offset_for_start {
+                    // The space given to starting annotations by the static offset is already
+                    // used by other annotations, which can happen for unusual combinations of
+                    // annotations. Instead of overlapping with those, fall back to the vertical
+                    // offsets after all other annotations, and after the labels directly next to
+                    // the connecting line, so that they don't look like its label:
+                    // 2 | | fn b(x) {
+                    //   | |    --
+                    //   | |____||
+                    //   |      |another thing
+                    //   |  ____|
+                    next_start_vertical_offset = next_start_vertical_offset.max(next_vertical_offset)
+                        .max(first_offset_after_adjacent_labels(starts_ends, &vertical_offsets, start.location.column_index));
+                    end_offset_for_start = u32::MAX;
+                }
 
                 vertical_offsets[i] = next_start_vertical_offset;
                 next_start_vertical_offset += 1;
@@ -330,27 +440,100 @@ fn calculate_vertical_offsets<FileId: Copy + Debug>(starts_ends: &[(&Annotation<
         }
     }
 
-    // Assert that all annotations have been given a vertical offset
-    // (so that it is false that any annotation has not been given one)
-    assert!(!processed.into_iter().any(|x| !x), "an annotation has not been given a vertical offset");
+    // All annotations should have been given a vertical offset by now. If any of them
+    // haven't, place them after all other annotations instead of overlapping with them.
+    let fallback_vertical_offsets = vertical_offsets.iter().max().map_or(0, |offset| offset + 1)..;
+    let unprocessed = processed.into_iter().enumerate().filter(|(_, processed)| !processed).map(|(i, _)| i).collect::<Vec<_>>();
+
+    for (i, vertical_offset) in unprocessed.into_iter().zip(fallback_vertical_offsets) {
+        vertical_offsets[i] = vertical_offset;
+    }
+
     Ok(vertical_offsets)
 }
 
-fn calculate_final_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, files: &impl Files<FileId=FileId>, file: FileId,
-                                      line_index: usize,
-                                      starts_ends: &[(&Annotation<FileId>, StartEndAnnotationData)],
-                                      mut vertical_offsets: Vec<u32>,
-                                      continuing_annotations: &[&Annotation<FileId>]) -> Result<Vec<Vec<AnnotationData>>, Error> {
+/// Returns whether the starting annotation at index `i` can connect with its continuing
+/// vertical bar on the line used for underlines, which is the case if no other annotation
+/// starts before it, and no annotation ends on this line after starting on a previous one.
+fn can_connect_on_underline<FileId>(starts_ends: &[(&Annotation<FileId>, StartEndAnnotationData)], i: usize) -> bool {
+    let StartEndAnnotationData::Start(start) = &starts_ends[i].1 else {
+        return false;
+    };
+
+    starts_ends.iter().enumerate()
+        .filter(|&(j, _)| j != i)
+        .all(|(j, (_, start_end))| match start_end {
+            StartEndAnnotationData::Start(other) => other.location.column_index > start.location.column_index
+                || (other.location.column_index == start.location.column_index && j > i),
+            StartEndAnnotationData::Both(other, _) => other.location.column_index >= start.location.column_index,
+            // Its vertical bar would have to cross the one of the starting annotation
+            StartEndAnnotationData::End(_) => false,
+        })
+}
+
+/// Returns the first vertical offset below the labels of ending and single-line annotations
+/// that start at `column_index` or the column after it.
+fn first_offset_after_adjacent_labels<FileId>(starts_ends: &[(&Annotation<FileId>, StartEndAnnotationData)], vertical_offsets: &[u32], column_index: usize) -> u32 {
+    starts_ends.iter().zip(vertical_offsets.iter())
+        .filter_map(|((a, start_end), &offset)| {
+            let label_column_index = match start_end {
+                StartEndAnnotationData::End(end) => end.location.column_index,
+                StartEndAnnotationData::Both(start, _) => start.location.column_index,
+                StartEndAnnotationData::Start(_) => return None,
+            };
+
+            (offset > 0 && !a.label.is_empty() && (column_index..=column_index + 1).contains(&label_column_index))
+                .then(|| offset + label_lines(a) + 1)
+        })
+        .max().unwrap_or(0)
+}
+
+/// Returns the number of lines of the label of an annotation.
+fn label_lines<FileId>(annotation: &Annotation<FileId>) -> u32 {
+    annotation.label.lines().count() as u32
+}
+
+/// Moves annotations down to make room for the additional lines of multi-line labels
+/// above them.
+///
+/// Labels are shown in the rows after the vertical offset of their annotation, or next to
+/// the underline for the last annotation with vertical offset 0. Every further line of a label
+/// takes up another row, so all annotations with a greater vertical offset are moved down
+/// by that many rows, which keeps their hanging bars running past the label.
+fn make_room_for_labels<FileId>(starts_ends: &[(&Annotation<FileId>, StartEndAnnotationData)], vertical_offsets: Vec<u32>) -> Vec<u32> {
+    let last = starts_ends.len().saturating_sub(1);
+    let additional_lines = starts_ends.iter().zip(vertical_offsets.iter()).enumerate()
+        .map(|(i, ((a, start_end), &offset))| match start_end {
+            // Starting annotations don't have labels on this line
+            StartEndAnnotationData::Start(_) => 0,
+            _ if offset == 0 && i != last => 0,
+            _ => label_lines(a).saturating_sub(1),
+        })
+        .collect::<Vec<_>>();
+
+    vertical_offsets.iter().map(|&offset| {
+        offset + vertical_offsets.iter().zip(additional_lines.iter())
+            .filter(|(&other, _)| other < offset)
+            .map(|(_, &lines)| lines)
+            .sum::<u32>()
+    }).collect()
+}
+
+fn calculate_final_data<'a, FileId: Clone>(diagnostic: &Diagnostic<FileId>, files: &impl Files<FileId=FileId>, file: &FileId,
+                                          line_index: usize,
+                                          starts_ends: &[(&'a Annotation<FileId>, StartEndAnnotationData)],
+                                          mut vertical_offsets: Vec<u32>,
+                                          continuing_annotations: &[(usize, &Annotation<FileId>)]) -> Result<Vec<Vec<AnnotationData<'a>>>, Error> {
     // Create a sorted vector with the vertical offsets (and an index into starts_ends)
     let mut vertical_offsets_sorted = vertical_offsets.iter().enumerate()
         .map(|(i, offset)| (i, *offset)).collect::<Vec<_>>();
-    vertical_offsets_sorted.sort_by(|(_, a), (_, b)| a.cmp(b)); // sort by the vertical offset
+    vertical_offsets_sorted.sort_by_key(|(_, a)| *a); // sort by the vertical offset
     // How many elements from the start of continuing_annotations to take.
     // Exclusive, the index referred to is not included.
     let mut continuing_end_index: usize = 0;
 
-    for (i, a) in continuing_annotations.iter().enumerate() {
-        let start_line_index = files.line_index(file, a.range.start)?;
+    for (i, (_, a)) in continuing_annotations.iter().enumerate() {
+        let start_line_index = files.line_index(file.clone(), a.range.start)?;
 
         // Once we reach a continuing annotation that started on this line,
         // all the ones after it in the vector should start later too, so we can stop here.
@@ -410,38 +593,43 @@ fn calculate_final_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, files: &i
     // data (always Start, ConnectingSingleline, End, with nothing in between).
     // However, for intersecting annotations, the Start data of one has to appear before
     // the End data of the last for rendering this properly.
+    // The vertical bars of multi-line annotations are left of all columns, so they have to
+    // come first even when other data is at column 0.
     for data in final_data.iter_mut() {
-        data.sort_by_key(|a| a.start_column_index());
+        data.sort_by_key(|a| (a.start_column_index(),
+                              !matches!(a, AnnotationData::ContinuingMultiline(_) | AnnotationData::ConnectingMultiline(_))));
     }
 
     Ok(final_data)
 }
 
 #[allow(clippy::too_many_arguments)]
-fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _files: &impl Files<FileId=FileId>, _file: FileId,
-                                            line_index: usize, vertical_index: u32,
-                                            continuing_annotations: &[&Annotation<FileId>], continuing_end_index: &mut usize,
-                                            additional_continuing_indices: &mut Vec<usize>,
-                                            starts_ends: &[(&Annotation<FileId>, StartEndAnnotationData)],
-                                            vertical_offsets: &mut [u32],
-                                            already_connected: &mut [bool]) -> Result<Vec<AnnotationData>, Error> {
+fn calculate_single_line_data<'a, FileId: Clone>(diagnostic: &Diagnostic<FileId>, _files: &impl Files<FileId=FileId>, _file: &FileId,
+                                                line_index: usize, vertical_index: u32,
+                                                continuing_annotations: &[(usize, &Annotation<FileId>)], continuing_end_index: &mut usize,
+                                                additional_continuing_indices: &mut Vec<usize>,
+                                                starts_ends: &[(&'a Annotation<FileId>, StartEndAnnotationData)],
+                                                vertical_offsets: &mut [u32],
+                                                already_connected: &mut [bool]) -> Result<Vec<AnnotationData<'a>>, Error> {
     // Create ContinuingMultiline data for the continuing vertical bars at the start.
     let mut data = continuing_annotations.iter().take(*continuing_end_index)
-        .fold(Vec::new(), |mut acc, a| {
+        .fold(Vec::new(), |mut acc, &(annotation_index, a)| {
             acc.push(AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: a.style,
                 severity: diagnostic.severity,
+                annotation_index,
                 vertical_bar_index: acc.len(),
             }));
             acc
         });
 
     for (i, index) in additional_continuing_indices.iter().enumerate() {
-        let (annotation, _) = &starts_ends[*index];
+        let (annotation, start_end) = &starts_ends[*index];
 
         data.push(AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
             style: annotation.style,
             severity: diagnostic.severity,
+            annotation_index: start_end.annotation_index(),
             vertical_bar_index: *continuing_end_index + i,
         }));
     }
@@ -509,7 +697,7 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
                     assert_ne!(*offset, to_offset);
 
                     *offset = next_vertical_offset;
-                    next_vertical_offset += 1;
+                    next_vertical_offset += label_lines(a);
                 }
             }
         }
@@ -522,6 +710,7 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
     //   add the hanging annotation data (the "|" over labels or for connecting lines) and labels
     //
     // In both cases, in can also add connecting lines.
+    let mut connected_starts = Vec::new();
     let mut data = vertical_offsets.iter().enumerate().fold(data, |mut acc, (i, &offset)| {
         let (annotation, start_end) = &starts_ends[i];
 
@@ -535,7 +724,8 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
                     acc.push(AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                         style: annotation.style,
                         severity: diagnostic.severity,
-                        end_location: start.location.clone(),
+                        annotation_index: start.annotation_index,
+                        end_location: start.location,
                         vertical_bar_index: *continuing_end_index + additional_continuing_indices.len(),
                     }));
                     additional_continuing_indices.push(i);
@@ -544,10 +734,15 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
                     // push_down_end = Some((i, vertical_index));
                 }
 
-                if vertical_index == 0 {
+                if vertical_index == 0 && offset == 0 {
+                    // The start boundary marker of an annotation connecting on the line used for
+                    // underlines. It is added after all other data, so that it is drawn over other
+                    // annotations starting in the same column after the connecting line.
+                    connected_starts.push(AnnotationData::Start(*start));
+                } else if vertical_index == 0 {
                     // A single start boundary marker. This should either have a connecting element
                     // either in this line or on a later one (with hanging elements ("|") in between)
-                    acc.push(AnnotationData::Start(start.clone()));
+                    acc.push(AnnotationData::Start(*start));
                 } else if offset >= vertical_index {
                     // eprintln!("[debug] adding hanging data; i: {}, vertical index: {}, offset: {} (start)", i, vertical_index, offset);
 
@@ -556,7 +751,8 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
                     acc.push(AnnotationData::Hanging(HangingAnnotationLineData {
                         style: annotation.style,
                         severity: diagnostic.severity,
-                        location: start.location.clone(),
+                        annotation_index: start.annotation_index,
+                        location: start.location,
                     }));
                 }
             },
@@ -567,7 +763,8 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
                     acc.push(AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                         style: annotation.style,
                         severity: diagnostic.severity,
-                        end_location: end.location.clone(),
+                        annotation_index: end.annotation_index,
+                        end_location: end.location,
                         vertical_bar_index: (*continuing_end_index + additional_continuing_indices.len()) - 1,
                     }));
                     *continuing_end_index -= 1;
@@ -581,18 +778,22 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
                 }
 
                 if vertical_index == 0 {
-                    acc.push(AnnotationData::End(end.clone()));
-                } else if offset != 0 && offset + 1 == vertical_index && !annotation.label.is_empty() {
+                    acc.push(AnnotationData::End(*end));
+                } else if offset != 0 && offset < vertical_index {
                     // eprintln!("[debug] adding label at index {} for offset {} (end)", vertical_index, offset);
 
-                    // If we're just under the continuing line and this annotation has a label, add it.
+                    // If we're just under the continuing line and this annotation has a label, add it,
+                    // with every further line of the label in the rows below.
                     // Note: further starting annotations are able to push vertical_offset further down.
-                    acc.push(AnnotationData::Label(LabelAnnotationLineData {
-                        style: annotation.style,
-                        severity: diagnostic.severity,
-                        location: end.location.clone(),
-                        label: annotation.label.clone(),
-                    }));
+                    if let Some(label) = annotation.label.lines().nth((vertical_index - offset - 1) as usize) {
+                        acc.push(AnnotationData::Label(LabelAnnotationLineData {
+                            style: annotation.style,
+                            severity: diagnostic.severity,
+                            annotation_index: end.annotation_index,
+                            location: end.location,
+                            label,
+                        }));
+                    }
                 } else if offset >= vertical_index {
                     // eprintln!("[debug] adding hanging data; i: {}, vertical index: {}, offset: {} (end)", i, vertical_index, offset);
 
@@ -601,7 +802,8 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
                     acc.push(AnnotationData::Hanging(HangingAnnotationLineData {
                         style: annotation.style,
                         severity: diagnostic.severity,
-                        location: end.location.clone(),
+                        annotation_index: end.annotation_index,
+                        location: end.location,
                     }));
                 }
             },
@@ -610,28 +812,33 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
                     // Add start and end boundary elements and the connecting line between them.
                     // They all have the same character, so they will be rendered as a single line:
                     // "^^^^^^^^^" or "---------"
-                    acc.push(AnnotationData::Start(start.clone()));
+                    acc.push(AnnotationData::Start(*start));
                     acc.push(AnnotationData::ConnectingSingleline(ConnectingSinglelineAnnotationData {
                         style: annotation.style,
                         as_multiline: false,
                         severity: diagnostic.severity,
+                        annotation_index: start.annotation_index,
                         line_index,
                         // Intersects with the start boundary character, but the renderer will prefer
                         // that one over this connecting line anyway
                         start_column_index: start.location.column_index,
                         end_column_index: end.location.column_index,
                     }));
-                    acc.push(AnnotationData::End(end.clone()));
-                } else if offset != 0 && offset + 1 == vertical_index && !annotation.label.is_empty() {
+                    acc.push(AnnotationData::End(*end));
+                } else if offset != 0 && offset < vertical_index {
                     // eprintln!("[debug] adding label at index {} for offset {} (both)", vertical_index, offset);
 
-                    // If we're under the hanging elements ("|") and this annotation has a label, add it.
-                    acc.push(AnnotationData::Label(LabelAnnotationLineData {
-                        style: annotation.style,
-                        severity: diagnostic.severity,
-                        location: start.location.clone(),
-                        label: annotation.label.clone(),
-                    }));
+                    // If we're under the hanging elements ("|") and this annotation has a label, add it,
+                    // with every further line of the label in the rows below.
+                    if let Some(label) = annotation.label.lines().nth((vertical_index - offset - 1) as usize) {
+                        acc.push(AnnotationData::Label(LabelAnnotationLineData {
+                            style: annotation.style,
+                            severity: diagnostic.severity,
+                            annotation_index: start.annotation_index,
+                            location: start.location,
+                            label,
+                        }));
+                    }
                 } else if offset >= vertical_index {
                     // eprintln!("[debug] adding hanging data; i: {}, vertical index: {}, offset: {} (both)", i, vertical_index, offset);
 
@@ -640,7 +847,8 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
                     acc.push(AnnotationData::Hanging(HangingAnnotationLineData {
                         style: annotation.style,
                         severity: diagnostic.severity,
-                        location: start.location.clone(),
+                        annotation_index: start.annotation_index,
+                        location: start.location,
                     }));
                 }
             },
@@ -648,10 +856,11 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
 
         acc
     });
+    data.append(&mut connected_starts);
 
-    // If we're on vertical index 0 (which has the underlines) and the last annotation
-    // has vertical offset 0, add its label if it has one.
-    if vertical_index == 0 && vertical_offsets[starts_ends.len() - 1] == 0 {
+    // If the last annotation has vertical offset 0, its label is added next to the underline
+    // on vertical index 0, and every further line of the label below it, at the same column.
+    if vertical_offsets[starts_ends.len() - 1] == 0 {
         let (a, start_end) = &starts_ends[starts_ends.len() - 1];
 
         let label_pos = match start_end {
@@ -659,14 +868,14 @@ fn calculate_single_line_data<FileId: Copy>(diagnostic: &Diagnostic<FileId>, _fi
             StartEndAnnotationData::Both(_, end) => Some(end.location.column_index),
             StartEndAnnotationData::Start(_) => None,
         };
-        let has_label = label_pos.is_some() && !a.label.is_empty();
 
-        if let (true, Some(label_pos)) = (has_label, label_pos) {
+        if let (Some(label_pos), Some(label)) = (label_pos, a.label.lines().nth(vertical_index as usize)) {
             data.push(AnnotationData::Label(LabelAnnotationLineData {
                 style: a.style,
                 severity: diagnostic.severity,
+                annotation_index: start_end.annotation_index(),
                 location: LineColumn::new(line_index, label_pos + 2),
-                label: a.label.clone(),
+                label,
             }));
         }
     }
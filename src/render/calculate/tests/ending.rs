@@ -15,45 +15,51 @@ fn test_1() {
     //   | |____^ // vertical offset 0
 
     // Line 1
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation1], &[&annotation1]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(0, &annotation1)], &[(0, &annotation1)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(0, 0),
                 vertical_bar_index: 0,
             }),
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 0),
             }),
         ],
     ]);
     // Line 2
-    assert_eq!(calculate(&diagnostic, &file, (), 1, &[&annotation1], &[&annotation1]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 1, &[(0, &annotation1)], &[(0, &annotation1)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(1, 3),
                 vertical_bar_index: 0,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 3),
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 5),
-                label: String::from("something"),
+                label: "something",
             }),
         ],
     ]);
@@ -78,22 +84,25 @@ fn test_2() {
     //   |         |      something   // vertical offset 2
     //   |         something else     // vertical offset 3
 
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation1, &annotation2], &[&annotation1, &annotation2]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(0, &annotation1), (1, &annotation2)], &[(0, &annotation1), (1, &annotation2)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(0, 0),
                 vertical_bar_index: 0,
             }),
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 0),
             }),
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }),
         ],
@@ -101,48 +110,56 @@ fn test_2() {
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 end_location: LineColumn::new(0, 4),
                 vertical_bar_index: 1,
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }),
         ],
     ]);
 
-    assert_eq!(calculate(&diagnostic, &file, (), 1, &[&annotation2, &annotation1], &[&annotation1, &annotation2]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 1, &[(1, &annotation2), (0, &annotation1)], &[(0, &annotation1), (1, &annotation2)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 vertical_bar_index: 1,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 end_location: LineColumn::new(1, 3),
                 vertical_bar_index: 1,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 3),
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 11),
             }),
         ],
@@ -150,22 +167,26 @@ fn test_2() {
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(1, 11),
                 vertical_bar_index: 0,
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 3),
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 11),
             }),
         ],
@@ -173,21 +194,24 @@ fn test_2() {
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 3),
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 11),
-                label: String::from("something"),
+                label: "something",
             }),
         ],
         vec![
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 3),
-                label: String::from("something else"),
+                label: "something else",
             }),
         ],
     ]);
@@ -213,22 +237,25 @@ fn test_overlapping_1() {
     //   |         something               // vertical offset 3
 
     // Line 1 is the same as test_2
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation1, &annotation2], &[&annotation1, &annotation2]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(0, &annotation1), (1, &annotation2)], &[(0, &annotation1), (1, &annotation2)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(0, 0),
                 vertical_bar_index: 0,
             }),
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 0),
             }),
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }),
         ],
@@ -236,42 +263,49 @@ fn test_overlapping_1() {
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 end_location: LineColumn::new(0, 4),
                 vertical_bar_index: 1,
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }),
         ],
     ]);
     // Line 2
-    assert_eq!(calculate(&diagnostic, &file, (), 1, &[&annotation1, &annotation2], &[&annotation1, &annotation2]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 1, &[(0, &annotation1), (1, &annotation2)], &[(0, &annotation1), (1, &annotation2)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 vertical_bar_index: 1,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 3),
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 12),
             }),
         ],
@@ -279,27 +313,32 @@ fn test_overlapping_1() {
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 vertical_bar_index: 1,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 end_location: LineColumn::new(1, 12),
                 vertical_bar_index: 1,
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 3),
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 12),
             }),
         ],
@@ -307,32 +346,37 @@ fn test_overlapping_1() {
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(1, 3),
                 vertical_bar_index: 0,
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 3),
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 12),
-                label: String::from("something else"),
+                label: "something else",
             }),
         ],
         vec![
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 3),
-                label: String::from("something"),
+                label: "something",
             }),
         ],
     ]);
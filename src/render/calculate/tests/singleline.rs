@@ -11,17 +11,19 @@ fn test_1() {
     // 1 | test file contents
     //   |      ^^^^ test label
 
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation], &[]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(0, &annotation)], &[], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 5),
             }),
             AnnotationData::ConnectingSingleline(ConnectingSinglelineAnnotationData {
                 style: AnnotationStyle::Primary,
                 as_multiline: false,
                 severity: Severity::Error,
+                annotation_index: 0,
                 line_index: 0,
                 start_column_index: 5,
                 end_column_index: 8,
@@ -29,13 +31,15 @@ fn test_1() {
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 8),
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 10),
-                label: String::from("test label"),
+                label: "test label",
             }),
         ],
     ]);
@@ -56,56 +60,64 @@ fn test_separate_lines_1() {
     // 2 | something += 3.0;
     //   |              --- due to this
 
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation1], &[]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(0, &annotation1)], &[], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 3),
             }),
             AnnotationData::ConnectingSingleline(ConnectingSinglelineAnnotationData {
                 style: AnnotationStyle::Primary,
                 as_multiline: false,
                 severity: Severity::Error,
+                annotation_index: 0,
                 line_index: 0, start_column_index: 3, end_column_index: 12,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 12),
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 14),
-                label: String::from("expected type annotation here"),
+                label: "expected type annotation here",
             }),
         ],
     ]);
 
-    assert_eq!(calculate(&diagnostic, &file, (), 1, &[&annotation2], &[]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 1, &[(1, &annotation2)], &[], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 13),
             }),
             AnnotationData::ConnectingSingleline(ConnectingSinglelineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 as_multiline: false,
                 severity: Severity::Error,
+                annotation_index: 1,
                 line_index: 1, start_column_index: 13, end_column_index: 15,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 15),
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 17),
-                label: String::from("due to this"),
+                label: "due to this",
             }),
         ],
     ]);
@@ -126,48 +138,55 @@ fn test_same_line_1() {
     //   |     |
     //   |     identifier
 
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation2, &annotation1], &[]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(1, &annotation2), (0, &annotation1)], &[], &RenderConfig::default()).unwrap(), vec![
         vec![
             // First underline (secondary, annotation2)
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }),
             AnnotationData::ConnectingSingleline(ConnectingSinglelineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 as_multiline: false,
                 severity: Severity::Error,
+                annotation_index: 1,
                 line_index: 0, start_column_index: 4, end_column_index: 7,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 7),
             }),
             // Second underline (primary, annotation1)
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 11),
             }),
             AnnotationData::ConnectingSingleline(ConnectingSinglelineAnnotationData {
                 style: AnnotationStyle::Primary,
                 as_multiline: false,
                 severity: Severity::Error,
+                annotation_index: 0,
                 line_index: 0, start_column_index: 11, end_column_index: 12,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 12),
             }),
             // Label for primary annotation (annotation1)
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 14),
-                label: String::from("number"),
+                label: "number",
             }),
         ],
         // Label for secondary annotation (annotation2)
@@ -176,6 +195,7 @@ fn test_same_line_1() {
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             })
         ],
@@ -183,8 +203,9 @@ fn test_same_line_1() {
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
-                label: String::from("identifier"),
+                label: "identifier",
             })
         ],
     ]);
@@ -206,17 +227,19 @@ fn test_overlapping_1() {
     //   |     |   something else
     //   |     something
 
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation2, &annotation1], &[]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(1, &annotation2), (0, &annotation1)], &[], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 4),
             }),
             AnnotationData::ConnectingSingleline(ConnectingSinglelineAnnotationData {
                 style: AnnotationStyle::Primary,
                 as_multiline: false,
                 severity: Severity::Error,
+                annotation_index: 0,
                 line_index: 0,
                 start_column_index: 4,
                 end_column_index: 12,
@@ -224,12 +247,14 @@ fn test_overlapping_1() {
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 8),
             }),
             AnnotationData::ConnectingSingleline(ConnectingSinglelineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 as_multiline: false,
                 severity: Severity::Error,
+                annotation_index: 1,
                 line_index: 0,
                 start_column_index: 8,
                 end_column_index: 10,
@@ -237,11 +262,13 @@ fn test_overlapping_1() {
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 10),
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 12),
             }),
         ],
@@ -249,11 +276,13 @@ fn test_overlapping_1() {
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 4),
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 8),
             }),
         ],
@@ -261,21 +290,24 @@ fn test_overlapping_1() {
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 4),
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 8),
-                label: String::from("something else"),
+                label: "something else",
             }),
         ],
         vec![
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 4),
-                label: String::from("something"),
+                label: "something",
             }),
         ],
     ]);
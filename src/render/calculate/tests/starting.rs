@@ -15,44 +15,50 @@ fn test_simple_1() {
     //   | |______________^ something // vertical offset 0
 
     // Line 1
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation1], &[&annotation1]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(0, &annotation1)], &[(0, &annotation1)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(0, 4),
                 vertical_bar_index: 0,
             }),
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 4),
             }),
         ],
     ]);
-    assert_eq!(calculate(&diagnostic, &file, (), 1, &[&annotation1], &[&annotation1]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 1, &[(0, &annotation1)], &[(0, &annotation1)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(1, 12),
                 vertical_bar_index: 0,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 12),
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 14),
-                label: String::from("something"),
+                label: "something",
             }),
         ],
     ]);
@@ -77,17 +83,19 @@ fn test_1() {
     //   | |______________^ something // vertical offset 0
 
     // Line 1
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation2, &annotation1], &[&annotation1]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(1, &annotation2), (0, &annotation1)], &[(0, &annotation1)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }),
             AnnotationData::ConnectingSingleline(ConnectingSinglelineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 as_multiline: false,
                 severity: Severity::Error,
+                annotation_index: 1,
                 line_index: 0,
                 start_column_index: 4,
                 end_column_index: 7,
@@ -95,11 +103,13 @@ fn test_1() {
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 7),
             }),
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 11),
             }),
         ],
@@ -107,17 +117,20 @@ fn test_1() {
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(0, 11),
                 vertical_bar_index: 0,
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 11),
             }),
         ],
@@ -125,11 +138,13 @@ fn test_1() {
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }),
         ],
@@ -137,40 +152,46 @@ fn test_1() {
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
-                label: String::from("something else"),
+                label: "something else",
             }),
         ],
     ]);
     // Line 2
-    assert_eq!(calculate(&diagnostic, &file, (), 1, &[&annotation1], &[&annotation1]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 1, &[(0, &annotation1)], &[(0, &annotation1)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(1, 12),
                 vertical_bar_index: 0,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 12),
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 14),
-                label: String::from("something"),
+                label: "something",
             }),
         ],
     ]);
@@ -197,43 +218,49 @@ fn test_with_ending_1() {
     //   | |_____^ something        // vertical offset 0
 
     // Line 1
-    assert_eq!(calculate(&diagnostic, &file, (), 0, &[&annotation2], &[&annotation2]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 0, &[(1, &annotation2)], &[(1, &annotation2)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 end_location: LineColumn::new(0, 11),
                 vertical_bar_index: 0,
             }),
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 11),
             }),
         ],
     ]);
     // Line 2
-    assert_eq!(calculate(&diagnostic, &file, (), 1, &[&annotation1, &annotation2], &[&annotation2, &annotation1]).unwrap(), vec![
+    assert_eq!(calculate(&diagnostic, &file, &(), 1, &[(0, &annotation1), (1, &annotation2)], &[(1, &annotation2), (0, &annotation1)], &RenderConfig::default()).unwrap(), vec![
         vec![
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 vertical_bar_index: 0,
             }),
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 end_location: LineColumn::new(1, 8),
                 vertical_bar_index: 0,
             }),
             AnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 8),
             }),
             AnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 13),
             }),
         ],
@@ -241,17 +268,20 @@ fn test_with_ending_1() {
             AnnotationData::ConnectingMultiline(ConnectingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 end_location: LineColumn::new(1, 13),
                 vertical_bar_index: 0,
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 8),
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 13),
             }),
         ],
@@ -259,11 +289,13 @@ fn test_with_ending_1() {
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::Hanging(HangingAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 8),
             }),
         ],
@@ -271,13 +303,15 @@ fn test_with_ending_1() {
             AnnotationData::ContinuingMultiline(ContinuingMultilineAnnotationData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 vertical_bar_index: 0,
             }),
             AnnotationData::Label(LabelAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 8),
-                label: String::from("something else"),
+                label: "something else",
             }),
         ],
     ]);
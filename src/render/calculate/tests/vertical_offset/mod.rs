@@ -24,10 +24,12 @@ mod singleline {
             (&annotation1, StartEndAnnotationData::Both(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 3),
             }, EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 12),
             })),
         ];
@@ -35,16 +37,18 @@ mod singleline {
             (&annotation2, StartEndAnnotationData::Both(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 13),
             }, EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 16),
             })),
         ];
 
-        assert_eq!(calculate_vertical_offsets(&starts_ends_1).unwrap(), vec![0]);
-        assert_eq!(calculate_vertical_offsets(&starts_ends_2).unwrap(), vec![0]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends_1, false).unwrap(), vec![0]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends_2, false).unwrap(), vec![0]);
     }
 
     #[test]
@@ -66,24 +70,28 @@ mod singleline {
             (&annotation2, StartEndAnnotationData::Both(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }, EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 8),
             })),
             (&annotation1, StartEndAnnotationData::Both(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 11),
             }, EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 13),
             })),
         ];
 
-        assert_eq!(calculate_vertical_offsets(&starts_ends).unwrap(), vec![1, 0]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends, false).unwrap(), vec![1, 0]);
     }
 
     #[test]
@@ -106,24 +114,28 @@ mod singleline {
             (&annotation1, StartEndAnnotationData::Both(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 4),
             }, EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 13),
             })),
             (&annotation2, StartEndAnnotationData::Both(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 8),
             }, EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 11),
             })),
         ];
 
-        assert_eq!(calculate_vertical_offsets(&starts_ends).unwrap(), vec![2, 1]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends, false).unwrap(), vec![2, 1]);
     }
 }
 
@@ -146,11 +158,12 @@ mod ending {
             (&annotation1, StartEndAnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 4),
             })),
         ];
 
-        assert_eq!(calculate_vertical_offsets(&starts_ends).unwrap(), vec![0]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends, false).unwrap(), vec![0]);
     }
 
     #[test]
@@ -174,16 +187,18 @@ mod ending {
             (&annotation2, StartEndAnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 4),
             })),
             (&annotation1, StartEndAnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 13),
             })),
         ];
 
-        assert_eq!(calculate_vertical_offsets(&starts_ends).unwrap(), vec![0, 1]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends, false).unwrap(), vec![0, 1]);
     }
 
     #[test]
@@ -208,16 +223,18 @@ mod ending {
             (&annotation1, StartEndAnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(1, 4),
             })),
             (&annotation2, StartEndAnnotationData::End(EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 13),
             })),
         ];
 
-        assert_eq!(calculate_vertical_offsets(&starts_ends).unwrap(), vec![2, 1]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends, false).unwrap(), vec![2, 1]);
     }
 }
 
@@ -241,11 +258,12 @@ mod starting {
             (&annotation1, StartEndAnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 4),
             })),
         ];
 
-        assert_eq!(calculate_vertical_offsets(&starts_ends).unwrap(), vec![0]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends, false).unwrap(), vec![0]);
     }
 
     #[test]
@@ -269,20 +287,23 @@ mod starting {
             (&annotation2, StartEndAnnotationData::Both(StartAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 4),
             }, EndAnnotationLineData {
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(0, 8),
             })),
             (&annotation1, StartEndAnnotationData::Start(StartAnnotationLineData {
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 11),
             })),
         ];
 
-        assert_eq!(calculate_vertical_offsets(&starts_ends).unwrap(), vec![2, 1]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends, false).unwrap(), vec![2, 1]);
     }
 
     #[test]
@@ -309,16 +330,18 @@ mod starting {
                 // the one ending on line 2
                 style: AnnotationStyle::Secondary,
                 severity: Severity::Error,
+                annotation_index: 1,
                 location: LineColumn::new(1, 9),
             })),
             (&annotation1, StartEndAnnotationData::Start(StartAnnotationLineData {
                 // the one starting on line 2
                 style: AnnotationStyle::Primary,
                 severity: Severity::Error,
+                annotation_index: 0,
                 location: LineColumn::new(0, 11),
             })),
         ];
 
-        assert_eq!(calculate_vertical_offsets(&starts_ends).unwrap(), vec![0, 1]);
+        assert_eq!(calculate_vertical_offsets(&starts_ends, false).unwrap(), vec![0, 1]);
     }
 }
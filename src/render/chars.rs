@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use crate::diagnostic::{AnnotationStyle, Severity};
+
+/// The characters used for drawing annotations and the gutter
+/// next to the source code.
+///
+/// Two presets are provided:
+/// - [`CharSet::ascii`], which only uses ASCII characters, like `rustc` does by default
+/// - [`CharSet::unicode`], which uses Unicode box-drawing characters.
+///
+/// [`CharSet::ascii`]: CharSet::ascii
+/// [`CharSet::unicode`]: CharSet::unicode
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CharSet {
+    /// The vertical bar used for the gutter, continuing multi-line annotations,
+    /// and hanging labels.
+    pub vertical_bar: char,
+    /// The horizontal line connecting multi-line annotations with their vertical bar.
+    pub horizontal_bar: char,
+    /// Drawn at the top of the vertical bar of a multi-line annotation, on the line
+    /// where it connects with the start of that annotation.
+    pub multiline_start: char,
+    /// Drawn at the bottom of the vertical bar of a multi-line annotation, on the line
+    /// where it connects with the end of that annotation.
+    pub multiline_end: char,
+    /// Drawn below the first or last character of a multi-line annotation, where its horizontal
+    /// line connects with it on the row of the underlines. If this is `None`, the underline
+    /// character of the annotation is used, like in `rustc`.
+    pub multiline_junction: Option<char>,
+    /// Drawn where the horizontal line of a multi-line annotation connects with the vertical bar
+    /// coming down from its start, when the start is connected below the row of the underlines.
+    pub multiline_corner: char,
+    /// The underline character for annotations with [`AnnotationStyle::Primary`].
+    ///
+    /// [`AnnotationStyle::Primary`]: AnnotationStyle::Primary
+    pub primary_underline: char,
+    /// The underline character for annotations with [`AnnotationStyle::Secondary`].
+    ///
+    /// [`AnnotationStyle::Secondary`]: AnnotationStyle::Secondary
+    pub secondary_underline: char,
+    /// The underline characters for annotations of diagnostics with a specific severity,
+    /// as `(primary, secondary)`. These are used instead of [`CharSet::primary_underline`] and
+    /// [`CharSet::secondary_underline`], so that, for example, warnings can be underlined
+    /// with `~`. Empty in both presets.
+    ///
+    /// [`CharSet::primary_underline`]: CharSet::primary_underline
+    /// [`CharSet::secondary_underline`]: CharSet::secondary_underline
+    pub severity_underlines: BTreeMap<Severity, (char, char)>,
+    /// Written in place of the line number separator in the header of the file containing
+    /// the primary annotation, before its path.
+    pub file_arrow: String,
+    /// Written in place of the line number separator in the headers of the other files.
+    pub file_continuation: String,
+    /// Written in place of the line number separator for leaving out lines between
+    /// annotated ones.
+    pub skipped_lines: String,
+    /// Shown in place of the parts of source lines that were cut off because they are
+    /// longer than [`RenderConfig::max_line_width`].
+    ///
+    /// [`RenderConfig::max_line_width`]: crate::render::RenderConfig::max_line_width
+    pub ellipsis: String,
+}
+
+impl CharSet {
+    /// A character set that only uses ASCII characters.
+    ///
+    /// ```text
+    /// 1 |   let main = 23;
+    ///   |  ____________^
+    /// 2 | | something += 3.0;
+    ///   | |_________^ something
+    /// ```
+    pub fn ascii() -> Self {
+        CharSet {
+            vertical_bar: '|',
+            horizontal_bar: '_',
+            multiline_start: ' ',
+            multiline_end: '|',
+            multiline_junction: None,
+            multiline_corner: '|',
+            primary_underline: '^',
+            secondary_underline: '-',
+            severity_underlines: BTreeMap::new(),
+            file_arrow: String::from("-->"),
+            file_continuation: String::from(":::"),
+            skipped_lines: String::from("..."),
+            ellipsis: String::from("..."),
+        }
+    }
+
+    /// A character set that uses Unicode box-drawing characters.
+    ///
+    /// ```text
+    /// 1 │   let main = 23;
+    ///   │ ╭────────────┴
+    /// 2 │ │ something += 3.0;
+    ///   │ ╰─────────┴ something
+    /// ```
+    pub fn unicode() -> Self {
+        CharSet {
+            vertical_bar: '│',
+            horizontal_bar: '─',
+            multiline_start: '╭',
+            multiline_end: '╰',
+            multiline_junction: Some('┴'),
+            multiline_corner: '╯',
+            primary_underline: '━',
+            secondary_underline: '┄',
+            severity_underlines: BTreeMap::new(),
+            file_arrow: String::from("-->"),
+            file_continuation: String::from(":::"),
+            skipped_lines: String::from("..."),
+            ellipsis: String::from("…"),
+        }
+    }
+
+    /// Returns the underline character for annotations with the given style.
+    pub fn underline(&self, style: AnnotationStyle) -> char {
+        match style {
+            AnnotationStyle::Primary => self.primary_underline,
+            AnnotationStyle::Secondary => self.secondary_underline,
+        }
+    }
+
+    /// Returns the underline character for annotations with the given style in diagnostics
+    /// with the given severity, which is the one from [`CharSet::severity_underlines`] if
+    /// there is one for that severity.
+    ///
+    /// [`CharSet::severity_underlines`]: CharSet::severity_underlines
+    pub fn severity_underline(&self, style: AnnotationStyle, severity: Severity) -> char {
+        match (self.severity_underlines.get(&severity), style) {
+            (Some(&(primary, _)), AnnotationStyle::Primary) => primary,
+            (Some(&(_, secondary)), AnnotationStyle::Secondary) => secondary,
+            (None, style) => self.underline(style),
+        }
+    }
+
+    /// Sets the underline characters for annotations of diagnostics with the given severity.
+    ///
+    /// ```
+    /// use diagnostic_render::diagnostic::{AnnotationStyle, Severity};
+    /// use diagnostic_render::render::chars::CharSet;
+    ///
+    /// let charset = CharSet::ascii().with_severity_underline(Severity::Warning, '~', '~');
+    /// assert_eq!(charset.severity_underline(AnnotationStyle::Primary, Severity::Warning), '~');
+    /// assert_eq!(charset.severity_underline(AnnotationStyle::Primary, Severity::Error), '^');
+    /// ```
+    pub fn with_severity_underline(mut self, severity: Severity, primary: char, secondary: char) -> Self {
+        self.severity_underlines.insert(severity, (primary, secondary));
+        self
+    }
+}
+
+impl Default for CharSet {
+    fn default() -> Self {
+        Self::ascii()
+    }
+}
+
+/// Character sets can be deserialized from the name of a preset (`"ascii"` or `"unicode"`),
+/// or from a table of characters. Characters missing from the table are taken from the preset
+/// named by the `base` key, or from [`CharSet::ascii`] if there is none.
+///
+/// [`CharSet::ascii`]: CharSet::ascii
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CharSet {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Preset {
+            Ascii,
+            Unicode,
+        }
+
+        impl From<Preset> for CharSet {
+            fn from(preset: Preset) -> Self {
+                match preset {
+                    Preset::Ascii => CharSet::ascii(),
+                    Preset::Unicode => CharSet::unicode(),
+                }
+            }
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Custom {
+            base: Option<Preset>,
+            vertical_bar: Option<char>,
+            horizontal_bar: Option<char>,
+            multiline_start: Option<char>,
+            multiline_end: Option<char>,
+            multiline_junction: Option<char>,
+            multiline_corner: Option<char>,
+            primary_underline: Option<char>,
+            secondary_underline: Option<char>,
+            severity_underlines: Option<BTreeMap<Severity, (char, char)>>,
+            file_arrow: Option<String>,
+            file_continuation: Option<String>,
+            skipped_lines: Option<String>,
+            ellipsis: Option<String>,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Preset(Preset),
+            Custom(Custom),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Preset(preset) => preset.into(),
+            Repr::Custom(custom) => {
+                let base = custom.base.map(CharSet::from).unwrap_or_default();
+
+                CharSet {
+                    vertical_bar: custom.vertical_bar.unwrap_or(base.vertical_bar),
+                    horizontal_bar: custom.horizontal_bar.unwrap_or(base.horizontal_bar),
+                    multiline_start: custom.multiline_start.unwrap_or(base.multiline_start),
+                    multiline_end: custom.multiline_end.unwrap_or(base.multiline_end),
+                    multiline_junction: custom.multiline_junction.or(base.multiline_junction),
+                    multiline_corner: custom.multiline_corner.unwrap_or(base.multiline_corner),
+                    primary_underline: custom.primary_underline.unwrap_or(base.primary_underline),
+                    secondary_underline: custom.secondary_underline.unwrap_or(base.secondary_underline),
+                    severity_underlines: custom.severity_underlines.unwrap_or(base.severity_underlines),
+                    file_arrow: custom.file_arrow.unwrap_or(base.file_arrow),
+                    file_continuation: custom.file_continuation.unwrap_or(base.file_continuation),
+                    skipped_lines: custom.skipped_lines.unwrap_or(base.skipped_lines),
+                    ellipsis: custom.ellipsis.unwrap_or(base.ellipsis),
+                }
+            },
+        })
+    }
+}
@@ -1,16 +1,31 @@
-use termcolor::{Color, ColorSpec, WriteColor};
+use std::ffi::OsString;
+use crate::render::style::{Color, ColorSpec, WriteColor};
 use crate::diagnostic::{AnnotationStyle, Severity};
+use crate::render::{DiagnosticRenderer, RenderConfig};
+use crate::render::theme::TextStyle;
 
 type Result = std::io::Result<()>;
 
+/// The colors [`ColorConfig::indexed_annotation`] cycles through by default.
+///
+/// [`ColorConfig::indexed_annotation`]: ColorConfig::indexed_annotation
+pub const ANNOTATION_PALETTE: [Color; 6] = [Color::Magenta, Color::Cyan, Color::Green, Color::Yellow, Color::Blue, Color::Red];
+
 /// Provides the terminal colors used in diagnostics.
 ///
-/// Two default implementations are provided:
+/// Three default implementations are provided:
 /// - [`DefaultColorConfig`], which should be similar to the colors used by `rustc`
+/// - [`LightColorConfig`], which is like [`DefaultColorConfig`], but readable on light backgrounds
 /// - [`DisabledColorConfig`], which outputs no colors.
 ///
+/// Custom colors can also be used without implementing this trait through [`ThemeColors`],
+/// and [`AutoColorConfig`] decides whether to use colors based on the environment.
+///
 /// [`DefaultColorConfig`]: DefaultColorConfig
+/// [`LightColorConfig`]: LightColorConfig
 /// [`DisabledColorConfig`]: DisabledColorConfig
+/// [`ThemeColors`]: crate::render::theme::ThemeColors
+/// [`AutoColorConfig`]: AutoColorConfig
 pub trait ColorConfig {
     /// Resets all style and formatting.
     fn reset(&self, f: &mut impl WriteColor) -> Result;
@@ -83,12 +98,183 @@ pub trait ColorConfig {
     /// [`Self::severity`]: Self::severity
     fn annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity) -> Result;
 
+    /// Sets the formatting for an annotation if [`RenderConfig::cycle_annotation_colors`]
+    /// is enabled, where `index` is the position of the annotation among the annotations
+    /// in its file, in the order they start in.
+    /// This is used instead of [`Self::annotation`] for underlines, labels and connecting lines,
+    /// so that annotations with the same style can be told apart.
+    ///
+    /// By default, this cycles through the colors of [`ANNOTATION_PALETTE`], using bold text
+    /// for primary annotations.
+    ///
+    /// [`RenderConfig::cycle_annotation_colors`]: RenderConfig::cycle_annotation_colors
+    /// [`Self::annotation`]: Self::annotation
+    /// [`ANNOTATION_PALETTE`]: ANNOTATION_PALETTE
+    fn indexed_annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, _severity: Severity, index: usize) -> Result {
+        f.set_color(ColorSpec::new()
+            .set_fg(Some(ANNOTATION_PALETTE[index % ANNOTATION_PALETTE.len()]))
+            .set_bold(style == AnnotationStyle::Primary))
+    }
+
+    /// Sets the formatting for an annotation with an explicit [`Annotation::color`], which
+    /// is passed as `color`. This takes precedence over [`Self::annotation`] and
+    /// [`Self::indexed_annotation`].
+    ///
+    /// This uses `color` as it is by default.
+    ///
+    /// [`Annotation::color`]: crate::diagnostic::Annotation::color
+    /// [`Self::annotation`]: Self::annotation
+    /// [`Self::indexed_annotation`]: Self::indexed_annotation
+    fn styled_annotation(&self, f: &mut impl WriteColor, _style: AnnotationStyle, _severity: Severity, color: &TextStyle) -> Result {
+        f.set_color(&color.to_color_spec())
+    }
+
     /// Sets the formatting for a line of source code.
     fn source(&self, f: &mut impl WriteColor) -> Result;
 
+    /// Sets the formatting for the part of a line of source code that is covered by an
+    /// annotation, if [`RenderConfig::highlight_source`] is enabled. The annotation style
+    /// and the diagnostic severity are provided as context.
+    ///
+    /// This uses the formatting of [`Self::annotation`] by default.
+    ///
+    /// [`RenderConfig::highlight_source`]: RenderConfig::highlight_source
+    /// [`Self::annotation`]: Self::annotation
+    fn annotated_source(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity) -> Result {
+        self.annotation(f, style, severity)
+    }
+
+    /// Sets the formatting for the severity prefix of a note (like `note` in `= note: ...`).
     fn note_severity(&self, f: &mut impl WriteColor, severity: Severity) -> Result;
 
+    /// Sets the formatting for a custom prefix of a note (like `hint` in `= hint: ...`),
+    /// set with [`Note::with_prefix`]. This uses [`Self::note_severity`] by default.
+    ///
+    /// [`Note::with_prefix`]: crate::diagnostic::Note::with_prefix
+    /// [`Self::note_severity`]: Self::note_severity
+    fn note_prefix(&self, f: &mut impl WriteColor, severity: Severity, _prefix: &str) -> Result {
+        self.note_severity(f, severity)
+    }
+
+    /// Sets the formatting for the message of a note.
     fn note_message(&self, f: &mut impl WriteColor, severity: Severity) -> Result;
+
+    /// Sets the formatting for the markers underneath code inserted by a suggestion,
+    /// and for added code in the diff of a suggestion.
+    fn suggestion(&self, f: &mut impl WriteColor) -> Result {
+        self.help(f)
+    }
+
+    /// Sets the formatting for removed code in the diff of a suggestion.
+    fn suggestion_removal(&self, f: &mut impl WriteColor) -> Result {
+        self.error(f)
+    }
+
+    /// Sets the formatting for text inside backticks in messages, labels and notes, if
+    /// [`RenderConfig::emphasize_backticks`] is enabled. The severity is the one of the
+    /// diagnostic, note or annotation the text is in.
+    ///
+    /// This uses the formatting of [`Self::message`] by default.
+    ///
+    /// [`RenderConfig::emphasize_backticks`]: RenderConfig::emphasize_backticks
+    /// [`Self::message`]: Self::message
+    fn emphasis(&self, f: &mut impl WriteColor, _severity: Severity) -> Result {
+        self.message(f)
+    }
+}
+
+impl<C: ColorConfig + ?Sized> ColorConfig for &C {
+    fn reset(&self, f: &mut impl WriteColor) -> Result {
+        (**self).reset(f)
+    }
+
+    fn severity(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        (**self).severity(f, severity)
+    }
+
+    fn bug(&self, f: &mut impl WriteColor) -> Result {
+        (**self).bug(f)
+    }
+
+    fn error(&self, f: &mut impl WriteColor) -> Result {
+        (**self).error(f)
+    }
+
+    fn warning(&self, f: &mut impl WriteColor) -> Result {
+        (**self).warning(f)
+    }
+
+    fn note(&self, f: &mut impl WriteColor) -> Result {
+        (**self).note(f)
+    }
+
+    fn help(&self, f: &mut impl WriteColor) -> Result {
+        (**self).help(f)
+    }
+
+    fn name(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        (**self).name(f, severity)
+    }
+
+    fn message(&self, f: &mut impl WriteColor) -> Result {
+        (**self).message(f)
+    }
+
+    fn path(&self, f: &mut impl WriteColor) -> Result {
+        (**self).path(f)
+    }
+
+    fn line_number(&self, f: &mut impl WriteColor) -> Result {
+        (**self).line_number(f)
+    }
+
+    fn line_number_separator(&self, f: &mut impl WriteColor) -> Result {
+        (**self).line_number_separator(f)
+    }
+
+    fn annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity) -> Result {
+        (**self).annotation(f, style, severity)
+    }
+
+    fn indexed_annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity, index: usize) -> Result {
+        (**self).indexed_annotation(f, style, severity, index)
+    }
+
+    fn styled_annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity, color: &TextStyle) -> Result {
+        (**self).styled_annotation(f, style, severity, color)
+    }
+
+    fn source(&self, f: &mut impl WriteColor) -> Result {
+        (**self).source(f)
+    }
+
+    fn annotated_source(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity) -> Result {
+        (**self).annotated_source(f, style, severity)
+    }
+
+    fn note_severity(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        (**self).note_severity(f, severity)
+    }
+
+    fn note_prefix(&self, f: &mut impl WriteColor, severity: Severity, prefix: &str) -> Result {
+        (**self).note_prefix(f, severity, prefix)
+    }
+
+    fn note_message(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        (**self).note_message(f, severity)
+    }
+
+    fn suggestion(&self, f: &mut impl WriteColor) -> Result {
+        (**self).suggestion(f)
+    }
+
+    fn suggestion_removal(&self, f: &mut impl WriteColor) -> Result {
+        (**self).suggestion_removal(f)
+    }
+
+    fn emphasis(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        (**self).emphasis(f, severity)
+    }
 }
 
 /// The default color configuration.
@@ -97,7 +283,7 @@ pub struct DefaultColorConfig;
 
 impl ColorConfig for DefaultColorConfig {
     fn reset(&self, f: &mut impl WriteColor) -> Result {
-        f.reset().into()
+        f.reset()
     }
 
     fn severity(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
@@ -149,6 +335,68 @@ impl ColorConfig for DefaultColorConfig {
     }
 }
 
+/// A color configuration for terminals with a light background.
+///
+/// This avoids the intense colors of [`DefaultColorConfig`], which are hard to read on
+/// white, and uses a dark orange instead of yellow for warnings.
+///
+/// [`DefaultColorConfig`]: DefaultColorConfig
+pub struct LightColorConfig;
+
+impl ColorConfig for LightColorConfig {
+    fn reset(&self, f: &mut impl WriteColor) -> Result {
+        f.reset()
+    }
+
+    fn severity(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        f.set_color(ColorSpec::new().set_fg(Some(match severity {
+            Severity::Help => Color::Green,
+            Severity::Note => Color::Blue,
+            Severity::Warning => Color::Ansi256(130),
+            Severity::Error | Severity::Bug => Color::Red,
+        })).set_bold(true))
+    }
+
+    fn name(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        self.severity(f, severity)
+    }
+
+    fn message(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(ColorSpec::new().set_bold(true))
+    }
+
+    fn path(&self, f: &mut impl WriteColor) -> Result {
+        self.reset(f)
+    }
+
+    fn line_number(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))
+    }
+
+    fn line_number_separator(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))
+    }
+
+    fn annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity) -> Result {
+        match style {
+            AnnotationStyle::Primary => self.severity(f, severity),
+            AnnotationStyle::Secondary => f.set_color(ColorSpec::new().set_fg(Some(Color::Blue))),
+        }
+    }
+
+    fn source(&self, f: &mut impl WriteColor) -> Result {
+        self.reset(f)
+    }
+
+    fn note_severity(&self, f: &mut impl WriteColor, _severity: Severity) -> Result {
+        f.set_color(ColorSpec::new().set_bold(true))
+    }
+
+    fn note_message(&self, f: &mut impl WriteColor, _severity: Severity) -> Result {
+        self.reset(f)
+    }
+}
+
 /// A no-op color configuration.
 /// Sets no formatting and outputs no formatting codes.
 pub struct DisabledColorConfig;
@@ -186,6 +434,14 @@ impl ColorConfig for DisabledColorConfig {
         Ok(())
     }
 
+    fn indexed_annotation(&self, _f: &mut impl WriteColor, _style: AnnotationStyle, _severity: Severity, _index: usize) -> Result {
+        Ok(())
+    }
+
+    fn styled_annotation(&self, _f: &mut impl WriteColor, _style: AnnotationStyle, _severity: Severity, _color: &TextStyle) -> Result {
+        Ok(())
+    }
+
     fn source(&self, _f: &mut impl WriteColor) -> Result {
         Ok(())
     }
@@ -198,3 +454,197 @@ impl ColorConfig for DisabledColorConfig {
         Ok(())
     }
 }
+
+/// Returns whether colored output should be used, following common conventions:
+///
+/// - If `NO_COLOR` is set to a non-empty value, colors are disabled.
+/// - Otherwise, if `CLICOLOR_FORCE` is set to a value other than `0`, colors are enabled.
+/// - Otherwise, colors are enabled if the output is a terminal, `TERM` is not `dumb`,
+///   and `CLICOLOR` is not `0`.
+///
+/// `is_terminal` should be whether the output is written to a terminal, which can be
+/// determined using [`IsTerminal`], for example `std::io::stderr().is_terminal()`.
+///
+/// [`IsTerminal`]: std::io::IsTerminal
+pub fn colors_enabled(is_terminal: bool) -> bool {
+    detect_colors(is_terminal, |name| std::env::var_os(name))
+}
+
+fn detect_colors(is_terminal: bool, var: impl Fn(&str) -> Option<OsString>) -> bool {
+    if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        return false;
+    }
+
+    if var("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0") {
+        return true;
+    }
+
+    is_terminal && var("TERM").is_none_or(|value| value != "dumb") && var("CLICOLOR").is_none_or(|value| value != "0")
+}
+
+/// Wraps another color configuration, and only uses it if colors are enabled.
+///
+/// [`AutoColorConfig::new`] decides this using [`colors_enabled`].
+///
+/// [`AutoColorConfig::new`]: AutoColorConfig::new
+/// [`colors_enabled`]: colors_enabled
+pub struct AutoColorConfig<C> {
+    colors: Option<C>,
+}
+
+impl<C> AutoColorConfig<C> {
+    /// Creates a color configuration that uses `colors` if [`colors_enabled`] returns `true`
+    /// for `is_terminal`, and outputs no colors otherwise.
+    ///
+    /// ```
+    /// use std::io::IsTerminal;
+    /// use diagnostic_render::render::color::{AutoColorConfig, DefaultColorConfig};
+    ///
+    /// let colors = AutoColorConfig::new(DefaultColorConfig, std::io::stderr().is_terminal());
+    /// ```
+    ///
+    /// [`colors_enabled`]: colors_enabled
+    pub fn new(colors: C, is_terminal: bool) -> Self {
+        Self::with_enabled(colors, colors_enabled(is_terminal))
+    }
+
+    /// Creates a color configuration that uses `colors` if `enabled` is `true`,
+    /// and outputs no colors otherwise.
+    pub fn with_enabled(colors: C, enabled: bool) -> Self {
+        AutoColorConfig {
+            colors: enabled.then_some(colors),
+        }
+    }
+
+    /// Returns whether colors are enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.colors.is_some()
+    }
+}
+
+impl<C: ColorConfig> ColorConfig for AutoColorConfig<C> {
+    fn reset(&self, f: &mut impl WriteColor) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.reset(f))
+    }
+
+    fn severity(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.severity(f, severity))
+    }
+
+    fn name(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.name(f, severity))
+    }
+
+    fn message(&self, f: &mut impl WriteColor) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.message(f))
+    }
+
+    fn path(&self, f: &mut impl WriteColor) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.path(f))
+    }
+
+    fn line_number(&self, f: &mut impl WriteColor) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.line_number(f))
+    }
+
+    fn line_number_separator(&self, f: &mut impl WriteColor) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.line_number_separator(f))
+    }
+
+    fn annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.annotation(f, style, severity))
+    }
+
+    fn indexed_annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity, index: usize) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.indexed_annotation(f, style, severity, index))
+    }
+
+    fn styled_annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity, color: &TextStyle) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.styled_annotation(f, style, severity, color))
+    }
+
+    fn source(&self, f: &mut impl WriteColor) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.source(f))
+    }
+
+    fn annotated_source(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.annotated_source(f, style, severity))
+    }
+
+    fn note_severity(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.note_severity(f, severity))
+    }
+
+    fn note_prefix(&self, f: &mut impl WriteColor, severity: Severity, prefix: &str) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.note_prefix(f, severity, prefix))
+    }
+
+    fn note_message(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.note_message(f, severity))
+    }
+
+    fn suggestion(&self, f: &mut impl WriteColor) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.suggestion(f))
+    }
+
+    fn suggestion_removal(&self, f: &mut impl WriteColor) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.suggestion_removal(f))
+    }
+
+    fn emphasis(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        self.colors.as_ref().map_or(Ok(()), |colors| colors.emphasis(f, severity))
+    }
+}
+
+impl<'w, W, C, FileId, F> DiagnosticRenderer<'w, W, AutoColorConfig<C>, FileId, F> {
+    /// Creates a new diagnostics renderer that uses `colors` if [`colors_enabled`] returns `true`
+    /// for `is_terminal`, and outputs no colors otherwise.
+    ///
+    /// ```
+    /// # #[cfg(feature = "termcolor")]
+    /// # fn main() {
+    /// use std::io::IsTerminal;
+    /// use termcolor::{ColorChoice, StandardStream};
+    /// use diagnostic_render::diagnostic::{Diagnostic, Severity};
+    /// use diagnostic_render::file::SimpleFile;
+    /// use diagnostic_render::render::{DiagnosticRenderer, RenderConfig};
+    /// use diagnostic_render::render::color::DefaultColorConfig;
+    ///
+    /// let mut stderr = StandardStream::stderr(ColorChoice::Always);
+    /// let file = SimpleFile::new("main.rs", "fn main() {}\n");
+    /// let mut renderer = DiagnosticRenderer::with_auto_colors(&mut stderr, DefaultColorConfig,
+    ///     std::io::stderr().is_terminal(), file, RenderConfig::default());
+    /// renderer.render(vec![Diagnostic::new(Severity::Note).with_message("colors are only used in terminals")]).unwrap();
+    /// # }
+    /// # #[cfg(not(feature = "termcolor"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`colors_enabled`]: colors_enabled
+    pub fn with_auto_colors(f: &'w mut W, colors: C, is_terminal: bool, files: F, config: RenderConfig) -> Self {
+        DiagnosticRenderer::new(f, AutoColorConfig::new(colors, is_terminal), files, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(is_terminal: bool, vars: &[(&str, &str)]) -> bool {
+        detect_colors(is_terminal, |name| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| OsString::from(value)))
+    }
+
+    #[test]
+    fn test_detect_colors() {
+        assert!(detect(true, &[]));
+        assert!(!detect(false, &[]));
+        assert!(!detect(true, &[("NO_COLOR", "1")]));
+        assert!(detect(true, &[("NO_COLOR", "")]));
+        assert!(!detect(true, &[("TERM", "dumb")]));
+        assert!(!detect(true, &[("CLICOLOR", "0")]));
+        assert!(detect(false, &[("CLICOLOR_FORCE", "1")]));
+        assert!(detect(true, &[("CLICOLOR_FORCE", "1"), ("TERM", "dumb")]));
+        assert!(!detect(false, &[("CLICOLOR_FORCE", "0")]));
+        assert!(!detect(false, &[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]));
+    }
+}
@@ -1,3 +1,16 @@
+//! The data describing how annotations are drawn below a line of source code.
+//!
+//! [`layout`] computes this data for a diagnostic, so that other backends can draw annotations
+//! the same way as the text renderer, without parsing its output.
+//!
+//! Every piece of data has the style and severity of the annotation it belongs to, and the
+//! index of that annotation among the annotations of the diagnostic in the same file, sorted by
+//! their start. This index is used to give every annotation its own color with
+//! [`RenderConfig::cycle_annotation_colors`].
+//!
+//! [`layout`]: crate::render::layout::layout
+//! [`RenderConfig::cycle_annotation_colors`]: crate::render::RenderConfig::cycle_annotation_colors
+
 use std::fmt::{Debug, Formatter};
 use crate::diagnostic::{AnnotationStyle, Severity};
 use crate::render::LineColumn;
@@ -6,10 +19,12 @@ use crate::render::LineColumn;
 /// on a line before the currently rendered one, and ends after it.
 ///
 /// This is drawn as a single `"|"` character to the left of the source code.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct ContinuingMultilineAnnotationData {
     pub style: AnnotationStyle,
     pub severity: Severity,
+    pub annotation_index: usize,
     /// the index of this continuing vertical bar
     pub vertical_bar_index: usize,
 }
@@ -22,10 +37,12 @@ pub struct ContinuingMultilineAnnotationData {
 /// It can only occur once per line (but of course, multiple times per source line).
 ///
 /// This is drawn as underscores from the vertical bars to `end_location` (exclusive).
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct ConnectingMultilineAnnotationData {
     pub style: AnnotationStyle,
     pub severity: Severity,
+    pub annotation_index: usize,
     pub end_location: LineColumn,
     /// the index of the continuing vertical bar on the left
     /// this annotation connects with
@@ -37,10 +54,12 @@ pub struct ConnectingMultilineAnnotationData {
 ///
 /// This is drawn as a single boundary character at `location`.
 /// This can occur multiple times per line.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct StartAnnotationLineData {
     pub style: AnnotationStyle,
     pub severity: Severity,
+    pub annotation_index: usize,
     pub location: LineColumn,
 }
 
@@ -51,10 +70,12 @@ pub struct StartAnnotationLineData {
 /// This is drawn as underline characters (or underscores if `as_multiline` is `true`)
 /// running from `start_column_index` (inclusive) to `end_column_index` (exclusive).
 /// This can occur multiple times per line.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct ConnectingSinglelineAnnotationData {
     pub style: AnnotationStyle, pub as_multiline: bool,
     pub severity: Severity,
+    pub annotation_index: usize,
     pub line_index: usize,
     pub start_column_index: usize, pub end_column_index: usize,
 }
@@ -64,10 +85,12 @@ pub struct ConnectingSinglelineAnnotationData {
 ///
 /// This is drawn as a single boundary character at `location`.
 /// This can occur multiple times per line.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct EndAnnotationLineData {
     pub style: AnnotationStyle,
     pub severity: Severity,
+    pub annotation_index: usize,
     pub location: LineColumn,
 }
 
@@ -79,10 +102,12 @@ pub struct EndAnnotationLineData {
 /// This can occur multiple times per line.
 ///
 /// [`StartAnnotationLineData`]: StartAnnotationLineData
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct HangingAnnotationLineData {
     pub style: AnnotationStyle,
     pub severity: Severity,
+    pub annotation_index: usize,
     pub location: LineColumn,
 }
 
@@ -96,13 +121,20 @@ pub struct HangingAnnotationLineData {
 /// at the end of the line or at `location`.
 /// This can only occur once per line.
 ///
+/// The label is borrowed from the annotation it belongs to, so that calculating
+/// the data for a line doesn't copy every label. For labels with multiple lines, this is
+/// a single line of the label, and the further lines follow in the rows below at the same
+/// `location`.
+///
 /// [`EndAnnotationLineData`]: EndAnnotationLineData
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct LabelAnnotationLineData {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LabelAnnotationLineData<'a> {
     pub style: AnnotationStyle,
     pub severity: Severity,
+    pub annotation_index: usize,
     pub location: LineColumn,
-    pub label: String,
+    pub label: &'a str,
 }
 
 /// An enum with variants for [`StartAnnotationLineData`] and
@@ -110,26 +142,38 @@ pub struct LabelAnnotationLineData {
 ///
 /// [`StartAnnotationLineData`]: StartAnnotationLineData
 /// [`EndAnnotationLineData`]: EndAnnotationLineData
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub enum StartEndAnnotationData {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StartEndAnnotationData {
     Start(StartAnnotationLineData),
     End(EndAnnotationLineData),
     Both(StartAnnotationLineData, EndAnnotationLineData),
 }
 
+impl StartEndAnnotationData {
+    /// Returns the index of the annotation this data belongs to.
+    pub(crate) fn annotation_index(&self) -> usize {
+        match self {
+            StartEndAnnotationData::Start(start) | StartEndAnnotationData::Both(start, _) => start.annotation_index,
+            StartEndAnnotationData::End(end) => end.annotation_index,
+        }
+    }
+}
+
 /// An enum for the different types of annotation data.
 #[derive(Clone, PartialEq, Eq)]
-pub enum AnnotationData {
+#[non_exhaustive]
+pub enum AnnotationData<'a> {
     ContinuingMultiline(ContinuingMultilineAnnotationData),
     ConnectingMultiline(ConnectingMultilineAnnotationData),
     Start(StartAnnotationLineData),
     ConnectingSingleline(ConnectingSinglelineAnnotationData),
     End(EndAnnotationLineData),
     Hanging(HangingAnnotationLineData),
-    Label(LabelAnnotationLineData),
+    Label(LabelAnnotationLineData<'a>),
 }
 
-impl AnnotationData {
+impl AnnotationData<'_> {
+    /// Returns the column index this data starts at.
     pub fn start_column_index(&self) -> usize {
         match self {
             // Doesn't have position information, but it's always at the beginning
@@ -148,7 +192,7 @@ impl AnnotationData {
     }
 }
 
-impl Debug for AnnotationData {
+impl Debug for AnnotationData<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             AnnotationData::ContinuingMultiline(data) => data.fmt(f),
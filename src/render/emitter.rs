@@ -0,0 +1,165 @@
+//! Contains the [`CollectingEmitter`], which accepts diagnostics one at a time and renders them
+//! using a [`DiagnosticRenderer`].
+//!
+//! [`CollectingEmitter`]: CollectingEmitter
+//! [`DiagnosticRenderer`]: DiagnosticRenderer
+
+use std::borrow::Cow;
+use std::fmt::Debug;
+use crate::render::style::WriteColor;
+use crate::diagnostic::{Diagnostic, Severity, SeverityCounts};
+use crate::file::Files;
+use crate::render::color::ColorConfig;
+use crate::render::{DiagnosticRenderer, RenderError, Result};
+
+/// When a [`CollectingEmitter`] renders the diagnostics it accepts.
+///
+/// [`CollectingEmitter`]: CollectingEmitter
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmitMode {
+    /// Diagnostics are collected, and rendered sorted by the location of their primary annotation
+    /// when the emitter is flushed or finished. Diagnostics without annotations come last.
+    #[default]
+    Buffered,
+    /// Diagnostics are rendered and the writer is flushed as soon as they are emitted, so that
+    /// long-running tools show diagnostics while they are still running.
+    ///
+    /// Errors exceeding [`RenderConfig::max_errors`] are counted in a single line when the
+    /// emitter is flushed or finished.
+    ///
+    /// [`RenderConfig::max_errors`]: crate::render::RenderConfig::max_errors
+    Streaming,
+}
+
+/// Accepts diagnostics one at a time, for example from multiple passes of a compiler,
+/// and renders them using a [`DiagnosticRenderer`].
+///
+/// ```rust
+/// use diagnostic_render::diagnostic::{Annotation, Diagnostic};
+/// use diagnostic_render::file::SimpleFile;
+/// use diagnostic_render::render::{DiagnosticRenderer, RenderConfig};
+/// use diagnostic_render::render::color::DefaultColorConfig;
+/// use diagnostic_render::render::emitter::{EmitMode, CollectingEmitter};
+/// use diagnostic_render::render::string::FmtWriter;
+///
+/// let mut buf = FmtWriter::new(String::new());
+/// let file = SimpleFile::new("main.test", "let main = 23;\n");
+/// let renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, file, RenderConfig::default());
+/// let mut emitter = CollectingEmitter::new(renderer, EmitMode::Buffered);
+///
+/// emitter.emit(Diagnostic::warning().with_message("Unused value")
+///     .with_annotation(Annotation::primary((), 4..8))).unwrap();
+/// emitter.abort_if_errors().unwrap();
+///
+/// let counts = emitter.finish().unwrap();
+/// assert!(!counts.has_errors());
+/// ```
+///
+/// [`DiagnosticRenderer`]: DiagnosticRenderer
+#[derive(Debug)]
+pub struct CollectingEmitter<'w, W, C, FileId, F> {
+    renderer: DiagnosticRenderer<'w, W, C, FileId, F>,
+    mode: EmitMode,
+    diagnostics: Vec<Diagnostic<FileId>>,
+    counts: SeverityCounts,
+    /// Errors suppressed in [`EmitMode::Streaming`] that have not been counted in the output yet.
+    ///
+    /// [`EmitMode::Streaming`]: EmitMode::Streaming
+    suppressed: usize,
+}
+
+impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> CollectingEmitter<'w, W, C, FileId, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Creates a new emitter, rendering diagnostics using `renderer`.
+    pub fn new(renderer: DiagnosticRenderer<'w, W, C, FileId, F>, mode: EmitMode) -> Self {
+        CollectingEmitter {
+            renderer,
+            mode,
+            diagnostics: Vec::new(),
+            counts: SeverityCounts::new(),
+            suppressed: 0,
+        }
+    }
+
+    /// Emits a diagnostic, rendering it immediately in [`EmitMode::Streaming`].
+    ///
+    /// Diagnostics below [`RenderConfig::min_severity`] are ignored.
+    ///
+    /// [`EmitMode::Streaming`]: EmitMode::Streaming
+    /// [`RenderConfig::min_severity`]: crate::render::RenderConfig::min_severity
+    pub fn emit(&mut self, diagnostic: Diagnostic<FileId>) -> Result {
+        if diagnostic.severity < self.renderer.config.min_severity {
+            return Ok(());
+        }
+
+        self.counts.add(diagnostic.severity);
+
+        match self.mode {
+            EmitMode::Buffered => {
+                self.diagnostics.push(diagnostic);
+                Ok(())
+            },
+            EmitMode::Streaming => {
+                if self.renderer.suppress_error(diagnostic.severity) {
+                    self.suppressed += 1;
+                    return Ok(());
+                }
+
+                self.renderer.render_impl(vec![Cow::Owned(diagnostic)])?;
+                self.renderer.f.flush()?;
+                Ok(())
+            },
+        }
+    }
+
+    /// Returns the number of diagnostics of every severity emitted so far,
+    /// including the ones that have not been rendered yet.
+    pub fn counts(&self) -> SeverityCounts {
+        self.counts
+    }
+
+    /// Renders all diagnostics that have not been rendered yet, and flushes the writer.
+    pub fn flush(&mut self) -> Result {
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+
+        // The sort is stable, so diagnostics at the same location stay in the order they were emitted in
+        diagnostics.sort_by_key(|d| {
+            let location = d.primary_annotation().map(|a| (a.file_id.clone(), a.range.start));
+            (location.is_none(), location)
+        });
+
+        self.renderer.render(diagnostics)?;
+
+        if self.suppressed > 0 {
+            writeln!(self.renderer.f, "{}", self.renderer.strings.suppressed(self.suppressed))?;
+            self.suppressed = 0;
+        }
+
+        self.renderer.f.flush()?;
+        Ok(())
+    }
+
+    /// Renders all diagnostics that have not been rendered yet and a summary, and fails with
+    /// [`RenderError::Aborted`] if any errors have been emitted.
+    ///
+    /// This is intended to be called between the passes of a compiler, to stop if a pass failed.
+    ///
+    /// [`RenderError::Aborted`]: RenderError::Aborted
+    pub fn abort_if_errors(&mut self) -> Result {
+        if !self.counts.has_errors() {
+            return Ok(());
+        }
+
+        self.flush()?;
+        self.renderer.render_summary()?;
+        Err(RenderError::Aborted { errors: self.counts.get(Severity::Error) + self.counts.get(Severity::Bug) })
+    }
+
+    /// Renders all diagnostics that have not been rendered yet and a summary,
+    /// and returns the number of emitted diagnostics of every severity.
+    pub fn finish(mut self) -> Result<SeverityCounts> {
+        self.flush()?;
+        self.renderer.render_summary()?;
+        Ok(self.counts)
+    }
+}
@@ -0,0 +1,269 @@
+//! Contains the code for rendering annotations as an annotated hexdump,
+//! used for [`SnippetStyle::Hexdump`].
+//!
+//! Every row shows the offset of its first byte, [`ROW_LEN`] bytes in hexadecimal, and the
+//! same bytes as ASCII characters. Annotated bytes are underlined both in the hexadecimal
+//! and in the ASCII column.
+//!
+//! [`SnippetStyle::Hexdump`]: crate::render::SnippetStyle::Hexdump
+
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::ops::Range;
+use crate::render::style::WriteColor;
+use unicode_width::UnicodeWidthStr;
+use crate::diagnostic::{Annotation, AnnotationStyle, Diagnostic};
+use crate::file::Files;
+use crate::render::color::ColorConfig;
+use crate::render::{DiagnosticRenderer, FilesExt, Result, Separator};
+
+/// The number of bytes shown in every row.
+const ROW_LEN: usize = 16;
+
+/// The minimum number of hexadecimal digits of the offsets in the gutter.
+const MIN_OFFSET_DIGITS: u32 = 8;
+
+/// Returns the column of the hexadecimal digits of the byte at the given index in a row.
+/// The two halves of a row are separated by an extra space.
+///
+/// The ASCII column starts at `column(ROW_LEN)`.
+fn column(index: usize) -> usize {
+    3 * index + index / (ROW_LEN / 2)
+}
+
+/// Returns the indices of the first and last row containing a byte range.
+/// Empty ranges are displayed on the byte they start at.
+fn rows(range: &Range<usize>) -> (usize, usize) {
+    let first = range.start / ROW_LEN;
+
+    if range.end > range.start {
+        (first, (range.end - 1) / ROW_LEN)
+    } else {
+        (first, first)
+    }
+}
+
+/// Returns the number of hexadecimal digits used for the offsets in the gutter, given the last
+/// annotated byte index and the number of surrounding rows shown for context.
+pub fn offset_digits(last_byte_index: usize, surrounding_rows: usize) -> u32 {
+    let last_offset = (last_byte_index / ROW_LEN + surrounding_rows) * ROW_LEN;
+
+    (last_offset.checked_ilog2().unwrap_or(0) / 4 + 1).max(MIN_OFFSET_DIGITS)
+}
+
+impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> DiagnosticRenderer<'w, W, C, FileId, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    pub(super) fn render_hexdump(&mut self, diagnostic: &Diagnostic<FileId>, file: &FileId, mut annotations: Vec<Annotation<FileId>>,
+                                 separator: Separator) -> Result {
+        if self.config.file_headers {
+            let offset = match separator {
+                Separator::Continuation => annotations.iter().min_by_key(|a| (a.style, a.range.start)),
+                _ => annotations.iter().find(|a| a.style == AnnotationStyle::Primary),
+            }.map(|a| a.range.start);
+
+            self.write_line_number(None, separator)?;
+            write!(self.f, " ")?;
+            self.colors.path(self.f)?;
+            self.write_path(file, None)?;
+
+            if let Some(offset) = offset {
+                write!(self.f, "+{:#x}", offset)?;
+            }
+
+            self.colors.reset(self.f)?;
+            writeln!(self.f)?;
+        }
+
+        let len = self.files.file_bytes(file)?.len();
+
+        annotations.sort_by_key(|a| a.range.start);
+        self.annotation_colors = annotations.iter().map(|a| a.color.clone()).collect();
+
+        let last_row_in_file = len.saturating_sub(1) / ROW_LEN;
+        let mut printed_rows = BTreeSet::new();
+
+        for annotation in annotations.iter() {
+            let (first, last) = rows(&annotation.range);
+            let last_printed = (last + self.config.surrounding_lines).min(last_row_in_file.max(last));
+
+            printed_rows.extend(first.saturating_sub(self.config.surrounding_lines)..=last_printed);
+        }
+
+        let mut previous_row = None;
+
+        for row in printed_rows.into_iter() {
+            if previous_row.is_some_and(|previous| row > previous + 1) {
+                self.write_line_number(None, Separator::Ellipsis)?;
+                writeln!(self.f)?;
+            }
+
+            self.write_hexdump_row(file, row)?;
+            self.render_hexdump_annotations(diagnostic, row, len, &annotations)?;
+            previous_row = Some(row);
+        }
+
+        self.annotation_colors.clear();
+        Ok(())
+    }
+
+    /// Writes the offset, hexadecimal bytes, and ASCII characters of a row.
+    fn write_hexdump_row(&mut self, file: &FileId, row: usize) -> Result {
+        let bytes = self.files.file_bytes(file)?;
+        let bytes = &bytes[(row * ROW_LEN).min(bytes.len())..((row + 1) * ROW_LEN).min(bytes.len())];
+
+        write!(self.f, "{:indent$}", "", indent = self.indent)?;
+        self.colors.line_number(self.f)?;
+        write!(self.f, "{:0fill$x}", row * ROW_LEN, fill = self.line_digits as usize)?;
+        self.colors.line_number_separator(self.f)?;
+        write!(self.f, " {}", self.config.charset.vertical_bar)?;
+        self.colors.reset(self.f)?;
+
+        if bytes.is_empty() {
+            writeln!(self.f)?;
+            return Ok(());
+        }
+
+        let mut line = String::with_capacity(column(ROW_LEN) + ROW_LEN);
+
+        for (i, byte) in bytes.iter().enumerate() {
+            line.push_str(&" ".repeat(column(i) - line.len()));
+            line.push_str(&format!("{:02x}", byte));
+        }
+
+        line.push_str(&" ".repeat(column(ROW_LEN) - line.len()));
+        line.extend(bytes.iter().map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }));
+
+        write!(self.f, " ")?;
+        self.colors.source(self.f)?;
+        write!(self.f, "{}", line)?;
+        self.colors.reset(self.f)?;
+        writeln!(self.f)?;
+        Ok(())
+    }
+
+    /// Writes the underlines and labels of the annotations on a row.
+    ///
+    /// The label of the rightmost annotation ending on this row is written after its
+    /// underline, while the labels of the others are written below, connected to the
+    /// start of their annotation with a vertical bar.
+    ///
+    /// Empty annotations at the end of the file, where there is no byte to underline, are drawn
+    /// as a single marker where the next byte would be, like in source code.
+    fn render_hexdump_annotations(&mut self, diagnostic: &Diagnostic<FileId>, row: usize, len: usize,
+                                  annotations: &[Annotation<FileId>]) -> Result {
+        let annotations = annotations.iter().enumerate()
+            .filter(|(_, a)| {
+                let (first, last) = rows(&a.range);
+                first <= row && row <= last
+            })
+            .collect::<Vec<_>>();
+
+        if annotations.is_empty() {
+            return Ok(());
+        }
+
+        let row_start = row * ROW_LEN;
+        // The style and index of the annotation drawn in each cell
+        let mut cells: Vec<Option<(AnnotationStyle, usize)>> = vec![None; column(ROW_LEN) + ROW_LEN];
+        let mut labels = Vec::new();
+
+        for &(index, annotation) in annotations.iter() {
+            let start = annotation.range.start.max(row_start) - row_start;
+            let end = (annotation.range.end.min(row_start + ROW_LEN) - row_start).max(start + 1);
+
+            let (hex_cells, ascii_cells) = if annotation.range.is_empty() && annotation.range.start == len {
+                (column(start)..column(start) + 1, 0..0)
+            } else {
+                (column(start)..column(end - 1) + 2, column(ROW_LEN) + start..column(ROW_LEN) + end)
+            };
+
+            // Primary annotations are drawn on top of secondary ones
+            for cell in hex_cells.chain(ascii_cells) {
+                if !matches!(cells[cell], Some((AnnotationStyle::Primary, _))) {
+                    cells[cell] = Some((annotation.style, index));
+                }
+            }
+
+            if rows(&annotation.range).1 == row && !annotation.label.is_empty() {
+                labels.push((column(start), (annotation.style, index), annotation.label.as_str()));
+            }
+        }
+
+        let mut segments: Vec<(usize, (AnnotationStyle, usize), String)> = Vec::new();
+
+        for (i, cell) in cells.iter().enumerate() {
+            let Some((style, index)) = *cell else {
+                continue;
+            };
+
+            match segments.last_mut() {
+                Some((start, last_style, text)) if *last_style == (style, index) && *start + text.chars().count() == i => {
+                    text.push(self.config.charset.severity_underline(style, diagnostic.severity));
+                },
+                _ => segments.push((i, (style, index), self.config.charset.severity_underline(style, diagnostic.severity).to_string())),
+            }
+        }
+
+        let vertical_bar = self.config.charset.vertical_bar;
+        let bars = |count: usize| labels[..count].iter()
+            .map(|&(column, style, _)| (column, style, vertical_bar.to_string()))
+            .collect::<Vec<_>>();
+        // Further lines of a label are written below its first line, next to the bars
+        // of the labels that come after it
+        let mut continuation_lines = Vec::new();
+
+        if let Some((_, style, label)) = labels.last() {
+            let end = cells.iter().rposition(Option::is_some).map_or(0, |i| i + 1);
+            let mut lines = label.lines();
+            segments.push((end + 1, *style, lines.next().unwrap_or_default().to_string()));
+
+            for line in lines {
+                let mut segments = bars(labels.len() - 1);
+                segments.push((end + 1, *style, line.to_string()));
+                continuation_lines.push(segments);
+            }
+        }
+
+        self.write_hexdump_segments(diagnostic, &segments)?;
+
+        for segments in continuation_lines.into_iter() {
+            self.write_hexdump_segments(diagnostic, &segments)?;
+        }
+
+        for i in (0..labels.len().saturating_sub(1)).rev() {
+            self.write_hexdump_segments(diagnostic, &bars(i + 1))?;
+
+            let (column, style, label) = labels[i];
+
+            for line in label.lines() {
+                let mut segments = bars(i);
+                segments.push((column, style, line.to_string()));
+                self.write_hexdump_segments(diagnostic, &segments)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes an annotation line consisting of pieces of text at the given columns, which are
+    /// relative to the start of the hexadecimal bytes, and are colored like the annotation
+    /// with the given style and index. The segments have to be sorted by column.
+    fn write_hexdump_segments(&mut self, diagnostic: &Diagnostic<FileId>, segments: &[(usize, (AnnotationStyle, usize), String)]) -> Result {
+        self.write_line_number(None, Separator::Bar)?;
+        write!(self.f, " ")?;
+
+        let mut horizontal_index = 0;
+
+        for &(column, (style, index), ref text) in segments.iter() {
+            self.write_repeated(' ', column.saturating_sub(horizontal_index))?;
+            self.annotation_color(style, diagnostic.severity, index)?;
+            write!(self.f, "{}", text)?;
+            self.colors.reset(self.f)?;
+
+            horizontal_index = column.max(horizontal_index) + text.width();
+        }
+
+        writeln!(self.f)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! Contains a writer that captures rendered diagnostics as HTML.
+//!
+//! Together with [`render_to_string`], this allows showing diagnostics in environments without
+//! a terminal, like in-browser playgrounds compiled to `wasm32-unknown-unknown`. Neither of them
+//! accesses the terminal or any other platform-specific API.
+//!
+//! # Example
+//! ```rust
+//! use diagnostic_render::diagnostic::{Annotation, Diagnostic};
+//! use diagnostic_render::file::SimpleFile;
+//! use diagnostic_render::render::{DiagnosticRenderer, RenderConfig};
+//! use diagnostic_render::render::color::DefaultColorConfig;
+//! use diagnostic_render::render::html::HtmlWriter;
+//!
+//! let file = SimpleFile::new("main.test", "let main = 23;\n");
+//! let diagnostic = Diagnostic::error()
+//!     .with_message("Mismatched types")
+//!     .with_annotation(Annotation::primary((), 11..13).with_label("expected `String`"));
+//!
+//! let mut html = HtmlWriter::new();
+//! DiagnosticRenderer::new(&mut html, DefaultColorConfig, file, RenderConfig::default())
+//!     .render(vec![diagnostic]).unwrap();
+//! let html = html.finish();
+//!
+//! assert!(html.starts_with("<pre"));
+//! ```
+//!
+//! [`render_to_string`]: crate::render::string::render_to_string
+
+use std::fmt::Write as _;
+use std::io::Write;
+use crate::render::style::{ColorSpec, WriteColor};
+use crate::render::svg::{css_color, escape};
+
+/// A [`WriteColor`] implementation that records the written text and its colors,
+/// and turns them into a `<pre>` element when [`finish`] is called.
+///
+/// [`WriteColor`]: WriteColor
+/// [`finish`]: HtmlWriter::finish
+#[derive(Clone, Debug)]
+pub struct HtmlWriter {
+    /// The background color of the element, as a CSS color, or `None` to not set one.
+    pub background: Option<String>,
+    /// The color used for text without formatting, as a CSS color, or `None` to not set one.
+    pub foreground: Option<String>,
+    spans: Vec<(ColorSpec, Vec<u8>)>,
+    spec: ColorSpec,
+}
+
+impl HtmlWriter {
+    /// Creates a new HTML writer, which uses the colors of the surrounding page.
+    pub fn new() -> Self {
+        HtmlWriter {
+            background: None,
+            foreground: None,
+            spans: Vec::new(),
+            spec: ColorSpec::new(),
+        }
+    }
+
+    /// Returns a `<pre>` element containing everything written so far.
+    pub fn finish(&self) -> String {
+        let mut html = String::from("<pre class=\"diagnostic\"");
+        let declarations = [("background-color", self.background.as_ref()), ("color", self.foreground.as_ref())].into_iter()
+            .filter_map(|(property, value)| value.map(|value| format!("{}: {}", property, value)))
+            .collect::<Vec<_>>();
+
+        if !declarations.is_empty() {
+            let _ = write!(html, " style=\"{}\"", escape(&declarations.join("; ")));
+        }
+
+        html.push('>');
+
+        for (spec, bytes) in self.spans.iter() {
+            let text = escape(&String::from_utf8_lossy(bytes));
+            let style = style(spec);
+
+            if style.is_empty() {
+                html.push_str(&text);
+            } else {
+                // Writing to a String can't fail
+                let _ = write!(html, "<span style=\"{}\">{}</span>", style, text);
+            }
+        }
+
+        html.push_str("</pre>\n");
+        html
+    }
+}
+
+impl Default for HtmlWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for HtmlWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.spans.last_mut() {
+            Some((spec, bytes)) if *spec == self.spec => bytes.extend_from_slice(buf),
+            _ => self.spans.push((self.spec.clone(), buf.to_vec())),
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl WriteColor for HtmlWriter {
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> std::io::Result<()> {
+        self.spec = spec.clone();
+        Ok(())
+    }
+
+    fn reset(&mut self) -> std::io::Result<()> {
+        self.spec = ColorSpec::new();
+        Ok(())
+    }
+}
+
+/// Returns the CSS declarations for the formatting in a color spec.
+fn style(spec: &ColorSpec) -> String {
+    let mut declarations = Vec::new();
+
+    if let Some(color) = spec.fg() {
+        declarations.push(format!("color: {}", css_color(color, spec.intense())));
+    }
+
+    if let Some(color) = spec.bg() {
+        declarations.push(format!("background-color: {}", css_color(color, spec.intense())));
+    }
+
+    if spec.bold() {
+        declarations.push(String::from("font-weight: bold"));
+    }
+
+    if spec.italic() {
+        declarations.push(String::from("font-style: italic"));
+    }
+
+    if spec.underline() {
+        declarations.push(String::from("text-decoration: underline"));
+    }
+
+    declarations.join("; ")
+}
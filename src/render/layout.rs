@@ -0,0 +1,96 @@
+//! Computing the layout of the annotations of a diagnostic, for backends that draw
+//! diagnostics themselves, like editors or TUI applications.
+//!
+//! [`layout`] returns the same [`AnnotationData`] the text renderer draws, with positions in
+//! display columns of the source lines, so that other backends don't have to derive the layout
+//! from the text output.
+//!
+//! [`layout`]: layout
+//! [`AnnotationData`]: AnnotationData
+
+use std::fmt::Debug;
+use crate::diagnostic::{Annotation, Diagnostic};
+use crate::file::Files;
+use crate::render::calculate;
+use crate::render::data::AnnotationData;
+use crate::render::{annotated_lines, group_by_file, sort_files, FileOperation, FilesExt, RenderConfig, Result};
+
+/// A line of source code with annotations on it, and the rows of annotation data
+/// drawn below it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RenderedLine<'a, FileId> {
+    /// The file this line is in.
+    pub file_id: FileId,
+    /// The `0`-indexed line index.
+    pub line_index: usize,
+    /// The line number shown in the gutter.
+    pub line_number: usize,
+    /// The rows drawn below the line, from top to bottom. The data in every row is sorted by
+    /// column, and multi-line annotations continuing past this line appear as
+    /// [`AnnotationData::ContinuingMultiline`] in every row.
+    ///
+    /// [`AnnotationData::ContinuingMultiline`]: AnnotationData::ContinuingMultiline
+    pub rows: Vec<Vec<AnnotationData<'a>>>,
+}
+
+/// Computes the layout of the annotations of a diagnostic.
+///
+/// The result contains every line on which an annotation starts or ends, grouped by file in the
+/// order the text renderer shows the files in, and sorted by line within a file. Labels are
+/// borrowed from the annotations of `diagnostic`.
+///
+/// The annotations are laid out as they are; [`RenderConfig::merge_annotations`] and
+/// [`RenderConfig::max_annotations_per_line`] only apply to the text renderer.
+/// Sub-diagnostics are not included, but can be laid out by calling this function for them.
+///
+/// ```
+/// use diagnostic_render::diagnostic::{Annotation, AnnotationStyle, Diagnostic, Severity};
+/// use diagnostic_render::file::SimpleFile;
+/// use diagnostic_render::render::RenderConfig;
+/// use diagnostic_render::render::data::AnnotationData;
+/// use diagnostic_render::render::layout::layout;
+///
+/// let file = SimpleFile::new("main.rs", "let x = 1;\n");
+/// let diagnostic = Diagnostic::new(Severity::Warning)
+///     .with_message("unused variable")
+///     .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..5).with_label("x"));
+///
+/// let lines = layout(&diagnostic, &file, &RenderConfig::default()).unwrap();
+/// assert_eq!(lines.len(), 1);
+/// assert!(lines[0].rows[0].iter().any(|data| matches!(data, AnnotationData::Label(label) if label.label == "x")));
+/// ```
+///
+/// [`RenderConfig::merge_annotations`]: RenderConfig::merge_annotations
+/// [`RenderConfig::max_annotations_per_line`]: RenderConfig::max_annotations_per_line
+pub fn layout<'a, F: Files>(diagnostic: &'a Diagnostic<F::FileId>, files: &F, config: &RenderConfig) -> Result<Vec<RenderedLine<'a, F::FileId>>>
+    where F::FileId: Clone + Debug + Eq + Ord {
+    let primary_file = diagnostic.primary_annotation().map(|a| &a.file_id);
+    let mut annotations = group_by_file(diagnostic.annotations.iter(), |a| &a.file_id);
+    sort_files(&mut annotations, primary_file, config.file_order);
+
+    let mut lines = Vec::new();
+
+    for (file, mut annotations) in annotations.into_iter() {
+        // Sort by start byte index
+        annotations.sort_by_key(|a| a.range.start);
+
+        for line in annotated_lines(files, &file, &annotations)? {
+            let line_index = line.line_index;
+            let on_line = line.on_line.iter().map(|&i| (i, annotations[i])).collect::<Vec<(usize, &Annotation<F::FileId>)>>();
+            let continuing = line.continuing.iter().map(|&i| (i, annotations[i])).collect::<Vec<_>>();
+
+            let rows = calculate::calculate(diagnostic, files, &file, line_index, &on_line, &continuing, config)
+                .map_err(|err| files.file_error(&file, FileOperation::Layout { line_index }, err))?;
+
+            lines.push(RenderedLine {
+                line_number: files.file_line_number(&file, line_index)?,
+                file_id: file.clone(),
+                line_index,
+                rows,
+            });
+        }
+    }
+
+    Ok(lines)
+}
@@ -1,21 +1,184 @@
-use std::collections::BTreeMap;
+use std::borrow::{Borrow, Cow};
 use std::fmt::Debug;
 use std::marker::PhantomData;
-use termcolor::WriteColor;
-use crate::diagnostic::{Annotation, AnnotationStyle, Diagnostic};
-use crate::file::{Error, Files};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use crate::diagnostic::{Annotation, AnnotationStyle, Diagnostic, ExpectedFound, Note, Replacement, Severity, SeverityCounts, Suggestion};
+use crate::file::{Error, Files, Location};
+use crate::render::chars::CharSet;
 use crate::render::color::ColorConfig;
 use crate::render::data::AnnotationData;
+use crate::render::strings::{EnglishStrings, Strings};
+use crate::render::style::{HyperlinkSpec, WriteColor};
+use crate::render::theme::TextStyle;
 
+pub mod backend;
+pub mod chars;
 pub mod color;
+pub mod data;
+pub mod emitter;
+pub mod html;
+pub mod layout;
+pub mod report;
+pub mod string;
+pub mod strings;
+pub mod style;
+pub mod svg;
+pub mod theme;
+
+#[cfg(feature = "rayon")]
+mod parallel;
 
-mod data;
 mod calculate;
+mod hexdump;
+mod text;
 
 /// Result type for methods writing to a [`WriteColor`].
 ///
 /// [`WriteColor`]: WriteColor
-type Result = std::result::Result<(), Error>;
+type Result<T = ()> = std::result::Result<T, RenderError>;
+
+/// An error that happened while rendering diagnostics.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RenderError {
+    /// Accessing a file in the file database failed.
+    File {
+        /// The name of the file, or its ID formatted using [`Debug`] if the name is not available.
+        ///
+        /// [`Debug`]: Debug
+        file: String,
+        /// The operation that failed.
+        operation: FileOperation,
+        /// The error returned by the file database, or found while checking its result.
+        error: Error,
+    },
+    /// Writing the output failed.
+    Io(std::io::Error),
+    /// Errors were emitted, and [`CollectingEmitter::abort_if_errors`] was called.
+    ///
+    /// [`CollectingEmitter::abort_if_errors`]: emitter::CollectingEmitter::abort_if_errors
+    Aborted {
+        /// The number of emitted errors, including bugs.
+        errors: usize,
+    },
+}
+
+impl From<std::io::Error> for RenderError {
+    fn from(err: std::io::Error) -> RenderError {
+        RenderError::Io(err)
+    }
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::File { file, operation, error } => {
+                write!(f, "failed to {} in file `{}`: {}", operation, file, error)
+            },
+            RenderError::Io(err) => write!(f, "failed to write diagnostics: {}", err),
+            RenderError::Aborted { errors } => write!(f, "aborted due to {} error{}", errors, if *errors == 1 { "" } else { "s" }),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::File { error, .. } => Some(error),
+            RenderError::Io(err) => Some(err),
+            RenderError::Aborted { .. } => None,
+        }
+    }
+}
+
+/// An operation on a file that can fail while rendering diagnostics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FileOperation {
+    /// Getting the name of the file.
+    Name,
+    /// Getting the source code of the file.
+    Source,
+    /// Getting the bytes of the file, for [`SnippetStyle::Hexdump`].
+    ///
+    /// [`SnippetStyle::Hexdump`]: SnippetStyle::Hexdump
+    Bytes,
+    /// Finding the line containing a byte index.
+    LineIndex { byte_index: usize },
+    /// Getting the byte range of a line.
+    LineRange { line_index: usize },
+    /// Getting the user-facing line number of a line.
+    LineNumber { line_index: usize },
+    /// Getting the user-facing location of a byte index.
+    Location { byte_index: usize },
+    /// Accessing the source code at a byte index of an annotation or a suggestion.
+    SourceIndex { byte_index: usize },
+    /// Calculating the layout of the annotations on a line.
+    Layout { line_index: usize },
+}
+
+impl std::fmt::Display for FileOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileOperation::Name => write!(f, "get the file name"),
+            FileOperation::Source => write!(f, "get the source code"),
+            FileOperation::Bytes => write!(f, "get the bytes"),
+            FileOperation::LineIndex { byte_index } => write!(f, "find the line containing byte index {}", byte_index),
+            FileOperation::LineRange { line_index } => write!(f, "get the range of line index {}", line_index),
+            FileOperation::LineNumber { line_index } => write!(f, "get the line number of line index {}", line_index),
+            FileOperation::Location { byte_index } => write!(f, "get the location of byte index {}", byte_index),
+            FileOperation::SourceIndex { byte_index } => write!(f, "access the source code at byte index {}", byte_index),
+            FileOperation::Layout { line_index } => write!(f, "lay out the annotations on line index {}", line_index),
+        }
+    }
+}
+
+/// Accessors for a file database that add the name of the file and the failed operation
+/// to its errors.
+trait FilesExt: Files where Self::FileId: Debug {
+    /// Wraps an error of the file database, adding the name of the file and the failed operation.
+    fn file_error(&self, file: &Self::FileId, operation: FileOperation, error: Error) -> RenderError {
+        let file = match self.name(file.clone()) {
+            Ok(name) => name.to_string(),
+            Err(_) => format!("{:?}", file),
+        };
+
+        RenderError::File { file, operation, error }
+    }
+
+    fn file_name(&self, file: &Self::FileId) -> Result<&str> {
+        self.name(file.clone()).map_err(|err| self.file_error(file, FileOperation::Name, err))
+    }
+
+    fn file_source(&self, file: &Self::FileId) -> Result<Cow<'_, str>> {
+        self.source(file.clone()).map_err(|err| self.file_error(file, FileOperation::Source, err))
+    }
+
+    fn file_bytes(&self, file: &Self::FileId) -> Result<Cow<'_, [u8]>> {
+        self.bytes(file.clone()).map_err(|err| self.file_error(file, FileOperation::Bytes, err))
+    }
+
+    fn file_line_index(&self, file: &Self::FileId, byte_index: usize) -> Result<usize> {
+        self.line_index(file.clone(), byte_index)
+            .map_err(|err| self.file_error(file, FileOperation::LineIndex { byte_index }, err))
+    }
+
+    fn file_line_range(&self, file: &Self::FileId, line_index: usize) -> Result<Range<usize>> {
+        self.line_range(file.clone(), line_index)
+            .map_err(|err| self.file_error(file, FileOperation::LineRange { line_index }, err))
+    }
+
+    fn file_line_number(&self, file: &Self::FileId, line_index: usize) -> Result<usize> {
+        self.line_number(file.clone(), line_index)
+            .map_err(|err| self.file_error(file, FileOperation::LineNumber { line_index }, err))
+    }
+}
+
+impl<F: Files + ?Sized> FilesExt for F where F::FileId: Debug {}
 
 /// Represents a location in a specific source file,
 /// using line and column indices.
@@ -25,7 +188,7 @@ type Result = std::result::Result<(), Error>;
 ///
 /// It is not necessarily checked that this position exists
 /// in the source file.
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct LineColumn {
     /// The `0`-indexed line index.
     pub line_index: usize,
@@ -49,38 +212,667 @@ impl From<(usize, usize)> for LineColumn {
     }
 }
 
+/// The visible part of source lines that are wider than [`RenderConfig::max_line_width`].
+///
+/// [`RenderConfig::max_line_width`]: RenderConfig::max_line_width
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Window {
+    /// The number of columns cut off at the start of every line.
+    pub offset: usize,
+    /// The number of visible columns of source code, not including the ellipses at the cut edges.
+    pub width: usize,
+    /// The width of the ellipsis in columns.
+    pub ellipsis_width: usize,
+}
+
+impl Window {
+    /// Converts a column index in a source line into the column it is displayed at.
+    ///
+    /// Columns that were cut off are moved onto the ellipsis at the edge they are beyond,
+    /// so annotations outside the window still point at where their text would be.
+    pub fn column(&self, column_index: usize) -> usize {
+        let start = if self.offset > 0 { self.ellipsis_width } else { 0 };
+
+        if column_index < self.offset {
+            start.saturating_sub(1)
+        } else {
+            start + (column_index - self.offset).min(self.width)
+        }
+    }
+}
+
 /// Contains some configuration parameters for [`DiagnosticRenderer`].
 ///
+/// As new options can be added in the future, this cannot be constructed with a struct
+/// literal outside of this crate. Use [`RenderConfig::default`] and the builder methods instead.
+///
+/// With the `serde` feature, this can be deserialized, for example from a section of the
+/// configuration file of an application. Missing fields use their default value.
+///
 /// [`DiagnosticRenderer`]: DiagnosticRenderer
+/// [`RenderConfig::default`]: RenderConfig::default
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default, deny_unknown_fields))]
+#[non_exhaustive]
 pub struct RenderConfig {
     /// How many lines of source code to include around annotated lines for context.
     pub surrounding_lines: usize,
+    /// The characters used for drawing annotations and the gutter.
+    pub charset: CharSet,
+    /// The format used for displaying diagnostics.
+    pub display_style: DisplayStyle,
+    /// The width in columns at which the text of notes is wrapped, or `None` to disable wrapping.
+    ///
+    /// Continuation lines are indented to line up with the start of the message. Words longer
+    /// than the available width are not broken up.
+    pub wrap_width: Option<usize>,
+    /// The format of the OSC-8 hyperlinks that file paths are wrapped in, or `None` to not
+    /// output hyperlinks. Hyperlinks are only written if the writer supports them.
+    ///
+    /// The placeholders `{path}`, `{line}`, and `{column}` are replaced with the name of the file,
+    /// and the line and column number of the location. For example, `file://{path}` opens the file,
+    /// and `vscode://file/{path}:{line}:{column}` opens it at the location in Visual Studio Code.
+    pub hyperlink_format: Option<String>,
+    /// The maximum width of source lines in columns, or `None` to never trim them.
+    ///
+    /// Longer lines are trimmed around the annotated region, with [`CharSet::ellipsis`]
+    /// shown at the edges that were cut off. The ellipses count towards the width, and
+    /// annotations outside of the visible part of a line point at the ellipsis of its edge.
+    ///
+    /// [`CharSet::ellipsis`]: CharSet::ellipsis
+    pub max_line_width: Option<usize>,
+    /// The number of columns a tab character in source code is expanded to.
+    pub tab_width: usize,
+    /// The unit that columns are counted in.
+    pub column_unit: ColumnUnit,
+    /// How control characters in source lines are displayed.
+    pub control_chars: ControlChars,
+    /// How the annotated parts of source files are displayed.
+    pub snippet_style: SnippetStyle,
+    /// The order in which the files of a diagnostic are displayed.
+    pub file_order: FileOrder,
+    /// The minimum severity of rendered diagnostics. Diagnostics with a lower severity are skipped.
+    pub min_severity: Severity,
+    /// Whether [`RenderConfig::min_severity`] also applies to the notes and sub-diagnostics
+    /// of rendered diagnostics.
+    ///
+    /// [`RenderConfig::min_severity`]: RenderConfig::min_severity
+    pub filter_children: bool,
+    /// The maximum number of errors and bugs that are rendered, or `None` to render all of them.
+    ///
+    /// Errors after that are not rendered, but added to the [`Diagnostic::suppressed_count`]
+    /// of the last diagnostic rendered in the same call of [`DiagnosticRenderer::render`].
+    /// They are still included in [`DiagnosticRenderer::counts`].
+    ///
+    /// [`Diagnostic::suppressed_count`]: Diagnostic::suppressed_count
+    /// [`DiagnosticRenderer::render`]: DiagnosticRenderer::render
+    /// [`DiagnosticRenderer::counts`]: DiagnosticRenderer::counts
+    pub max_errors: Option<usize>,
+    /// Whether single-line annotations on the same line with the same style and label are
+    /// merged into one annotation if they overlap or touch.
+    ///
+    /// This reduces noise when annotations are created from the spans of individual tokens.
+    pub merge_annotations: bool,
+    /// The maximum number of single-line annotations shown on a source line, or `None` to show
+    /// all of them.
+    ///
+    /// Primary annotations are kept first, then annotations in the order they start in. The other
+    /// annotations are summarized below the line, and their labels are moved to the notes of the
+    /// diagnostic. At least one annotation is always shown, so that every annotated line is
+    /// still rendered. This does not apply to [`SnippetStyle::Hexdump`].
+    ///
+    /// [`SnippetStyle::Hexdump`]: SnippetStyle::Hexdump
+    pub max_annotations_per_line: Option<NonZeroUsize>,
+    /// Whether text inside backticks in messages, labels and notes is written with
+    /// [`ColorConfig::emphasis`], like code in `rustc` diagnostics. The backticks themselves
+    /// are still written.
+    ///
+    /// [`ColorConfig::emphasis`]: ColorConfig::emphasis
+    pub emphasize_backticks: bool,
+    /// Whether every annotation of a diagnostic gets its own color, using
+    /// [`ColorConfig::indexed_annotation`], so that overlapping underlines can be matched to
+    /// their labels. By default, annotations are colored by their style and severity only.
+    ///
+    /// [`ColorConfig::indexed_annotation`]: ColorConfig::indexed_annotation
+    pub cycle_annotation_colors: bool,
+    /// Whether the annotated parts of source lines are written with
+    /// [`ColorConfig::annotated_source`], so that they stand out even without looking at the
+    /// underlines below them. Where annotations overlap, primary annotations take precedence.
+    ///
+    /// [`ColorConfig::annotated_source`]: ColorConfig::annotated_source
+    pub highlight_source: bool,
+    /// Whether the annotated code of every file is preceded by a header with its path and
+    /// the location of its first annotation, like `--> main.rs:1:5`. Leaving them out saves
+    /// space, but makes it impossible to tell which file the code is from.
+    pub file_headers: bool,
+    /// The minimum number of digits that is reserved for line numbers in the gutter, or for
+    /// byte offsets when rendering hexdumps.
+    ///
+    /// By default, the gutter is only as wide as the last printed line number of each
+    /// diagnostic, so snippets from different diagnostics or files don't line up with each
+    /// other. Setting this to a fixed width makes the output easier to compare or concatenate.
+    pub min_line_number_width: u32,
+    /// Whether the byte range of the annotation whose location is shown in a file header is
+    /// written after it, like `--> main.rs:3:7 (bytes 41..58)`. This is useful for formats
+    /// that are close to binary, and for debugging how spans are computed.
+    pub byte_offsets: bool,
+    /// How much information about every diagnostic is rendered.
+    pub verbosity: Verbosity,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        RenderConfig {
+            surrounding_lines: 1,
+            charset: CharSet::default(),
+            display_style: DisplayStyle::default(),
+            wrap_width: None,
+            hyperlink_format: None,
+            max_line_width: None,
+            tab_width: 4,
+            column_unit: ColumnUnit::default(),
+            control_chars: ControlChars::default(),
+            snippet_style: SnippetStyle::default(),
+            file_order: FileOrder::default(),
+            min_severity: Severity::Help,
+            filter_children: false,
+            max_errors: None,
+            merge_annotations: false,
+            max_annotations_per_line: None,
+            emphasize_backticks: false,
+            cycle_annotation_colors: false,
+            highlight_source: false,
+            file_headers: true,
+            min_line_number_width: 0,
+            byte_offsets: false,
+            verbosity: Verbosity::default(),
+        }
+    }
+}
+
+/// Builder methods, so that configurations can be created without struct literals.
+///
+/// ```
+/// # use diagnostic_render::render::{ColumnUnit, RenderConfig};
+/// let config = RenderConfig::default()
+///     .surrounding_lines(2)
+///     .unicode(true)
+///     .column_unit(ColumnUnit::Chars);
+/// ```
+impl RenderConfig {
+    /// Sets [`RenderConfig::surrounding_lines`].
+    ///
+    /// [`RenderConfig::surrounding_lines`]: RenderConfig::surrounding_lines
+    pub fn surrounding_lines(mut self, surrounding_lines: usize) -> Self {
+        self.surrounding_lines = surrounding_lines;
+        self
+    }
+
+    /// Sets [`RenderConfig::charset`].
+    ///
+    /// [`RenderConfig::charset`]: RenderConfig::charset
+    pub fn charset(mut self, charset: CharSet) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Sets [`RenderConfig::charset`] to [`CharSet::unicode`] if `unicode` is `true`,
+    /// and to [`CharSet::ascii`] otherwise.
+    ///
+    /// [`RenderConfig::charset`]: RenderConfig::charset
+    /// [`CharSet::unicode`]: CharSet::unicode
+    /// [`CharSet::ascii`]: CharSet::ascii
+    pub fn unicode(self, unicode: bool) -> Self {
+        self.charset(if unicode { CharSet::unicode() } else { CharSet::ascii() })
+    }
+
+    /// Sets [`RenderConfig::display_style`].
+    ///
+    /// [`RenderConfig::display_style`]: RenderConfig::display_style
+    pub fn display_style(mut self, display_style: DisplayStyle) -> Self {
+        self.display_style = display_style;
+        self
+    }
+
+    /// Sets [`RenderConfig::wrap_width`].
+    ///
+    /// [`RenderConfig::wrap_width`]: RenderConfig::wrap_width
+    pub fn wrap_width(mut self, wrap_width: Option<usize>) -> Self {
+        self.wrap_width = wrap_width;
+        self
+    }
+
+    /// Sets [`RenderConfig::hyperlink_format`].
+    ///
+    /// [`RenderConfig::hyperlink_format`]: RenderConfig::hyperlink_format
+    pub fn hyperlink_format(mut self, hyperlink_format: Option<String>) -> Self {
+        self.hyperlink_format = hyperlink_format;
+        self
+    }
+
+    /// Sets [`RenderConfig::max_line_width`].
+    ///
+    /// [`RenderConfig::max_line_width`]: RenderConfig::max_line_width
+    pub fn max_line_width(mut self, max_line_width: Option<usize>) -> Self {
+        self.max_line_width = max_line_width;
+        self
+    }
+
+    /// Sets [`RenderConfig::tab_width`].
+    ///
+    /// [`RenderConfig::tab_width`]: RenderConfig::tab_width
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Sets [`RenderConfig::column_unit`].
+    ///
+    /// [`RenderConfig::column_unit`]: RenderConfig::column_unit
+    pub fn column_unit(mut self, column_unit: ColumnUnit) -> Self {
+        self.column_unit = column_unit;
+        self
+    }
+
+    /// Sets [`RenderConfig::control_chars`].
+    ///
+    /// [`RenderConfig::control_chars`]: RenderConfig::control_chars
+    pub fn control_chars(mut self, control_chars: ControlChars) -> Self {
+        self.control_chars = control_chars;
+        self
+    }
+
+    /// Sets [`RenderConfig::snippet_style`].
+    ///
+    /// [`RenderConfig::snippet_style`]: RenderConfig::snippet_style
+    pub fn snippet_style(mut self, snippet_style: SnippetStyle) -> Self {
+        self.snippet_style = snippet_style;
+        self
+    }
+
+    /// Sets [`RenderConfig::file_order`].
+    ///
+    /// [`RenderConfig::file_order`]: RenderConfig::file_order
+    pub fn file_order(mut self, file_order: FileOrder) -> Self {
+        self.file_order = file_order;
+        self
+    }
+
+    /// Sets [`RenderConfig::min_severity`].
+    ///
+    /// [`RenderConfig::min_severity`]: RenderConfig::min_severity
+    pub fn min_severity(mut self, min_severity: Severity) -> Self {
+        self.min_severity = min_severity;
+        self
+    }
+
+    /// Sets [`RenderConfig::filter_children`].
+    ///
+    /// [`RenderConfig::filter_children`]: RenderConfig::filter_children
+    pub fn filter_children(mut self, filter_children: bool) -> Self {
+        self.filter_children = filter_children;
+        self
+    }
+
+    /// Sets [`RenderConfig::max_errors`].
+    ///
+    /// [`RenderConfig::max_errors`]: RenderConfig::max_errors
+    pub fn max_errors(mut self, max_errors: Option<usize>) -> Self {
+        self.max_errors = max_errors;
+        self
+    }
+
+    /// Sets [`RenderConfig::merge_annotations`].
+    ///
+    /// [`RenderConfig::merge_annotations`]: RenderConfig::merge_annotations
+    pub fn merge_annotations(mut self, merge_annotations: bool) -> Self {
+        self.merge_annotations = merge_annotations;
+        self
+    }
+
+    /// Sets [`RenderConfig::max_annotations_per_line`].
+    ///
+    /// [`RenderConfig::max_annotations_per_line`]: RenderConfig::max_annotations_per_line
+    pub fn max_annotations_per_line(mut self, max_annotations_per_line: Option<NonZeroUsize>) -> Self {
+        self.max_annotations_per_line = max_annotations_per_line;
+        self
+    }
+
+    /// Sets [`RenderConfig::emphasize_backticks`].
+    ///
+    /// [`RenderConfig::emphasize_backticks`]: RenderConfig::emphasize_backticks
+    pub fn emphasize_backticks(mut self, emphasize_backticks: bool) -> Self {
+        self.emphasize_backticks = emphasize_backticks;
+        self
+    }
+
+    /// Sets [`RenderConfig::cycle_annotation_colors`].
+    ///
+    /// [`RenderConfig::cycle_annotation_colors`]: RenderConfig::cycle_annotation_colors
+    pub fn cycle_annotation_colors(mut self, cycle_annotation_colors: bool) -> Self {
+        self.cycle_annotation_colors = cycle_annotation_colors;
+        self
+    }
+
+    /// Sets [`RenderConfig::highlight_source`].
+    ///
+    /// [`RenderConfig::highlight_source`]: RenderConfig::highlight_source
+    pub fn highlight_source(mut self, highlight_source: bool) -> Self {
+        self.highlight_source = highlight_source;
+        self
+    }
+
+    /// Sets [`RenderConfig::file_headers`].
+    ///
+    /// [`RenderConfig::file_headers`]: RenderConfig::file_headers
+    pub fn file_headers(mut self, file_headers: bool) -> Self {
+        self.file_headers = file_headers;
+        self
+    }
+
+    /// Sets [`RenderConfig::min_line_number_width`].
+    ///
+    /// [`RenderConfig::min_line_number_width`]: RenderConfig::min_line_number_width
+    pub fn min_line_number_width(mut self, min_line_number_width: u32) -> Self {
+        self.min_line_number_width = min_line_number_width;
+        self
+    }
+
+    /// Sets [`RenderConfig::byte_offsets`].
+    ///
+    /// [`RenderConfig::byte_offsets`]: RenderConfig::byte_offsets
+    pub fn byte_offsets(mut self, byte_offsets: bool) -> Self {
+        self.byte_offsets = byte_offsets;
+        self
+    }
+
+    /// Sets [`RenderConfig::verbosity`].
+    ///
+    /// [`RenderConfig::verbosity`]: RenderConfig::verbosity
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+}
+
+/// How control characters in source lines, like NUL, BEL, or the escape character
+/// starting ANSI escape sequences, are displayed.
+///
+/// Tabs and line endings are not affected by this.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum ControlChars {
+    /// Control characters are written to the output unchanged.
+    ///
+    /// This allows source code to change the formatting of the terminal, or corrupt
+    /// its output, so it should only be used for trusted input.
+    Raw,
+    /// Control characters are replaced with the corresponding symbols from the
+    /// Unicode "Control Pictures" block, like `␀` or `␛`. Other control characters
+    /// are escaped like `\u{85}`.
+    #[default]
+    Pictures,
+    /// Control characters are escaped like `\x1b` or `\u{85}`.
+    Escaped,
+}
+
+/// The unit that columns are counted in, both for the column numbers shown in locations
+/// and for the positions of annotations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum ColumnUnit {
+    /// Column numbers count bytes. Annotations are positioned like with [`ColumnUnit::Chars`].
+    ///
+    /// [`ColumnUnit::Chars`]: ColumnUnit::Chars
+    Bytes,
+    /// Column numbers count Unicode scalar values, as returned by [`Files::column_number`].
+    /// Annotations are positioned using the display width of every character.
+    ///
+    /// [`Files::column_number`]: Files::column_number
+    #[default]
+    Chars,
+    /// Column numbers count extended grapheme clusters. Annotations are positioned using
+    /// the display width of every grapheme cluster, so combining characters and emoji sequences
+    /// don't shift them.
+    Graphemes,
+    /// Column numbers count UTF-16 code units, like in the Language Server Protocol and
+    /// in many editors written in JavaScript. Annotations are positioned like with [`ColumnUnit::Chars`].
+    ///
+    /// [`ColumnUnit::Chars`]: ColumnUnit::Chars
+    Utf16,
+    /// Column numbers count the columns the text before them takes up when displayed, with tabs
+    /// expanded to [`RenderConfig::tab_width`] spaces, like in terminal editors.
+    /// Annotations are positioned like with [`ColumnUnit::Chars`].
+    ///
+    /// [`ColumnUnit::Chars`]: ColumnUnit::Chars
+    /// [`RenderConfig::tab_width`]: RenderConfig::tab_width
+    Width,
+}
+
+/// The format used for displaying diagnostics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum DisplayStyle {
+    /// Output a header, the annotated source code, and notes.
+    ///
+    /// ```text
+    /// error[E0001]: Mismatched types
+    ///  --> test_file.test:1:5
+    /// 1 | let main = 23;
+    ///   |     ^^^^ expected type annotation here
+    ///   = help: Really helpful tip
+    /// ```
+    #[default]
+    Rich,
+    /// Output a single line for every diagnostic, starting with the location of its
+    /// primary annotation. This is similar to the format used by `gcc`.
+    ///
+    /// ```text
+    /// test_file.test:1:5: error[E0001]: Mismatched types
+    /// ```
+    Short,
+    /// Output a header, a line of text describing every annotation instead of the annotated
+    /// source code, and notes. This is easier to follow with a screen reader than the
+    /// underlines and connecting lines of [`DisplayStyle::Rich`].
+    ///
+    /// ```text
+    /// error[E0001]: Mismatched types
+    ///   error in test_file.test at line 1, columns 5-8: expected type annotation here
+    ///   = help: Really helpful tip
+    /// ```
+    ///
+    /// The descriptions are returned by [`Strings::annotation_description`].
+    ///
+    /// [`DisplayStyle::Rich`]: DisplayStyle::Rich
+    /// [`Strings::annotation_description`]: Strings::annotation_description
+    Descriptive,
+}
+
+/// How much information about every diagnostic is rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Verbosity {
+    /// Render only the header and the primary annotations of diagnostics, for example for
+    /// CI logs. Secondary annotations, notes, suggestions and sub-diagnostics are left out,
+    /// and so are the lines counting suppressed diagnostics.
+    Quiet,
+    /// Render everything.
+    #[default]
+    Normal,
+}
+
+/// How the annotated parts of source files are displayed in [`DisplayStyle::Rich`].
+///
+/// [`DisplayStyle::Rich`]: DisplayStyle::Rich
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum SnippetStyle {
+    /// Output the annotated lines of source code, with annotations below them.
+    #[default]
+    Source,
+    /// Output an annotated hexdump of the bytes returned by [`Files::bytes`], for binary files.
+    /// The location in the file path header is the byte offset of the primary annotation.
+    ///
+    /// ```text
+    /// error: Invalid chunk type
+    ///         --> image.png+0xc
+    /// 00000000 | 89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 21  .PNG........IHD!
+    ///          |                                      ^^^^^^^^^^^              ^^^^ expected `IHDR`
+    /// ```
+    ///
+    /// [`Files::bytes`]: Files::bytes
+    Hexdump,
+}
+
+/// The order in which the files of a diagnostic, and of the replacements of a suggestion,
+/// are displayed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum FileOrder {
+    /// The file containing the primary annotation comes first, followed by files containing
+    /// other primary annotations, and then files only containing secondary annotations.
+    /// Files with the same priority are ordered by the first appearance of their annotations
+    /// in [`Diagnostic::annotations`], or their replacements in [`Suggestion::replacements`].
+    ///
+    /// [`Diagnostic::annotations`]: Diagnostic::annotations
+    /// [`Suggestion::replacements`]: Suggestion::replacements
+    #[default]
+    Relevance,
+    /// Files are ordered by their file ID.
+    FileId,
+}
+
+/// The number of columns that sub-diagnostics are indented by, relative to their parent.
+const SUB_DIAGNOSTIC_INDENT: usize = 2;
+
+/// The separator between the line number and the line of source code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Separator {
+    /// `" |"`, used for source lines and annotations.
+    Bar,
+    /// [`CharSet::file_arrow`], used for the file path header of the file containing
+    /// the primary annotation.
+    Arrow,
+    /// [`CharSet::file_continuation`], used for the file path headers of other files.
+    Continuation,
+    /// [`CharSet::skipped_lines`], used for leaving out lines.
+    Ellipsis,
+    /// `" ="`, used for notes.
+    Note,
+    /// `" -"`, used for removed lines of suggestions.
+    Removal,
+    /// `" +"`, used for added lines of suggestions.
+    Addition,
+}
+
+impl Separator {
+    /// The number of columns this separator takes up with the given character set.
+    fn width(self, charset: &CharSet) -> usize {
+        match self {
+            Separator::Bar | Separator::Note | Separator::Removal | Separator::Addition => 2,
+            Separator::Arrow => charset.file_arrow.width(),
+            Separator::Continuation => charset.file_continuation.width(),
+            Separator::Ellipsis => charset.skipped_lines.width(),
+        }
+    }
 }
 
 /// An ASCII renderer for diagnostics.
 #[derive(Debug)]
 pub struct DiagnosticRenderer<'w, W, C, FileId, F> {
     f: &'w mut W, colors: C, files: F, config: RenderConfig,
-    max_nested_blocks: usize, line_digits: u32,
+    max_nested_blocks: usize, line_digits: u32, indent: usize, window: Option<Window>,
+    counts: SeverityCounts, suppressed_errors: usize,
+    hidden_annotations: Vec<(usize, usize)>,
+    annotation_colors: Vec<Option<TextStyle>>,
+    annotated_ranges: Vec<(Range<usize>, AnnotationStyle)>,
+    strings: Arc<dyn Strings>,
+    path_formatter: Option<PathFormatter>,
     _phantom_data: PhantomData<FileId>,
 }
 
+/// A function changing how file paths are displayed in file headers.
+#[derive(Clone)]
+struct PathFormatter(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl Debug for PathFormatter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PathFormatter")
+    }
+}
+
 impl<'w, W, C, FileId, F> DiagnosticRenderer<'w, W, C, FileId, F> {
     /// Creates a new diagnostics renderer.
+    ///
+    /// `files` can also be a reference or a smart pointer like [`Rc`] to a file database,
+    /// so that it can be shared with the rest of a compiler.
+    ///
+    /// [`Rc`]: std::rc::Rc
     pub fn new(f: &'w mut W, colors: C, files: F, config: RenderConfig) -> Self {
         DiagnosticRenderer {
             f, colors, files, config,
-            max_nested_blocks: 0, line_digits: 0,
+            max_nested_blocks: 0, line_digits: 0, indent: 0, window: None,
+            counts: SeverityCounts::new(), suppressed_errors: 0,
+            hidden_annotations: Vec::new(),
+            annotation_colors: Vec::new(),
+            annotated_ranges: Vec::new(),
+            strings: Arc::new(EnglishStrings),
+            path_formatter: None,
             _phantom_data: PhantomData,
         }
     }
+
+    /// Returns the configuration of this renderer.
+    pub fn config(&self) -> &RenderConfig {
+        &self.config
+    }
+
+    /// Sets the texts written by this renderer, like the names of severities, for example
+    /// to localize them. By default, [`EnglishStrings`] is used.
+    ///
+    /// [`EnglishStrings`]: EnglishStrings
+    pub fn with_strings(mut self, strings: impl Strings + 'static) -> Self {
+        self.strings = Arc::new(strings);
+        self
+    }
+
+    /// Sets a function that changes how the names of files are displayed in the file headers
+    /// (like `--> main.rs:1:5`), for example to strip a workspace prefix, to make paths relative,
+    /// or to anonymize them for reproducible test output.
+    ///
+    /// Hyperlinks created with [`RenderConfig::hyperlink_format`] still use the original name.
+    ///
+    /// [`RenderConfig::hyperlink_format`]: RenderConfig::hyperlink_format
+    pub fn with_path_formatter(mut self, path_formatter: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.path_formatter = Some(PathFormatter(Arc::new(path_formatter)));
+        self
+    }
 }
 
 impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> DiagnosticRenderer<'w, W, C, FileId, F>
-    where FileId: Copy + Debug + Eq + Ord {
+    where FileId: Clone + Debug + Eq + Ord {
     /// Renders the given diagnostics.
     pub fn render(&mut self, diagnostics: Vec<Diagnostic<F::FileId>>) -> Result {
+        self.render_diagnostics(diagnostics.into_iter().map(Cow::Owned).collect())
+    }
+
+    /// Renders the given diagnostics without taking ownership of them.
+    ///
+    /// The output is the same as with [`DiagnosticRenderer::render`], so the same diagnostics
+    /// can be rendered multiple times, for example to different outputs.
+    ///
+    /// [`DiagnosticRenderer::render`]: DiagnosticRenderer::render
+    pub fn render_slice(&mut self, diagnostics: &[Diagnostic<F::FileId>]) -> Result {
+        self.render_diagnostics(diagnostics.iter().map(Cow::Borrowed).collect())
+    }
+
+    fn render_diagnostics(&mut self, mut diagnostics: Vec<Cow<'_, Diagnostic<F::FileId>>>) -> Result {
+        diagnostics.retain(|d| d.severity >= self.config.min_severity);
+
+        if let Some(max_errors) = self.config.max_errors {
+            diagnostics = self.suppress_errors(diagnostics, max_errors)?;
+        }
+
         if diagnostics.is_empty() {
             return Ok(());
         }
@@ -88,10 +880,187 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         self.render_impl(diagnostics)
     }
 
-    fn render_impl(&mut self, diagnostics: Vec<Diagnostic<F::FileId>>) -> Result {
+    /// Renders the given diagnostics using a different configuration than the one of this
+    /// renderer, for example to show more surrounding lines for some diagnostics.
+    ///
+    /// The configuration of this renderer is restored afterwards. The diagnostics are still
+    /// included in [`DiagnosticRenderer::counts`].
+    ///
+    /// ```
+    /// # use diagnostic_render::diagnostic::{Diagnostic, Severity};
+    /// # use diagnostic_render::file::SimpleFile;
+    /// # use diagnostic_render::render::{DiagnosticRenderer, DisplayStyle, RenderConfig};
+    /// # use diagnostic_render::render::color::DefaultColorConfig;
+    /// # use diagnostic_render::render::string::FmtWriter;
+    /// let mut buf = FmtWriter::new(String::new());
+    /// let file = SimpleFile::new("main.rs", "fn main() {}\n");
+    /// let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, file, RenderConfig::default());
+    ///
+    /// let config = renderer.config().clone().display_style(DisplayStyle::Short);
+    /// renderer.render_with_config(vec![Diagnostic::new(Severity::Warning).with_message("unused function")], config).unwrap();
+    /// ```
+    ///
+    /// [`DiagnosticRenderer::counts`]: DiagnosticRenderer::counts
+    pub fn render_with_config(&mut self, diagnostics: Vec<Diagnostic<F::FileId>>, config: RenderConfig) -> Result {
+        let config = std::mem::replace(&mut self.config, config);
+        let result = self.render(diagnostics);
+        self.config = config;
+        result
+    }
+
+    /// Renders a single diagnostic.
+    ///
+    /// This is equivalent to calling [`DiagnosticRenderer::render`] with only this diagnostic.
+    ///
+    /// [`DiagnosticRenderer::render`]: DiagnosticRenderer::render
+    pub fn render_one(&mut self, diagnostic: &Diagnostic<F::FileId>) -> Result {
+        self.render_cows(std::iter::once(Cow::Borrowed(diagnostic)))
+    }
+
+    /// Renders diagnostics as they are produced by an iterator, without collecting them first.
+    ///
+    /// The output is the same as with [`DiagnosticRenderer::render`], except that errors exceeding
+    /// [`RenderConfig::max_errors`] are counted in a line after the last rendered diagnostic,
+    /// as it is not known which diagnostic is the last one before that.
+    ///
+    /// [`DiagnosticRenderer::render`]: DiagnosticRenderer::render
+    /// [`RenderConfig::max_errors`]: RenderConfig::max_errors
+    pub fn render_iter(&mut self, diagnostics: impl IntoIterator<Item=Diagnostic<F::FileId>>) -> Result {
+        self.render_cows(diagnostics.into_iter().map(Cow::Owned))
+    }
+
+    fn render_cows<'d>(&mut self, diagnostics: impl Iterator<Item=Cow<'d, Diagnostic<F::FileId>>>) -> Result
+        where F::FileId: 'd {
+        let mut suppressed = 0;
+
+        for diagnostic in diagnostics {
+            if diagnostic.severity < self.config.min_severity {
+                continue;
+            }
+
+            if self.suppress_error(diagnostic.severity) {
+                suppressed += 1;
+                continue;
+            }
+
+            self.render_impl(vec![diagnostic])?;
+        }
+
+        if suppressed > 0 && self.config.verbosity != Verbosity::Quiet {
+            writeln!(self.f, "{:indent$}{}", "", self.strings.suppressed(suppressed as usize), indent = self.indent)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether a diagnostic with the given severity exceeds [`RenderConfig::max_errors`].
+    /// If it does, it is counted as a suppressed error.
+    ///
+    /// [`RenderConfig::max_errors`]: RenderConfig::max_errors
+    fn suppress_error(&mut self, severity: Severity) -> bool {
+        let Some(max_errors) = self.config.max_errors else {
+            return false;
+        };
+
+        if severity < Severity::Error || self.rendered_errors() < max_errors {
+            return false;
+        }
+
+        self.counts.add(severity);
+        self.suppressed_errors += 1;
+        true
+    }
+
+    /// Returns the number of errors and bugs rendered so far, not including suppressed ones.
+    fn rendered_errors(&self) -> usize {
+        self.counts.get(Severity::Error) + self.counts.get(Severity::Bug) - self.suppressed_errors
+    }
+
+    /// Removes the errors exceeding `max_errors`, and adds them to the suppressed count
+    /// of the last remaining diagnostic.
+    fn suppress_errors<'d>(&mut self, diagnostics: Vec<Cow<'d, Diagnostic<F::FileId>>>, max_errors: usize) -> Result<Vec<Cow<'d, Diagnostic<F::FileId>>>> {
+        let mut rendered_errors = self.rendered_errors();
+        let mut remaining = Vec::with_capacity(diagnostics.len());
+        let mut suppressed = 0;
+
+        for diagnostic in diagnostics.into_iter() {
+            if diagnostic.severity >= Severity::Error {
+                if rendered_errors >= max_errors {
+                    self.counts.add(diagnostic.severity);
+                    suppressed += 1;
+                    continue;
+                }
+
+                rendered_errors += 1;
+            }
+
+            remaining.push(diagnostic);
+        }
+
+        self.suppressed_errors += suppressed as usize;
+
+        if suppressed > 0 {
+            match remaining.last_mut() {
+                Some(last) => last.to_mut().suppressed_count += suppressed,
+                // All diagnostics were suppressed, so they are counted after the ones
+                // rendered by previous calls
+                None if self.config.verbosity == Verbosity::Quiet => {},
+                None => writeln!(self.f, "{:indent$}{}", "", self.strings.suppressed(suppressed as usize), indent = self.indent)?,
+            }
+        }
+
+        Ok(remaining)
+    }
+
+    /// Returns the number of diagnostics of every severity rendered so far,
+    /// not including sub-diagnostics.
+    pub fn counts(&self) -> SeverityCounts {
+        self.counts
+    }
+
+    /// Renders a summary of the diagnostics rendered so far, like
+    /// `error: aborting due to 3 previous errors; 2 warnings emitted`.
+    ///
+    /// Nothing is rendered if no errors or warnings have been rendered.
+    pub fn render_summary(&mut self) -> Result {
+        let strings = Arc::clone(&self.strings);
+        self.render_summary_with(|counts| strings.summary(counts))
+    }
+
+    /// Renders a summary of the diagnostics rendered so far, with the severity and message
+    /// returned by `summary`. Nothing is rendered if it returns `None`.
+    pub fn render_summary_with(&mut self, summary: impl FnOnce(&SeverityCounts) -> Option<(Severity, String)>) -> Result {
+        let Some((severity, message)) = summary(&self.counts) else {
+            return Ok(());
+        };
+
+        if self.separates_next() {
+            writeln!(self.f)?;
+        }
+
+        self.render_diagnostic_header(&Diagnostic::new(severity), &message)
+    }
+
+    fn render_impl(&mut self, diagnostics: Vec<Cow<'_, Diagnostic<F::FileId>>>) -> Result {
         let diagnostics_len = diagnostics.len();
 
+        for diagnostic in diagnostics.iter() {
+            self.check_ranges(diagnostic)?;
+        }
+
+        // Separate these diagnostics from the ones rendered by previous calls
+        if self.separates_next() {
+            writeln!(self.f)?;
+        }
+
         for (i, diagnostic) in diagnostics.into_iter().enumerate() {
+            self.counts.add(diagnostic.severity);
+
+            if self.config.display_style == DisplayStyle::Short {
+                self.render_diagnostic_short(&diagnostic)?;
+                continue;
+            }
+
             self.render_diagnostic(diagnostic)?;
 
             if i < diagnostics_len - 1 {
@@ -102,48 +1071,257 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         Ok(())
     }
 
-    fn render_diagnostic(&mut self, mut diagnostic: Diagnostic<FileId>) -> Result {
-        self.render_diagnostic_header(&diagnostic)?;
+    /// Checks the ranges of all annotations and replacements of a diagnostic and its
+    /// sub-diagnostics, so that invalid ranges fail in every display style before anything
+    /// of the diagnostic is written.
+    ///
+    /// Ranges must not end before they start or extend past the end of their file, which is
+    /// measured in bytes for [`SnippetStyle::Hexdump`]. The ranges of replacements must also
+    /// start and end on char boundaries.
+    ///
+    /// [`SnippetStyle::Hexdump`]: SnippetStyle::Hexdump
+    fn check_ranges(&self, diagnostic: &Diagnostic<FileId>) -> Result {
+        let check = |file: &FileId, range: &Range<usize>, source: Option<&str>, len: usize| {
+            let (index, error) = if range.start > range.end {
+                (range.start, Error::InvalidRange { start: range.start, end: range.end })
+            } else if range.end > len {
+                (range.end, Error::IndexTooLarge { given: range.end, max: len })
+            } else if let Some(index) = source.and_then(|source| [range.start, range.end].into_iter().find(|&i| !source.is_char_boundary(i))) {
+                (index, Error::InvalidCharBoundary { given: index })
+            } else {
+                return Ok(());
+            };
+
+            Err(self.files.file_error(file, FileOperation::SourceIndex { byte_index: index }, error))
+        };
+
+        for annotation in diagnostic.annotations.iter() {
+            let len = match self.config.snippet_style {
+                SnippetStyle::Source => self.files.file_source(&annotation.file_id)?.len(),
+                SnippetStyle::Hexdump => self.files.file_bytes(&annotation.file_id)?.len(),
+            };
+
+            check(&annotation.file_id, &annotation.range, None, len)?;
+        }
+
+        for replacement in diagnostic.suggestions.iter().flat_map(|s| s.replacements.iter()) {
+            let source = self.files.file_source(&replacement.file_id)?;
+            check(&replacement.file_id, &replacement.range, Some(&source), source.len())?;
+        }
+
+        diagnostic.sub_diagnostics.iter().try_for_each(|sub_diagnostic| self.check_ranges(sub_diagnostic))
+    }
+
+    fn render_diagnostic<'d>(&mut self, mut diagnostic: Cow<'d, Diagnostic<FileId>>) -> Result {
+        let min_severity = self.config.min_severity;
+
+        // Borrowed diagnostics are only cloned if something actually has to be filtered
+        if self.config.filter_children && (diagnostic.notes.iter().any(|note| note.severity < min_severity)
+            || diagnostic.sub_diagnostics.iter().any(|d| d.severity < min_severity)) {
+            let diagnostic = diagnostic.to_mut();
+            diagnostic.notes.retain(|note| note.severity >= min_severity);
+            diagnostic.sub_diagnostics.retain(|d| d.severity >= min_severity);
+        }
+
+        if self.config.verbosity == Verbosity::Quiet && (diagnostic.annotations.iter().any(|a| a.style == AnnotationStyle::Secondary)
+            || !diagnostic.notes.is_empty() || !diagnostic.suggestions.is_empty() || !diagnostic.sub_diagnostics.is_empty()
+            || diagnostic.suppressed_count > 0) {
+            let diagnostic = diagnostic.to_mut();
+            diagnostic.annotations.retain(|a| a.style == AnnotationStyle::Primary);
+            diagnostic.notes.clear();
+            diagnostic.suggestions.clear();
+            diagnostic.sub_diagnostics.clear();
+            diagnostic.suppressed_count = 0;
+        }
+
+        self.resolve_messages(&mut diagnostic);
+
+        write!(self.f, "{:indent$}", "", indent = self.indent)?;
+        self.render_diagnostic_header(&diagnostic, &diagnostic.message)?;
 
         let suppressed_count = diagnostic.suppressed_count;
 
-        if !diagnostic.annotations.is_empty() {
-            let (file, last_annotated_line_byte_offset) = diagnostic.annotations.iter()
-                .map(|a| (a.file_id, a.range.end)).max_by(|(_, a), (_, b)| a.cmp(b))
-                .expect("No annotations in diagnostic despite previous check");
-            let last_annotated_line_index = self.files.line_index(file, last_annotated_line_byte_offset)?;
+        if let Some((file, last_annotated_line_byte_offset)) = diagnostic.annotations.iter()
+            .map(|a| (&a.file_id, a.range.end)).max_by(|(_, a), (_, b)| a.cmp(b))
+            .map(|(file, end)| (file.clone(), end)) {
+            let last_annotated_line_index = self.files.file_line_index(&file, last_annotated_line_byte_offset)?;
             let last_printed_line_index = last_annotated_line_index + self.config.surrounding_lines;
-            let last_printed_line_number = self.files.line_number(file, last_printed_line_index)?;
+            let last_printed_line_number = self.files.file_line_number(&file, last_printed_line_index)?;
 
             // eprintln!("[debug] Last printed line: {}", last_printed_line_number);
-            self.line_digits = last_printed_line_number.ilog10() + 1;
+            self.line_digits = match self.config.snippet_style {
+                SnippetStyle::Source => last_printed_line_number.ilog10() + 1,
+                SnippetStyle::Hexdump => hexdump::offset_digits(last_annotated_line_byte_offset, self.config.surrounding_lines),
+            }.max(self.config.min_line_number_width);
+
+            // The file containing the primary annotation gets a `-->` header, and all other files
+            // a `:::` header, like in rustc
+            let primary_file = diagnostic.primary_annotation().map(|a| a.file_id.clone());
+            let annotations = match &mut diagnostic {
+                Cow::Owned(diagnostic) => std::mem::take(&mut diagnostic.annotations),
+                Cow::Borrowed(diagnostic) => diagnostic.annotations.clone(),
+            };
+            let mut annotations = group_by_file(annotations, |a| &a.file_id);
+
+            sort_files(&mut annotations, primary_file.as_ref(), self.config.file_order);
 
-            let annotations = diagnostic.annotations.drain(0..diagnostic.annotations.len())
-                .fold(BTreeMap::<F::FileId, Vec<Annotation<F::FileId>>>::new(), |mut acc, a| {
-                    acc.entry(a.file_id).or_default().push(a);
-                    acc
-                });
+            let mut hidden_notes = Vec::new();
 
             for (file, annotations) in annotations.into_iter() {
-                self.render_diagnostic_file(&diagnostic, file, annotations)?;
+                let separator = if primary_file.as_ref() == Some(&file) { Separator::Arrow } else { Separator::Continuation };
+                if self.config.display_style == DisplayStyle::Descriptive {
+                    self.describe_annotations(&diagnostic, &file, annotations)?;
+                    continue;
+                }
+
+                hidden_notes.extend(self.render_diagnostic_file(&diagnostic, &file, annotations, separator)?);
+            }
+
+            if !hidden_notes.is_empty() && self.config.verbosity != Verbosity::Quiet {
+                diagnostic.to_mut().notes.extend(hidden_notes);
             }
         }
 
-        self.render_diagnostic_footer(&diagnostic)?;
+        if diagnostic.duplicate_count > 0 && self.config.verbosity != Verbosity::Quiet {
+            let note = Note::note(self.strings.repeated(diagnostic.duplicate_count as usize + 1));
+            diagnostic.to_mut().notes.push(note);
+        }
 
-        if suppressed_count > 0 {
-            writeln!(self.f, "... and {} more", suppressed_count)?;
+        for replacement in diagnostic.suggestions.iter().flat_map(|s| s.replacements.iter()) {
+            let last_line_index = self.files.file_line_index(&replacement.file_id, replacement.range.end)?;
+            let last_line_number = self.files.file_line_number(&replacement.file_id, last_line_index)? + replacement.text.matches('\n').count();
+            self.line_digits = self.line_digits.max(last_line_number.ilog10() + 1);
         }
 
+        self.render_diagnostic_footer(&diagnostic)?;
+
         self.max_nested_blocks = 0;
         self.line_digits = 0;
 
+        // Sub-diagnostics are rendered like top-level diagnostics, but indented
+        self.indent += SUB_DIAGNOSTIC_INDENT;
+
+        let sub_diagnostics: Vec<Cow<'d, Diagnostic<FileId>>> = match &mut diagnostic {
+            Cow::Owned(diagnostic) => diagnostic.sub_diagnostics.drain(..).map(Cow::Owned).collect(),
+            Cow::Borrowed(diagnostic) => {
+                let diagnostic: &'d Diagnostic<FileId> = diagnostic;
+                diagnostic.sub_diagnostics.iter().map(Cow::Borrowed).collect()
+            },
+        };
+
+        for sub_diagnostic in sub_diagnostics {
+            self.render_diagnostic(sub_diagnostic)?;
+        }
+
+        self.indent -= SUB_DIAGNOSTIC_INDENT;
+
+        if suppressed_count > 0 {
+            writeln!(self.f, "{:indent$}{}", "", self.strings.suppressed(suppressed_count as usize), indent = self.indent)?;
+        }
+
         Ok(())
     }
 
-    fn render_diagnostic_header(&mut self, diagnostic: &Diagnostic<FileId>) -> Result {
+    fn render_diagnostic_short(&mut self, diagnostic: &Diagnostic<FileId>) -> Result {
+        if let Some(annotation) = diagnostic.primary_annotation() {
+            let location = self.location(&annotation.file_id, annotation.range.start)?;
+
+            self.colors.path(self.f)?;
+            self.write_path(&annotation.file_id, Some(location))?;
+            self.colors.reset(self.f)?;
+            write!(self.f, ": ")?;
+        }
+
+        let message = self.strings.message(&diagnostic.message, &diagnostic.args).into_owned();
+        self.render_diagnostic_header(diagnostic, &message)
+    }
+
+    /// Writes a line describing every annotation in a file, for [`DisplayStyle::Descriptive`].
+    ///
+    /// [`DisplayStyle::Descriptive`]: DisplayStyle::Descriptive
+    fn describe_annotations(&mut self, diagnostic: &Diagnostic<FileId>, file: &FileId, mut annotations: Vec<Annotation<FileId>>) -> Result {
+        annotations.sort_by_key(|a| (a.style, a.range.start));
+
+        let name = self.files.file_name(file)?;
+        let source = self.files.file_source(file)?;
+        let mut descriptions = Vec::with_capacity(annotations.len());
+
+        for annotation in annotations.iter() {
+            // The last annotated character starts at the last char boundary in the range
+            let mut last = annotation.range.end.saturating_sub(1).max(annotation.range.start);
+
+            while last > annotation.range.start && !source.is_char_boundary(last) {
+                last -= 1;
+            }
+
+            let start = self.location(file, annotation.range.start)?;
+            let end = self.location(file, last)?;
+            descriptions.push(self.strings.annotation_description(diagnostic.severity, annotation.style, name, start, end));
+        }
+
+        for (i, (annotation, description)) in annotations.iter().zip(descriptions).enumerate() {
+            write!(self.f, "{:indent$}", "", indent = self.indent + self.line_digits as usize + 1)?;
+            annotation_color(&self.colors, self.f, self.config.cycle_annotation_colors, annotation.color.as_ref(),
+                annotation.style, diagnostic.severity, i)?;
+            write!(self.f, "{}", description)?;
+            self.colors.reset(self.f)?;
+
+            if !annotation.label.is_empty() {
+                write!(self.f, ": ")?;
+                self.write_message_text(&annotation.label, diagnostic.severity, &mut false, |colors, f| colors.note_message(f, diagnostic.severity))?;
+            }
+
+            writeln!(self.f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the message, labels, notes, note prefixes and suggestion messages of a diagnostic through
+    /// [`Strings::message`]. Borrowed diagnostics are only cloned if one of them changes.
+    ///
+    /// [`Strings::message`]: Strings::message
+    fn resolve_messages(&self, diagnostic: &mut Cow<'_, Diagnostic<FileId>>) {
+        fn owned(message: Cow<'_, str>) -> Option<String> {
+            match message {
+                Cow::Owned(message) => Some(message),
+                Cow::Borrowed(_) => None,
+            }
+        }
+
+        if let Some(message) = owned(self.strings.message(&diagnostic.message, &diagnostic.args)) {
+            diagnostic.to_mut().message = message;
+        }
+
+        for i in 0..diagnostic.annotations.len() {
+            if let Some(label) = owned(self.strings.message(&diagnostic.annotations[i].label, &diagnostic.args)) {
+                diagnostic.to_mut().annotations[i].label = label;
+            }
+        }
+
+        for i in 0..diagnostic.notes.len() {
+            if let Some(message) = owned(self.strings.message(&diagnostic.notes[i].message, &diagnostic.args)) {
+                diagnostic.to_mut().notes[i].message = message;
+            }
+
+            if let Some(prefix) = diagnostic.notes[i].prefix.as_ref().and_then(|prefix| owned(self.strings.message(prefix, &diagnostic.args))) {
+                diagnostic.to_mut().notes[i].prefix = Some(prefix);
+            }
+        }
+
+        for i in 0..diagnostic.suggestions.len() {
+            if let Some(message) = owned(self.strings.message(&diagnostic.suggestions[i].message, &diagnostic.args)) {
+                diagnostic.to_mut().suggestions[i].message = message;
+            }
+        }
+    }
+
+    fn render_diagnostic_header(&mut self, diagnostic: &Diagnostic<FileId>, message: &str) -> Result {
         self.colors.severity(self.f, diagnostic.severity)?;
-        write!(self.f, "{}", diagnostic.severity)?;
+        match diagnostic.severity_name.as_deref() {
+            Some(name) => write!(self.f, "{}", name)?,
+            None => write!(self.f, "{}", self.strings.severity(diagnostic.severity))?,
+        }
         // self.colors.reset(f)?;
 
         if let Some(name) = diagnostic.name.as_ref() {
@@ -154,72 +1332,310 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
             write!(self.f, "]")?;
         }
 
-        if !diagnostic.message.is_empty() {
+        if !message.is_empty() {
             self.colors.message(self.f)?;
-            writeln!(self.f, ": {}", &diagnostic.message)?;
+            write!(self.f, ": ")?;
+            self.write_message_text(message, diagnostic.severity, &mut false, |colors, f| colors.message(f))?;
+            writeln!(self.f)?;
         }
 
         self.colors.reset(self.f)?;
 
-        if diagnostic.message.is_empty() {
-            writeln!(self.f)?;
+        if message.is_empty() {
+            writeln!(self.f)?;
+        }
+
+        Ok(())
+    }
+
+    fn render_diagnostic_footer(&mut self, diagnostic: &Diagnostic<FileId>) -> Result {
+        for note in diagnostic.notes.iter() {
+            let severity_str = match note.prefix.as_ref() {
+                Some(prefix) => prefix.clone(),
+                None => self.strings.severity(note.severity).into_owned(),
+            };
+            let severity_len = severity_str.width();
+
+            self.write_line_number(None, Separator::Note)?;
+            write!(self.f, " ")?;
+
+            match note.prefix.as_ref() {
+                Some(prefix) => self.colors.note_prefix(self.f, note.severity, prefix)?,
+                None => self.colors.note_severity(self.f, note.severity)?,
+            }
+
+            write!(self.f, "{}", severity_str)?;
+            self.colors.reset(self.f)?;
+            write!(self.f, ": ")?;
+            self.colors.note_message(self.f, note.severity)?;
+
+            let prefix_width = self.indent + self.line_digits as usize + 5 + severity_len;
+
+            if let Some(expected_found) = note.expected_found.as_ref() {
+                self.write_expected_found(expected_found, note.severity, prefix_width)?;
+                self.colors.reset(self.f)?;
+                writeln!(self.f)?;
+                continue;
+            }
+            let mut join_str = String::from("\n");
+            join_str.push_str(&" ".repeat(prefix_width));
+
+            let lines = match self.config.wrap_width {
+                Some(wrap_width) => note.message.lines()
+                    .flat_map(|line| wrap_line(line, wrap_width.saturating_sub(prefix_width)))
+                    .collect::<Vec<_>>(),
+                None => note.message.lines().map(Cow::Borrowed).collect(),
+            };
+
+            // Emphasis continues across wrapped lines, but not into the indentation
+            let mut emphasized = false;
+
+            for (i, line) in lines.iter().enumerate() {
+                if i > 0 {
+                    write!(self.f, "{}", join_str)?;
+                }
+
+                self.write_message_text(line, note.severity, &mut emphasized, |colors, f| colors.note_message(f, note.severity))?;
+            }
+
+            self.colors.reset(self.f)?;
+            writeln!(self.f)?;
+        }
+
+        if let Some(url) = diagnostic.url.as_ref() {
+            self.write_line_number(None, Separator::Note)?;
+            write!(self.f, " ")?;
+            self.colors.note_severity(self.f, Severity::Note)?;
+            write!(self.f, "{}", self.strings.severity(Severity::Note))?;
+            self.colors.reset(self.f)?;
+            write!(self.f, ": ")?;
+            self.colors.note_message(self.f, Severity::Note)?;
+            write!(self.f, "{}", self.strings.more_information(url))?;
+            self.colors.reset(self.f)?;
+            writeln!(self.f)?;
+        }
+
+        for suggestion in diagnostic.suggestions.iter() {
+            self.render_suggestion(suggestion)?;
+        }
+
+        Ok(())
+    }
+
+    fn render_suggestion(&mut self, suggestion: &Suggestion<FileId>) -> Result {
+        self.write_line_number(None, Separator::Note)?;
+        write!(self.f, " ")?;
+        self.colors.note_severity(self.f, Severity::Help)?;
+        write!(self.f, "{}", self.strings.severity(Severity::Help))?;
+        self.colors.reset(self.f)?;
+        write!(self.f, ": ")?;
+        self.colors.note_message(self.f, Severity::Help)?;
+        self.write_message_text(&suggestion.message, Severity::Help, &mut false, |colors, f| colors.note_message(f, Severity::Help))?;
+        self.colors.reset(self.f)?;
+        writeln!(self.f)?;
+
+        let mut replacements = group_by_file(suggestion.replacements.iter(), |r| &r.file_id);
+
+        if self.config.file_order == FileOrder::FileId {
+            replacements.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+
+        for (file, mut replacements) in replacements.into_iter() {
+            replacements.sort_by_key(|r| (r.range.start, r.range.end));
+
+            // Replacements on the same or adjacent lines are applied together in a single snippet
+            let mut group: Vec<&Replacement<FileId>> = Vec::new();
+            let mut group_last_line_index = 0;
+
+            for replacement in replacements.into_iter() {
+                let start_line_index = self.files.file_line_index(&file, replacement.range.start)?;
+
+                if let Some(previous) = group.last() {
+                    if replacement.range.start < previous.range.end || start_line_index > group_last_line_index + 1 {
+                        self.render_replacements(&file, &group)?;
+                        group.clear();
+                    }
+                }
+
+                group_last_line_index = self.files.file_line_index(&file, replacement.range.end)?;
+                group.push(replacement);
+            }
+
+            if !group.is_empty() {
+                self.render_replacements(&file, &group)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders the lines affected by some non-overlapping replacements in a file, sorted by
+    /// their position, with all of them applied.
+    ///
+    /// If all replacements are insertions, the modified lines are shown with the inserted text
+    /// marked underneath. Otherwise, the replacements are shown as a diff of the old and new lines.
+    fn render_replacements(&mut self, file: &FileId, replacements: &[&Replacement<FileId>]) -> Result {
+        let source = self.files.file_source(file)?;
+
+        let (Some(first), Some(last)) = (replacements.first(), replacements.last()) else {
+            return Ok(());
+        };
+
+        let first_line_index = self.files.file_line_index(file, first.range.start)?;
+        let last_line_index = self.files.file_line_index(file, last.range.end)?;
+        let start = self.files.file_line_range(file, first_line_index)?.start;
+        let end = self.files.file_line_range(file, last_line_index)?.end;
+        let first_line_number = self.files.file_line_number(file, first_line_index)?;
+
+        // The unchanged parts of the source between the replacements, and the old and new changed parts
+        let mut old_segments = Vec::with_capacity(2 * replacements.len() + 1);
+        let mut new_segments = Vec::with_capacity(2 * replacements.len() + 1);
+        let mut position = start;
+
+        for replacement in replacements.iter() {
+            old_segments.push((&source[position..replacement.range.start], false));
+            old_segments.push((&source[replacement.range.clone()], true));
+            new_segments.push((&source[position..replacement.range.start], false));
+            new_segments.push((replacement.text.as_str(), true));
+            position = replacement.range.end;
+        }
+
+        old_segments.push((&source[position..end], false));
+        new_segments.push((&source[position..end], false));
+
+        let new_lines = split_changed_lines(&new_segments);
+
+        if replacements.iter().all(|r| r.range.is_empty()) {
+            for (i, line) in new_lines.iter().enumerate() {
+                self.write_line_number(Some(first_line_number + i), Separator::Bar)?;
+
+                if !line.is_empty() {
+                    write!(self.f, " ")?;
+                    self.colors.source(self.f)?;
+                    write!(self.f, "{}", text::display(&line.iter().map(|(c, _)| c).collect::<String>(), &self.config))?;
+                    self.colors.reset(self.f)?;
+                }
+
+                writeln!(self.f)?;
+
+                let Some(last_inserted) = line.iter().rposition(|(_, inserted)| *inserted) else {
+                    continue;
+                };
+
+                self.write_line_number(None, Separator::Bar)?;
+                write!(self.f, " ")?;
+
+                for run in line[..=last_inserted].chunk_by(|(_, a), (_, b)| a == b) {
+                    let run_width = text::width(&run.iter().map(|(c, _)| c).collect::<String>(), &self.config);
+
+                    if run[0].1 {
+                        self.colors.suggestion(self.f)?;
+                        self.write_repeated('+', run_width)?;
+                        self.colors.reset(self.f)?;
+                    } else {
+                        self.write_repeated(' ', run_width)?;
+                    }
+                }
+
+                writeln!(self.f)?;
+            }
+        } else {
+            let old_lines = split_changed_lines(&old_segments);
+
+            for (i, line) in old_lines.iter().enumerate() {
+                self.write_line_number(Some(first_line_number + i), Separator::Removal)?;
+                self.write_changed_line(line, false)?;
+            }
+
+            for (i, line) in new_lines.iter().enumerate() {
+                self.write_line_number(Some(first_line_number + i), Separator::Addition)?;
+                self.write_changed_line(line, true)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a line of a diff, highlighting the changed characters as either removed or added.
+    fn write_changed_line(&mut self, line: &[(char, bool)], added: bool) -> Result {
+        if !line.is_empty() {
+            write!(self.f, " ")?;
+        }
+
+        for run in line.chunk_by(|(_, a), (_, b)| a == b) {
+            if !run[0].1 {
+                self.colors.source(self.f)?;
+            } else if added {
+                self.colors.suggestion(self.f)?;
+            } else {
+                self.colors.suggestion_removal(self.f)?;
+            }
+
+            write!(self.f, "{}", text::display(&run.iter().map(|(c, _)| c).collect::<String>(), &self.config))?;
+            self.colors.reset(self.f)?;
         }
 
+        writeln!(self.f)?;
         Ok(())
     }
 
-    fn render_diagnostic_footer(&mut self, diagnostic: &Diagnostic<FileId>) -> Result {
-        for note in diagnostic.notes.iter() {
-            let severity_str = note.severity.to_string();
-            let severity_len = severity_str.len();
-
-            self.write_line_number(None, " =")?;
-            write!(self.f, " ")?;
-            self.colors.note_severity(self.f, note.severity)?;
-            write!(self.f, "{}", severity_str)?;
-            self.colors.reset(self.f)?;
-            write!(self.f, ": ")?;
-            self.colors.note_message(self.f, note.severity)?;
+    /// Renders the annotated source code of a file, and returns the notes for the labels of
+    /// annotations hidden because of [`RenderConfig::max_annotations_per_line`].
+    ///
+    /// [`RenderConfig::max_annotations_per_line`]: RenderConfig::max_annotations_per_line
+    fn render_diagnostic_file(&mut self, diagnostic: &Diagnostic<F::FileId>, file: &FileId, mut annotations: Vec<Annotation<FileId>>,
+                              separator: Separator) -> Result<Vec<Note>> {
+        if self.config.snippet_style == SnippetStyle::Hexdump {
+            self.render_hexdump(diagnostic, file, annotations, separator)?;
+            return Ok(Vec::new());
+        }
 
-            let mut join_str = String::from("\n");
-            join_str.push_str(&" ".repeat(self.line_digits as usize + 5 + severity_len));
+        if self.config.file_headers {
+            // The location of the primary annotation, or of the first annotation in files
+            // that only contain secondary annotations
+            let range = match separator {
+                Separator::Continuation => annotations.iter().min_by_key(|a| (a.style, a.range.start)),
+                _ => annotations.iter().find(|a| a.style == AnnotationStyle::Primary),
+            }.map(|a| a.range.clone());
 
-            write!(self.f, "{}", note.message.lines().collect::<Vec<_>>().join(&join_str))?;
-        }
+            self.write_line_number(None, separator)?;
+            write!(self.f, " ")?;
+            self.colors.path(self.f)?;
 
-        Ok(())
-    }
+            let location = match range.as_ref() {
+                Some(range) => Some(self.location(file, range.start)?),
+                None => None,
+            };
 
-    fn render_diagnostic_file(&mut self, diagnostic: &Diagnostic<F::FileId>, file: FileId, mut annotations: Vec<Annotation<FileId>>) -> Result {
-        let location = annotations.iter()
-            .filter(|a| a.style == AnnotationStyle::Primary)
-            .map(|a| (a.file_id, a.range.start))
-            .next();
+            self.write_path(file, location)?;
+            self.colors.reset(self.f)?;
 
-        self.write_line_number(None, "-->")?;
-        write!(self.f, " ")?;
-        self.colors.path(self.f)?;
-        write!(self.f, "{}", self.files.name(file)?)?;
+            if let Some(range) = range.filter(|_| self.config.byte_offsets) {
+                write!(self.f, " {}", self.strings.byte_range(range))?;
+            }
 
-        if let Some((file, a)) = location {
-            let location = self.files.location(file, a)?;
-            writeln!(self.f, ":{}:{}", location.line_number, location.column_number)?;
-        } else {
             writeln!(self.f)?;
         }
 
-        self.colors.reset(self.f)?;
-
         // Sort by start byte index
-        annotations.sort_by(|a, b| a.range.start.cmp(&b.range.start));
+        annotations.sort_by_key(|a| a.range.start);
+
+        if self.config.merge_annotations {
+            annotations = self.merge_annotations(file, annotations)?;
+        }
+
+        let hidden_notes = match self.config.max_annotations_per_line {
+            Some(max_annotations) => self.hide_annotations(file, &mut annotations, max_annotations.get())?,
+            None => Vec::new(),
+        };
 
         {
             let mut max_nested_blocks = 0;
             let mut current_nested_blocks: Vec<usize> = Vec::new();
 
             for annotation in annotations.iter() {
-                let start_line_index = self.files.line_index(file, annotation.range.start)?;
-                let end_line_index = self.files.line_index(file, annotation.range.end)?;
+                let start_line_index = self.files.file_line_index(file, annotation.range.start)?;
+                let end_line_index = self.files.file_line_index(file, annotation.range.end)?;
 
                 if start_line_index == end_line_index {
                     continue;
@@ -233,66 +1649,183 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
             self.max_nested_blocks = max_nested_blocks;
         }
 
+        self.window = self.calculate_window(file, &annotations)?;
+        self.annotation_colors = annotations.iter().map(|a| a.color.clone()).collect();
+
+        if self.config.highlight_source {
+            self.annotated_ranges = annotations.iter().map(|a| (a.range.clone(), a.style)).collect();
+        }
+
         self.render_lines_with_annotations(diagnostic, file, annotations)?;
-        Ok(())
+        self.window = None;
+        self.hidden_annotations.clear();
+        self.annotation_colors.clear();
+        self.annotated_ranges.clear();
+        Ok(hidden_notes)
     }
 
-    fn render_lines_with_annotations(&mut self, diagnostic: &Diagnostic<FileId>, file: FileId, annotations: Vec<Annotation<FileId>>) -> Result {
-        let mut already_printed_end_index = 0;
-        let mut annotations_on_line_indices = Vec::new();
-        let mut continuing_annotations_indices = Vec::new();
-        let mut current_line_index = 0;
-        let mut last_line_index = None;
-        let mut first_iteration = true;
+    /// Removes the single-line annotations exceeding the maximum number of annotations on their
+    /// line, and returns notes containing their labels. The number of removed annotations on
+    /// every line is stored in `self.hidden_annotations`.
+    fn hide_annotations(&mut self, file: &FileId, annotations: &mut Vec<Annotation<FileId>>, max_annotations: usize) -> Result<Vec<Note>> {
+        let mut lines: Vec<(usize, Vec<usize>)> = Vec::new();
 
-        let last_line_index_in_file = self.get_last_line_index(file)?;
+        for (i, annotation) in annotations.iter().enumerate() {
+            let start_line_index = self.files.file_line_index(file, annotation.range.start)?;
+            let end_line_index = self.files.file_line_index(file, annotation.range.end)?;
 
-        loop {
-            current_line_index = if first_iteration {
-                current_line_index
-            } else {
-                current_line_index + 1
-            };
+            if start_line_index != end_line_index {
+                continue;
+            }
 
-            if current_line_index > last_line_index_in_file {
-                break;
+            match lines.iter_mut().find(|(line_index, _)| *line_index == start_line_index) {
+                Some((_, indices)) => indices.push(i),
+                None => lines.push((start_line_index, vec![i])),
             }
+        }
 
-            for (i, annotation) in annotations.iter().enumerate() {
-                let start_line_index = self.files.line_index(file, annotation.range.start)?;
-                let end_line_index = self.files.line_index(file, annotation.range.end)?;
+        let mut hidden = Vec::new();
 
-                if start_line_index > current_line_index && end_line_index > current_line_index {
-                    break;
-                } else if end_line_index < current_line_index && start_line_index < current_line_index {
-                    continue;
-                }
+        for (line_index, mut indices) in lines.into_iter() {
+            if indices.len() <= max_annotations {
+                continue;
+            }
 
-                if continuing_annotations_indices.contains(&i) || annotations_on_line_indices.contains(&i) {
-                    eprintln!("Bug in error message formatter: adding an index twice ({})!", i);
-                }
+            indices.sort_by_key(|&i| (annotations[i].style, annotations[i].range.start));
+            self.hidden_annotations.push((line_index, indices.len() - max_annotations));
+            hidden.extend_from_slice(&indices[max_annotations..]);
+        }
 
-                if start_line_index < current_line_index {
-                    continuing_annotations_indices.push(i);
-                }
+        hidden.sort_unstable();
+        let mut notes = Vec::new();
 
-                if start_line_index == current_line_index || end_line_index == current_line_index {
-                    annotations_on_line_indices.push(i);
-                }
+        for &i in hidden.iter() {
+            let annotation = &annotations[i];
+
+            if !annotation.label.is_empty() {
+                let location = self.location(file, annotation.range.start)?;
+                notes.push(Note::note(self.strings.hidden_annotation_note(location, &annotation.label)));
+            }
+        }
+
+        for (removed, i) in hidden.into_iter().enumerate() {
+            annotations.remove(i - removed);
+        }
+
+        Ok(notes)
+    }
+
+    /// Merges single-line annotations on the same line with the same style and label if they
+    /// overlap or touch. The annotations have to be sorted by their start byte index.
+    fn merge_annotations(&self, file: &FileId, annotations: Vec<Annotation<FileId>>) -> Result<Vec<Annotation<FileId>>> {
+        // Every annotation with the line it is on, or `None` for multi-line annotations
+        let mut merged: Vec<(Annotation<FileId>, Option<usize>)> = Vec::with_capacity(annotations.len());
+
+        for annotation in annotations.into_iter() {
+            let start_line_index = self.files.file_line_index(file, annotation.range.start)?;
+            let end_line_index = self.files.file_line_index(file, annotation.range.end)?;
+            let line_index = (start_line_index == end_line_index).then_some(start_line_index);
+
+            let target = merged.iter_mut().rev().find(|(a, a_line_index)| line_index.is_some() && *a_line_index == line_index
+                && a.style == annotation.style && a.label == annotation.label && a.color == annotation.color
+                && a.range.end >= annotation.range.start);
+
+            match target {
+                Some((target, _)) => target.range.end = target.range.end.max(annotation.range.end),
+                None => merged.push((annotation, line_index)),
+            }
+        }
+
+        Ok(merged.into_iter().map(|(annotation, _)| annotation).collect())
+    }
+
+    /// Calculates the visible part of the source lines of a file, so that the annotated region
+    /// fits into [`RenderConfig::max_line_width`] as well as possible, including the ellipses
+    /// at the cut edges. If not all annotations fit, the window is moved to the primary ones.
+    ///
+    /// At least one column of source code is shown, even if the ellipses alone are wider
+    /// than the maximum width.
+    ///
+    /// [`RenderConfig::max_line_width`]: RenderConfig::max_line_width
+    fn calculate_window(&self, file: &FileId, annotations: &[Annotation<FileId>]) -> Result<Option<Window>> {
+        let Some(max_line_width) = self.config.max_line_width else {
+            return Ok(None);
+        };
+
+        let source = self.files.file_source(file)?;
+        let mut max_width = 0;
+        // The start and (exclusive) end columns of all annotations, and of the primary ones
+        let mut region: Option<(usize, usize)> = None;
+        let mut primary_region: Option<(usize, usize)> = None;
+
+        for annotation in annotations.iter() {
+            let mut columns = [0; 2];
+
+            for (column, byte_index) in columns.iter_mut().zip([annotation.range.start, annotation.range.end]) {
+                let line_index = self.files.file_line_index(file, byte_index)?;
+                let line_range = self.files.file_line_range(file, line_index)?;
+                let line = source.get(line_range.start..byte_index.max(line_range.start))
+                    .ok_or_else(|| self.files.file_error(file, FileOperation::SourceIndex { byte_index },
+                        Error::InvalidCharBoundary { given: byte_index }))?;
+                *column = text::width(line, &self.config);
+
+                max_width = max_width.max(text::width(source[line_range].trim_end_matches(['\n', '\r']), &self.config));
             }
 
-            if /* current_line_index != 0 && */ !annotations_on_line_indices.is_empty() {
-                self.render_part_lines(diagnostic, file, current_line_index, last_line_index,
-                    annotations_on_line_indices.iter().map(|i| &annotations[*i]).collect::<Vec<_>>(),
-                    continuing_annotations_indices.iter().map(|i| &annotations[*i]).collect::<Vec<_>>(),
-                    &mut already_printed_end_index)?;
-                annotations_on_line_indices.clear();
+            // Multi-line annotations can end on a column before their start
+            let start = columns[0].min(columns[1]);
+            let end = columns[0].max(columns[1]).max(start + 1);
+            let extend = |region: Option<(usize, usize)>| Some(region.map_or((start, end), |(s, e)| (s.min(start), e.max(end))));
+
+            region = extend(region);
 
-                last_line_index = Some(current_line_index);
+            if annotation.style == AnnotationStyle::Primary {
+                primary_region = extend(primary_region);
             }
+        }
+
+        let Some(region) = region else {
+            return Ok(None);
+        };
+
+        if max_width <= max_line_width {
+            return Ok(None);
+        }
+
+        let ellipsis_width = self.config.charset.ellipsis.width();
+        // The visible width of source code if only one or both edges are cut off
+        let one_sided = max_line_width.saturating_sub(ellipsis_width).max(1);
+        let two_sided = max_line_width.saturating_sub(2 * ellipsis_width).max(1);
+        let window = |offset, width| Ok(Some(Window { offset, width, ellipsis_width }));
+
+        let fits = |(start, end): (usize, usize)| end - start <= two_sided;
+        let (start, end) = if fits(region) { region } else { primary_region.unwrap_or(region) };
+
+        if end <= one_sided {
+            window(0, one_sided)
+        } else if max_width - one_sided <= start {
+            window(max_width - one_sided, one_sided)
+        } else if fits((start, end)) {
+            // Center the annotated region in the visible window
+            window(start - (two_sided - (end - start)) / 2, two_sided)
+        } else if start < one_sided {
+            window(0, one_sided)
+        } else {
+            window(start, two_sided)
+        }
+    }
+
+    fn render_lines_with_annotations(&mut self, diagnostic: &Diagnostic<FileId>, file: &FileId, annotations: Vec<Annotation<FileId>>) -> Result {
+        let mut already_printed_end_index = 0;
+        let mut last_line_index = None;
 
-            continuing_annotations_indices.clear();
-            first_iteration = false;
+        for line in annotated_lines(&self.files, file, &annotations)? {
+            self.render_part_lines(diagnostic, file, line.line_index, last_line_index,
+                line.on_line.iter().map(|&i| (i, &annotations[i])).collect::<Vec<_>>(),
+                line.continuing.iter().map(|&i| (i, &annotations[i])).collect::<Vec<_>>(),
+                &mut already_printed_end_index)?;
+
+            last_line_index = Some(line.line_index);
         }
 
         if let Some(last_line) = last_line_index {
@@ -304,8 +1837,8 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         Ok(())
     }
 
-    fn render_post_surrounding_lines(&mut self, diagnostic: &Diagnostic<FileId>, file: FileId, main_line: usize, last_line: usize,
-                                     continuing_annotations: &[&Annotation<FileId>],
+    fn render_post_surrounding_lines(&mut self, diagnostic: &Diagnostic<FileId>, file: &FileId, main_line: usize, last_line: usize,
+                                     continuing_annotations: &[(usize, &Annotation<FileId>)],
                                      already_printed_end_line_index: &mut usize) -> Result {
         // writeln!(f, "[debug] potentially printing post surrounding lines, last line: {}, already printed to: {}", last_line, *already_printed_to)?;
 
@@ -327,10 +1860,10 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn render_part_lines(&mut self, diagnostic: &Diagnostic<FileId>, file: FileId,
+    fn render_part_lines(&mut self, diagnostic: &Diagnostic<FileId>, file: &FileId,
                          main_line_index: usize, last_line_index: Option<usize>,
-                         annotations_on_line: Vec<&Annotation<FileId>>,
-                         continuing_annotations: Vec<&Annotation<FileId>>,
+                         annotations_on_line: Vec<(usize, &Annotation<FileId>)>,
+                         continuing_annotations: Vec<(usize, &Annotation<FileId>)>,
                          already_printed_end_line_index: &mut usize) -> Result {
         // eprintln!("[debug] Rendering part lines (main {}, last {:?}, already printed to {})", main_line_index, last_line_index.as_ref(), *already_printed_end_line_index);
 
@@ -344,7 +1877,17 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         // writeln!(f, "[debug] current line ({}); first = {}, last = {}", main_line, first_print_line, last_print_line)?;
 
         if *already_printed_end_line_index != 0 && first_print_line_index > *already_printed_end_line_index {
-            self.write_source_line(diagnostic, None, "...", &continuing_annotations)?;
+            self.write_source_line(diagnostic, None, Separator::Ellipsis, &continuing_annotations)?;
+
+            // Show how much of a multi-line annotation was folded
+            if !continuing_annotations.is_empty() {
+                let skipped_lines = first_print_line_index - *already_printed_end_line_index;
+                write!(self.f, "{:>nested_blocks$}", "", nested_blocks = (2 * self.max_nested_blocks - (2 * continuing_annotations.len()).saturating_sub(1)).max(1))?;
+                self.colors.line_number_separator(self.f)?;
+                write!(self.f, "{}", self.strings.lines_skipped(skipped_lines))?;
+                self.colors.reset(self.f)?;
+            }
+
             writeln!(self.f)?;
         }
 
@@ -356,11 +1899,11 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         Ok(())
     }
 
-    fn render_single_source_line(&mut self, diagnostic: &Diagnostic<FileId>, file: FileId,
+    fn render_single_source_line(&mut self, diagnostic: &Diagnostic<FileId>, file: &FileId,
                                  line_index: usize, main_line_index: usize,
-                                 annotations: &[&Annotation<FileId>],
-                                 continuing_annotations: &[&Annotation<FileId>]) -> Result {
-        self.write_source_line(diagnostic, Some((file, line_index)), " |", continuing_annotations)?;
+                                 annotations: &[(usize, &Annotation<FileId>)],
+                                 continuing_annotations: &[(usize, &Annotation<FileId>)]) -> Result {
+        self.write_source_line(diagnostic, Some((file, line_index)), Separator::Bar, continuing_annotations)?;
 
         if line_index != main_line_index {
             return Ok(());
@@ -369,21 +1912,39 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         self.render_single_source_annotations(diagnostic, file, line_index, annotations, continuing_annotations)
     }
 
-    fn render_single_source_annotations(&mut self, diagnostic: &Diagnostic<FileId>, file: FileId,
+    fn render_single_source_annotations(&mut self, diagnostic: &Diagnostic<FileId>, file: &FileId,
                                         line_index: usize,
-                                        annotations: &[&Annotation<FileId>], continuing_annotations: &[&Annotation<FileId>]) -> Result {
-        let data = calculate::calculate(diagnostic, &self.files, file, line_index, annotations, continuing_annotations)?;
+                                        annotations: &[(usize, &Annotation<FileId>)], continuing_annotations: &[(usize, &Annotation<FileId>)]) -> Result {
+        let data = calculate::calculate_windowed(diagnostic, &self.files, file, line_index, annotations, continuing_annotations,
+            &self.config, self.window.as_ref())
+            .map_err(|err| self.files.file_error(file, FileOperation::Layout { line_index }, err))?;
         let mut data_stack = Vec::new();
         let mut stack_removal_indices = Vec::new();
 
         // eprintln!("[debug] Data:\n{:#?}", &data);
 
         for line_data in data.into_iter() {
-            self.write_line_number(None, " |")?;
+            self.write_line_number(None, Separator::Bar)?;
 
             let mut horizontal_index = 0;
             let mut last = false;
 
+            // The vertical bars of multi-line annotations that connect with their end on this line.
+            // These are drawn with a corner character instead of the usual vertical bar.
+            let ending_bar_indices = line_data.iter().filter_map(|data| match data {
+                AnnotationData::ConnectingMultiline(data) => Some(data.vertical_bar_index),
+                _ => None,
+            }).filter(|&index| line_data.iter().any(|data| matches!(data,
+                AnnotationData::ContinuingMultiline(data) if data.vertical_bar_index == index)))
+                .collect::<Vec<_>>();
+
+            // The annotations and columns that a multi-line annotation connects with on this line.
+            // These are drawn with a junction or corner character instead of the usual one.
+            let connected_columns = line_data.iter().filter_map(|data| match data {
+                AnnotationData::ConnectingMultiline(data) => Some((data.annotation_index, data.end_location.column_index)),
+                _ => None,
+            }).collect::<Vec<_>>();
+
             for data in line_data.into_iter() {
                 if last {
                     eprintln!("Bug in error message formatter: annotation part after label");
@@ -401,7 +1962,7 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
 
                 if horizontal_index < to_horizontal_index {
                     for data in data_stack.iter().rev() {
-                        self.write_annotation_data(data, Some(to_horizontal_index), &mut horizontal_index, &mut last)?;
+                        self.write_annotation_data(data, Some(to_horizontal_index), &ending_bar_indices, &connected_columns, &mut horizontal_index, &mut last)?;
                     }
 
                     for (i, data) in data_stack.iter().enumerate() {
@@ -429,17 +1990,27 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
             }
 
             for data in data_stack.iter().rev() {
-                self.write_annotation_data(data, None, &mut horizontal_index, &mut last)?;
+                self.write_annotation_data(data, None, &ending_bar_indices, &connected_columns, &mut horizontal_index, &mut last)?;
             }
 
             data_stack.clear();
             writeln!(self.f)?;
         }
 
+        if let Some(&(_, count)) = self.hidden_annotations.iter().find(|(hidden_line_index, _)| *hidden_line_index == line_index) {
+            self.write_source_line(diagnostic, None, Separator::Bar, continuing_annotations)?;
+            write!(self.f, "{:>nested_blocks$}", "", nested_blocks = (2 * self.max_nested_blocks - (2 * continuing_annotations.len()).saturating_sub(1)).max(1))?;
+            writeln!(self.f, "{}", self.strings.hidden_annotations(count))?;
+        }
+
         Ok(())
     }
 
-    fn write_annotation_data(&mut self, data: &AnnotationData, to_horizontal_index: Option<usize>, horizontal_index: &mut usize, last: &mut bool) -> Result {
+    fn write_annotation_data(&mut self, data: &AnnotationData, to_horizontal_index: Option<usize>, ending_bar_indices: &[usize],
+                             connected_columns: &[(usize, usize)], horizontal_index: &mut usize, last: &mut bool) -> Result {
+        let is_connected = |annotation_index: usize, location: &LineColumn|
+            connected_columns.contains(&(annotation_index, location.column_index));
+
         match data {
             AnnotationData::ContinuingMultiline(data) => {
                 let start = data.vertical_bar_index * 2 + 1;
@@ -449,12 +2020,18 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
                 }
 
                 if start > *horizontal_index {
-                    write!(self.f, "{}", " ".repeat(start - *horizontal_index))?;
+                    self.write_repeated(' ', start - *horizontal_index)?;
                     *horizontal_index = start;
                 }
 
-                self.colors.annotation(self.f, data.style, data.severity)?;
-                write!(self.f, "|")?;
+                let c = if ending_bar_indices.contains(&data.vertical_bar_index) {
+                    self.config.charset.multiline_end
+                } else {
+                    self.config.charset.vertical_bar
+                };
+
+                self.annotation_color(data.style, data.severity, data.annotation_index)?;
+                write!(self.f, "{}", c)?;
                 self.colors.reset(self.f)?;
 
                 *horizontal_index += 1;
@@ -467,8 +2044,21 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
                     return Ok(());
                 }
 
+                // The corner at the top of the vertical bar, for annotations that start on this line
+                if !ending_bar_indices.contains(&data.vertical_bar_index) && *horizontal_index < start {
+                    if start - 1 > *horizontal_index {
+                        self.write_repeated(' ', start - 1 - *horizontal_index)?;
+                    }
+
+                    self.annotation_color(data.style, data.severity, data.annotation_index)?;
+                    write!(self.f, "{}", self.config.charset.multiline_start)?;
+                    self.colors.reset(self.f)?;
+
+                    *horizontal_index = start;
+                }
+
                 if start > *horizontal_index {
-                    write!(self.f, "{}", " ".repeat(start - *horizontal_index))?;
+                    self.write_repeated(' ', start - *horizontal_index)?;
                     *horizontal_index = start;
                 }
 
@@ -478,11 +2068,11 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
                     end
                 };
 
-                self.colors.annotation(self.f, data.style, data.severity)?;
-                write!(self.f, "{}", "_".repeat(to_index - *horizontal_index))?;
+                self.annotation_color(data.style, data.severity, data.annotation_index)?;
+                self.write_repeated(self.config.charset.horizontal_bar, to_index.saturating_sub(*horizontal_index))?;
                 self.colors.reset(self.f)?;
 
-                *horizontal_index = to_index;
+                *horizontal_index = to_index.max(*horizontal_index);
             },
             AnnotationData::Start(data) => {
                 let start = data.location.column_index + 2 * self.max_nested_blocks + 1;
@@ -492,12 +2082,17 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
                 }
 
                 if start > *horizontal_index {
-                    write!(self.f, "{}", " ".repeat(start - *horizontal_index))?;
+                    self.write_repeated(' ', start - *horizontal_index)?;
                     *horizontal_index = start;
                 }
 
-                self.colors.annotation(self.f, data.style, data.severity)?;
-                write!(self.f, "{}", if data.style == AnnotationStyle::Primary { "^" } else { "-" })?;
+                let c = match self.config.charset.multiline_junction {
+                    Some(junction) if is_connected(data.annotation_index, &data.location) => junction,
+                    _ => self.config.charset.severity_underline(data.style, data.severity),
+                };
+
+                self.annotation_color(data.style, data.severity, data.annotation_index)?;
+                write!(self.f, "{}", c)?;
                 self.colors.reset(self.f)?;
 
                 *horizontal_index += 1;
@@ -511,7 +2106,7 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
                 }
 
                 if start > *horizontal_index {
-                    write!(self.f, "{}", " ".repeat(start - *horizontal_index))?;
+                    self.write_repeated(' ', start - *horizontal_index)?;
                     *horizontal_index = start;
                 }
 
@@ -521,12 +2116,12 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
                     end
                 };
 
-                self.colors.annotation(self.f, data.style, data.severity)?;
-                write!(self.f, "{}", if data.as_multiline { "_" } else if data.style == AnnotationStyle::Primary { "^" } else { "-" }
-                    .repeat(to_index - *horizontal_index))?;
+                self.annotation_color(data.style, data.severity, data.annotation_index)?;
+                let c = if data.as_multiline { self.config.charset.horizontal_bar } else { self.config.charset.severity_underline(data.style, data.severity) };
+                self.write_repeated(c, to_index.saturating_sub(*horizontal_index))?;
                 self.colors.reset(self.f)?;
 
-                *horizontal_index = to_index;
+                *horizontal_index = to_index.max(*horizontal_index);
             },
             AnnotationData::End(data) => {
                 let start = data.location.column_index + 2 * self.max_nested_blocks + 1;
@@ -536,12 +2131,17 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
                 }
 
                 if start > *horizontal_index {
-                    write!(self.f, "{}", " ".repeat(start - *horizontal_index))?;
+                    self.write_repeated(' ', start - *horizontal_index)?;
                     *horizontal_index = start;
                 }
 
-                self.colors.annotation(self.f, data.style, data.severity)?;
-                write!(self.f, "{}", if data.style == AnnotationStyle::Primary { "^" } else { "-" })?;
+                let c = match self.config.charset.multiline_junction {
+                    Some(junction) if is_connected(data.annotation_index, &data.location) => junction,
+                    _ => self.config.charset.severity_underline(data.style, data.severity),
+                };
+
+                self.annotation_color(data.style, data.severity, data.annotation_index)?;
+                write!(self.f, "{}", c)?;
                 self.colors.reset(self.f)?;
 
                 *horizontal_index += 1;
@@ -554,12 +2154,18 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
                 }
 
                 if start > *horizontal_index {
-                    write!(self.f, "{}", " ".repeat(start - *horizontal_index))?;
+                    self.write_repeated(' ', start - *horizontal_index)?;
                     *horizontal_index = start;
                 }
 
-                self.colors.annotation(self.f, data.style, data.severity)?;
-                write!(self.f, "|")?;
+                let c = if is_connected(data.annotation_index, &data.location) {
+                    self.config.charset.multiline_corner
+                } else {
+                    self.config.charset.vertical_bar
+                };
+
+                self.annotation_color(data.style, data.severity, data.annotation_index)?;
+                write!(self.f, "{}", c)?;
                 self.colors.reset(self.f)?;
 
                 *horizontal_index += 1;
@@ -572,15 +2178,18 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
                 }
 
                 if start > *horizontal_index {
-                    write!(self.f, "{}", " ".repeat(start - *horizontal_index))?;
+                    self.write_repeated(' ', start - *horizontal_index)?;
                     *horizontal_index = start;
                 }
 
-                self.colors.annotation(self.f, data.style, data.severity)?;
-                write!(self.f, "{}", &data.label)?;
+                let cycle_colors = self.config.cycle_annotation_colors;
+                let color = self.annotation_colors.get(data.annotation_index).cloned().flatten();
+                self.annotation_color(data.style, data.severity, data.annotation_index)?;
+                self.write_message_text(data.label, data.severity, &mut false, |colors, f|
+                    annotation_color(colors, f, cycle_colors, color.as_ref(), data.style, data.severity, data.annotation_index))?;
                 self.colors.reset(self.f)?;
 
-                *horizontal_index += data.label.len();
+                *horizontal_index += data.label.width();
                 *last = true;
             },
         }
@@ -588,7 +2197,184 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         Ok(())
     }
 
-    fn write_line_number(&mut self, line: Option<usize>, separator: &str) -> Result {
+    /// Returns the location of a byte index in a file, with the column number counted
+    /// in [`RenderConfig::column_unit`].
+    ///
+    /// [`RenderConfig::column_unit`]: RenderConfig::column_unit
+    fn location(&self, file: &FileId, byte_index: usize) -> Result<Location> {
+        location(&self.files, &self.config, file, byte_index)
+    }
+
+    /// Writes the name of a file, followed by the line and column number if a location is given.
+    /// The name is changed by the path formatter, if one is set.
+    ///
+    /// If a hyperlink format is configured and the writer supports hyperlinks,
+    /// this is wrapped in a hyperlink.
+    fn write_path(&mut self, file: &FileId, location: Option<Location>) -> Result {
+        let name = self.files.file_name(file)?;
+        let hyperlink = match self.config.hyperlink_format.as_ref() {
+            Some(format) if self.f.supports_hyperlinks() => {
+                let (line_number, column_number) = location.as_ref()
+                    .map(|location| (location.line_number, location.column_number)).unwrap_or((1, 1));
+
+                Some(format.replace("{path}", name)
+                    .replace("{line}", &line_number.to_string())
+                    .replace("{column}", &column_number.to_string()))
+            },
+            _ => None,
+        };
+
+        if let Some(hyperlink) = hyperlink.as_ref() {
+            self.f.set_hyperlink(&HyperlinkSpec::open(hyperlink.as_bytes()))?;
+        }
+
+        write!(self.f, "{}", self.display_path(file)?)?;
+
+        if let Some(location) = location {
+            write!(self.f, ":{}:{}", location.line_number, location.column_number)?;
+        }
+
+        if hyperlink.is_some() {
+            self.f.set_hyperlink(&HyperlinkSpec::close())?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name of a file as it is written in file headers, which is changed by
+    /// the path formatter, if one is set.
+    fn display_path(&self, file: &FileId) -> Result<String> {
+        let name = self.files.file_name(file)?;
+
+        Ok(match self.path_formatter.as_ref() {
+            Some(PathFormatter(format)) => format(name),
+            None => name.to_string(),
+        })
+    }
+
+    /// Returns whether the next rendered diagnostics are separated from the ones
+    /// rendered before by an empty line.
+    fn separates_next(&self) -> bool {
+        self.config.display_style != DisplayStyle::Short && self.counts.total() > self.suppressed_errors
+    }
+
+    /// Sets the formatting for the annotation with the given index in the current file,
+    /// which is its explicit color if it has one, and depends on its index if
+    /// [`RenderConfig::cycle_annotation_colors`] is enabled.
+    ///
+    /// [`RenderConfig::cycle_annotation_colors`]: RenderConfig::cycle_annotation_colors
+    fn annotation_color(&mut self, style: AnnotationStyle, severity: Severity, index: usize) -> Result {
+        let color = self.annotation_colors.get(index).and_then(Option::as_ref);
+        annotation_color(&self.colors, self.f, self.config.cycle_annotation_colors, color, style, severity, index)?;
+        Ok(())
+    }
+
+    /// Writes text of a message, label or note. With [`RenderConfig::emphasize_backticks`],
+    /// text inside backticks is written with [`ColorConfig::emphasis`], and `style` sets the
+    /// formatting of the surrounding text again after it.
+    ///
+    /// `emphasized` is whether `text` starts inside backticks, and is set to whether it ends
+    /// inside them, so that wrapped lines can continue the emphasis of the previous line.
+    /// The formatting of the surrounding text is always set again at the end.
+    ///
+    /// [`RenderConfig::emphasize_backticks`]: RenderConfig::emphasize_backticks
+    /// [`ColorConfig::emphasis`]: ColorConfig::emphasis
+    fn write_message_text(&mut self, text: &str, severity: Severity, emphasized: &mut bool,
+                          style: impl Fn(&C, &mut W) -> std::io::Result<()>) -> Result {
+        if !self.config.emphasize_backticks {
+            write!(self.f, "{}", text)?;
+            return Ok(());
+        }
+
+        let mut parts = text.split('`');
+
+        if *emphasized {
+            self.colors.emphasis(self.f, severity)?;
+        }
+
+        write!(self.f, "{}", parts.next().unwrap_or_default())?;
+
+        for part in parts {
+            // The backticks are written in the formatting of the surrounding text
+            if *emphasized {
+                style(&self.colors, self.f)?;
+                write!(self.f, "`")?;
+            } else {
+                write!(self.f, "`")?;
+                self.colors.emphasis(self.f, severity)?;
+            }
+
+            *emphasized = !*emphasized;
+            write!(self.f, "{}", part)?;
+        }
+
+        if *emphasized {
+            style(&self.colors, self.f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the values of a note comparing them on two lines, with the words before them
+    /// aligned to the right, and the second line indented by `prefix_width`.
+    fn write_expected_found(&mut self, expected_found: &ExpectedFound, severity: Severity, prefix_width: usize) -> Result {
+        let expected = self.strings.expected().into_owned();
+        let found = self.strings.found().into_owned();
+        let width = expected.width().max(found.width());
+
+        let (prefix, suffix) = if expected_found.highlight_differences {
+            expected_found.common_affixes()
+        } else {
+            (0, 0)
+        };
+
+        for (i, (word, value)) in [(expected, &expected_found.expected), (found, &expected_found.found)].into_iter().enumerate() {
+            if i > 0 {
+                write!(self.f, "\n{:prefix_width$}", "")?;
+            }
+
+            write!(self.f, "{:>width$} ", word, width = width)?;
+
+            if !expected_found.highlight_differences {
+                write!(self.f, "{}", value)?;
+                continue;
+            }
+
+            let end = value.len() - suffix;
+            write!(self.f, "{}", &value[..prefix])?;
+            self.colors.emphasis(self.f, severity)?;
+            write!(self.f, "{}", &value[prefix..end])?;
+            self.colors.note_message(self.f, severity)?;
+            write!(self.f, "{}", &value[end..])?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `c` `count` times, without allocating a string for it.
+    fn write_repeated(&mut self, c: char, count: usize) -> Result {
+        const CHUNK_LEN: usize = 16;
+        let mut buf = [0; 4 * CHUNK_LEN];
+        let char_len = c.len_utf8();
+
+        for i in 0..CHUNK_LEN {
+            c.encode_utf8(&mut buf[i * char_len..]);
+        }
+
+        let mut remaining = count;
+
+        while remaining > 0 {
+            let len = remaining.min(CHUNK_LEN);
+            self.f.write_all(&buf[..len * char_len])?;
+            remaining -= len;
+        }
+
+        Ok(())
+    }
+
+    fn write_line_number(&mut self, line: Option<usize>, separator: Separator) -> Result {
+        write!(self.f, "{:indent$}", "", indent = self.indent)?;
+
         if let Some(line) = line {
             self.colors.line_number(self.f)?;
             write!(self.f, "{:>fill$}", line, fill = self.line_digits as usize)?;
@@ -597,14 +2383,24 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         }
 
         self.colors.line_number_separator(self.f)?;
-        write!(self.f, "{}", separator)?;
+
+        match separator {
+            Separator::Bar => write!(self.f, " {}", self.config.charset.vertical_bar)?,
+            Separator::Arrow => write!(self.f, "{}", self.config.charset.file_arrow)?,
+            Separator::Continuation => write!(self.f, "{}", self.config.charset.file_continuation)?,
+            Separator::Ellipsis => write!(self.f, "{}", self.config.charset.skipped_lines)?,
+            Separator::Note => write!(self.f, " =")?,
+            Separator::Removal => write!(self.f, " -")?,
+            Separator::Addition => write!(self.f, " +")?,
+        }
+
         self.colors.reset(self.f)?;
         Ok(())
     }
 
-    fn write_source_line(&mut self, diagnostic: &Diagnostic<FileId>, line: Option<(FileId, usize)>, separator: &str, continuing_annotations: &[&Annotation<FileId>]) -> Result {
+    fn write_source_line(&mut self, diagnostic: &Diagnostic<FileId>, line: Option<(&FileId, usize)>, separator: Separator, continuing_annotations: &[(usize, &Annotation<FileId>)]) -> Result {
         let line_number = if let Some((file, line_index)) = line.as_ref() {
-            Some(self.files.line_number(*file, *line_index)?)
+            Some(self.files.file_line_number(file, *line_index)?)
         } else {
             None
         };
@@ -613,13 +2409,15 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
 
         // eprintln!("[debug] writing line begin; line: {:?}, separator: {}, continuing: {}, max nested blocks: {}", line.as_ref(), separator.len(), continuing_annotations.len(), self.max_nested_blocks);
 
-        if separator.len() < 3 && (!continuing_annotations.is_empty() || self.max_nested_blocks > 0) {
-            write!(self.f, "{}", " ".repeat(3 - separator.len()))?;
+        let separator_width = separator.width(&self.config.charset);
+
+        if separator_width < 3 && (!continuing_annotations.is_empty() || self.max_nested_blocks > 0) {
+            self.write_repeated(' ', 3 - separator_width)?;
         }
 
-        for (i, annotation) in continuing_annotations.iter().enumerate() {
-            self.colors.annotation(self.f, annotation.style, diagnostic.severity)?;
-            write!(self.f, "|")?;
+        for (i, &(index, annotation)) in continuing_annotations.iter().enumerate() {
+            self.annotation_color(annotation.style, diagnostic.severity, index)?;
+            write!(self.f, "{}", self.config.charset.vertical_bar)?;
             self.colors.reset(self.f)?;
 
             if i < continuing_annotations.len() - 1 {
@@ -628,7 +2426,8 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         }
 
         if let Some((file, line_index)) = line {
-            let source = &self.files.source(file)?[self.files.line_range(file, line_index)?];
+            let line_range = self.files.file_line_range(file, line_index)?;
+            let source = &self.files.file_source(file)?[line_range.clone()];
             let is_empty = source.trim().is_empty();
 
             if !is_empty {
@@ -636,13 +2435,63 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
 
                 self.colors.source(self.f)?;
 
-                if source.ends_with('\n') {
+                // The column ranges of the annotated parts of this line, in the order
+                // they take precedence in. Ranges that aren't on character boundaries
+                // are not highlighted.
+                let mut highlights = self.annotated_ranges.iter()
+                    .map(|(range, style)| (range.start.clamp(line_range.start, line_range.end), range.end.clamp(line_range.start, line_range.end), *style))
+                    .filter(|(start, end, _)| start < end)
+                    .filter_map(|(start, end, style)| Some((text::width(source.get(..start - line_range.start)?, &self.config)
+                        ..text::width(source.get(..end - line_range.start)?, &self.config), style)))
+                    .collect::<Vec<_>>();
+                highlights.sort_by_key(|(range, style)| (*style, std::cmp::Reverse(range.start)));
+
+                // Line endings are written separately, so that `\r\n` is printed like `\n`
+                let source = text::display(source.trim_end_matches(['\n', '\r']), &self.config);
+                let (source, cut_end) = match &self.window {
+                    Some(window) => window_line(&source, window),
+                    None => (source, false),
+                };
+
+                if self.window.is_some_and(|window| window.offset > 0) {
+                    write!(self.f, "{}", self.config.charset.ellipsis)?;
+                }
+
+                if highlights.is_empty() {
                     write!(self.f, "{}", source)?;
                 } else {
-                    writeln!(self.f, "{}", source)?;
+                    // Characters of the windowed line are at the same column as in the full line,
+                    // minus the window offset
+                    let mut column = self.window.map_or(0, |window| window.offset);
+                    let mut current = None;
+
+                    for c in source.chars() {
+                        let highlight = highlights.iter().find(|(range, _)| range.contains(&column)).map(|&(_, style)| style);
+
+                        if highlight != current {
+                            match highlight {
+                                Some(style) => self.colors.annotated_source(self.f, style, diagnostic.severity)?,
+                                None => self.colors.source(self.f)?,
+                            }
+
+                            current = highlight;
+                        }
+
+                        write!(self.f, "{}", c)?;
+                        column += c.width().unwrap_or(0);
+                    }
+
+                    if cut_end && current.is_some() {
+                        self.colors.source(self.f)?;
+                    }
+                }
+
+                if cut_end {
+                    write!(self.f, "{}", self.config.charset.ellipsis)?;
                 }
 
                 self.colors.reset(self.f)?;
+                writeln!(self.f)?;
             } else {
                 writeln!(self.f)?;
             }
@@ -655,13 +2504,236 @@ impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> Diagnos
         line_index.saturating_sub(self.config.surrounding_lines)
     }
 
-    fn get_last_print_line(&self, file: FileId, line: usize) -> std::result::Result<usize, Error> {
+    fn get_last_print_line(&self, file: &FileId, line: usize) -> Result<usize> {
         Ok((line + self.config.surrounding_lines).min(self.get_last_line_index(file)?))
     }
 
-    fn get_last_line_index(&self, file: FileId) -> std::result::Result<usize, Error> {
-        self.files.line_index(file, self.files.source(file)?.len().saturating_sub(1))
+    fn get_last_line_index(&self, file: &FileId) -> Result<usize> {
+        let last_byte_index = self.files.file_source(file)?.len().saturating_sub(1);
+        self.files.file_line_index(file, last_byte_index)
+    }
+}
+
+fn annotation_color<C: ColorConfig>(colors: &C, f: &mut impl WriteColor, cycle_colors: bool, color: Option<&TextStyle>,
+                                    style: AnnotationStyle, severity: Severity, index: usize) -> std::io::Result<()> {
+    if let Some(color) = color {
+        colors.styled_annotation(f, style, severity, color)
+    } else if cycle_colors {
+        colors.indexed_annotation(f, style, severity, index)
+    } else {
+        colors.annotation(f, style, severity)
+    }
+}
+
+/// Sorts the files of a diagnostic, with their annotations, in the order they are rendered in.
+fn sort_files<FileId: Ord, A: Borrow<Annotation<FileId>>>(files: &mut [(FileId, Vec<A>)], primary_file: Option<&FileId>, file_order: FileOrder) {
+    match file_order {
+        // The sort is stable, so files with the same priority stay in the order of their first annotation
+        FileOrder::Relevance => files.sort_by_key(|(file, annotations)|
+            (primary_file != Some(file), annotations.iter().map(|a| a.borrow().style).min())),
+        FileOrder::FileId => files.sort_by(|(a, _), (b, _)| a.cmp(b)),
+    }
+}
+
+/// A line on which at least one annotation starts or ends.
+struct AnnotatedLine {
+    line_index: usize,
+    /// The indices of the annotations starting or ending on this line.
+    on_line: Vec<usize>,
+    /// The indices of the multi-line annotations that started before this line.
+    continuing: Vec<usize>,
+}
+
+/// Returns every line on which at least one of `annotations` starts or ends.
+///
+/// Annotations are only drawn on these lines. The lines in between are rendered as surrounding
+/// lines, or skipped, so they don't have to be visited. `annotations` have to be sorted by their
+/// start byte index.
+fn annotated_lines<F: Files, A: Borrow<Annotation<F::FileId>>>(files: &F, file: &F::FileId, annotations: &[A]) -> Result<Vec<AnnotatedLine>>
+    where F::FileId: Debug {
+    // The first and last line index of every annotation, so that they are only looked up once
+    let spans = annotations.iter()
+        .map(|a| {
+            let a = a.borrow();
+            Ok((files.file_line_index(file, a.range.start)?, files.file_line_index(file, a.range.end)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut line_indices = spans.iter().flat_map(|&(start, end)| [start, end]).collect::<Vec<_>>();
+    line_indices.sort_unstable();
+    line_indices.dedup();
+
+    let mut lines = Vec::with_capacity(line_indices.len());
+
+    for current_line_index in line_indices {
+        let mut on_line = Vec::new();
+        let mut continuing = Vec::new();
+
+        for (i, &(start_line_index, end_line_index)) in spans.iter().enumerate() {
+            if start_line_index > current_line_index && end_line_index > current_line_index {
+                break;
+            } else if end_line_index < current_line_index && start_line_index < current_line_index {
+                continue;
+            }
+
+            if start_line_index < current_line_index {
+                continuing.push(i);
+            }
+
+            if start_line_index == current_line_index || end_line_index == current_line_index {
+                on_line.push(i);
+            }
+        }
+
+        if !on_line.is_empty() {
+            lines.push(AnnotatedLine { line_index: current_line_index, on_line, continuing });
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Returns the location of a byte index, with the column number counted in
+/// [`RenderConfig::column_unit`].
+///
+/// [`RenderConfig::column_unit`]: RenderConfig::column_unit
+fn location<F: Files>(files: &F, config: &RenderConfig, file: &F::FileId, byte_index: usize) -> Result<Location>
+    where F::FileId: Debug {
+    if config.column_unit == ColumnUnit::Chars {
+        return files.location(file.clone(), byte_index)
+            .map_err(|err| files.file_error(file, FileOperation::Location { byte_index }, err));
+    }
+
+    let line_index = files.file_line_index(file, byte_index)?;
+    let line_start = files.file_line_range(file, line_index)?.start;
+    let column_index = if config.column_unit == ColumnUnit::Bytes {
+        byte_index.saturating_sub(line_start)
+    } else {
+        let source = files.file_source(file)?;
+        let before = source.get(line_start..byte_index.max(line_start))
+            .ok_or_else(|| files.file_error(file, FileOperation::SourceIndex { byte_index },
+                Error::InvalidCharBoundary { given: byte_index }))?;
+
+        match config.column_unit {
+            ColumnUnit::Utf16 => before.encode_utf16().count(),
+            ColumnUnit::Width => text::width(before, config),
+            _ => before.graphemes(true).count(),
+        }
+    };
+
+    Ok(Location {
+        line_number: files.file_line_number(file, line_index)?,
+        column_number: column_index + 1,
+    })
+}
+
+/// Groups items by their file, keeping the files in the order of their first item.
+fn group_by_file<T, FileId: Clone + Eq>(items: impl IntoIterator<Item=T>, file_of: impl Fn(&T) -> &FileId) -> Vec<(FileId, Vec<T>)> {
+    let mut groups: Vec<(FileId, Vec<T>)> = Vec::new();
+
+    for item in items {
+        match groups.iter_mut().find(|(file, _)| file == file_of(&item)) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((file_of(&item).clone(), vec![item])),
+        }
+    }
+
+    groups
+}
+
+/// Returns the part of a line that is visible in `window`, without the ellipses, and whether
+/// the end of the line was cut off. Wide characters that are only partially visible are
+/// replaced with spaces.
+fn window_line<'a>(line: &'a str, window: &Window) -> (Cow<'a, str>, bool) {
+    let line_width = line.width();
+    let start = window.offset;
+    let end = window.offset + window.width;
+
+    if start == 0 && line_width <= end {
+        return (Cow::Borrowed(line), false);
+    }
+
+    let cut_end = line_width > end;
+    let mut result = String::with_capacity(window.width);
+    let mut column = 0;
+    let mut written = start;
+
+    for c in line.chars() {
+        let char_width = c.width().unwrap_or(0);
+
+        if column >= start && column + char_width <= end {
+            // Fill the columns of wide characters that were only partially visible
+            result.extend(std::iter::repeat_n(' ', column.saturating_sub(written)));
+            result.push(c);
+            written = column + char_width;
+        }
+
+        column += char_width;
+    }
+
+    if cut_end {
+        result.extend(std::iter::repeat_n(' ', end.saturating_sub(written)));
+    }
+
+    (Cow::Owned(result), cut_end)
+}
+
+/// Wraps a line of text at spaces so that every part is at most `width` columns wide, unless it
+/// consists of a single longer word. Lines that already fit are returned unchanged.
+fn wrap_line(line: &str, width: usize) -> Vec<Cow<'_, str>> {
+    if line.width() <= width {
+        return vec![Cow::Borrowed(line)];
+    }
+
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+
+    // Splitting on single spaces keeps runs of spaces as empty words, so the original spacing is kept
+    for (i, word) in line.split(' ').enumerate() {
+        let word_width = word.width();
+
+        if i > 0 {
+            if !current.is_empty() && current_width + 1 + word_width > width {
+                // Spaces at the point where the line is wrapped are dropped
+                lines.push(Cow::Owned(current.trim_end_matches(' ').to_owned()));
+                current.clear();
+                current_width = 0;
+            } else if !current.is_empty() || lines.is_empty() {
+                current.push(' ');
+                current_width += 1;
+            }
+        }
+
+        current.push_str(word);
+        current_width += word_width;
+    }
+
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(Cow::Owned(current));
+    }
+
+    lines
+}
+
+/// Splits the lines affected by a change into characters, and whether they are part of
+/// a changed segment.
+fn split_changed_lines(segments: &[(&str, bool)]) -> Vec<Vec<(char, bool)>> {
+    let mut lines: Vec<Vec<(char, bool)>> = vec![Vec::new()];
+
+    for (c, is_changed) in segments.iter().flat_map(|(segment, is_changed)| segment.chars().map(move |c| (c, *is_changed))) {
+        match c {
+            '\n' => lines.push(Vec::new()),
+            '\r' => {},
+            c => lines.last_mut().expect("no lines despite adding one before").push((c, is_changed)),
+        }
+    }
+
+    if lines.len() > 1 && lines.last().map(|line| line.is_empty()).unwrap_or(false) {
+        lines.pop();
     }
+
+    lines
 }
 
 #[cfg(test)]
@@ -0,0 +1,168 @@
+//! Rendering many diagnostics in parallel, using [`rayon`].
+//!
+//! Every diagnostic is laid out into its own buffer on the rayon thread pool, and the buffers
+//! are then written to the output in the original order, so the output is the same as with
+//! [`DiagnosticRenderer::render`].
+//!
+//! [`rayon`]: rayon
+//! [`DiagnosticRenderer::render`]: DiagnosticRenderer::render
+
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::io;
+use std::sync::Arc;
+use rayon::prelude::*;
+use crate::render::style::{ColorSpec, HyperlinkSpec, WriteColor};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::file::Files;
+use crate::render::color::ColorConfig;
+use crate::render::{DiagnosticRenderer, DisplayStyle, Result};
+
+impl<W: WriteColor, C: ColorConfig + Sync, FileId, F: Files<FileId=FileId> + Sync> DiagnosticRenderer<'_, W, C, FileId, F>
+    where FileId: Clone + Debug + Eq + Ord + Send + Sync {
+    /// Renders the given diagnostics like [`DiagnosticRenderer::render`], but lays them out
+    /// in parallel.
+    ///
+    /// Every diagnostic is rendered into its own buffer on the rayon thread pool, and the buffers
+    /// are written to the output in order afterwards. This is faster for large numbers of
+    /// diagnostics, at the cost of keeping the output of all of them in memory at once.
+    ///
+    /// [`DiagnosticRenderer::render`]: DiagnosticRenderer::render
+    pub fn render_parallel(&mut self, diagnostics: Vec<Diagnostic<F::FileId>>) -> Result {
+        let mut diagnostics = diagnostics.into_iter().map(Cow::Owned).collect::<Vec<Cow<Diagnostic<FileId>>>>();
+        diagnostics.retain(|d| d.severity >= self.config.min_severity);
+
+        if let Some(max_errors) = self.config.max_errors {
+            diagnostics = self.suppress_errors(diagnostics, max_errors)?;
+        }
+
+        if diagnostics.is_empty() {
+            return Ok(());
+        }
+
+        for diagnostic in diagnostics.iter() {
+            self.check_ranges(diagnostic)?;
+        }
+
+        let supports_color = self.f.supports_color();
+        let supports_hyperlinks = self.f.supports_hyperlinks();
+
+        let outputs = diagnostics.into_par_iter().map(|diagnostic| {
+            let severity = diagnostic.severity;
+            let mut output = RecordedOutput::new(supports_color, supports_hyperlinks);
+            let mut renderer = DiagnosticRenderer::new(&mut output, &self.colors, &self.files, self.config.clone());
+            renderer.indent = self.indent;
+            renderer.strings = Arc::clone(&self.strings);
+            renderer.path_formatter = self.path_formatter.clone();
+
+            if self.config.display_style == DisplayStyle::Short {
+                renderer.render_diagnostic_short(&diagnostic)?;
+            } else {
+                renderer.render_diagnostic(diagnostic)?;
+            }
+
+            Ok((severity, output))
+        }).collect::<Result<Vec<(Severity, RecordedOutput)>>>()?;
+
+        let outputs_len = outputs.len();
+
+        // Separate these diagnostics from the ones rendered by previous calls
+        if self.separates_next() {
+            writeln!(self.f)?;
+        }
+
+        for (i, (severity, output)) in outputs.into_iter().enumerate() {
+            self.counts.add(severity);
+            output.write_to(self.f)?;
+
+            if self.config.display_style != DisplayStyle::Short && i < outputs_len - 1 {
+                writeln!(self.f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A change of formatting, applied at a byte index of the recorded text.
+#[derive(Debug)]
+enum StyleChange {
+    Color(ColorSpec),
+    Reset,
+    Hyperlink(Option<Vec<u8>>),
+}
+
+/// A [`WriteColor`] that records the written text and formatting, so that it can be
+/// written to another [`WriteColor`] later.
+///
+/// [`WriteColor`]: WriteColor
+#[derive(Debug)]
+struct RecordedOutput {
+    text: Vec<u8>,
+    changes: Vec<(usize, StyleChange)>,
+    supports_color: bool,
+    supports_hyperlinks: bool,
+}
+
+impl RecordedOutput {
+    fn new(supports_color: bool, supports_hyperlinks: bool) -> Self {
+        RecordedOutput {
+            text: Vec::new(), changes: Vec::new(),
+            supports_color, supports_hyperlinks,
+        }
+    }
+
+    fn write_to(&self, f: &mut impl WriteColor) -> io::Result<()> {
+        let mut written = 0;
+
+        for (index, change) in self.changes.iter() {
+            f.write_all(&self.text[written..*index])?;
+            written = *index;
+
+            match change {
+                StyleChange::Color(spec) => f.set_color(spec)?,
+                StyleChange::Reset => f.reset()?,
+                StyleChange::Hyperlink(Some(uri)) => f.set_hyperlink(&HyperlinkSpec::open(uri))?,
+                StyleChange::Hyperlink(None) => f.set_hyperlink(&HyperlinkSpec::close())?,
+            }
+        }
+
+        f.write_all(&self.text[written..])
+    }
+}
+
+impl io::Write for RecordedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.text.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl WriteColor for RecordedOutput {
+    fn supports_color(&self) -> bool {
+        self.supports_color
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        self.changes.push((self.text.len(), StyleChange::Color(spec.clone())));
+        Ok(())
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.changes.push((self.text.len(), StyleChange::Reset));
+        Ok(())
+    }
+
+    fn supports_hyperlinks(&self) -> bool {
+        self.supports_hyperlinks
+    }
+
+    fn set_hyperlink(&mut self, link: &HyperlinkSpec) -> io::Result<()> {
+        self.changes.push((self.text.len(), StyleChange::Hyperlink(link.uri().map(<[u8]>::to_vec))));
+        Ok(())
+    }
+}
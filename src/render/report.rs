@@ -0,0 +1,161 @@
+//! Contains [`DiagnosticRenderer::render_report`], which renders diagnostics grouped by file,
+//! followed by a table counting the errors and warnings of every file, like the output of
+//! a lint runner.
+//!
+//! [`DiagnosticRenderer::render_report`]: DiagnosticRenderer::render_report
+
+use std::fmt::Debug;
+use crate::render::style::WriteColor;
+use unicode_width::UnicodeWidthStr;
+use crate::diagnostic::{Diagnostic, Severity, SeverityCounts};
+use crate::file::Files;
+use crate::render::color::ColorConfig;
+use crate::render::{DiagnosticRenderer, DisplayStyle, Result};
+
+/// The diagnostics of a single file in a report rendered by [`DiagnosticRenderer::render_report`].
+///
+/// [`DiagnosticRenderer::render_report`]: DiagnosticRenderer::render_report
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileSummary<FileId> {
+    /// The file of the [primary annotation] of the diagnostics, or `None` for the diagnostics
+    /// without a primary annotation.
+    ///
+    /// [primary annotation]: Diagnostic::primary_annotation
+    pub file: Option<FileId>,
+    /// The number of diagnostics of every severity, not including sub-diagnostics.
+    pub counts: SeverityCounts,
+}
+
+impl<'w, W: WriteColor, C: ColorConfig, FileId, F: Files<FileId=FileId>> DiagnosticRenderer<'w, W, C, FileId, F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Renders a report of the given diagnostics, grouped by file.
+    ///
+    /// Diagnostics are grouped by the file of their [primary annotation], in the order these files
+    /// first appear in, followed by the diagnostics without a primary annotation. Every group is
+    /// rendered in its own section, starting with the name of the file and the number of errors
+    /// and warnings in it. The report ends with a table of these numbers for every file:
+    ///
+    /// ```text
+    /// src/main.rs (1 error, 1 warning)
+    ///
+    /// error: Mismatched types
+    /// ...
+    ///
+    /// file         errors  warnings
+    /// src/main.rs       1         1
+    /// total             1         1
+    /// ```
+    ///
+    /// Diagnostics below [`RenderConfig::min_severity`] are left out. Returns the number of
+    /// diagnostics of every severity in every section.
+    ///
+    /// [primary annotation]: Diagnostic::primary_annotation
+    /// [`RenderConfig::min_severity`]: crate::render::RenderConfig::min_severity
+    pub fn render_report(&mut self, diagnostics: Vec<Diagnostic<FileId>>) -> Result<Vec<FileSummary<FileId>>> {
+        let mut sections: Vec<(Option<FileId>, Vec<Diagnostic<FileId>>)> = Vec::new();
+
+        for diagnostic in diagnostics.into_iter().filter(|d| d.severity >= self.config.min_severity) {
+            let file = diagnostic.primary_annotation().map(|annotation| annotation.file_id.clone());
+
+            match sections.iter_mut().find(|(other, _)| *other == file) {
+                Some((_, diagnostics)) => diagnostics.push(diagnostic),
+                None => sections.push((file, vec![diagnostic])),
+            }
+        }
+
+        // The sort is stable, so the files stay in the order they first appeared in
+        sections.sort_by_key(|(file, _)| file.is_none());
+
+        let mut summaries = Vec::with_capacity(sections.len());
+
+        for (file, diagnostics) in sections.into_iter() {
+            let mut counts = SeverityCounts::new();
+
+            for diagnostic in diagnostics.iter() {
+                counts.add(diagnostic.severity);
+            }
+
+            if self.separates_next() {
+                writeln!(self.f)?;
+            }
+
+            self.render_report_header(file.as_ref(), &counts)?;
+
+            // Rendering the diagnostics only adds an empty line if something was rendered before
+            if self.config.display_style != DisplayStyle::Short && !self.separates_next() {
+                writeln!(self.f)?;
+            }
+
+            self.render(diagnostics)?;
+            summaries.push(FileSummary { file, counts });
+        }
+
+        self.render_report_table(&summaries)?;
+        Ok(summaries)
+    }
+
+    fn render_report_header(&mut self, file: Option<&FileId>, counts: &SeverityCounts) -> Result {
+        self.colors.path(self.f)?;
+
+        match file {
+            Some(file) => self.write_path(file, None)?,
+            None => write!(self.f, "{}", self.strings.report_no_file())?,
+        }
+
+        self.colors.reset(self.f)?;
+        writeln!(self.f, " ({})", self.strings.report_counts(counts))?;
+        Ok(())
+    }
+
+    fn render_report_table(&mut self, summaries: &[FileSummary<FileId>]) -> Result {
+        if summaries.is_empty() {
+            return Ok(());
+        }
+
+        let errors = |counts: &SeverityCounts| counts.get(Severity::Error) + counts.get(Severity::Bug);
+        let mut total = (0, 0);
+        let mut rows = Vec::with_capacity(summaries.len() + 1);
+
+        for summary in summaries.iter() {
+            let name = match summary.file.as_ref() {
+                Some(file) => self.display_path(file)?,
+                None => self.strings.report_no_file().into_owned(),
+            };
+
+            total.0 += errors(&summary.counts);
+            total.1 += summary.counts.get(Severity::Warning);
+            rows.push([name, errors(&summary.counts).to_string(), summary.counts.get(Severity::Warning).to_string()]);
+        }
+
+        rows.push([self.strings.report_total().into_owned(), total.0.to_string(), total.1.to_string()]);
+
+        let columns = self.strings.report_columns().map(|column| column.into_owned());
+        let widths = [0, 1, 2].map(|i| rows.iter().map(|row| &row[i]).chain(std::iter::once(&columns[i]))
+            .map(|text| text.width()).max().unwrap_or(0));
+
+        writeln!(self.f)?;
+        self.colors.message(self.f)?;
+        self.write_report_row(&columns, &widths)?;
+        self.colors.reset(self.f)?;
+        writeln!(self.f)?;
+
+        for row in rows.iter() {
+            self.write_report_row(row, &widths)?;
+            writeln!(self.f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a row of the table, with the file name aligned to the left and the
+    /// numbers aligned to the right.
+    fn write_report_row(&mut self, row: &[String; 3], widths: &[usize; 3]) -> Result {
+        write!(self.f, "{}{:padding$}", row[0], "", padding = widths[0] - row[0].width())?;
+
+        for i in 1..3 {
+            write!(self.f, "  {:padding$}{}", "", row[i], padding = widths[i] - row[i].width())?;
+        }
+
+        Ok(())
+    }
+}
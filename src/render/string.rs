@@ -0,0 +1,157 @@
+//! Rendering diagnostics into strings, for embedding them in log messages, other strings,
+//! or test assertions.
+//!
+//! [`render_to_string`] renders diagnostics without colors into a new string, while
+//! [`FmtWriter`] allows rendering into any [`fmt::Write`], like a [`Formatter`].
+//! [`Diagnostic::display`] returns a [`DisplayDiagnostic`] for a single diagnostic.
+//!
+//! [`render_to_string`]: render_to_string
+//! [`FmtWriter`]: FmtWriter
+//! [`Diagnostic::display`]: Diagnostic::display
+//! [`DisplayDiagnostic`]: DisplayDiagnostic
+//! [`fmt::Write`]: fmt::Write
+//! [`Formatter`]: fmt::Formatter
+
+use std::fmt::{self, Debug};
+use std::io;
+use crate::render::style::{ColorSpec, WriteColor};
+use crate::diagnostic::Diagnostic;
+use crate::file::Files;
+use crate::render::color::DisabledColorConfig;
+use crate::render::{DiagnosticRenderer, RenderConfig, Result};
+
+/// Renders diagnostics without colors, and returns the output as a string.
+///
+/// ```
+/// use diagnostic_render::diagnostic::{Annotation, AnnotationStyle, Diagnostic, Severity};
+/// use diagnostic_render::file::SimpleFile;
+/// use diagnostic_render::render::RenderConfig;
+/// use diagnostic_render::render::string::render_to_string;
+///
+/// let file = SimpleFile::new("main.rs", "let x = 1;\n");
+/// let diagnostic = Diagnostic::new(Severity::Warning)
+///     .with_message("unused variable")
+///     .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..5));
+///
+/// let output = render_to_string(file, RenderConfig::default(), vec![diagnostic]).unwrap();
+/// assert!(output.starts_with("warning: unused variable\n"));
+/// ```
+pub fn render_to_string<F: Files>(files: F, config: RenderConfig, diagnostics: Vec<Diagnostic<F::FileId>>) -> Result<String>
+    where F::FileId: Debug {
+    let mut output = String::new();
+    let mut writer = FmtWriter::new(&mut output);
+    DiagnosticRenderer::new(&mut writer, DisabledColorConfig, files, config).render(diagnostics)?;
+    drop(writer);
+    Ok(output)
+}
+
+/// Implements [`Display`] by rendering a diagnostic without colors.
+///
+/// This is returned by [`Diagnostic::display`], and uses [`RenderConfig::default`]
+/// unless a different configuration is set using [`DisplayDiagnostic::with_config`].
+/// Rendering errors are reported as [`fmt::Error`].
+///
+/// [`Display`]: fmt::Display
+/// [`Diagnostic::display`]: Diagnostic::display
+/// [`RenderConfig::default`]: RenderConfig::default
+/// [`DisplayDiagnostic::with_config`]: DisplayDiagnostic::with_config
+/// [`fmt::Error`]: fmt::Error
+pub struct DisplayDiagnostic<'a, F: Files> {
+    diagnostic: &'a Diagnostic<F::FileId>,
+    files: &'a F,
+    config: RenderConfig,
+}
+
+impl<'a, F: Files> DisplayDiagnostic<'a, F> {
+    /// Creates a new adapter for rendering a diagnostic.
+    pub fn new(diagnostic: &'a Diagnostic<F::FileId>, files: &'a F) -> Self {
+        DisplayDiagnostic {
+            diagnostic, files, config: RenderConfig::default(),
+        }
+    }
+
+    /// Sets the configuration used for rendering.
+    pub fn with_config(mut self, config: RenderConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl<F: Files> fmt::Display for DisplayDiagnostic<'_, F> where F::FileId: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = FmtWriter::new(f);
+        DiagnosticRenderer::new(&mut writer, DisabledColorConfig, self.files, self.config.clone())
+            .render_slice(std::slice::from_ref(self.diagnostic))
+            .map_err(|_| fmt::Error)
+    }
+}
+
+/// Implements [`WriteColor`] for a [`fmt::Write`], without colors.
+///
+/// Writing bytes that are not valid UTF-8 fails with [`io::ErrorKind::InvalidData`].
+/// Characters split across multiple writes are supported.
+///
+/// [`WriteColor`]: WriteColor
+/// [`fmt::Write`]: fmt::Write
+/// [`io::ErrorKind::InvalidData`]: io::ErrorKind::InvalidData
+#[derive(Debug)]
+pub struct FmtWriter<W> {
+    inner: W,
+    pending: Vec<u8>,
+}
+
+impl<W: fmt::Write> FmtWriter<W> {
+    /// Creates a new writer, writing to `inner`.
+    pub fn new(inner: W) -> Self {
+        FmtWriter {
+            inner, pending: Vec::new(),
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: fmt::Write> io::Write for FmtWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+
+        // Incomplete characters at the end are kept until the rest of them is written
+        let valid_len = match std::str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(err) => {
+                self.pending.clear();
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+            },
+        };
+
+        let pending = std::mem::take(&mut self.pending);
+        let (valid, rest) = pending.split_at(valid_len);
+        let valid = std::str::from_utf8(valid).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        self.inner.write_str(valid).map_err(io::Error::other)?;
+        self.pending.extend_from_slice(rest);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> WriteColor for FmtWriter<W> {
+    fn supports_color(&self) -> bool {
+        false
+    }
+
+    fn set_color(&mut self, _spec: &ColorSpec) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
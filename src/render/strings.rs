@@ -0,0 +1,259 @@
+//! Contains the [`Strings`] trait, which provides the fixed texts written by the renderer,
+//! so that applications can localize their diagnostics output.
+//!
+//! [`Strings`]: Strings
+
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::ops::Range;
+use crate::diagnostic::{AnnotationStyle, Severity, SeverityCounts};
+use crate::file::Location;
+
+/// Provides the fixed texts written by [`DiagnosticRenderer`], like the names of severities
+/// or the line noting suppressed errors.
+///
+/// All methods return the English texts by default, so implementations only have to
+/// override the ones they change. Two implementations are provided:
+/// - [`EnglishStrings`], which uses the default for everything
+/// - [`SeverityNames`], which only changes the names of severities
+///
+/// Messages, labels, notes and note prefixes of diagnostics are passed through [`Strings::message`],
+/// which returns them as they are by default, so they have to be localized by the application.
+/// With the `fluent` feature, `fluent::FluentStrings` resolves them as message identifiers instead.
+///
+/// [`DiagnosticRenderer`]: crate::render::DiagnosticRenderer
+/// [`EnglishStrings`]: EnglishStrings
+/// [`SeverityNames`]: SeverityNames
+/// [`Strings::message`]: Strings::message
+pub trait Strings: Debug + Send + Sync {
+    /// Returns the name of the given severity, like `error` or `warning`.
+    fn severity(&self, severity: Severity) -> Cow<'_, str> {
+        Cow::Owned(severity.to_string())
+    }
+
+    /// Resolves the message, a label, a note, a note prefix or a suggestion message of a diagnostic
+    /// before it is rendered, using the [`Diagnostic::args`] of that diagnostic.
+    ///
+    /// Returning [`Cow::Borrowed`] keeps the text as it is, which is what this does by default.
+    ///
+    /// [`Diagnostic::args`]: crate::diagnostic::Diagnostic::args
+    /// [`Cow::Borrowed`]: Cow::Borrowed
+    fn message<'a>(&self, message: &'a str, _args: &[(String, String)]) -> Cow<'a, str> {
+        Cow::Borrowed(message)
+    }
+
+    /// Returns the line written in place of `count` diagnostics or sub-diagnostics that were
+    /// suppressed, like `... and 3 more`.
+    fn suppressed(&self, count: usize) -> String {
+        format!("... and {} more", count)
+    }
+
+    /// Returns the note added to a diagnostic that was reported `count` times, like
+    /// `(message repeated 3 times)`. See [`Diagnostic::duplicate_count`].
+    ///
+    /// [`Diagnostic::duplicate_count`]: crate::diagnostic::Diagnostic::duplicate_count
+    fn repeated(&self, count: usize) -> String {
+        format!("(message repeated {} times)", count)
+    }
+
+    /// Returns the text written in place of `count` unannotated lines that were skipped,
+    /// like `(3 lines skipped)`.
+    fn lines_skipped(&self, count: usize) -> String {
+        format!("({} line{} skipped)", count, plural(count))
+    }
+
+    /// Returns the line written below a line of source code for `count` annotations that
+    /// were hidden because of [`RenderConfig::max_annotations_per_line`].
+    ///
+    /// [`RenderConfig::max_annotations_per_line`]: crate::render::RenderConfig::max_annotations_per_line
+    fn hidden_annotations(&self, count: usize) -> String {
+        format!("... and {} more annotation{} on this line", count, plural(count))
+    }
+
+    /// Returns the note added for an annotation with a label that was hidden because of
+    /// [`RenderConfig::max_annotations_per_line`], like `1:12: number`. `location` is the
+    /// location of the start of that annotation.
+    ///
+    /// [`RenderConfig::max_annotations_per_line`]: crate::render::RenderConfig::max_annotations_per_line
+    fn hidden_annotation_note(&self, location: Location, label: &str) -> String {
+        format!("{}:{}: {}", location.line_number, location.column_number, label)
+    }
+
+    /// Returns the byte range written after the location in file headers if
+    /// [`RenderConfig::byte_offsets`] is enabled, like `(bytes 41..58)`.
+    ///
+    /// [`RenderConfig::byte_offsets`]: crate::render::RenderConfig::byte_offsets
+    fn byte_range(&self, range: Range<usize>) -> String {
+        format!("(bytes {}..{})", range.start, range.end)
+    }
+
+    /// Returns the description of an annotation written by [`DisplayStyle::Descriptive`],
+    /// like `error in main.rs at line 3, columns 5-9`. The label of the annotation is written
+    /// after it. `start` is the location of the first annotated character, and `end` the
+    /// location of the last one.
+    ///
+    /// [`DisplayStyle::Descriptive`]: crate::render::DisplayStyle::Descriptive
+    fn annotation_description(&self, severity: Severity, style: AnnotationStyle, name: &str, start: Location, end: Location) -> String {
+        let kind = match style {
+            AnnotationStyle::Primary => self.severity(severity),
+            AnnotationStyle::Secondary => Cow::Borrowed("related code"),
+        };
+
+        let position = if start.line_number != end.line_number {
+            format!("line {}, column {} to line {}, column {}", start.line_number, start.column_number,
+                end.line_number, end.column_number)
+        } else if start.column_number != end.column_number {
+            format!("line {}, columns {}-{}", start.line_number, start.column_number, end.column_number)
+        } else {
+            format!("line {}, column {}", start.line_number, start.column_number)
+        };
+
+        format!("{} in {} at {}", kind, name, position)
+    }
+
+    /// Returns the word written before the expected value of a note created with
+    /// [`Note::expected_found`].
+    ///
+    /// [`Note::expected_found`]: crate::diagnostic::Note::expected_found
+    fn expected(&self) -> Cow<'_, str> {
+        Cow::Borrowed("expected")
+    }
+
+    /// Returns the word written before the found value of a note created with
+    /// [`Note::expected_found`].
+    ///
+    /// [`Note::expected_found`]: crate::diagnostic::Note::expected_found
+    fn found(&self) -> Cow<'_, str> {
+        Cow::Borrowed("found")
+    }
+
+    /// Returns the message of the note linking to the documentation at `url`.
+    fn more_information(&self, url: &str) -> String {
+        format!("for more information, see {}", url)
+    }
+
+    /// Returns the summary line written by [`DiagnosticRenderer::render_summary`], and the
+    /// severity it is shown with, or `None` if there is nothing to summarize.
+    ///
+    /// [`DiagnosticRenderer::render_summary`]: crate::render::DiagnosticRenderer::render_summary
+    fn summary(&self, counts: &SeverityCounts) -> Option<(Severity, String)> {
+        counts.summary()
+    }
+
+    /// Returns the counts written after the name of a file in a report rendered by
+    /// [`DiagnosticRenderer::render_report`], like `2 errors, 1 warning`.
+    ///
+    /// [`DiagnosticRenderer::render_report`]: crate::render::DiagnosticRenderer::render_report
+    fn report_counts(&self, counts: &SeverityCounts) -> String {
+        let errors = counts.get(Severity::Error) + counts.get(Severity::Bug);
+        let warnings = counts.get(Severity::Warning);
+        let mut parts = Vec::new();
+
+        if errors > 0 {
+            parts.push(format!("{} error{}", errors, plural(errors)));
+        }
+
+        if warnings > 0 {
+            parts.push(format!("{} warning{}", warnings, plural(warnings)));
+        }
+
+        if parts.is_empty() {
+            parts.push(format!("{} diagnostic{}", counts.total(), plural(counts.total())));
+        }
+
+        parts.join(", ")
+    }
+
+    /// Returns the name used in a report in place of a file name for diagnostics without
+    /// a primary annotation.
+    fn report_no_file(&self) -> Cow<'_, str> {
+        Cow::Borrowed("(no file)")
+    }
+
+    /// Returns the headings of the file, error and warning columns of the table at the end
+    /// of a report.
+    fn report_columns(&self) -> [Cow<'_, str>; 3] {
+        [Cow::Borrowed("file"), Cow::Borrowed("errors"), Cow::Borrowed("warnings")]
+    }
+
+    /// Returns the name of the row with the totals in the table at the end of a report.
+    fn report_total(&self) -> Cow<'_, str> {
+        Cow::Borrowed("total")
+    }
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
+
+/// The default [`Strings`], in English.
+///
+/// [`Strings`]: Strings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EnglishStrings;
+
+impl Strings for EnglishStrings {}
+
+/// The names shown for every [`Severity`].
+///
+/// The default names are the ones of the [`Display`] implementation of [`Severity`],
+/// like `error` and `warning`. The severities keep their order, so changing their names
+/// doesn't affect filtering or counting them.
+///
+/// [`Severity`]: Severity
+/// [`Display`]: std::fmt::Display
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default, deny_unknown_fields))]
+pub struct SeverityNames {
+    /// The name of [`Severity::Bug`].
+    ///
+    /// [`Severity::Bug`]: Severity::Bug
+    pub bug: String,
+    /// The name of [`Severity::Error`].
+    ///
+    /// [`Severity::Error`]: Severity::Error
+    pub error: String,
+    /// The name of [`Severity::Warning`].
+    ///
+    /// [`Severity::Warning`]: Severity::Warning
+    pub warning: String,
+    /// The name of [`Severity::Note`].
+    ///
+    /// [`Severity::Note`]: Severity::Note
+    pub note: String,
+    /// The name of [`Severity::Help`].
+    ///
+    /// [`Severity::Help`]: Severity::Help
+    pub help: String,
+}
+
+impl SeverityNames {
+    /// Returns the name of the given severity.
+    pub fn get(&self, severity: Severity) -> &str {
+        match severity {
+            Severity::Bug => &self.bug,
+            Severity::Error => &self.error,
+            Severity::Warning => &self.warning,
+            Severity::Note => &self.note,
+            Severity::Help => &self.help,
+        }
+    }
+}
+
+impl Default for SeverityNames {
+    fn default() -> Self {
+        SeverityNames {
+            bug: Severity::Bug.to_string(),
+            error: Severity::Error.to_string(),
+            warning: Severity::Warning.to_string(),
+            note: Severity::Note.to_string(),
+            help: Severity::Help.to_string(),
+        }
+    }
+}
+
+impl Strings for SeverityNames {
+    fn severity(&self, severity: Severity) -> Cow<'_, str> {
+        Cow::Borrowed(self.get(severity))
+    }
+}
@@ -0,0 +1,360 @@
+//! Contains the [`WriteColor`] trait that diagnostics are rendered to, and the
+//! [`ColorSpec`] and [`Color`] types used for setting colors through it.
+//!
+//! These mirror the types of the same name in [`termcolor`]. With the `termcolor` feature
+//! (enabled by default), every [`termcolor::WriteColor`] implements [`WriteColor`], so
+//! [`termcolor::StandardStream`] and [`termcolor::Buffer`] can be rendered to directly.
+//! Without it, this crate doesn't depend on `termcolor`, and output can be written through
+//! `anstyle::AnstyleWriter` with the `anstyle` feature, or through a custom [`WriteColor`].
+//!
+//! [`WriteColor`]: WriteColor
+//! [`ColorSpec`]: ColorSpec
+//! [`Color`]: Color
+//! [`termcolor`]: https://docs.rs/termcolor
+//! [`termcolor::WriteColor`]: https://docs.rs/termcolor/latest/termcolor/trait.WriteColor.html
+//! [`termcolor::StandardStream`]: https://docs.rs/termcolor/latest/termcolor/struct.StandardStream.html
+//! [`termcolor::Buffer`]: https://docs.rs/termcolor/latest/termcolor/struct.Buffer.html
+
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::str::FromStr;
+
+/// A writer that supports setting colors and other formatting.
+///
+/// Only [`Self::supports_color`], [`Self::set_color`] and [`Self::reset`] have to be implemented.
+/// Hyperlinks are not supported by default.
+///
+/// [`Self::supports_color`]: Self::supports_color
+/// [`Self::set_color`]: Self::set_color
+/// [`Self::reset`]: Self::reset
+pub trait WriteColor: io::Write {
+    /// Returns whether this writer supports colors.
+    fn supports_color(&self) -> bool;
+
+    /// Sets the formatting of the text written after this call.
+    ///
+    /// If [`ColorSpec::reset`] is `true`, all previous formatting is reset first.
+    ///
+    /// [`ColorSpec::reset`]: ColorSpec::reset
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()>;
+
+    /// Resets all formatting.
+    fn reset(&mut self) -> io::Result<()>;
+
+    /// Returns whether this writer supports hyperlinks.
+    fn supports_hyperlinks(&self) -> bool {
+        false
+    }
+
+    /// Opens or closes a hyperlink around the text written after this call.
+    ///
+    /// This does nothing by default.
+    fn set_hyperlink(&mut self, _link: &HyperlinkSpec) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "termcolor")]
+impl<W: termcolor::WriteColor + ?Sized> WriteColor for W {
+    fn supports_color(&self) -> bool {
+        termcolor::WriteColor::supports_color(self)
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        termcolor::WriteColor::set_color(self, &spec.to_termcolor())
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        termcolor::WriteColor::reset(self)
+    }
+
+    fn supports_hyperlinks(&self) -> bool {
+        termcolor::WriteColor::supports_hyperlinks(self)
+    }
+
+    fn set_hyperlink(&mut self, link: &HyperlinkSpec) -> io::Result<()> {
+        termcolor::WriteColor::set_hyperlink(self, &match link.uri() {
+            Some(uri) => termcolor::HyperlinkSpec::open(uri),
+            None => termcolor::HyperlinkSpec::close(),
+        })
+    }
+}
+
+/// A terminal color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    Black,
+    Blue,
+    Green,
+    Red,
+    Cyan,
+    Magenta,
+    Yellow,
+    White,
+    /// A color of the 256-color palette of ANSI terminals.
+    Ansi256(u8),
+    /// A 24-bit RGB color.
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    #[cfg(feature = "termcolor")]
+    fn to_termcolor(self) -> termcolor::Color {
+        match self {
+            Color::Black => termcolor::Color::Black,
+            Color::Blue => termcolor::Color::Blue,
+            Color::Green => termcolor::Color::Green,
+            Color::Red => termcolor::Color::Red,
+            Color::Cyan => termcolor::Color::Cyan,
+            Color::Magenta => termcolor::Color::Magenta,
+            Color::Yellow => termcolor::Color::Yellow,
+            Color::White => termcolor::Color::White,
+            Color::Ansi256(index) => termcolor::Color::Ansi256(index),
+            Color::Rgb(r, g, b) => termcolor::Color::Rgb(r, g, b),
+        }
+    }
+}
+
+/// The error returned when parsing a [`Color`] fails.
+///
+/// [`Color`]: Color
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseColorError {
+    given: String,
+}
+
+impl Display for ParseColorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unrecognized color '{}', expected one of black, blue, green, red, cyan, magenta, yellow, white, \
+            an ANSI 256-color code like '33', or an RGB triple like '255,128,0'", self.given)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parses a color name like `red`, an ANSI 256-color code like `33` or `0x21`,
+    /// or an RGB triple like `255,128,0` or `0xff,0x80,0x00`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn component(s: &str) -> Option<u8> {
+            let s = s.trim();
+
+            match s.strip_prefix("0x") {
+                Some(hex) => u8::from_str_radix(hex, 16).ok(),
+                None => s.parse().ok(),
+            }
+        }
+
+        let color = match s.to_lowercase().as_str() {
+            "black" => Some(Color::Black),
+            "blue" => Some(Color::Blue),
+            "green" => Some(Color::Green),
+            "red" => Some(Color::Red),
+            "cyan" => Some(Color::Cyan),
+            "magenta" => Some(Color::Magenta),
+            "yellow" => Some(Color::Yellow),
+            "white" => Some(Color::White),
+            other => match other.split(',').collect::<Vec<_>>()[..] {
+                [index] => component(index).map(Color::Ansi256),
+                [r, g, b] => component(r).zip(component(g)).zip(component(b)).map(|((r, g), b)| Color::Rgb(r, g, b)),
+                _ => None,
+            },
+        };
+
+        color.ok_or_else(|| ParseColorError { given: s.to_owned() })
+    }
+}
+
+/// The formatting set through [`WriteColor::set_color`].
+///
+/// [`WriteColor::set_color`]: WriteColor::set_color
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorSpec {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+    intense: bool,
+    italic: bool,
+    underline: bool,
+    dimmed: bool,
+    strikethrough: bool,
+    reset: bool,
+}
+
+impl Default for ColorSpec {
+    fn default() -> Self {
+        ColorSpec {
+            fg: None, bg: None,
+            bold: false, intense: false, italic: false, underline: false, dimmed: false, strikethrough: false,
+            reset: true,
+        }
+    }
+}
+
+impl ColorSpec {
+    /// Creates a color spec without any formatting, which resets previous formatting.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the foreground color.
+    pub fn fg(&self) -> Option<&Color> {
+        self.fg.as_ref()
+    }
+
+    /// Sets the foreground color.
+    pub fn set_fg(&mut self, color: Option<Color>) -> &mut Self {
+        self.fg = color;
+        self
+    }
+
+    /// Returns the background color.
+    pub fn bg(&self) -> Option<&Color> {
+        self.bg.as_ref()
+    }
+
+    /// Sets the background color.
+    pub fn set_bg(&mut self, color: Option<Color>) -> &mut Self {
+        self.bg = color;
+        self
+    }
+
+    /// Returns whether the text is bold.
+    pub fn bold(&self) -> bool {
+        self.bold
+    }
+
+    /// Sets whether the text is bold.
+    pub fn set_bold(&mut self, yes: bool) -> &mut Self {
+        self.bold = yes;
+        self
+    }
+
+    /// Returns whether the colors are the intense variants.
+    pub fn intense(&self) -> bool {
+        self.intense
+    }
+
+    /// Sets whether the colors are the intense variants.
+    pub fn set_intense(&mut self, yes: bool) -> &mut Self {
+        self.intense = yes;
+        self
+    }
+
+    /// Returns whether the text is italic.
+    pub fn italic(&self) -> bool {
+        self.italic
+    }
+
+    /// Sets whether the text is italic.
+    pub fn set_italic(&mut self, yes: bool) -> &mut Self {
+        self.italic = yes;
+        self
+    }
+
+    /// Returns whether the text is underlined.
+    pub fn underline(&self) -> bool {
+        self.underline
+    }
+
+    /// Sets whether the text is underlined.
+    pub fn set_underline(&mut self, yes: bool) -> &mut Self {
+        self.underline = yes;
+        self
+    }
+
+    /// Returns whether the text is dimmed.
+    pub fn dimmed(&self) -> bool {
+        self.dimmed
+    }
+
+    /// Sets whether the text is dimmed.
+    pub fn set_dimmed(&mut self, yes: bool) -> &mut Self {
+        self.dimmed = yes;
+        self
+    }
+
+    /// Returns whether the text is struck through.
+    pub fn strikethrough(&self) -> bool {
+        self.strikethrough
+    }
+
+    /// Sets whether the text is struck through.
+    pub fn set_strikethrough(&mut self, yes: bool) -> &mut Self {
+        self.strikethrough = yes;
+        self
+    }
+
+    /// Returns whether previous formatting is reset before this one is applied.
+    /// This is `true` by default.
+    pub fn reset(&self) -> bool {
+        self.reset
+    }
+
+    /// Sets whether previous formatting is reset before this one is applied.
+    pub fn set_reset(&mut self, yes: bool) -> &mut Self {
+        self.reset = yes;
+        self
+    }
+
+    /// Returns whether this spec doesn't set any formatting.
+    pub fn is_none(&self) -> bool {
+        self.fg.is_none() && self.bg.is_none() && !self.bold && !self.intense && !self.italic
+            && !self.underline && !self.dimmed && !self.strikethrough
+    }
+
+    #[cfg(feature = "termcolor")]
+    fn to_termcolor(&self) -> termcolor::ColorSpec {
+        let mut spec = termcolor::ColorSpec::new();
+        spec.set_fg(self.fg.map(Color::to_termcolor)).set_bg(self.bg.map(Color::to_termcolor))
+            .set_bold(self.bold).set_intense(self.intense).set_italic(self.italic).set_underline(self.underline)
+            .set_dimmed(self.dimmed).set_strikethrough(self.strikethrough).set_reset(self.reset);
+        spec
+    }
+}
+
+/// A hyperlink set through [`WriteColor::set_hyperlink`].
+///
+/// [`WriteColor::set_hyperlink`]: WriteColor::set_hyperlink
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HyperlinkSpec<'a> {
+    uri: Option<&'a [u8]>,
+}
+
+impl<'a> HyperlinkSpec<'a> {
+    /// Creates a spec that opens a hyperlink to `uri`.
+    pub fn open(uri: &'a [u8]) -> Self {
+        HyperlinkSpec { uri: Some(uri) }
+    }
+
+    /// Creates a spec that closes the current hyperlink.
+    pub fn close() -> Self {
+        HyperlinkSpec { uri: None }
+    }
+
+    /// Returns the URI of the hyperlink, or `None` if this closes a hyperlink.
+    pub fn uri(&self) -> Option<&'a [u8]> {
+        self.uri
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!("red".parse(), Ok(Color::Red));
+        assert_eq!("Magenta".parse(), Ok(Color::Magenta));
+        assert_eq!("33".parse(), Ok(Color::Ansi256(33)));
+        assert_eq!("0x21".parse(), Ok(Color::Ansi256(33)));
+        assert_eq!("255,128,0".parse(), Ok(Color::Rgb(255, 128, 0)));
+        assert_eq!("0xff,0x80,0x00".parse(), Ok(Color::Rgb(255, 128, 0)));
+        assert!("256".parse::<Color>().is_err());
+        assert!("orange".parse::<Color>().is_err());
+        assert!("1,2".parse::<Color>().is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! Contains a writer that captures rendered diagnostics as an SVG image.
+//!
+//! This can be used to embed rendered diagnostics in documentation or on websites.
+//!
+//! # Example
+//! ```rust
+//! use diagnostic_render::diagnostic::{Annotation, Diagnostic};
+//! use diagnostic_render::file::SimpleFile;
+//! use diagnostic_render::render::{DiagnosticRenderer, RenderConfig};
+//! use diagnostic_render::render::color::DefaultColorConfig;
+//! use diagnostic_render::render::svg::SvgWriter;
+//!
+//! let file = SimpleFile::new("main.test", "let main = 23;\n");
+//! let diagnostic = Diagnostic::error()
+//!     .with_message("Mismatched types")
+//!     .with_annotation(Annotation::primary((), 11..13).with_label("expected `String`"));
+//!
+//! let mut svg = SvgWriter::new();
+//! DiagnosticRenderer::new(&mut svg, DefaultColorConfig, file, RenderConfig::default())
+//!     .render(vec![diagnostic]).unwrap();
+//! let image = svg.finish();
+//!
+//! assert!(image.starts_with("<svg"));
+//! ```
+
+use std::fmt::Write as _;
+use std::io::Write;
+use crate::render::style::{Color, ColorSpec, WriteColor};
+
+/// A [`WriteColor`] implementation that records the written text and its colors,
+/// and turns them into an SVG image when [`finish`] is called.
+///
+/// [`WriteColor`]: WriteColor
+/// [`finish`]: SvgWriter::finish
+#[derive(Clone, Debug)]
+pub struct SvgWriter {
+    /// The background color of the image, as a CSS color.
+    pub background: String,
+    /// The color used for text without formatting, as a CSS color.
+    pub foreground: String,
+    /// The font size in pixels.
+    pub font_size: f32,
+    spans: Vec<(ColorSpec, Vec<u8>)>,
+    spec: ColorSpec,
+}
+
+impl SvgWriter {
+    /// Creates a new SVG writer with a dark background.
+    pub fn new() -> Self {
+        SvgWriter {
+            background: String::from("#1e1e1e"),
+            foreground: String::from("#d4d4d4"),
+            font_size: 14.0,
+            spans: Vec::new(),
+            spec: ColorSpec::new(),
+        }
+    }
+
+    /// Returns the SVG image containing everything written so far.
+    pub fn finish(&self) -> String {
+        let mut lines: Vec<Vec<(&ColorSpec, String)>> = vec![Vec::new()];
+
+        for (spec, bytes) in self.spans.iter() {
+            let text = String::from_utf8_lossy(bytes);
+
+            for (i, part) in text.split('\n').enumerate() {
+                if i > 0 {
+                    lines.push(Vec::new());
+                }
+
+                if !part.is_empty() {
+                    lines.last_mut().expect("no lines despite adding one before").push((spec, part.to_owned()));
+                }
+            }
+        }
+
+        // Text ending with a line break doesn't have another line after it
+        if lines.len() > 1 && lines.last().map(|line| line.is_empty()).unwrap_or(false) {
+            lines.pop();
+        }
+
+        let line_height = self.font_size * 1.25;
+        let char_width = self.font_size * 0.6;
+        let padding = self.font_size;
+        let max_chars = lines.iter().map(|line| line.iter().map(|(_, text)| text.chars().count()).sum::<usize>()).max().unwrap_or(0);
+        let width = (max_chars as f32 * char_width + 2.0 * padding).ceil();
+        let height = (lines.len() as f32 * line_height + 2.0 * padding).ceil();
+
+        let mut svg = String::new();
+        // Writing to a String can't fail
+        let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#, width, height, width, height);
+        let _ = writeln!(svg, r#"  <rect width="100%" height="100%" fill="{}"/>"#, escape(&self.background));
+        let _ = writeln!(svg, r#"  <text font-family="monospace" font-size="{}" fill="{}" xml:space="preserve">"#, self.font_size, escape(&self.foreground));
+
+        for (i, line) in lines.iter().enumerate() {
+            let _ = write!(svg, r#"    <tspan x="{}" y="{}">"#, padding, padding + i as f32 * line_height + self.font_size);
+
+            for (spec, text) in line.iter() {
+                let attributes = attributes(spec);
+
+                if attributes.is_empty() {
+                    svg.push_str(&escape(text));
+                } else {
+                    let _ = write!(svg, "<tspan{}>{}</tspan>", attributes, escape(text));
+                }
+            }
+
+            svg.push_str("</tspan>\n");
+        }
+
+        svg.push_str("  </text>\n</svg>\n");
+        svg
+    }
+}
+
+impl Default for SvgWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for SvgWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.spans.last_mut() {
+            Some((spec, bytes)) if *spec == self.spec => bytes.extend_from_slice(buf),
+            _ => self.spans.push((self.spec.clone(), buf.to_vec())),
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl WriteColor for SvgWriter {
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> std::io::Result<()> {
+        self.spec = spec.clone();
+        Ok(())
+    }
+
+    fn reset(&mut self) -> std::io::Result<()> {
+        self.spec = ColorSpec::new();
+        Ok(())
+    }
+}
+
+/// Returns the SVG attributes for the formatting in a color spec, with a leading space.
+fn attributes(spec: &ColorSpec) -> String {
+    let mut attributes = String::new();
+
+    if let Some(color) = spec.fg() {
+        let _ = write!(attributes, r#" fill="{}""#, css_color(color, spec.intense()));
+    }
+
+    if spec.bold() {
+        attributes.push_str(r#" font-weight="bold""#);
+    }
+
+    if spec.italic() {
+        attributes.push_str(r#" font-style="italic""#);
+    }
+
+    if spec.underline() {
+        attributes.push_str(r#" text-decoration="underline""#);
+    }
+
+    attributes
+}
+
+/// Converts a terminal color to a CSS color.
+pub(super) fn css_color(color: &Color, intense: bool) -> String {
+    let (normal, bright) = match color {
+        Color::Black => ("#000000", "#666666"),
+        Color::Blue => ("#2472c8", "#3b8eea"),
+        Color::Green => ("#0dbc79", "#23d18b"),
+        Color::Red => ("#cd3131", "#f14c4c"),
+        Color::Cyan => ("#11a8cd", "#29b8db"),
+        Color::Magenta => ("#bc3fbc", "#d670d6"),
+        Color::Yellow => ("#e5e510", "#f5f543"),
+        Color::White => ("#e5e5e5", "#ffffff"),
+        Color::Rgb(r, g, b) => return format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Ansi256(n) => return ansi256_color(*n),
+    };
+
+    String::from(if intense { bright } else { normal })
+}
+
+/// Converts a color of the 256-color palette to a CSS color.
+fn ansi256_color(n: u8) -> String {
+    const BASIC: [Color; 8] = [Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan, Color::White];
+
+    match n {
+        0..=7 => css_color(&BASIC[n as usize], false),
+        8..=15 => css_color(&BASIC[n as usize - 8], true),
+        16..=231 => {
+            let n = n - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            format!("#{:02x}{:02x}{:02x}", level(n / 36), level((n / 6) % 6), level(n % 6))
+        },
+        232..=255 => {
+            let v = 8 + (n - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", v, v, v)
+        },
+    }
+}
+
+/// Escapes text for use in SVG text and attribute values.
+pub(super) fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
@@ -0,0 +1,148 @@
+use termcolor::Buffer;
+use crate::render::color::{DisabledColorConfig, LightColorConfig};
+use crate::render::style::Color;
+use super::*;
+
+#[test]
+fn test_hyperlink_1() {
+    let mut buf = Buffer::ansi();
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DisabledColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, hyperlink_format: Some(String::from("vscode://file/{path}:{line}:{column}")), ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))
+    ]).unwrap();
+
+    let buf = buf.into_inner();
+    let result = String::from_utf8_lossy(&buf).replace('\x1b', "ESC");
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> ESC]8;;vscode://file/main.test:1:5ESC\main.test:1:5ESC]8;;ESC\
+    1 | let main = 23;
+      |     ^^^^
+    "###);
+}
+
+#[test]
+fn test_light_colors_1() {
+    let mut buf = Buffer::ansi();
+    let mut renderer = DiagnosticRenderer::new(&mut buf, LightColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Warning)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)
+                .with_label("primary"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 11..13)
+                .with_label("secondary"))
+    ]).unwrap();
+
+    let buf = buf.into_inner();
+    let result = String::from_utf8_lossy(&buf).replace('\x1b', "ESC");
+
+    insta::assert_snapshot!(result, @r###"
+    ESC[0mESC[1mESC[38;5;130mwarningESC[0mESC[1m: Test message
+    ESC[0m ESC[0mESC[1mESC[34m-->ESC[0m ESC[0mmain.test:1:5ESC[0m
+    ESC[0mESC[1mESC[34m1ESC[0mESC[1mESC[34m |ESC[0m ESC[0mlet main = 23;ESC[0m
+     ESC[0mESC[1mESC[34m |ESC[0m     ESC[0mESC[1mESC[38;5;130m^ESC[0mESC[0mESC[1mESC[38;5;130m^^ESC[0mESC[0mESC[1mESC[38;5;130m^ESC[0m   ESC[0mESC[34m-ESC[0mESC[0mESC[34m-ESC[0m ESC[0mESC[34msecondaryESC[0m
+     ESC[0mESC[1mESC[34m |ESC[0m     ESC[0mESC[1mESC[38;5;130m|ESC[0m
+     ESC[0mESC[1mESC[34m |ESC[0m     ESC[0mESC[1mESC[38;5;130mprimaryESC[0m
+    "###);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_render_parallel_1() {
+    let diagnostics = (0..50).map(|i| Diagnostic::new(if i % 3 == 0 { Severity::Warning } else { Severity::Error })
+        .with_message(format!("Message {}", i))
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8).with_label(format!("label {}", i)))
+        .with_note(Note::note("Some note"))).collect::<Vec<_>>();
+
+    let render = |parallel: bool, display_style: DisplayStyle| {
+        let mut buf = Buffer::ansi();
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            SimpleFile::new("main.test", "let main = 23;\n"),
+            RenderConfig { max_errors: Some(20), display_style, ..Default::default() });
+
+        if parallel {
+            renderer.render_parallel(diagnostics.clone()).unwrap();
+            renderer.render_parallel(diagnostics.clone()).unwrap();
+        } else {
+            renderer.render(diagnostics.clone()).unwrap();
+            renderer.render(diagnostics.clone()).unwrap();
+        }
+
+        assert_eq!(renderer.counts().get(Severity::Error), 66);
+        String::from_utf8_lossy(&buf.into_inner()).into_owned()
+    };
+
+    assert_eq!(render(true, DisplayStyle::Rich), render(false, DisplayStyle::Rich));
+    assert_eq!(render(true, DisplayStyle::Short), render(false, DisplayStyle::Short));
+}
+
+#[test]
+fn test_cycle_annotation_colors_1() {
+    fn render(config: RenderConfig) -> String {
+        let mut buf = Buffer::ansi();
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            SimpleFile::new("main.test", "let main = other(23,\n    42);\n"), config);
+        renderer.render(vec![
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..28)
+                    .with_label("call"))
+                .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 11..16)
+                    .with_label("function"))
+                .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 17..19)
+                    .with_label("argument")),
+        ]).unwrap();
+
+        String::from_utf8_lossy(&buf.into_inner()).into_owned()
+    }
+
+    let default = render(RenderConfig::default());
+    let cycled = render(RenderConfig::default().cycle_annotation_colors(true));
+
+    // Annotations are indexed in the order they start in, so the call is magenta and bold,
+    // the function name cyan, and the argument green
+    assert!(cycled.contains("\u{1b}[0m\u{1b}[1m\u{1b}[35m"));
+    assert!(cycled.contains("\u{1b}[0m\u{1b}[36mfunction"));
+    assert!(cycled.contains("\u{1b}[0m\u{1b}[32margument"));
+    assert!(!default.contains("\u{1b}[35m") && !default.contains("\u{1b}[36m"));
+
+    // Only the colors differ
+    let strip = |text: &str| text.split('\u{1b}').enumerate()
+        .map(|(i, part)| if i == 0 { part } else { part.split_once('m').map_or(part, |(_, rest)| rest) })
+        .collect::<String>();
+    assert_eq!(strip(&cycled), strip(&default));
+}
+
+#[test]
+fn test_annotation_color_1() {
+    let mut buf = Buffer::ansi();
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main: String = 23;\n"), RenderConfig::default().cycle_annotation_colors(true));
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("mismatched types")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 19..21)
+                .with_label("found integer")
+                .with_color(TextStyle::fg(Color::Red).bold()))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 10..16)
+                .with_label("expected `String`")
+                .with_color(TextStyle::fg(Color::Green))),
+    ]).unwrap();
+
+    let result = String::from_utf8_lossy(&buf.into_inner()).into_owned();
+
+    // Explicit colors take precedence over cycled ones
+    assert!(result.contains("\u{1b}[0m\u{1b}[32m----"));
+    assert!(result.contains("\u{1b}[0m\u{1b}[32mexpected `String`"));
+    assert!(result.contains("\u{1b}[0m\u{1b}[1m\u{1b}[31m^"));
+    assert!(result.contains("\u{1b}[0m\u{1b}[1m\u{1b}[31mfound integer"));
+    assert!(!result.contains("\u{1b}[35m") && !result.contains("\u{1b}[36m"));
+}
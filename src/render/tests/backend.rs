@@ -0,0 +1,84 @@
+use std::fmt::Write;
+use crate::file::Location;
+use crate::render::backend::{render_with_backend, RenderBackend};
+use crate::render::data::{ConnectingSinglelineAnnotationData, LabelAnnotationLineData};
+use crate::render::layout::RenderedLine;
+use super::*;
+
+/// Records the calls of every method as a line of text.
+#[derive(Default)]
+struct Recorder {
+    events: String,
+}
+
+impl RenderBackend<()> for Recorder {
+    fn start_diagnostic(&mut self, diagnostic: &Diagnostic<()>) -> Result {
+        writeln!(self.events, "start {:?}: {}", diagnostic.severity, diagnostic.message).unwrap();
+        Ok(())
+    }
+
+    fn file(&mut self, _file_id: &(), name: &str, location: Location) -> Result {
+        writeln!(self.events, "file {}:{}:{}", name, location.line_number, location.column_number).unwrap();
+        Ok(())
+    }
+
+    fn source_line(&mut self, line: &RenderedLine<'_, ()>, text: &str) -> Result {
+        writeln!(self.events, "line {}: {}", line.line_number, text).unwrap();
+        Ok(())
+    }
+
+    fn underline(&mut self, data: &ConnectingSinglelineAnnotationData) -> Result {
+        writeln!(self.events, "underline {:?} {}..={}", data.style, data.start_column_index, data.end_column_index).unwrap();
+        Ok(())
+    }
+
+    fn label(&mut self, data: &LabelAnnotationLineData<'_>) -> Result {
+        writeln!(self.events, "label {:?} at {}: {}", data.style, data.location.column_index, data.label).unwrap();
+        Ok(())
+    }
+
+    fn note(&mut self, note: &Note) -> Result {
+        writeln!(self.events, "note {:?}: {}", note.severity, note.message).unwrap();
+        Ok(())
+    }
+
+    fn end_diagnostic(&mut self, diagnostic: &Diagnostic<()>) -> Result {
+        writeln!(self.events, "end {:?}", diagnostic.severity).unwrap();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_recorder_1() {
+    let file = SimpleFile::new("main.test", "let main = 23;\nsomething += 3.0;\n");
+    let diagnostics = vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 15..24)
+                .with_label("primary"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
+                .with_label("secondary"))
+            .with_note(Note::note("Some note"))
+            .with_sub_diagnostic(Diagnostic::new(Severity::Help)
+                .with_message("Sub-diagnostic")),
+        Diagnostic::new(Severity::Help).with_message("Filtered out"),
+    ];
+
+    let mut recorder = Recorder::default();
+    render_with_backend(&mut recorder, &file, &RenderConfig { min_severity: Severity::Note, ..Default::default() }, &diagnostics).unwrap();
+
+    insta::assert_snapshot!(recorder.events, @r###"
+    start Error: Test message
+    file main.test:2:1
+    line 1: let main = 23;
+    underline Secondary 4..=7
+    label Secondary at 9: secondary
+    line 2: something += 3.0;
+    underline Primary 0..=8
+    label Primary at 10: primary
+    note Note: Some note
+    start Help: Sub-diagnostic
+    end Help
+    end Error
+    "###);
+}
@@ -0,0 +1,188 @@
+use crate::render::chars::CharSet;
+use super::*;
+
+#[test]
+fn test_unicode_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Mismatched types")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)
+            .with_label("number"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
+            .with_label("identifier"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, charset: CharSet::unicode(), ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Mismatched types
+     --> test_file.test:1:12
+    1 │ let main = 23;
+      │     ┄┄┄┄   ━━ number
+      │     │
+      │     identifier
+    "###);
+}
+
+#[test]
+fn test_unicode_multiline_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 28..38)
+            .with_label("something"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 11..24)
+            .with_label("something else"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, charset: CharSet::unicode(), ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Some message
+     --> test_file.test:2:14
+    1 │   let main = 23;
+      │ ╭────────────┴
+    2 │ │ something += 3.0;
+      │ ╰─────────┴    ━
+      │ ╭─────────│────╯
+      │ │         │
+      │ │         something else
+    3 │ │ print(example_source);
+      │ ╰─────┴ something
+    "###);
+}
+
+#[test]
+fn test_unicode_multiline_2() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = [other(*23, 42)];\nsomething += 3.0;\nprint(x);\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..50)
+            .with_label("outer"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 12..36)
+            .with_label("inner"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, charset: CharSet::unicode(), ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Some message
+     --> test_file.test:1:5
+    1 │     let main = [other(*23, 42)];
+      │ ╭───────┴       ┄
+      │ │ ╭─────────────╯
+    2 │ │ │ something += 3.0;
+      │ │ ╰───────┴ inner
+    3 │ │   print(x);
+      │ ╰─────┴ outer
+    "###);
+}
+
+#[test]
+fn test_severity_underlines_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\n");
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig {
+            surrounding_lines: 0,
+            charset: CharSet::ascii().with_severity_underline(Severity::Warning, '~', '~').with_severity_underline(Severity::Note, '.', '.'),
+            ..Default::default()
+        });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Warning)
+            .with_message("Unused variable")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)
+                .with_label("never used"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 11..13)
+                .with_label("assigned here")),
+        Diagnostic::new(Severity::Note)
+            .with_message("Variable declared here")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)),
+        Diagnostic::new(Severity::Error)
+            .with_message("Some error")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    warning: Unused variable
+     --> test_file.test:1:5
+    1 | let main = 23;
+      |     ~~~~   ~~ assigned here
+      |     |
+      |     never used
+
+    note: Variable declared here
+     --> test_file.test:1:5
+    1 | let main = 23;
+      |     ....
+
+    error: Some error
+     --> test_file.test:1:12
+    1 | let main = 23;
+      |            ^^
+    "###);
+}
+
+#[test]
+fn test_separators_1() {
+    let mut files = SimpleFiles::new();
+    let main = files.add("main.test", "let main = 23;\n\n\nsomething += 3.0;\n");
+    let other = files.add("other.test", "let other = 23;\n");
+
+    let diagnostic = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 4..8)
+            .with_label("here"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, main, 17..26)
+            .with_label("and here"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, other, 4..9));
+    let charset = CharSet {
+        file_arrow: String::from("->"),
+        file_continuation: String::from("::"),
+        skipped_lines: String::from("~"),
+        ..CharSet::ascii()
+    };
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, &files,
+        RenderConfig { surrounding_lines: 0, charset: charset.clone(), ..Default::default() });
+    renderer.render(vec![diagnostic.clone()]).unwrap();
+
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, &files,
+        RenderConfig { surrounding_lines: 0, charset, ..Default::default() }.file_headers(false));
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Some message
+     -> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^ here
+     ~
+    4 | something += 3.0;
+      | --------- and here
+     :: other.test:1:5
+    1 | let other = 23;
+      |     -----
+    error: Some message
+    1 | let main = 23;
+      |     ^^^^ here
+     ~
+    4 | something += 3.0;
+      | --------- and here
+    1 | let other = 23;
+      |     -----
+    "###);
+}
@@ -0,0 +1,141 @@
+use crate::render::emitter::{EmitMode, CollectingEmitter};
+use crate::render::style::{ColorSpec, WriteColor};
+use super::*;
+
+#[test]
+fn test_buffered_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\n");
+    let renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    let mut emitter = CollectingEmitter::new(renderer, EmitMode::Buffered);
+
+    emitter.emit(Diagnostic::new(Severity::Warning)
+        .with_message("Unused variable")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 15..24))).unwrap();
+    emitter.emit(Diagnostic::new(Severity::Error)
+        .with_message("Without annotations")).unwrap();
+    emitter.emit(Diagnostic::new(Severity::Error)
+        .with_message("Mismatched types")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))).unwrap();
+
+    assert!(matches!(emitter.abort_if_errors(), Err(RenderError::Aborted { errors: 2 })));
+
+    let counts = emitter.counts();
+    assert_eq!(counts.get(Severity::Error), 2);
+    assert_eq!(counts.get(Severity::Warning), 1);
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Mismatched types
+     --> test_file.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+
+    warning: Unused variable
+     --> test_file.test:2:1
+    2 | something += 3.0;
+      | ^^^^^^^^^
+
+    error: Without annotations
+
+    error: aborting due to 2 previous errors; 1 warning emitted
+    "###);
+}
+
+#[test]
+fn test_streaming_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\n");
+    let renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    let mut emitter = CollectingEmitter::new(renderer, EmitMode::Streaming);
+
+    emitter.emit(Diagnostic::new(Severity::Warning)
+        .with_message("Unused variable")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 15..24))).unwrap();
+    emitter.abort_if_errors().unwrap();
+    emitter.emit(Diagnostic::new(Severity::Note)
+        .with_message("Note message")).unwrap();
+
+    let counts = emitter.finish().unwrap();
+    assert!(!counts.has_errors());
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    warning: Unused variable
+     --> test_file.test:2:1
+    2 | something += 3.0;
+      | ^^^^^^^^^
+
+    note: Note message
+
+    warning: 1 warning emitted
+    "###);
+}
+
+#[test]
+fn test_streaming_2() {
+    /// Counts how often the output is flushed.
+    struct FlushCounter {
+        buf: FmtWriter<String>,
+        flushes: usize,
+    }
+
+    impl std::io::Write for FlushCounter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.buf.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    impl WriteColor for FlushCounter {
+        fn supports_color(&self) -> bool {
+            false
+        }
+
+        fn set_color(&mut self, _spec: &ColorSpec) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn reset(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut output = FlushCounter { buf: FmtWriter::new(String::new()), flushes: 0 };
+    let file = SimpleFile::new("test_file.test", "let main = 23;\n");
+    let renderer = DiagnosticRenderer::new(&mut output, DefaultColorConfig,
+        file, RenderConfig { max_errors: Some(1), ..Default::default() });
+    let mut emitter = CollectingEmitter::new(renderer, EmitMode::Streaming);
+
+    for i in 1..=3 {
+        emitter.emit(Diagnostic::new(Severity::Error)
+            .with_message(format!("Error {}", i))).unwrap();
+    }
+
+    emitter.emit(Diagnostic::new(Severity::Warning)
+        .with_message("Warning message")).unwrap();
+
+    let counts = emitter.finish().unwrap();
+    assert_eq!(counts.get(Severity::Error), 3);
+    // Once for each rendered diagnostic, and once when finishing
+    assert_eq!(output.flushes, 3);
+
+    let result = output.buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Error 1
+
+    warning: Warning message
+    ... and 2 more
+
+    error: aborting due to 3 previous errors; 1 warning emitted
+    "###);
+}
@@ -2,25 +2,24 @@ use super::*;
 
 #[test]
 fn test_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
     let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
         .with_message("Some message")
         .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 0..19)
             .with_label("something"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
 
 #[test]
 fn test_2() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
     let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
         .with_name("E001")
@@ -30,18 +29,17 @@ fn test_2() {
         .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..19)
             .with_label("something else"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
 
 #[test]
 fn test_overlapping_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
     let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
         .with_name("E002")
@@ -51,11 +49,50 @@ fn test_overlapping_1() {
         .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..31)
             .with_label("something else"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn test_large_file_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let source = (1..=100_000).map(|i| format!("line {}\n", i)).collect::<String>();
+    let last_line_start = source.len() - "line 100000\n".len();
+    let file = SimpleFile::new("test_file.test", source.as_str());
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 7..last_line_start + 4)
+            .with_label("something"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), last_line_start + 5..last_line_start + 11)
+            .with_label("something else"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn test_multiline_label_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Test message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..19)
+            .with_label("this spans two lines\nand has a label with two lines"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 28..31)
+            .with_label("a number\nwith a fraction"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
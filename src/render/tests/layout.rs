@@ -0,0 +1,28 @@
+use crate::render::data::AnnotationData;
+use crate::render::layout::layout;
+use super::*;
+
+#[test]
+fn test_1() {
+    let mut files = SimpleFiles::new();
+    let main = files.add("main.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let other = files.add("other.test", "let other = 23;\n");
+
+    let diagnostic = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, other, 4..9)
+            .with_label("other"))
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 4..27)
+            .with_label("something"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, main, 15..24)
+            .with_label("something else"));
+
+    let lines = layout(&diagnostic, &files, &RenderConfig::default()).unwrap();
+
+    assert_eq!(lines.iter().map(|line| (line.file_id, line.line_index, line.line_number)).collect::<Vec<_>>(),
+        vec![(main, 0, 1), (main, 1, 2), (other, 0, 1)]);
+    assert!(lines[1].rows.iter().flatten().any(|data| matches!(data, AnnotationData::ContinuingMultiline(_))));
+    assert!(lines[2].rows.iter().flatten().any(|data| matches!(data, AnnotationData::Label(data) if data.label == "other")));
+
+    insta::assert_debug_snapshot!(lines);
+}
@@ -1,23 +1,23 @@
-use termcolor::Buffer;
 use crate::diagnostic::{Note, Severity};
-use crate::file::SimpleFile;
+use crate::file::{BytesFile, LineOffsets, SimpleFile, SimpleFiles};
 use crate::render::color::DefaultColorConfig;
+use crate::render::string::FmtWriter;
+use crate::render::strings::{SeverityNames, Strings};
 use super::*;
 
 #[test]
 fn test_header_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
         SimpleFile::new("main.test", "unused source"),
-        RenderConfig { surrounding_lines: 0 });
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![
         Diagnostic::new(Severity::Error)
             .with_name("test/diagnostic_1")
             .with_message("Test message")
     ]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result, @r###"
     error[test/diagnostic_1]: Test message
@@ -26,10 +26,10 @@ fn test_header_1() {
 
 #[test]
 fn test_footer_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
         SimpleFile::new("main.test", "unused source"),
-        RenderConfig { surrounding_lines: 0 });
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![
         Diagnostic::new(Severity::Error)
             .with_name("test/diagnostic_2")
@@ -37,8 +37,7 @@ fn test_footer_1() {
             .with_note(Note::note("This is a test note\nYes."))
     ]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result, @r###"
     error[test/diagnostic_2]: Test message
@@ -47,6 +46,1585 @@ fn test_footer_1() {
     "###);
 }
 
+#[test]
+fn test_footer_2() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)
+                .with_label("test label"))
+            .with_note(Note::note("First note"))
+            .with_note(Note::help("Second note,\nwith a second line"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^ test label
+      = note: First note
+      = help: Second note,
+              with a second line
+    "###);
+}
+
+#[test]
+fn test_footer_url_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Warning)
+            .with_name("unused_variables")
+            .with_url("https://example.com/lints/unused_variables")
+            .with_message("Unused variable: `main`")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))
+            .with_note(Note::help("Remove it"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    warning[unused_variables]: Unused variable: `main`
+     --> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+      = help: Remove it
+      = note: for more information, see https://example.com/lints/unused_variables
+    "###);
+}
+
+#[test]
+fn test_footer_wrap_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, wrap_width: Some(40), ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))
+            .with_note(Note::note("This is a long note that does not fit into the configured width"))
+            .with_note(Note::help("Short line\nand a very_long_word_that_cannot_be_wrapped_anywhere"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+      = note: This is a long note that does
+              not fit into the configured
+              width
+      = help: Short line
+              and a
+              very_long_word_that_cannot_be_wrapped_anywhere
+    "###);
+}
+
+#[test]
+fn test_footer_wrap_2() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, wrap_width: Some(30), ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))
+            .with_note(Note::note("変数 名前 は 使われて いません です"))
+            .with_note(Note::help("expected:  `a`,  found:  `b` here"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+      = note: 変数 名前 は
+              使われて いません
+              です
+      = help: expected:  `a`,
+              found:  `b` here
+    "###);
+}
+
+#[test]
+fn test_max_line_width_1() {
+    let source = "var a=1;var b=2;var c=3;var d=4;var e=5;var f=a+b+c+d+e+undefinedValue;var g=6;var h=7;var i=8;var j=9;\n";
+    let start = source.find("undefinedValue").unwrap();
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.min.js", source),
+        RenderConfig { surrounding_lines: 0, max_line_width: Some(40), ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Cannot find value `undefinedValue` in this scope")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), start..start + 14)
+                .with_label("not found in this scope"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), start - 8..start - 7)
+                .with_label("used here"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Cannot find value `undefinedValue` in this scope
+     --> main.min.js:1:57
+    1 | ...r f=a+b+c+d+e+undefinedValue;var g...
+      |          -       ^^^^^^^^^^^^^^ not found in this scope
+      |          |
+      |          used here
+    "###);
+}
+
+#[test]
+fn test_max_line_width_2() {
+    // The ellipses take up columns of the maximum width, instead of covering the annotated text
+    let source = format!("{}\n", "abcdefghijklmnopqrstuvwxyz".repeat(8));
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", source.as_str()),
+        RenderConfig { surrounding_lines: 0, max_line_width: Some(10), ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 50..80)
+                .with_label("too long"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:51
+    1 | ...yzab...
+      |    ^^^^^ too long
+    "###);
+}
+
+#[test]
+fn test_max_line_width_3() {
+    // Annotations outside of the window point at the ellipsis of the edge they are beyond
+    let source = format!("{}\n", "abcdefghijklmnopqrstuvwxyz".repeat(8));
+    let mut result = String::new();
+
+    for (first, second) in [(AnnotationStyle::Primary, AnnotationStyle::Secondary), (AnnotationStyle::Secondary, AnnotationStyle::Primary)] {
+        let mut buf = FmtWriter::new(String::new());
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            SimpleFile::new("main.test", source.as_str()),
+            RenderConfig { surrounding_lines: 0, max_line_width: Some(40), ..Default::default() });
+        renderer.render(vec![
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_annotation(Annotation::new(first, (), 10..12)
+                    .with_label("first"))
+                .with_annotation(Annotation::new(second, (), 150..152)
+                    .with_label("second"))
+        ]).unwrap();
+
+        result.push_str(&buf.into_inner());
+    }
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:11
+    1 | abcdefghijklmnopqrstuvwxyzabcdefghijk...
+      |           ^^                         - second
+      |           |
+      |           first
+    error: Test message
+     --> main.test:1:151
+    1 | ...efghijklmnopqrstuvwxyzabcdefghijkl...
+      |   -                ^^ second
+      |   |
+      |   first
+    "###);
+}
+
+#[test]
+fn test_max_line_width_4() {
+    // At least one column of source code is shown if the ellipses don't fit into the width
+    let source = format!("{}\n", "abcdefghijklmnopqrstuvwxyz".repeat(8));
+    let mut result = String::new();
+
+    for max_line_width in [0, 1, 3] {
+        let mut buf = FmtWriter::new(String::new());
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            SimpleFile::new("main.test", source.as_str()),
+            RenderConfig { surrounding_lines: 0, max_line_width: Some(max_line_width), ..Default::default() });
+        renderer.render(vec![
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 50..52)
+                    .with_label("here"))
+        ]).unwrap();
+
+        result.push_str(&buf.into_inner());
+    }
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:51
+    1 | ...y...
+      |    ^^ here
+    error: Test message
+     --> main.test:1:51
+    1 | ...y...
+      |    ^^ here
+    error: Test message
+     --> main.test:1:51
+    1 | ...y...
+      |    ^^ here
+    "###);
+}
+
+#[test]
+fn test_max_line_width_5() {
+    let source = format!("{}\n{}\n", "abcdefghijklmnopqrstuvwxyz".repeat(4), "ABCDEFGHIJKLMNOPQRSTUVWXYZ".repeat(4));
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", source.as_str()),
+        RenderConfig { surrounding_lines: 0, max_line_width: Some(30), ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 60..165)
+                .with_label("multi-line"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:61
+    1 |   ...xyzabcdefghijklmnopqrstu...
+      |  _______________^
+    2 | | ...XYZABCDEFGHIJKLMNOPQRSTU...
+      | |______________^ multi-line
+    "###);
+}
+
+#[test]
+fn test_tab_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "fn main() {\n\tlet\tmain = 23;\n}\n"),
+        RenderConfig { surrounding_lines: 0, tab_width: 2, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 17..21)
+                .with_label("test label"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:2:6
+    2 |   let  main = 23;
+      |        ^^^^ test label
+    "###);
+}
+
+#[test]
+fn test_unicode_width_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let 名前 = \"🗻∈🌏\";\n"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..10)
+                .with_label("名前"))
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 13..26)
+                .with_label("string"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:10
+    1 | let 名前 = "🗻∈🌏";
+      |     ----   ^^^^^^^ string
+      |     |
+      |     名前
+    "###);
+}
+
+#[test]
+fn test_unicode_width_2() {
+    // Annotations only covering a combining mark still get a caret below the character it
+    // is combined with, and a space before their label
+    fn render(column_unit: ColumnUnit) -> String {
+        let mut buf = FmtWriter::new(String::new());
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            SimpleFile::new("main.test", "let cafe\u{301} = \u{6f22}\u{301}x;\n"),
+            RenderConfig { surrounding_lines: 0, column_unit, ..Default::default() });
+        renderer.render(vec![
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 8..10)
+                    .with_label("accent"))
+                .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 16..18)
+                    .with_label("wide")),
+        ]).unwrap();
+
+        buf.into_inner()
+    }
+
+    let result = render(ColumnUnit::Chars);
+    assert_eq!(result, render(ColumnUnit::Graphemes));
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:9
+    1 | let café = 漢́x;
+      |        ^   -- wide
+      |        |
+      |        accent
+    "###);
+}
+
+#[test]
+fn test_column_unit_1() {
+    // "e\u{301}" is an "e" with a combining acute accent, and the emoji is a sequence
+    // of three emoji joined by zero-width joiners
+    let source = "let cafe\u{301} = \"\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\"; unknown;\n";
+    let start = source.find("unknown").unwrap();
+
+    for column_unit in [ColumnUnit::Bytes, ColumnUnit::Chars, ColumnUnit::Graphemes, ColumnUnit::Utf16, ColumnUnit::Width] {
+        let mut buf = FmtWriter::new(String::new());
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            SimpleFile::new("main.test", source),
+            RenderConfig { surrounding_lines: 0, display_style: DisplayStyle::Short, column_unit, ..Default::default() });
+        renderer.render(vec![
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), start..start + 7))
+        ]).unwrap();
+
+        let result = buf.into_inner();
+        let expected = match column_unit {
+            ColumnUnit::Bytes => "main.test:1:36: error: Test message\n",
+            ColumnUnit::Chars => "main.test:1:22: error: Test message\n",
+            ColumnUnit::Graphemes => "main.test:1:17: error: Test message\n",
+            ColumnUnit::Utf16 => "main.test:1:25: error: Test message\n",
+            ColumnUnit::Width => "main.test:1:22: error: Test message\n",
+        };
+
+        assert_eq!(result, expected);
+    }
+}
+
+#[test]
+fn test_column_unit_2() {
+    let source = "let cafe\u{301} = \"\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\"; unknown;\n";
+    let start = source.find("unknown").unwrap();
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", source),
+        RenderConfig { surrounding_lines: 0, column_unit: ColumnUnit::Graphemes, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), start..start + 7))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:17
+    1 | let café = "👨‍👩‍👧"; unknown;
+      |                  ^^^^^^^
+    "###);
+}
+
+#[test]
+fn test_crlf_1() {
+    fn render(source: &str, newline_len: usize) -> String {
+        let mut buf = FmtWriter::new(String::new());
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            SimpleFile::new("main.test", source),
+            RenderConfig { surrounding_lines: 1, ..Default::default() });
+        renderer.render(vec![
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13 + newline_len + 16)
+                    .with_label("multi-line"))
+                .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
+                    .with_label("single-line"))
+        ]).unwrap();
+
+        buf.into_inner()
+    }
+
+    let lf = render("let main = 23;\nsomething += 3.0;\nprint(example_source);\n", 1);
+    let crlf = render("let main = 23;\r\nsomething += 3.0;\r\nprint(example_source);\r\n", 2);
+
+    assert!(!crlf.contains('\r'));
+    assert_eq!(crlf, lf);
+}
+
+#[test]
+fn test_control_chars_1() {
+    fn render(control_chars: ControlChars) -> String {
+        let mut buf = FmtWriter::new(String::new());
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            SimpleFile::new("main.test", "let \x1b[31mmain\x07 = 23;\n"),
+            RenderConfig { surrounding_lines: 0, control_chars, ..Default::default() });
+        renderer.render(vec![
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 17..19)
+                    .with_label("number"))
+        ]).unwrap();
+
+        buf.into_inner()
+    }
+
+    insta::assert_snapshot!(render(ControlChars::Pictures), @r###"
+    error: Test message
+     --> main.test:1:18
+    1 | let ␛[31mmain␇ = 23;
+      |                  ^^ number
+    "###);
+    insta::assert_snapshot!(render(ControlChars::Escaped), @r###"
+    error: Test message
+     --> main.test:1:18
+    1 | let \x1b[31mmain\x07 = 23;
+      |                        ^^ number
+    "###);
+}
+
+#[test]
+fn test_bytes_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        BytesFile::new("main.test", b"let \xffmain = \"\xe2\x88\x88\xe2\";\n".as_slice()),
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..9)
+                .with_label("identifier"))
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 12..18)
+                .with_label("string"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:13
+    1 | let ␚main = "∈␚";
+      |     -----   ^^^^ string
+      |     |
+      |     identifier
+    "###);
+}
+
+#[test]
+fn test_hexdump_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        BytesFile::new("image.png", b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHD!\0\0\0\x01\0\0\0\x01\x08\x02\0\0\0".as_slice()),
+        RenderConfig { surrounding_lines: 0, snippet_style: SnippetStyle::Hexdump, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Invalid chunk type")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 12..16)
+                .with_label("expected `IHDR`"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 8..12)
+                .with_label("chunk length"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 14..24)
+                .with_label("image size"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Invalid chunk type
+            --> image.png+0xc
+    00000000 | 89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 21  .PNG........IHD!
+             |                          ----------- ^^^^^^^^^^^          ----^^^^ expected `IHDR`
+             |                          |
+             |                          chunk length
+    00000010 | 00 00 00 01 00 00 00 01  08 02 00 00 00           .............
+             | -----------------------                           -------- image size
+    "###);
+}
+
+#[test]
+fn test_hexdump_2() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        BytesFile::new("image.png", b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHD!".as_slice()),
+        RenderConfig { surrounding_lines: 0, snippet_style: SnippetStyle::Hexdump, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Invalid chunk type")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 12..16)
+                .with_label("expected `IHDR`\nfound `IHD!`"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 8..12)
+                .with_label("chunk length\nof 13 bytes"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 0..8)
+                .with_label("signature"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Invalid chunk type
+            --> image.png+0xc
+    00000000 | 89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 21  .PNG........IHD!
+             | -----------------------  ----------- ^^^^^^^^^^^  ------------^^^^ expected `IHDR`
+             | |                        |                                         found `IHD!`
+             | |                        |
+             | |                        chunk length
+             | |                        of 13 bytes
+             | |
+             | signature
+    "###);
+}
+
+#[test]
+fn test_hexdump_eof_1() {
+    fn render(bytes: &[u8], range: Range<usize>) -> String {
+        let mut buf = FmtWriter::new(String::new());
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            BytesFile::new("data.bin", bytes),
+            RenderConfig { surrounding_lines: 0, snippet_style: SnippetStyle::Hexdump, ..Default::default() });
+        renderer.render(vec![
+            Diagnostic::new(Severity::Error)
+                .with_message("Unexpected end of file")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), range)
+                    .with_label("expected a header"))
+        ]).unwrap();
+
+        buf.into_inner()
+    }
+
+    insta::assert_snapshot!(render(b"", 0..0), @r###"
+    error: Unexpected end of file
+            --> data.bin+0x0
+    00000000 |
+             | ^ expected a header
+    "###);
+    insta::assert_snapshot!(render(b"\x89PNG", 4..4), @r###"
+    error: Unexpected end of file
+            --> data.bin+0x4
+    00000000 | 89 50 4e 47                                       .PNG
+             |             ^ expected a header
+    "###);
+    insta::assert_snapshot!(render(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR", 16..16), @r###"
+    error: Unexpected end of file
+            --> data.bin+0x10
+    00000010 |
+             | ^ expected a header
+    "###);
+}
+
+#[test]
+fn test_clone_file_id_1() {
+    struct NamedFiles(Vec<SimpleFile<String, String>>);
+
+    impl NamedFiles {
+        fn get(&self, name: &str) -> std::result::Result<&SimpleFile<String, String>, Error> {
+            self.0.iter().find(|&file| file.name() == name).ok_or(Error::FileMissing)
+        }
+    }
+
+    impl Files for NamedFiles {
+        type FileId = String;
+
+        fn name(&self, id: String) -> std::result::Result<&str, Error> {
+            Ok(self.get(&id)?.name())
+        }
+
+        fn source(&self, id: String) -> std::result::Result<Cow<'_, str>, Error> {
+            Ok(Cow::Borrowed(self.get(&id)?.source()))
+        }
+
+        fn line_start(&self, id: String, line_index: usize) -> std::result::Result<usize, Error> {
+            self.get(&id)?.line_start((), line_index)
+        }
+    }
+
+    let files = NamedFiles(vec![
+        SimpleFile::new(String::from("main.test"), String::from("let main = other;\n")),
+        SimpleFile::new(String::from("other.test"), String::from("let other = 23;\n")),
+    ]);
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, files,
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Use of private value")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, String::from("main.test"), 11..16)
+                .with_label("private value"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, String::from("other.test"), 4..9)
+                .with_label("declared here"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Use of private value
+     --> main.test:1:12
+    1 | let main = other;
+      |            ^^^^^ private value
+     ::: other.test:1:5
+    1 | let other = 23;
+      |     ----- declared here
+    "###);
+}
+
+#[test]
+fn test_multiple_files_1() {
+    let mut files = SimpleFiles::new();
+    let main = files.add("main.test", "let main = other + another;\n");
+    let other = files.add("other.test", "let other = 23;\n");
+    let another = files.add("another.test", "\nlet another = 42;\n");
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, files,
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Use of private values")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 11..26)
+                .with_label("private values"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, other, 4..9)
+                .with_label("declared here"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, another, 5..12)
+                .with_label("and here"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Use of private values
+     --> main.test:1:12
+    1 | let main = other + another;
+      |            ^^^^^^^^^^^^^^^ private values
+     ::: other.test:1:5
+    1 | let other = 23;
+      |     ----- declared here
+     ::: another.test:2:5
+    2 | let another = 42;
+      |     ------- and here
+    "###);
+}
+
+#[test]
+fn test_file_order_1() {
+    let mut files = SimpleFiles::new();
+    let other = files.add("other.test", "let other = 23;\n");
+    let main = files.add("main.test", "let main = other;\n");
+
+    let diagnostic = Diagnostic::new(Severity::Error)
+        .with_message("Use of private value")
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, other, 4..9)
+            .with_label("declared here"))
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 11..16)
+            .with_label("private value"));
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, &files,
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic.clone()]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Use of private value
+     --> main.test:1:12
+    1 | let main = other;
+      |            ^^^^^ private value
+     ::: other.test:1:5
+    1 | let other = 23;
+      |     ----- declared here
+    "###);
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, &files,
+        RenderConfig { surrounding_lines: 0, file_order: FileOrder::FileId, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Use of private value
+     ::: other.test:1:5
+    1 | let other = 23;
+      |     ----- declared here
+     --> main.test:1:12
+    1 | let main = other;
+      |            ^^^^^ private value
+    "###);
+}
+
+#[test]
+fn test_owned_source_1() {
+    // Generates the source every time it is accessed, instead of keeping it in memory
+    struct GeneratedFile(usize);
+
+    impl Files for GeneratedFile {
+        type FileId = ();
+
+        fn name(&self, (): ()) -> std::result::Result<&str, Error> {
+            Ok("generated.test")
+        }
+
+        fn source(&self, (): ()) -> std::result::Result<Cow<'_, str>, Error> {
+            Ok(Cow::Owned((0..self.0).map(|i| format!("let value{} = {};\n", i, i)).collect()))
+        }
+
+        fn line_start(&self, (): (), line_index: usize) -> std::result::Result<usize, Error> {
+            SimpleFile::new("", self.source(())?).line_start((), line_index)
+        }
+    }
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, GeneratedFile(3),
+        RenderConfig { surrounding_lines: 1, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Warning)
+            .with_message("Unused value")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 20..26)
+                .with_label("never used"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    warning: Unused value
+     --> generated.test:2:5
+    1 | let value0 = 0;
+    2 | let value1 = 1;
+      |     ^^^^^^ never used
+    3 | let value2 = 2;
+    "###);
+}
+
+#[test]
+fn test_error_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let 🗻 = 23;\n"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    let err = renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 5..8)
+                .with_label("not a char boundary"))
+    ]).unwrap_err();
+
+    insta::assert_snapshot!(err, @"failed to lay out the annotations on line index 0 in file `main.test`: index is not a code point boundary");
+    assert!(matches!(err, RenderError::File { error: Error::InvalidCharBoundary { given: 5 }, .. }));
+}
+
+#[test]
+fn test_invalid_ranges_1() {
+    // Invalid ranges must fail with an error for every combination of options, not panic
+    let source = "let 🗻 = 23;\nfn b(x) {\n}\n";
+    let ranges = [Range { start: 8, end: 4 }, Range { start: 20, end: 2 }, 10..100, 100..200, 5..6, 4..5, 5..20];
+    let configs = [
+        RenderConfig::default(),
+        RenderConfig::default().highlight_source(true),
+        RenderConfig::default().max_line_width(Some(8)),
+        RenderConfig::default().display_style(DisplayStyle::Short),
+        RenderConfig::default().display_style(DisplayStyle::Descriptive),
+        RenderConfig::default().snippet_style(SnippetStyle::Hexdump),
+        RenderConfig::default().max_annotations_per_line(NonZeroUsize::new(1)).merge_annotations(true),
+        RenderConfig::default().verbosity(Verbosity::Quiet).column_unit(ColumnUnit::Utf16),
+    ];
+
+    for range in ranges.iter() {
+        for config in configs.iter() {
+            let diagnostics = [
+                Diagnostic::new(Severity::Error)
+                    .with_message("Test message")
+                    .with_annotation(Annotation::new(AnnotationStyle::Primary, (), range.clone())
+                        .with_label("invalid")),
+                Diagnostic::new(Severity::Error)
+                    .with_message("Test message")
+                    .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 14..16)
+                        .with_label("valid"))
+                    .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), range.clone())),
+                Diagnostic::new(Severity::Error)
+                    .with_message("Test message")
+                    .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 14..16))
+                    .with_suggestion(Suggestion::new("Replace it")
+                        .with_replacement(Replacement::new((), range.clone(), "replacement"))),
+            ];
+
+            for diagnostic in diagnostics.into_iter() {
+                let mut buf = FmtWriter::new(String::new());
+                let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+                    SimpleFile::new("main.test", source), config.clone());
+
+                if let Err(err) = renderer.render(vec![diagnostic]) {
+                    assert!(matches!(err, RenderError::File { .. }), "unexpected error for {:?}: {}", range, err);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_invalid_ranges_2() {
+    // Ranges past the end of the file or ending before they start fail in every display style
+    // before anything is written
+    let source = "let main = 23;\n";
+    let display_styles = [DisplayStyle::Rich, DisplayStyle::Short, DisplayStyle::Descriptive];
+
+    for range in [14..20, 20..30, Range { start: 8, end: 4 }] {
+        let diagnostics = [
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), range.clone())),
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))
+                .with_sub_diagnostic(Diagnostic::new(Severity::Note)
+                    .with_message("Sub-diagnostic")
+                    .with_annotation(Annotation::new(AnnotationStyle::Primary, (), range.clone()))),
+            Diagnostic::new(Severity::Error)
+                .with_message("Test message")
+                .with_suggestion(Suggestion::new("Replace it")
+                    .with_replacement(Replacement::new((), range.clone(), "replacement"))),
+        ];
+
+        for display_style in display_styles {
+            for diagnostic in diagnostics.iter() {
+                let mut buf = FmtWriter::new(String::new());
+                let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+                    SimpleFile::new("main.test", source), RenderConfig::default().display_style(display_style));
+
+                assert!(matches!(renderer.render_one(diagnostic), Err(RenderError::File { operation: FileOperation::SourceIndex { .. }, .. })),
+                    "no error for {:?} in {:?}", range, display_style);
+                assert_eq!(renderer.counts().total(), 0);
+                drop(renderer);
+                assert_eq!(buf.into_inner(), "");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_end_of_file_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\nlet other = main\n"),
+        RenderConfig { surrounding_lines: 1, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Unexpected end of input")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 32..32)
+                .with_label("expected `;`"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 15..31)
+                .with_label("in this statement"))
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Unexpected end of input
+     --> main.test:3:1
+    1 | let main = 23;
+    2 | let other = main
+      | ---------------- in this statement
+    3 |
+      | ^ expected `;`
+    "###);
+}
+
+#[test]
+fn test_empty_file_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", ""),
+        RenderConfig { surrounding_lines: 1, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Empty file")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 0..0)
+                .with_label("expected a declaration")),
+        Diagnostic::new(Severity::Warning)
+            .with_message("Warning without annotations"),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Empty file
+     --> main.test:1:1
+    1 |
+      | ^ expected a declaration
+
+    warning: Warning without annotations
+    "###);
+}
+
+#[test]
+fn test_past_end_of_file_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    let err = renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..20))
+    ]).unwrap_err();
+
+    assert!(matches!(err, RenderError::File { error: Error::IndexTooLarge { given: 20, max: 15 }, .. }));
+}
+
+#[test]
+fn test_short_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\nsomething += 3.0;\n"),
+        RenderConfig { surrounding_lines: 0, display_style: DisplayStyle::Short, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_name("test/diagnostic_3")
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
+                .with_label("secondary"))
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 25..28)
+                .with_label("primary"))
+            .with_note(Note::note("This note is not shown")),
+        Diagnostic::new(Severity::Warning)
+            .with_message("Warning without annotations"),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    main.test:2:11: error[test/diagnostic_3]: Test message
+    warning: Warning without annotations
+    "###);
+}
+
+#[test]
+fn test_render_with_config_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\nsomething += 3.0;\n"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    let diagnostic = Diagnostic::new(Severity::Warning)
+        .with_message("Test message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 25..28)
+            .with_label("primary"));
+
+    let config = RenderConfig { display_style: DisplayStyle::Short, ..renderer.config().clone() };
+    renderer.render_with_config(vec![diagnostic.clone()], config).unwrap();
+    renderer.render(vec![diagnostic]).unwrap();
+    assert_eq!(renderer.config().display_style, DisplayStyle::Rich);
+    assert_eq!(renderer.counts().get(Severity::Warning), 2);
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    main.test:2:11: warning: Test message
+
+    warning: Test message
+     --> main.test:2:11
+    2 | something += 3.0;
+      |           ^^^ primary
+    "###);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_config_1() {
+    let config: RenderConfig = toml::from_str(r#"
+        surrounding_lines = 2
+        display_style = "short"
+        min_severity = "warning"
+        max_errors = 10
+
+        [charset]
+        base = "unicode"
+        ellipsis = "..."
+        severity_underlines = { warning = ["~", "-"] }
+    "#).unwrap();
+
+    assert_eq!(config.surrounding_lines, 2);
+    assert_eq!(config.display_style, DisplayStyle::Short);
+    assert_eq!(config.min_severity, Severity::Warning);
+    assert_eq!(config.max_errors, Some(10));
+    assert_eq!(config.charset, CharSet { ellipsis: String::from("..."), ..CharSet::unicode() }.with_severity_underline(Severity::Warning, '~', '-'));
+    assert_eq!(config.tab_width, RenderConfig::default().tab_width);
+
+    let config: RenderConfig = toml::from_str(r#"charset = "unicode""#).unwrap();
+    assert_eq!(config.charset, CharSet::unicode());
+
+    assert!(toml::from_str::<RenderConfig>("unknown_option = true").is_err());
+    assert!(toml::from_str::<RenderConfig>("max_annotations_per_line = 0").is_err());
+}
+
+#[test]
+fn test_min_severity_1() {
+    let diagnostics = vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_note(Note::note("This is a note"))
+            .with_note(Note::help("This is a help message"))
+            .with_sub_diagnostic(Diagnostic::new(Severity::Help).with_message("Sub-diagnostic")),
+        Diagnostic::new(Severity::Warning)
+            .with_message("Warning message"),
+        Diagnostic::new(Severity::Note)
+            .with_message("Note message"),
+    ];
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "unused source"),
+        RenderConfig { min_severity: Severity::Warning, ..Default::default() });
+    renderer.render(diagnostics.clone()).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     = note: This is a note
+     = help: This is a help message
+      help: Sub-diagnostic
+
+    warning: Warning message
+    "###);
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "unused source"),
+        RenderConfig { min_severity: Severity::Note, filter_children: true, ..Default::default() });
+    renderer.render(diagnostics).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     = note: This is a note
+
+    warning: Warning message
+
+    note: Note message
+    "###);
+}
+
+#[test]
+fn test_summary_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "unused source"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("First error")
+            .with_sub_diagnostic(Diagnostic::new(Severity::Error).with_message("Not counted")),
+        Diagnostic::new(Severity::Warning)
+            .with_message("Warning message"),
+        Diagnostic::new(Severity::Error)
+            .with_message("Second error"),
+    ]).unwrap();
+
+    let counts = renderer.counts();
+    assert_eq!(counts.get(Severity::Error), 2);
+    assert_eq!(counts.get(Severity::Warning), 1);
+    assert!(counts.has_errors());
+
+    renderer.render_summary().unwrap();
+    renderer.render_summary_with(|counts| Some((Severity::Note, format!("{} diagnostics in total", counts.total())))).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: First error
+      error: Not counted
+
+    warning: Warning message
+
+    error: Second error
+
+    error: aborting due to 2 previous errors; 1 warning emitted
+
+    note: 3 diagnostics in total
+    "###);
+}
+
+#[test]
+fn test_max_errors_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "unused source"),
+        RenderConfig { max_errors: Some(2), ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error).with_message("First error"),
+        Diagnostic::new(Severity::Error).with_message("Second error"),
+        Diagnostic::new(Severity::Warning).with_message("Warning message"),
+        Diagnostic::new(Severity::Error).with_message("Third error"),
+        Diagnostic::new(Severity::Bug).with_message("Bug message"),
+    ]).unwrap();
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error).with_message("Fourth error"),
+    ]).unwrap();
+
+    assert_eq!(renderer.counts().get(Severity::Error), 4);
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: First error
+
+    error: Second error
+
+    warning: Warning message
+    ... and 2 more
+    ... and 1 more
+    "###);
+}
+
+#[test]
+fn test_render_iter_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { max_errors: Some(1), min_severity: Severity::Warning, ..Default::default() });
+    renderer.render_one(&Diagnostic::new(Severity::Warning)
+        .with_message("Warning message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))).unwrap();
+    renderer.render_iter((1..=3).map(|i| Diagnostic::new(Severity::Error).with_message(format!("Error {}", i)))
+        .chain(std::iter::once(Diagnostic::new(Severity::Help).with_message("Filtered out")))).unwrap();
+
+    assert_eq!(renderer.counts().get(Severity::Error), 3);
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    warning: Warning message
+     --> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+
+    error: Error 1
+    ... and 2 more
+    "###);
+}
+
+#[test]
+fn test_render_slice_1() {
+    let diagnostics = vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Error message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8).with_label("first"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 11..13).with_label("second"))
+            .with_note(Note::help("Filtered out"))
+            .with_sub_diagnostic(Diagnostic::new(Severity::Warning)
+                .with_message("Sub-diagnostic")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 0..3))),
+        Diagnostic::new(Severity::Error).with_message("Suppressed"),
+    ];
+    let config = RenderConfig {
+        max_errors: Some(1), min_severity: Severity::Warning, filter_children: true,
+        max_annotations_per_line: NonZeroUsize::new(1), ..Default::default()
+    };
+
+    fn render_with(config: &RenderConfig, render: impl FnOnce(&mut DiagnosticRenderer<FmtWriter<String>, DefaultColorConfig, (), SimpleFile<&str, &str>>)) -> String {
+        let mut buf = FmtWriter::new(String::new());
+        let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+            SimpleFile::new("main.test", "let main = 23;\n"), config.clone());
+        render(&mut renderer);
+        buf.into_inner()
+    }
+
+    let borrowed = render_with(&config, |renderer| renderer.render_slice(&diagnostics).unwrap());
+    assert_eq!(borrowed, render_with(&config, |renderer| renderer.render(diagnostics.clone()).unwrap()));
+    assert_eq!(borrowed, render_with(&config, |renderer| renderer.render_slice(&diagnostics).unwrap()));
+    assert!(borrowed.contains("Sub-diagnostic") && !borrowed.contains("Filtered out"));
+    assert_eq!(diagnostics[0].annotations.len(), 2);
+    assert_eq!(diagnostics[0].notes.len(), 1);
+    assert_eq!(diagnostics[0].suppressed_count, 0);
+}
+
+#[test]
+fn test_severity_names_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let names = SeverityNames { warning: String::from("Warnung"), note: String::from("Hinweis"), ..Default::default() };
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, min_severity: Severity::Warning, ..Default::default() }).with_strings(names);
+    renderer.render(vec![
+        Diagnostic::new(Severity::Warning)
+            .with_message("Unused variable")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))
+            .with_note(Note::note("A note with\nmultiple lines")),
+        Diagnostic::new(Severity::Warning)
+            .with_severity_name("deprecation")
+            .with_message("Deprecated function"),
+        Diagnostic::new(Severity::Note)
+            .with_severity_name("pedantic")
+            .with_message("Filtered out"),
+    ]).unwrap();
+
+    assert_eq!(renderer.counts().get(Severity::Warning), 2);
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    Warnung: Unused variable
+     --> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+      = Hinweis: A note with
+                 multiple lines
+
+    deprecation: Deprecated function
+    "###);
+}
+
+#[test]
+fn test_strings_1() {
+    #[derive(Debug)]
+    struct GermanStrings;
+
+    impl Strings for GermanStrings {
+        fn severity(&self, severity: Severity) -> Cow<'_, str> {
+            Cow::Borrowed(match severity {
+                Severity::Error => "Fehler",
+                Severity::Help => "Hilfe",
+                _ => "Hinweis",
+            })
+        }
+
+        fn suppressed(&self, count: usize) -> String {
+            format!("... und {} weitere", count)
+        }
+
+        fn more_information(&self, url: &str) -> String {
+            format!("mehr Informationen unter {}", url)
+        }
+
+        fn summary(&self, counts: &SeverityCounts) -> Option<(Severity, String)> {
+            Some((Severity::Error, format!("Abbruch wegen {} Fehlern", counts.get(Severity::Error))))
+        }
+    }
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { max_errors: Some(1), ..Default::default() }).with_strings(GermanStrings);
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Unbekannte Variable")
+            .with_url("https://example.com/E0425")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)),
+        Diagnostic::new(Severity::Error)
+            .with_message("Unterdrückt"),
+    ]).unwrap();
+    renderer.render_summary().unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    Fehler: Unbekannte Variable
+     --> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+      = Hinweis: mehr Informationen unter https://example.com/E0425
+    ... und 1 weitere
+
+    Fehler: Abbruch wegen 2 Fehlern
+    "###);
+}
+
+#[test]
+fn test_strings_2() {
+    #[derive(Debug)]
+    struct GermanStrings;
+
+    impl Strings for GermanStrings {
+        fn hidden_annotations(&self, count: usize) -> String {
+            format!("... und {} weitere Markierungen in dieser Zeile", count)
+        }
+
+        fn hidden_annotation_note(&self, location: Location, label: &str) -> String {
+            format!("Zeile {}, Spalte {}: {}", location.line_number, location.column_number, label)
+        }
+    }
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, max_annotations_per_line: NonZeroUsize::new(1), ..Default::default() }).with_strings(GermanStrings);
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Falscher Typ")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)
+                .with_label("Zahl"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
+                .with_label("Name")),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Falscher Typ
+     --> main.test:1:12
+    1 | let main = 23;
+      |            ^^ Zahl
+      | ... und 1 weitere Markierungen in dieser Zeile
+      = note: Zeile 1, Spalte 5: Name
+    "###);
+}
+
+#[test]
+fn test_duplicate_count_1() {
+    #[derive(Debug)]
+    struct GermanStrings;
+
+    impl Strings for GermanStrings {
+        fn repeated(&self, count: usize) -> String {
+            format!("({} Mal gemeldet)", count)
+        }
+    }
+
+    let diagnostic = || Diagnostic::new(Severity::Warning)
+        .with_message("Unused variable")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8));
+    let diagnostics = crate::diagnostic::deduplicate(vec![diagnostic(), diagnostic(), diagnostic()]);
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"), RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(diagnostics.clone()).unwrap();
+
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let main = 23;\n"), RenderConfig { surrounding_lines: 0, ..Default::default() })
+        .with_strings(GermanStrings);
+    renderer.render(diagnostics).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    warning: Unused variable
+     --> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+      = note: (message repeated 3 times)
+    warning: Unused variable
+     --> main.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+      = note: (3 Mal gemeldet)
+    "###);
+}
+
+#[test]
+fn test_emphasize_backticks_1() {
+    /// Writes `<` and `>` around emphasized text instead of setting colors.
+    struct EmphasisMarkers(std::cell::Cell<bool>);
+
+    impl EmphasisMarkers {
+        fn close(&self, f: &mut impl WriteColor) -> std::io::Result<()> {
+            if self.0.replace(false) {
+                write!(f, ">")?;
+            }
+
+            Ok(())
+        }
+    }
+
+    impl ColorConfig for EmphasisMarkers {
+        fn reset(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn severity(&self, f: &mut impl WriteColor, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+        fn name(&self, f: &mut impl WriteColor, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+        fn message(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn path(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn line_number(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn line_number_separator(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn annotation(&self, f: &mut impl WriteColor, _style: AnnotationStyle, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+        fn source(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn note_severity(&self, f: &mut impl WriteColor, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+        fn note_message(&self, f: &mut impl WriteColor, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+
+        fn emphasis(&self, f: &mut impl WriteColor, _severity: Severity) -> std::io::Result<()> {
+            self.0.set(true);
+            write!(f, "<")
+        }
+    }
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, EmphasisMarkers(std::cell::Cell::new(false)),
+        SimpleFile::new("main.test", "let main = 23;\n"),
+        RenderConfig { surrounding_lines: 0, wrap_width: Some(40), ..Default::default() }.emphasize_backticks(true));
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("mismatched types: expected `String`, found `{integer}`")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)
+                .with_label("expected `String`"))
+            .with_note(Note::note("the value of `main` is `a very long expression that wraps across` several lines")),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: mismatched types: expected `<String>`, found `<{integer}>`
+     --> main.test:1:12
+    1 | let main = 23;
+      |            ^^ expected `<String>`
+      = note: the value of `<main>` is `<a very>
+              <long expression that wraps>
+              <across>` several lines
+    "###);
+}
+
+#[test]
+fn test_highlight_source_1() {
+    /// Writes `[` and `]` around highlighted source text, with `*` for primary annotations,
+    /// instead of setting colors.
+    struct HighlightMarkers(std::cell::Cell<bool>);
+
+    impl HighlightMarkers {
+        fn close(&self, f: &mut impl WriteColor) -> std::io::Result<()> {
+            if self.0.replace(false) {
+                write!(f, "]")?;
+            }
+
+            Ok(())
+        }
+    }
+
+    impl ColorConfig for HighlightMarkers {
+        fn reset(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn severity(&self, f: &mut impl WriteColor, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+        fn name(&self, f: &mut impl WriteColor, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+        fn message(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn path(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn line_number(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn line_number_separator(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn annotation(&self, f: &mut impl WriteColor, _style: AnnotationStyle, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+        fn source(&self, f: &mut impl WriteColor) -> std::io::Result<()> { self.close(f) }
+        fn note_severity(&self, f: &mut impl WriteColor, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+        fn note_message(&self, f: &mut impl WriteColor, _severity: Severity) -> std::io::Result<()> { self.close(f) }
+
+        fn annotated_source(&self, f: &mut impl WriteColor, style: AnnotationStyle, _severity: Severity) -> std::io::Result<()> {
+            self.close(f)?;
+            self.0.set(true);
+            write!(f, "{}", if style == AnnotationStyle::Primary { "[*" } else { "[" })
+        }
+    }
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, HighlightMarkers(std::cell::Cell::new(false)),
+        SimpleFile::new("main.test", "let main = other(23,\n    42);\nprint(main);\n"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() }.highlight_source(true));
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 11..28)
+                .with_label("call"))
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 17..19)
+                .with_label("argument"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 36..40)),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> main.test:1:18
+    1 |   let main = [other(][*23][,]
+      |  ____________-     ^^
+      | |                  |
+      | |                  argument
+    2 | | [    42)];
+      | |_______- call
+    3 |   print([main]);
+      |         ----
+    "###);
+}
+
+#[test]
+fn test_highlight_source_2() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        SimpleFile::new("main.test", "let 🗻 = 23;\n"),
+        RenderConfig { surrounding_lines: 0, ..Default::default() }.highlight_source(true));
+    let err = renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 5..6)
+                .with_label("not a char boundary"))
+    ]).unwrap_err();
+
+    assert!(matches!(err, RenderError::File { error: Error::InvalidCharBoundary { .. }, .. }));
+}
+
+#[test]
+fn test_sub_diagnostic_1() {
+    let mut files = SimpleFiles::new();
+    let main = files.add("main.test", "let main = other;\n");
+    let other = files.add("other.test", "let other = 23;\n");
+
+    let mut buf = FmtWriter::new(String::new());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, files,
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Use of private value")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 11..16)
+                .with_label("private value"))
+            .with_note(Note::help("Make it public"))
+            .with_sub_diagnostic(Diagnostic::new(Severity::Note)
+                .with_message("Value declared here")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, other, 4..9))
+                .with_note(Note::note("Values are private by default,\nunless declared otherwise")))
+            .with_suppressed_count(2),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Use of private value
+     --> main.test:1:12
+    1 | let main = other;
+      |            ^^^^^ private value
+      = help: Make it public
+      note: Value declared here
+       --> other.test:1:5
+      1 | let other = 23;
+        |     ^^^^^
+        = note: Values are private by default,
+                unless declared otherwise
+    ... and 2 more
+    "###);
+}
+
 // TODO this completely breaks the renderer right now
 #[test]
 fn test_fibonacci() {
@@ -70,7 +1648,7 @@ fn test_fibonacci() {
     sum
 }"#;
 
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", source);
 
     let mut diagnostic = Diagnostic::new(Severity::Note)
@@ -114,11 +1692,10 @@ fn test_fibonacci() {
     }
 
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
@@ -127,3 +1704,258 @@ fn test_fibonacci() {
 mod singleline;
 mod ending;
 mod starting;
+mod chars;
+mod svg;
+mod html;
+mod suggestion;
+mod emitter;
+mod theme;
+mod string;
+mod layout;
+mod backend;
+mod report;
+#[cfg(feature = "termcolor")]
+mod ansi;
+
+#[test]
+fn test_line_offsets_1() {
+    let mut files = SimpleFiles::new();
+    let snippet = files.add("README.md", "let main = 23;\nsomething += 3.0;\n");
+    let files = LineOffsets::new(files).with_offset(snippet, 97);
+
+    let mut buf = FmtWriter::new(String::new());
+    let diagnostic = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, snippet, 15..24)
+            .with_label("here"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        files, RenderConfig { surrounding_lines: 1, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Some message
+       --> README.md:99:1
+     98 | let main = 23;
+     99 | something += 3.0;
+        | ^^^^^^^^^ here
+    "###);
+}
+
+#[test]
+fn test_min_line_number_width_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let source = "let main = 23;\n".repeat(12);
+    let file = SimpleFile::new("test_file.test", source.as_str());
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() }.min_line_number_width(3));
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Some message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)
+                .with_label("here"))
+            .with_note(Note::note("Some note")),
+        Diagnostic::new(Severity::Error)
+            .with_message("Another message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 169..173)
+                .with_label("here")),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Some message
+       --> test_file.test:1:5
+      1 | let main = 23;
+        |     ^^^^ here
+        = note: Some note
+
+    error: Another message
+       --> test_file.test:12:5
+     12 | let main = 23;
+        |     ^^^^ here
+    "###);
+}
+
+#[test]
+fn test_path_formatter_1() {
+    let mut files = SimpleFiles::new();
+    let main = files.add("/home/user/project/src/main.test", "let main = 23;\n");
+    let other = files.add("/home/user/project/src/other.test", "let other = 23;\n");
+
+    let mut buf = FmtWriter::new(String::new());
+    let diagnostic = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 4..8)
+            .with_label("here"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, other, 4..9));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        &files, RenderConfig { surrounding_lines: 0, ..Default::default() })
+        .with_path_formatter(|path| path.strip_prefix("/home/user/project/").unwrap_or(path).to_owned());
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Some message
+     --> src/main.test:1:5
+    1 | let main = 23;
+      |     ^^^^ here
+     ::: src/other.test:1:5
+    1 | let other = 23;
+      |     -----
+    "###);
+}
+
+#[test]
+fn test_byte_offsets_1() {
+    let mut files = SimpleFiles::new();
+    let main = files.add("main.test", "let main = 23;\nsomething += 3.0;\n");
+    let other = files.add("other.test", "let other = 23;\n");
+
+    let mut buf = FmtWriter::new(String::new());
+    let diagnostic = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 15..24)
+            .with_label("here"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, other, 4..9));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        &files, RenderConfig { surrounding_lines: 0, ..Default::default() }.byte_offsets(true));
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Some message
+     --> main.test:2:1 (bytes 15..24)
+    2 | something += 3.0;
+      | ^^^^^^^^^ here
+     ::: other.test:1:5 (bytes 4..9)
+    1 | let other = 23;
+      |     -----
+    "###);
+}
+
+#[test]
+fn test_descriptive_1() {
+    let mut files = SimpleFiles::new();
+    let main = files.add("main.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let other = files.add("other.test", "let other = 23;\n");
+
+    let mut buf = FmtWriter::new(String::new());
+    let diagnostic = Diagnostic::new(Severity::Error)
+        .with_name("E0001")
+        .with_message("Mismatched types")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 11..13)
+            .with_label("expected `String`, found integer"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, main, 4..8))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, main, 28..38)
+            .with_label("spanning lines"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, other, 4..5)
+            .with_label("declared here"))
+        .with_note(Note::help("Really helpful tip"))
+        .with_sub_diagnostic(Diagnostic::new(Severity::Note)
+            .with_message("Sub-diagnostic")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 13..13)));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        &files, RenderConfig { surrounding_lines: 0, ..Default::default() }.display_style(DisplayStyle::Descriptive));
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error[E0001]: Mismatched types
+      error in main.test at line 1, columns 12-13: expected `String`, found integer
+      related code in main.test at line 1, columns 5-8
+      related code in main.test at line 2, column 14 to line 3, column 5: spanning lines
+      related code in other.test at line 1, column 5: declared here
+      = help: Really helpful tip
+      note: Sub-diagnostic
+        note in main.test at line 1, column 14
+    "###);
+}
+
+#[test]
+fn test_quiet_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\n");
+    let diagnostic = Diagnostic::new(Severity::Error)
+        .with_message("Mismatched types")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)
+            .with_label("expected `String`, found integer"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 15..24)
+            .with_label("due to this"))
+        .with_note(Note::help("Really helpful tip"))
+        .with_sub_diagnostic(Diagnostic::new(Severity::Note)
+            .with_message("Sub-diagnostic"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, max_errors: Some(1), ..Default::default() }.verbosity(Verbosity::Quiet));
+    renderer.render(vec![diagnostic.clone(), diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Mismatched types
+     --> test_file.test:1:12
+    1 | let main = 23;
+      |            ^^ expected `String`, found integer
+    "###);
+}
+
+#[test]
+fn test_expected_found_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main: String = 23;\n");
+    let diagnostic = Diagnostic::new(Severity::Error)
+        .with_message("Mismatched types")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 19..21)
+            .with_label("expected `String`, found integer"))
+        .with_note(Note::expected_found("`String`", "`{integer}`").with_highlighted_differences(true))
+        .with_sub_diagnostic(Diagnostic::new(Severity::Note)
+            .with_message("Sub-diagnostic")
+            .with_note(Note::expected_found("`Vec<String>`", "`Vec<i32>`")));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Mismatched types
+     --> test_file.test:1:20
+    1 | let main: String = 23;
+      |                    ^^ expected `String`, found integer
+      = note: expected `String`
+                 found `{integer}`
+      note: Sub-diagnostic
+       = note: expected `Vec<String>`
+                  found `Vec<i32>`
+    "###);
+}
+
+#[test]
+fn test_note_prefix_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\n");
+    let diagnostic = Diagnostic::new(Severity::Warning)
+        .with_message("Unused variable")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8))
+        .with_note(Note::help("Prefix it with an underscore\nto silence this warning").with_prefix("hint"))
+        .with_note(Note::note("https://example.com/unused").with_prefix("see also"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    warning: Unused variable
+     --> test_file.test:1:5
+    1 | let main = 23;
+      |     ^^^^
+      = hint: Prefix it with an underscore
+              to silence this warning
+      = see also: https://example.com/unused
+    "###);
+}
@@ -0,0 +1,106 @@
+use crate::diagnostic::Severity;
+use crate::file::SimpleFiles;
+use crate::render::color::DefaultColorConfig;
+use crate::render::report::FileSummary;
+use super::*;
+
+#[test]
+fn test_report_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut files = SimpleFiles::new();
+    let main = files.add("src/main.rs", "let main = 23;\nsomething += 3.0;\n");
+    let other = files.add("src/other.rs", "let other = 23;\n");
+
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, &files,
+        RenderConfig { surrounding_lines: 0, ..Default::default() });
+    let summaries = renderer.render_report(vec![
+        Diagnostic::new(Severity::Warning)
+            .with_message("Unused variable")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 4..8)),
+        Diagnostic::new(Severity::Error)
+            .with_message("Without annotations"),
+        Diagnostic::new(Severity::Note)
+            .with_message("Unused value")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, other, 12..14)),
+        Diagnostic::new(Severity::Error)
+            .with_name("E0308")
+            .with_message("Mismatched types")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 15..24)),
+    ]).unwrap();
+
+    let counts = |severities: &[Severity]| {
+        let mut counts = SeverityCounts::new();
+        severities.iter().for_each(|&severity| counts.add(severity));
+        counts
+    };
+
+    assert_eq!(summaries, vec![
+        FileSummary { file: Some(main), counts: counts(&[Severity::Warning, Severity::Error]) },
+        FileSummary { file: Some(other), counts: counts(&[Severity::Note]) },
+        FileSummary { file: None, counts: counts(&[Severity::Error]) },
+    ]);
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    src/main.rs (1 error, 1 warning)
+
+    warning: Unused variable
+     --> src/main.rs:1:5
+    1 | let main = 23;
+      |     ^^^^
+
+    error[E0308]: Mismatched types
+     --> src/main.rs:2:1
+    2 | something += 3.0;
+      | ^^^^^^^^^
+
+    src/other.rs (1 diagnostic)
+
+    note: Unused value
+     --> src/other.rs:1:13
+    1 | let other = 23;
+      |             ^^
+
+    (no file) (1 error)
+
+    error: Without annotations
+
+    file          errors  warnings
+    src/main.rs        1         1
+    src/other.rs       0         0
+    (no file)          1         0
+    total              2         1
+    "###);
+}
+
+#[test]
+fn test_report_short_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let mut files = SimpleFiles::new();
+    let main = files.add("src/main.rs", "let main = 23;\nsomething += 3.0;\n");
+
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig, &files,
+        RenderConfig { display_style: DisplayStyle::Short, ..Default::default() })
+        .with_path_formatter(|path| path.trim_start_matches("src/").to_string());
+    renderer.render_report(vec![
+        Diagnostic::new(Severity::Warning)
+            .with_message("Unused variable")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 4..8)),
+        Diagnostic::new(Severity::Warning)
+            .with_message("Unused value")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, main, 11..13)),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    main.rs (2 warnings)
+    main.rs:1:5: warning: Unused variable
+    main.rs:1:12: warning: Unused value
+
+    file     errors  warnings
+    main.rs       0         2
+    total         0         2
+    "###);
+}
@@ -2,7 +2,7 @@ use super::*;
 
 #[test]
 fn test_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "test file contents");
     let diagnostic = Diagnostic::new(Severity::Error)
         .with_message("Test message")
@@ -10,18 +10,17 @@ fn test_1() {
             .with_label("test label"))
         .with_note(Note::help("Change something"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
 
 #[test]
 fn test_separate_lines_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
     let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
         .with_message("Mismatched types")
@@ -31,18 +30,17 @@ fn test_separate_lines_1() {
             .with_label("due to this"))
         .with_note(Note::help("Really helpful multi-line tip:\n123456789"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
 
 #[test]
 fn test_same_line_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
     let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
         .with_message("Mismatched types")
@@ -51,18 +49,17 @@ fn test_same_line_1() {
         .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
             .with_label("identifier"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
 
 #[test]
 fn test_overlapping_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
     let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
         .with_message("Mismatched types")
@@ -71,11 +68,128 @@ fn test_overlapping_1() {
         .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 8..11)
             .with_label("something else"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn test_priority_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Mismatched types")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..13)
+            .with_label("something")
+            .with_priority(1))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 8..11)
+            .with_label("something else"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 15..24)
+            .with_label("cannot be next to the underline")
+            .with_priority(1))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 28..31)
+            .with_label("reaches further"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn test_zero_width_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23\nsomething += 3.0;\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 13..13)
+            .with_label("expected `;` here"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..4)
+            .with_label("pointing at a space"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 14..14)
+            .with_label("at the start of a line"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn test_merge_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 0..3)
+            .with_label("in this statement"))
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)
+            .with_label("in this statement"))
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 3..4)
+            .with_label("in this statement"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 11..13)
+            .with_label("value"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 15..22)
+            .with_label("overlapping"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 19..24)
+            .with_label("overlapping"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, merge_annotations: true, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn test_max_annotations_per_line_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = a + b + c + d;\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 11..12)
+            .with_label("first"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 15..16)
+            .with_label("second"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 19..20)
+            .with_label("third"))
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 23..24)
+            .with_label("fourth"))
+        .with_note(Note::note("Some note"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, max_annotations_per_line: NonZeroUsize::new(2), ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn test_multiline_label_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Mismatched types")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)
+            .with_label("expected `String`\nfound integer"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
+            .with_label("variable declared here\nwithout a type annotation"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 0..3)
+            .with_label("keyword"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
@@ -2,25 +2,24 @@ use super::*;
 
 #[test]
 fn test_simple_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
     let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
         .with_message("Some message")
         .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..31)
             .with_label("something"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
 
 #[test]
 fn test_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
     let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
         .with_message("Some message")
@@ -29,18 +28,17 @@ fn test_1() {
         .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
             .with_label("something else"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
 
 #[test]
 fn test_with_ending_1() {
-    let mut buf = Buffer::no_color();
+    let mut buf = FmtWriter::new(String::new());
     let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
     let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
         .with_message("Some message")
@@ -49,11 +47,53 @@ fn test_with_ending_1() {
         .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 11..24)
             .with_label("something else"));
     let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
-        file, RenderConfig { surrounding_lines: 0 });
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
     renderer.render(vec![diagnostic]).unwrap();
 
-    let buf = buf.into_inner();
-    let result = String::from_utf8_lossy(&buf);
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn test_with_crowded_start_1() {
+    // Previously failed an assertion in `calculate_vertical_offsets`, as there was no space
+    // left for the starting annotation
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let a = 1;\nfn b(x) {\n    c(x, y)\n}\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 29..32)
+            .with_label("something"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 14..34)
+            .with_label("something else"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 2..16)
+            .with_label("another thing"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn test_long_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let source = (0..20).map(|i| format!("line {}\n", i)).collect::<String>();
+    let start = source.find("line 2").unwrap();
+    let end = source.find("line 17").unwrap() + 4;
+    let file = SimpleFile::new("test_file.test", source);
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), start..end)
+            .with_label("long annotation"));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 1, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
 
     insta::assert_snapshot!(result);
 }
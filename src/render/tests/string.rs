@@ -0,0 +1,44 @@
+use std::io::Write;
+use crate::render::string::{render_to_string, FmtWriter};
+use super::*;
+
+#[cfg(feature = "termcolor")]
+#[test]
+fn test_render_to_string_1() {
+    let file = SimpleFile::new("test_file.test", "let 🗻 = 23;\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)
+            .with_label("mountain"));
+
+    let mut buf = termcolor::Buffer::no_color();
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file.clone(), RenderConfig::default());
+    renderer.render(vec![diagnostic.clone()]).unwrap();
+
+    let result = render_to_string(file, RenderConfig::default(), vec![diagnostic]).unwrap();
+    assert_eq!(result, String::from_utf8(buf.into_inner()).unwrap());
+}
+
+#[test]
+fn test_fmt_writer_1() {
+    let mut writer = FmtWriter::new(String::new());
+    let bytes = "a🗻b".as_bytes();
+
+    writer.write_all(&bytes[..3]).unwrap();
+    writer.write_all(&bytes[3..]).unwrap();
+    assert_eq!(writer.write(&[0xff]).map_err(|err| err.kind()), Err(std::io::ErrorKind::InvalidData));
+    assert_eq!(writer.into_inner(), "a🗻b");
+}
+
+#[test]
+fn test_display_1() {
+    let file = SimpleFile::new("test_file.test", "let main = 23;\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Some message")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8));
+
+    assert_eq!(diagnostic.display(&file).to_string(), render_to_string(&file, RenderConfig::default(), vec![diagnostic.clone()]).unwrap());
+    assert_eq!(format!("{}", diagnostic.display(&file).with_config(RenderConfig::default().display_style(DisplayStyle::Short))),
+        "test_file.test:1:5: error: Some message\n");
+}
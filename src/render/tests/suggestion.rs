@@ -0,0 +1,134 @@
+use crate::diagnostic::{Replacement, Suggestion};
+use super::*;
+
+#[test]
+fn test_insertion_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main: String = 23;\nsomething += 3.0;\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_name("E0308")
+        .with_message("Mismatched types")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 19..21)
+            .with_label("expected `String`, found integer"))
+        .with_note(Note::note("expected types to match"))
+        .with_suggestion(Suggestion::new("try using a conversion method")
+            .with_replacement(Replacement::new((), 21..21, ".to_string()")));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error[E0308]: Mismatched types
+     --> test_file.test:1:20
+    1 | let main: String = 23;
+      |                    ^^ expected `String`, found integer
+      = note: expected types to match
+      = help: try using a conversion method
+    1 | let main: String = 23.to_string();
+      |                      ++++++++++++
+    "###);
+}
+
+#[test]
+fn test_replacement_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Cannot find value `something` in this scope")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 15..24)
+            .with_label("not found in this scope"))
+        .with_suggestion(Suggestion::new("a local variable with a similar name exists")
+            .with_replacement(Replacement::new((), 15..24, "main")));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Cannot find value `something` in this scope
+     --> test_file.test:2:1
+    2 | something += 3.0;
+      | ^^^^^^^^^ not found in this scope
+      = help: a local variable with a similar name exists
+    2 - something += 3.0;
+    2 + main += 3.0;
+    "###);
+}
+
+#[test]
+fn test_replacement_multiline_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Warning)
+        .with_message("Unnecessary statements")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 15..32))
+        .with_suggestion(Suggestion::new("combine these lines")
+            .with_replacement(Replacement::new((), 11..38, "23 + 3.0;\nprint")));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    warning: Unnecessary statements
+     --> test_file.test:2:1
+    2 | something += 3.0;
+      | ^^^^^^^^^^^^^^^^^
+      = help: combine these lines
+    1 - let main = 23;
+    2 - something += 3.0;
+    3 - print(example_source);
+    1 + let main = 23 + 3.0;
+    2 + print(example_source);
+    "###);
+}
+
+#[test]
+fn test_multipart_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Mismatched types")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 28..31))
+        .with_suggestion(Suggestion::new("use a floating-point number")
+            .with_replacement(Replacement::new((), 13..13, ".0"))
+            .with_replacement(Replacement::new((), 8..8, ": f64"))
+            .with_replacement(Replacement::new((), 53..53, ".to_string()")));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Mismatched types
+     --> test_file.test:2:14
+    2 | something += 3.0;
+      |              ^^^
+      = help: use a floating-point number
+    1 | let main: f64 = 23.0;
+      |         +++++     ++
+    3 | print(example_source.to_string());
+      |                     ++++++++++++
+    "###);
+}
+
+#[test]
+fn test_reversed_range_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Mismatched types")
+        .with_suggestion(Suggestion::new("replace something")
+            .with_replacement(Replacement::new((), Range { start: 8, end: 4 }, "other")));
+    let mut renderer = DiagnosticRenderer::new(&mut buf, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    let err = renderer.render(vec![diagnostic]).unwrap_err();
+
+    insta::assert_snapshot!(err, @"failed to access the source code at byte index 8 in file `test_file.test`: invalid range 8..4, it ends before it starts");
+    assert!(matches!(err, RenderError::File { error: Error::InvalidRange { start: 8, end: 4 }, .. }));
+}
@@ -0,0 +1,19 @@
+use crate::render::svg::SvgWriter;
+use super::*;
+
+#[test]
+fn test_1() {
+    let mut svg = SvgWriter::new();
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\nprint(example_source);\n");
+    let diagnostic: Diagnostic<()> = Diagnostic::new(Severity::Error)
+        .with_message("Mismatched <types>")
+        .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)
+            .with_label("number"))
+        .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 4..8)
+            .with_label("identifier"));
+    let mut renderer = DiagnosticRenderer::new(&mut svg, DefaultColorConfig,
+        file, RenderConfig { surrounding_lines: 0, ..Default::default() });
+    renderer.render(vec![diagnostic]).unwrap();
+
+    insta::assert_snapshot!(svg.finish());
+}
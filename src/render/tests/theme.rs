@@ -0,0 +1,80 @@
+use crate::render::style::Color;
+#[cfg(feature = "termcolor")]
+use crate::diagnostic::{Replacement, Suggestion};
+use crate::render::chars::CharSet;
+use crate::render::theme::{TextStyle, Theme, ThemeColors};
+use super::*;
+
+#[cfg(feature = "termcolor")]
+fn render_with_colors<C: ColorConfig>(colors: C) -> String {
+    let mut buf = termcolor::Buffer::ansi();
+    let file = SimpleFile::new("test_file.test", "let main = 23;\nsomething += 3.0;\n");
+    let mut renderer = DiagnosticRenderer::new(&mut buf, colors, file, RenderConfig::default());
+    renderer.render(vec![
+        Diagnostic::new(Severity::Warning)
+            .with_name("W0001")
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)
+                .with_label("primary"))
+            .with_annotation(Annotation::new(AnnotationStyle::Secondary, (), 15..24)
+                .with_label("secondary"))
+            .with_note(Note::note("Some note"))
+            .with_suggestion(Suggestion::new("Rename it")
+                .with_replacement(Replacement::new((), 4..8, "other"))),
+    ]).unwrap();
+
+    String::from_utf8_lossy(&buf.into_inner()).into_owned()
+}
+
+#[cfg(feature = "termcolor")]
+#[test]
+fn test_default_1() {
+    assert_eq!(render_with_colors(ThemeColors::default()), render_with_colors(DefaultColorConfig));
+}
+
+#[test]
+fn test_with_theme_1() {
+    let mut buf = FmtWriter::new(String::new());
+    let file = SimpleFile::new("test_file.test", "let main = 23;\n");
+    let theme = Theme {
+        colors: ThemeColors { error: TextStyle::fg(Color::Magenta), ..Default::default() },
+        charset: CharSet::unicode(),
+    };
+    let mut renderer = DiagnosticRenderer::with_theme(&mut buf, theme, file, RenderConfig::default());
+    renderer.render(vec![
+        Diagnostic::new(Severity::Error)
+            .with_message("Test message")
+            .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 4..8)
+                .with_label("primary")),
+    ]).unwrap();
+
+    let result = buf.into_inner();
+
+    insta::assert_snapshot!(result, @r###"
+    error: Test message
+     --> test_file.test:1:5
+    1 │ let main = 23;
+      │     ━━━━ primary
+    "###);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_1() {
+    let theme: Theme = toml::from_str(r#"
+        charset = "unicode"
+
+        [colors]
+        error = { fg = "magenta", bold = true }
+        line_number = { fg = "8" }
+        source = { fg = "255,128,0", italic = true }
+    "#).unwrap();
+
+    assert_eq!(theme.charset, CharSet::unicode());
+    assert_eq!(theme.colors.error, TextStyle::fg(Color::Magenta).bold());
+    assert_eq!(theme.colors.line_number, TextStyle::fg(Color::Ansi256(8)));
+    assert_eq!(theme.colors.source, TextStyle { italic: true, ..TextStyle::fg(Color::Rgb(255, 128, 0)) });
+    assert_eq!(theme.colors.warning, ThemeColors::default().warning);
+
+    assert!(toml::from_str::<Theme>("[colors]\nerror = { fg = \"no color\" }").is_err());
+}
@@ -0,0 +1,105 @@
+//! Contains functions for converting source code into the text displayed for it,
+//! and for measuring the width of that text in columns.
+//!
+//! Column indices of annotations are calculated using [`width`], so the result of
+//! [`display`] always has to take up exactly that many columns.
+//!
+//! [`width`]: width
+//! [`display`]: display
+
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use crate::render::{ColumnUnit, ControlChars, RenderConfig};
+
+/// Returns the text displayed in place of a character of source code,
+/// or `None` if it is displayed as it is.
+fn replacement(c: char, config: &RenderConfig) -> Option<Cow<'static, str>> {
+    match c {
+        '\t' => Some(Cow::Owned(" ".repeat(config.tab_width))),
+        c if c.is_control() => match config.control_chars {
+            ControlChars::Raw => None,
+            ControlChars::Pictures => match c {
+                // The "Control Pictures" block contains symbols for C0 control characters and DEL
+                '\0'..='\x1f' => char::from_u32(0x2400 + c as u32).map(|c| Cow::Owned(c.to_string())),
+                '\x7f' => Some(Cow::Borrowed("\u{2421}")),
+                c => Some(Cow::Owned(c.escape_unicode().to_string())),
+            },
+            ControlChars::Escaped => Some(Cow::Owned(match c {
+                '\0'..='\x7f' => format!("\\x{:02x}", c as u32),
+                c => c.escape_unicode().to_string(),
+            })),
+        },
+        _ => None,
+    }
+}
+
+/// Returns the number of columns a character of source code takes up when displayed.
+///
+/// Wide characters, like most CJK characters and emoji, take up two columns, while
+/// combining characters don't take up any.
+fn char_width(c: char, config: &RenderConfig) -> usize {
+    match replacement(c, config) {
+        Some(replacement) => replacement.width(),
+        None => c.width().unwrap_or(0),
+    }
+}
+
+/// Returns the number of columns a grapheme cluster of source code takes up when displayed.
+fn grapheme_width(grapheme: &str, config: &RenderConfig) -> usize {
+    let mut chars = grapheme.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => char_width(c, config),
+        _ => grapheme.width(),
+    }
+}
+
+/// Returns the number of columns a piece of source code takes up when displayed.
+///
+/// Line endings (`\n` and `\r\n`) don't take up any columns.
+pub fn width(text: &str, config: &RenderConfig) -> usize {
+    match config.column_unit {
+        ColumnUnit::Graphemes => text.graphemes(true)
+            .map(|g| if g == "\n" || g == "\r\n" { 0 } else { grapheme_width(g, config) })
+            .sum(),
+        ColumnUnit::Bytes | ColumnUnit::Chars | ColumnUnit::Utf16 | ColumnUnit::Width => {
+            let mut chars = text.chars().peekable();
+            let mut width = 0;
+
+            while let Some(c) = chars.next() {
+                if c == '\n' || (c == '\r' && chars.peek() == Some(&'\n')) {
+                    continue;
+                }
+
+                width += char_width(c, config);
+            }
+
+            width
+        },
+    }
+}
+
+/// Returns the text displayed for a piece of source code, which should not contain line endings.
+///
+/// Tabs are expanded to [`RenderConfig::tab_width`] spaces, and control characters are
+/// replaced according to [`RenderConfig::control_chars`].
+///
+/// [`RenderConfig::tab_width`]: RenderConfig::tab_width
+/// [`RenderConfig::control_chars`]: RenderConfig::control_chars
+pub fn display<'a>(text: &'a str, config: &RenderConfig) -> Cow<'a, str> {
+    if !text.chars().any(|c| replacement(c, config).is_some()) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match replacement(c, config) {
+            Some(replacement) => result.push_str(&replacement),
+            None => result.push(c),
+        }
+    }
+
+    Cow::Owned(result)
+}
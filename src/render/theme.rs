@@ -0,0 +1,289 @@
+//! Contains [`Theme`], which bundles the colors and characters used for rendering
+//! diagnostics as data, so that custom looks don't require implementing [`ColorConfig`].
+//!
+//! [`Theme`]: Theme
+//! [`ColorConfig`]: ColorConfig
+
+use crate::render::style::{Color, ColorSpec, WriteColor};
+use crate::diagnostic::{AnnotationStyle, Severity};
+use crate::render::chars::CharSet;
+use crate::render::color::{ANNOTATION_PALETTE, ColorConfig};
+use crate::render::{DiagnosticRenderer, RenderConfig};
+
+type Result = std::io::Result<()>;
+
+/// The formatting of a piece of text.
+///
+/// With the `serde` feature, colors are deserialized from the names accepted by
+/// [`Color`]'s `FromStr` implementation, like `"red"`, `"33"` (an ANSI 256-color code),
+/// or `"255,128,0"` (RGB).
+///
+/// [`Color`]: Color
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default, deny_unknown_fields))]
+pub struct TextStyle {
+    /// The foreground color, or `None` to use the default color of the terminal.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_color"))]
+    pub fg: Option<Color>,
+    /// The background color, or `None` to use the default color of the terminal.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_color"))]
+    pub bg: Option<Color>,
+    /// Whether the text is bold.
+    pub bold: bool,
+    /// Whether the colors are the intense variants.
+    pub intense: bool,
+    /// Whether the text is italic.
+    pub italic: bool,
+    /// Whether the text is underlined.
+    pub underline: bool,
+    /// Whether the text is dimmed.
+    pub dimmed: bool,
+}
+
+impl TextStyle {
+    /// Creates a style with the given foreground color and no other formatting.
+    pub fn fg(color: Color) -> Self {
+        TextStyle {
+            fg: Some(color),
+            ..Default::default()
+        }
+    }
+
+    /// Makes this style bold.
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    /// Makes the colors of this style intense.
+    pub fn intense(mut self) -> Self {
+        self.intense = true;
+        self
+    }
+
+    /// Returns the [`ColorSpec`] for this style.
+    ///
+    /// [`ColorSpec`]: ColorSpec
+    pub fn to_color_spec(&self) -> ColorSpec {
+        let mut spec = ColorSpec::new();
+        spec.set_fg(self.fg).set_bg(self.bg)
+            .set_bold(self.bold).set_intense(self.intense).set_italic(self.italic)
+            .set_underline(self.underline).set_dimmed(self.dimmed);
+        spec
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_color<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<Color>, D::Error> {
+    use serde::Deserialize;
+
+    let color = Option::<String>::deserialize(deserializer)?;
+    color.map(|color| color.parse().map_err(serde::de::Error::custom)).transpose()
+}
+
+/// A [`ColorConfig`] using the styles stored in its fields.
+///
+/// The default value uses the same colors as [`DefaultColorConfig`].
+///
+/// [`ColorConfig`]: ColorConfig
+/// [`DefaultColorConfig`]: crate::render::color::DefaultColorConfig
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default, deny_unknown_fields))]
+pub struct ThemeColors {
+    /// The style for [`Severity::Bug`], also used for the name and primary annotations of bugs.
+    ///
+    /// [`Severity::Bug`]: Severity::Bug
+    pub bug: TextStyle,
+    /// The style for [`Severity::Error`], also used for the name and primary annotations of errors.
+    ///
+    /// [`Severity::Error`]: Severity::Error
+    pub error: TextStyle,
+    /// The style for [`Severity::Warning`], also used for the name and primary annotations of warnings.
+    ///
+    /// [`Severity::Warning`]: Severity::Warning
+    pub warning: TextStyle,
+    /// The style for [`Severity::Note`], also used for the name and primary annotations of notes.
+    ///
+    /// [`Severity::Note`]: Severity::Note
+    pub note: TextStyle,
+    /// The style for [`Severity::Help`], also used for the name and primary annotations of help messages.
+    ///
+    /// [`Severity::Help`]: Severity::Help
+    pub help: TextStyle,
+    /// The style for the main message of a diagnostic.
+    pub message: TextStyle,
+    /// The style for file paths, and line and column numbers in the header of a code block.
+    pub path: TextStyle,
+    /// The style for line numbers.
+    pub line_number: TextStyle,
+    /// The style for the separator between the line number and the line of source code.
+    pub line_number_separator: TextStyle,
+    /// The style for secondary annotations.
+    pub secondary_annotation: TextStyle,
+    /// The style for lines of source code.
+    pub source: TextStyle,
+    /// The style for the severity prefix of notes.
+    pub note_severity: TextStyle,
+    /// The style for the message of notes.
+    pub note_message: TextStyle,
+    /// The style for code inserted by a suggestion.
+    pub suggestion: TextStyle,
+    /// The style for code removed by a suggestion.
+    pub suggestion_removal: TextStyle,
+    /// The style for text inside backticks, if [`RenderConfig::emphasize_backticks`] is enabled.
+    ///
+    /// [`RenderConfig::emphasize_backticks`]: crate::render::RenderConfig::emphasize_backticks
+    pub emphasis: TextStyle,
+    /// The styles cycled through for annotations, if [`RenderConfig::cycle_annotation_colors`]
+    /// is enabled. Primary annotations are made bold. If this is empty, the styles for
+    /// primary and secondary annotations are used instead.
+    ///
+    /// [`RenderConfig::cycle_annotation_colors`]: crate::render::RenderConfig::cycle_annotation_colors
+    pub annotation_palette: Vec<TextStyle>,
+}
+
+impl ThemeColors {
+    /// Returns the style for the given severity.
+    pub fn severity_style(&self, severity: Severity) -> &TextStyle {
+        match severity {
+            Severity::Bug => &self.bug,
+            Severity::Error => &self.error,
+            Severity::Warning => &self.warning,
+            Severity::Note => &self.note,
+            Severity::Help => &self.help,
+        }
+    }
+}
+
+impl Default for ThemeColors {
+    fn default() -> Self {
+        ThemeColors {
+            bug: TextStyle::fg(Color::Red).bold(),
+            error: TextStyle::fg(Color::Red).bold(),
+            warning: TextStyle::fg(Color::Yellow),
+            note: TextStyle::fg(Color::Blue).intense().bold(),
+            help: TextStyle::fg(Color::Green),
+            message: TextStyle::default().bold(),
+            path: TextStyle::default(),
+            line_number: TextStyle::fg(Color::Blue).intense().bold(),
+            line_number_separator: TextStyle::fg(Color::Blue).intense().bold(),
+            secondary_annotation: TextStyle::fg(Color::Blue).intense().bold(),
+            source: TextStyle::default(),
+            note_severity: TextStyle::default().bold(),
+            note_message: TextStyle::default(),
+            suggestion: TextStyle::fg(Color::Green),
+            suggestion_removal: TextStyle::fg(Color::Red).bold(),
+            emphasis: TextStyle::default().bold(),
+            annotation_palette: ANNOTATION_PALETTE.into_iter().map(TextStyle::fg).collect(),
+        }
+    }
+}
+
+impl ColorConfig for ThemeColors {
+    fn reset(&self, f: &mut impl WriteColor) -> Result {
+        f.reset()
+    }
+
+    fn severity(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        f.set_color(&self.severity_style(severity).to_color_spec())
+    }
+
+    fn name(&self, f: &mut impl WriteColor, severity: Severity) -> Result {
+        self.severity(f, severity)
+    }
+
+    fn message(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(&self.message.to_color_spec())
+    }
+
+    fn path(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(&self.path.to_color_spec())
+    }
+
+    fn line_number(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(&self.line_number.to_color_spec())
+    }
+
+    fn line_number_separator(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(&self.line_number_separator.to_color_spec())
+    }
+
+    fn annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity) -> Result {
+        match style {
+            AnnotationStyle::Primary => self.severity(f, severity),
+            AnnotationStyle::Secondary => f.set_color(&self.secondary_annotation.to_color_spec()),
+        }
+    }
+
+    fn indexed_annotation(&self, f: &mut impl WriteColor, style: AnnotationStyle, severity: Severity, index: usize) -> Result {
+        if self.annotation_palette.is_empty() {
+            return self.annotation(f, style, severity);
+        }
+
+        let mut spec = self.annotation_palette[index % self.annotation_palette.len()].to_color_spec();
+
+        if style == AnnotationStyle::Primary {
+            spec.set_bold(true);
+        }
+
+        f.set_color(&spec)
+    }
+
+    fn source(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(&self.source.to_color_spec())
+    }
+
+    fn note_severity(&self, f: &mut impl WriteColor, _severity: Severity) -> Result {
+        f.set_color(&self.note_severity.to_color_spec())
+    }
+
+    fn note_message(&self, f: &mut impl WriteColor, _severity: Severity) -> Result {
+        f.set_color(&self.note_message.to_color_spec())
+    }
+
+    fn suggestion(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(&self.suggestion.to_color_spec())
+    }
+
+    fn suggestion_removal(&self, f: &mut impl WriteColor) -> Result {
+        f.set_color(&self.suggestion_removal.to_color_spec())
+    }
+
+    fn emphasis(&self, f: &mut impl WriteColor, _severity: Severity) -> Result {
+        f.set_color(&self.emphasis.to_color_spec())
+    }
+}
+
+/// The look of rendered diagnostics, consisting of colors and the characters used for
+/// drawing annotations.
+///
+/// With the `serde` feature, themes can be deserialized, for example from a TOML file:
+///
+/// ```toml
+/// charset = "unicode"
+///
+/// [colors]
+/// error = { fg = "magenta", bold = true }
+/// line_number = { fg = "8" }
+/// ```
+///
+/// Missing fields use their default value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default, deny_unknown_fields))]
+pub struct Theme {
+    /// The colors of this theme.
+    pub colors: ThemeColors,
+    /// The characters used for drawing annotations and the gutter.
+    pub charset: CharSet,
+}
+
+impl<'w, W, FileId, F> DiagnosticRenderer<'w, W, ThemeColors, FileId, F> {
+    /// Creates a new diagnostics renderer using the colors and character set of a theme.
+    ///
+    /// The character set of the theme replaces [`RenderConfig::charset`].
+    ///
+    /// [`RenderConfig::charset`]: RenderConfig::charset
+    pub fn with_theme(f: &'w mut W, theme: Theme, files: F, config: RenderConfig) -> Self {
+        DiagnosticRenderer::new(f, theme.colors, files, config.charset(theme.charset))
+    }
+}
@@ -0,0 +1,182 @@
+//! Emitting diagnostics as [`tracing`] events.
+//!
+//! This allows applications using `tracing` to capture diagnostics in their normal logging
+//! pipeline. Every diagnostic is rendered without colors and recorded as a single event,
+//! with a level depending on its severity.
+//!
+//! # Example
+//! ```rust
+//! use diagnostic_render::diagnostic::{Annotation, Diagnostic};
+//! use diagnostic_render::file::SimpleFile;
+//! use diagnostic_render::render::RenderConfig;
+//! use diagnostic_render::tracing::TracingEmitter;
+//!
+//! let file = SimpleFile::new("main.test", "let main = 23;\n");
+//! let mut emitter = TracingEmitter::new(&file, RenderConfig::default());
+//!
+//! emitter.emit(&[Diagnostic::warning().with_message("Unused value")
+//!     .with_annotation(Annotation::primary((), 4..8))]).unwrap();
+//! ```
+//!
+//! [`tracing`]: ::tracing
+
+use std::fmt::Debug;
+use ::tracing::Level;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::emit::{EmitError, Emitter};
+use crate::file::Files;
+use crate::render::RenderConfig;
+use crate::render::string::render_to_string;
+
+/// Calls a `tracing` macro with a level that is only known at runtime, as the macros
+/// require a constant level.
+macro_rules! with_level {
+    ($macro:ident, $level:expr, $($args:tt)*) => {
+        match $level {
+            Level::ERROR => ::tracing::$macro!(Level::ERROR, $($args)*),
+            Level::WARN => ::tracing::$macro!(Level::WARN, $($args)*),
+            Level::INFO => ::tracing::$macro!(Level::INFO, $($args)*),
+            Level::DEBUG => ::tracing::$macro!(Level::DEBUG, $($args)*),
+            Level::TRACE => ::tracing::$macro!(Level::TRACE, $($args)*),
+        }
+    };
+}
+
+impl From<Severity> for Level {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Bug | Severity::Error => Level::ERROR,
+            Severity::Warning => Level::WARN,
+            Severity::Note | Severity::Help => Level::INFO,
+        }
+    }
+}
+
+/// Records every diagnostic as an event, with the diagnostic rendered using a [`RenderConfig`]
+/// as its message. The level of the event is converted from the severity of the diagnostic.
+///
+/// Events also have a `severity` field and, if the diagnostic has a [name], a `name` field.
+/// Diagnostics are only rendered if their event would be enabled. The target of the events
+/// is always `diagnostic_render::tracing`, as `tracing` requires it to be a constant.
+///
+/// [`RenderConfig`]: RenderConfig
+/// [name]: Diagnostic::name
+#[derive(Debug)]
+pub struct TracingEmitter<F> {
+    files: F, config: RenderConfig,
+}
+
+impl<F> TracingEmitter<F> {
+    /// Creates a new tracing emitter, rendering diagnostics using `config`.
+    pub fn new(files: F, config: RenderConfig) -> Self {
+        TracingEmitter {
+            files, config,
+        }
+    }
+}
+
+impl<FileId, F: Files<FileId=FileId>> TracingEmitter<F>
+    where FileId: Clone + Debug + Eq + Ord {
+    /// Records an event for each of the given diagnostics.
+    pub fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result<(), EmitError> {
+        for diagnostic in diagnostics.iter() {
+            let level = Level::from(diagnostic.severity);
+
+            if !with_level!(event_enabled, level, severity = ::tracing::field::Empty, name = ::tracing::field::Empty) {
+                continue;
+            }
+
+            let output = render_to_string(&self.files, self.config.clone(), vec![diagnostic.clone()])?;
+
+            // Diagnostics can be filtered out by the configuration, leaving no output
+            if !output.is_empty() {
+                with_level!(event, level, severity = %diagnostic.severity, name = diagnostic.name.as_deref(),
+                    "{}", output.trim_end());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<FileId, F: Files<FileId=FileId>> Emitter<FileId> for TracingEmitter<F>
+    where FileId: Clone + Debug + Eq + Ord {
+    fn emit(&mut self, diagnostics: &[Diagnostic<FileId>]) -> Result<(), EmitError> {
+        TracingEmitter::emit(self, diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+    use std::sync::{Arc, Mutex};
+    use ::tracing::field::{Field, Visit};
+    use ::tracing::span::{Attributes, Id, Record};
+    use ::tracing::{Event, Metadata, Subscriber};
+    use crate::diagnostic::{Annotation, AnnotationStyle};
+    use crate::file::SimpleFile;
+    use super::*;
+
+    /// Records the level and fields of all events at or above [`Level::WARN`].
+    ///
+    /// [`Level::WARN`]: Level::WARN
+    #[derive(Clone, Default)]
+    struct TestSubscriber {
+        events: Arc<Mutex<Vec<(Level, String)>>>,
+    }
+
+    struct FieldVisitor(String);
+
+    impl Visit for FieldVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            write!(self.0, "{}={:?};", field.name(), value).unwrap();
+        }
+    }
+
+    impl Subscriber for TestSubscriber {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            *metadata.level() <= Level::WARN
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut visitor = FieldVisitor(String::new());
+            event.record(&mut visitor);
+            self.events.lock().unwrap().push((*event.metadata().level(), visitor.0));
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_1() {
+        let subscriber = TestSubscriber::default();
+        let file = SimpleFile::new("main.test", "let main = 23;\n");
+        let mut emitter = TracingEmitter::new(&file, RenderConfig::default());
+
+        ::tracing::subscriber::with_default(subscriber.clone(), || emitter.emit(&[
+            Diagnostic::new(Severity::Error)
+                .with_name("E0308")
+                .with_message("Mismatched types")
+                .with_annotation(Annotation::new(AnnotationStyle::Primary, (), 11..13)),
+            Diagnostic::new(Severity::Note)
+                .with_message("Not enabled"),
+            Diagnostic::new(Severity::Warning)
+                .with_message("Unused value"),
+        ])).unwrap();
+
+        assert_eq!(*subscriber.events.lock().unwrap(), vec![
+            (Level::ERROR, String::from("message=error[E0308]: Mismatched types\n --> main.test:1:12\n1 | let main = 23;\n  |            ^^;severity=error;name=\"E0308\";")),
+            (Level::WARN, String::from("message=warning: Unused value;severity=warning;")),
+        ]);
+    }
+}